@@ -0,0 +1,12 @@
+fn main() {
+    // the service of `eif-serve --grpc`, compiled without protoc
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/eif.proto");
+        let descriptors = protox::compile(["proto/eif.proto"], ["proto"]).unwrap();
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(descriptors)
+            .unwrap();
+    }
+}
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use eyre::Result;
 use plotters::prelude::*;
 
-use extended_isolation_forest::{Forest, ForestOptions};
+use extended_isolation_forest::{ExtensionLevel, Forest, ForestOptions};
 
 fn read_acceleration_data(
     filename: &str,
@@ -51,7 +51,8 @@ fn main() -> Result<()> {
             n_trees: 100,
             sample_size: 600,
             max_tree_depth: None,
-            extension_level: 1,
+            extension_level: ExtensionLevel::Fixed(1),
+            ..Default::default()
         },
     )?;
 
@@ -70,10 +71,7 @@ fn main() -> Result<()> {
         .set_label_area_size(LabelAreaPosition::Left, 60)
         .set_label_area_size(LabelAreaPosition::Bottom, 30)
         .caption("Acceleration while walking stairs", ("sans-serif", 14))
-        .build_cartesian_2d(
-            0.0..(rows.len() as f64 - 1.0),
-            (max_value * -1.0)..max_value,
-        )?;
+        .build_cartesian_2d(0.0..(rows.len() as f64 - 1.0), -max_value..max_value)?;
 
     upper_chart
         .configure_mesh()
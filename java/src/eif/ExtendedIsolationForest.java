@@ -0,0 +1,82 @@
+package eif;
+
+/**
+ * An extended isolation forest loaded with the native library built from the crate with the
+ * {@code jni} feature, which must be on the {@code java.library.path}.
+ *
+ * <p>Forests hold native memory until they are closed, and may be shared by threads.
+ */
+public final class ExtendedIsolationForest implements AutoCloseable {
+    static {
+        System.loadLibrary("extended_isolation_forest");
+    }
+
+    private long handle;
+
+    private ExtendedIsolationForest(long handle) {
+        this.handle = handle;
+    }
+
+    /** Load a forest in the binary format of the crate. */
+    public static ExtendedIsolationForest load(byte[] bytes) {
+        return new ExtendedIsolationForest(nativeLoad(bytes, 0));
+    }
+
+    /** Load a forest in the binary format of the crate, or stored as JSON with the given dimensions. */
+    public static ExtendedIsolationForest load(byte[] bytes, int dimensions) {
+        return new ExtendedIsolationForest(nativeLoad(bytes, dimensions));
+    }
+
+    /** The number of features of the samples. */
+    public synchronized int dimensions() {
+        return nativeDimensions(handle());
+    }
+
+    /** The score of the sample, which must have the features of the forest. */
+    public synchronized double score(double[] sample) {
+        checkDimensions(sample, dimensions());
+        return nativeScore(handle(), sample)[0];
+    }
+
+    /** The scores of the samples, which must all have the features of the forest. */
+    public synchronized double[] score(double[][] samples) {
+        int dimensions = dimensions();
+        double[] data = new double[samples.length * dimensions];
+        for (int i = 0; i < samples.length; i++) {
+            checkDimensions(samples[i], dimensions);
+            System.arraycopy(samples[i], 0, data, i * dimensions, dimensions);
+        }
+        return nativeScore(handle(), data);
+    }
+
+    /** Release the native memory of the forest, later calls throw an {@code IllegalStateException}. */
+    @Override
+    public synchronized void close() {
+        if (handle != 0) {
+            nativeFree(handle);
+            handle = 0;
+        }
+    }
+
+    private static void checkDimensions(double[] sample, int dimensions) {
+        if (sample.length != dimensions) {
+            throw new IllegalArgumentException(
+                    "expected " + dimensions + " dimensions, but got " + sample.length);
+        }
+    }
+
+    private long handle() {
+        if (handle == 0) {
+            throw new IllegalStateException("the forest is closed");
+        }
+        return handle;
+    }
+
+    private static native long nativeLoad(byte[] bytes, int dimensions);
+
+    private static native double[] nativeScore(long handle, double[] data);
+
+    private static native int nativeDimensions(long handle);
+
+    private static native void nativeFree(long handle);
+}
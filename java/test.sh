@@ -0,0 +1,7 @@
+#!/bin/sh
+# Builds the crate with the `jni` feature as a cdylib and runs the test of the class.
+set -e
+cd "$(dirname "$0")"
+cargo rustc --manifest-path ../Cargo.toml --lib --release --features jni --crate-type cdylib
+javac -d build src/eif/ExtendedIsolationForest.java test/ExtendedIsolationForestTest.java
+java -Djava.library.path="${CARGO_TARGET_DIR:-../target}/release" -cp build ExtendedIsolationForestTest
@@ -0,0 +1,55 @@
+import java.lang.reflect.InvocationTargetException;
+import java.lang.reflect.Method;
+import java.nio.file.Files;
+import java.nio.file.Paths;
+
+import eif.ExtendedIsolationForest;
+
+public class ExtendedIsolationForestTest {
+    public static void main(String[] args) throws Exception {
+        byte[] json = Files.readAllBytes(Paths.get("test", "forest.json"));
+        ExtendedIsolationForest forest = ExtendedIsolationForest.load(json, 2);
+        check(forest.dimensions() == 2, "dimensions");
+        double[] scores = forest.score(new double[][] {{10, 10}, {200, 200}});
+        check(scores.length == 2 && scores[1] > scores[0], "scores");
+        check(forest.score(new double[] {200, 200}) == scores[1], "score");
+        throwsMessage(() -> forest.score(new double[] {1, 2, 3}), "expected 2 dimensions, but got 3");
+        throwsMessage(() -> forest.score(new double[] {1, 2, 3, 4}), "expected 2 dimensions, but got 4");
+        throwsMessage(() -> forest.score(new double[][] {{1, 2, 3, 4}}), "expected 2 dimensions");
+
+        forest.close();
+        forest.close();
+        throwsMessage(() -> forest.dimensions(), "the forest is closed");
+        throwsMessage(() -> forest.score(new double[] {200, 200}), "the forest is closed");
+
+        // the native methods reject the handle of a closed forest as well
+        Method dimensions =
+                ExtendedIsolationForest.class.getDeclaredMethod("nativeDimensions", long.class);
+        dimensions.setAccessible(true);
+        try {
+            dimensions.invoke(null, 0L);
+            throw new AssertionError("expected an IllegalStateException");
+        } catch (InvocationTargetException e) {
+            check(e.getCause() instanceof IllegalStateException, e.getCause().toString());
+        }
+
+        throwsMessage(() -> ExtendedIsolationForest.load(json), "dimensions of JSON forests");
+        System.out.println("ok");
+    }
+
+    private static void check(boolean condition, String message) {
+        if (!condition) {
+            throw new AssertionError(message);
+        }
+    }
+
+    private static void throwsMessage(Runnable runnable, String message) {
+        try {
+            runnable.run();
+        } catch (RuntimeException e) {
+            check(e.getMessage().contains(message), e.getMessage());
+            return;
+        }
+        throw new AssertionError("expected an exception containing " + message);
+    }
+}
@@ -0,0 +1,20 @@
+// Builds the crate with the `napi` feature as a cdylib and copies it next to this file.
+const { execFileSync } = require("child_process");
+const fs = require("fs");
+const path = require("path");
+
+const root = path.join(__dirname, "..");
+const args = ["rustc", "--lib", "--release", "--features", "napi", "--crate-type", "cdylib"];
+if (process.platform === "darwin") {
+  // the symbols of node are resolved when the addon is loaded
+  args.push("--", "-C", "link-arg=-undefined", "-C", "link-arg=dynamic_lookup");
+}
+execFileSync("cargo", args, { cwd: root, stdio: "inherit" });
+
+const libraries = {
+  darwin: "libextended_isolation_forest.dylib",
+  win32: "extended_isolation_forest.dll",
+};
+const library = libraries[process.platform] || "libextended_isolation_forest.so";
+const target = process.env.CARGO_TARGET_DIR || path.join(root, "target");
+fs.copyFileSync(path.join(target, "release", library), path.join(__dirname, "eif.node"));
@@ -0,0 +1,21 @@
+export interface TrainOptions {
+  nTrees?: number;
+  sampleSize?: number;
+  extensionLevel?: number;
+  seed?: number;
+}
+
+export class Forest {
+  /** The scores of the samples, every sample being an array of its features. */
+  score(rows: number[][]): number[];
+  /** The forest in the binary format, for `loadFromBuffer`. */
+  toBuffer(): Buffer;
+  /** The number of features of the samples. */
+  get dimensions(): number;
+}
+
+/** Train a forest on the samples, which must all have the same number of features. */
+export function trainForest(rows: number[][], options?: TrainOptions): Forest;
+
+/** Load a forest from the binary format or from JSON, which requires the dimensions. */
+export function loadFromBuffer(buffer: Buffer, dimensions?: number): Forest;
@@ -0,0 +1,22 @@
+const assert = require("assert");
+const { loadFromBuffer, trainForest } = require(".");
+
+const rows = [];
+for (let i = 0; i < 400; i++) {
+  rows.push([i % 20, Math.floor(i / 20)]);
+}
+const forest = trainForest(rows, { nTrees: 50, sampleSize: 64, seed: 7 });
+assert.strictEqual(forest.dimensions, 2);
+const scores = forest.score([[10, 10], [200, 200]]);
+assert.strictEqual(scores.length, 2);
+assert.ok(scores[1] > scores[0]);
+
+const loaded = loadFromBuffer(forest.toBuffer());
+assert.deepStrictEqual(loaded.score([[10, 10], [200, 200]]), scores);
+
+assert.throws(() => forest.score([[1, 2, 3]]), /expected 2 dimensions, but got 3/);
+assert.throws(() => forest.score([[1, 2, 3, 4]]), /expected 2 dimensions, but got 4/);
+assert.deepStrictEqual(forest.score([]), []);
+assert.throws(() => trainForest([[1, 2], [3]]), /expected 2 dimensions, but got 1/);
+assert.throws(() => loadFromBuffer(Buffer.from("{}")), /dimensions of JSON forests/);
+console.log("ok");
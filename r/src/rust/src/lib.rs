@@ -0,0 +1,90 @@
+//! The Rust side of the `eif` R package, exporting [`DynForest`] as the class `EifForest`.
+//!
+//! R matrices are column-major, so they are passed to the forest as one slice per feature and
+//! scored without copying them.
+
+use extended_isolation_forest::dynamic::DynForest;
+use extended_isolation_forest::{ExtensionLevel, ForestOptions};
+use extendr_api::prelude::*;
+
+/// An extended isolation forest trained on the columns of a numeric matrix.
+#[extendr]
+struct EifForest {
+    forest: DynForest,
+}
+
+#[extendr]
+impl EifForest {
+    /// Train a forest on the rows of `x`, one column per feature. `seed` may be `NULL` for a
+    /// random seed.
+    fn train(
+        x: RMatrix<f64>,
+        n_trees: i32,
+        sample_size: i32,
+        extension_level: i32,
+        seed: Nullable<f64>,
+    ) -> Result<Self> {
+        let mut options = ForestOptions {
+            n_trees: count("n_trees", n_trees)?,
+            sample_size: count("sample_size", sample_size)?,
+            extension_level: ExtensionLevel::Fixed(count("extension_level", extension_level)?),
+            ..Default::default()
+        };
+        if let NotNull(seed) = seed {
+            if !(seed >= 0.0 && seed.fract() == 0.0 && seed < u64::MAX as f64) {
+                return Err(Error::Other(format!("invalid seed {}", seed)));
+            }
+            options.seed = Some(seed as u64);
+        }
+        let forest = DynForest::from_columns(&columns(&x), &options).map_err(r_error)?;
+        Ok(Self { forest })
+    }
+
+    /// The scores of the rows of `x`, which must have one column per feature.
+    fn score(&self, x: RMatrix<f64>) -> Result<Vec<f64>> {
+        self.forest.score_columns(&columns(&x)).map_err(r_error)
+    }
+
+    /// The number of features of the samples.
+    fn dimensions(&self) -> i32 {
+        self.forest.dimensions() as i32
+    }
+
+    /// Write the forest to `path` in the binary format of the crate.
+    fn save(&self, path: &str) -> Result<()> {
+        let bytes = self.forest.to_binary().map_err(r_error)?;
+        std::fs::write(path, bytes).map_err(|err| Error::Other(err.to_string()))
+    }
+
+    /// Read a forest written by `save`, or a forest stored as JSON with the given dimensions.
+    fn load(path: &str, dimensions: Nullable<i32>) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|err| Error::Other(err.to_string()))?;
+        let dimensions = match dimensions {
+            NotNull(dimensions) => Some(count("dimensions", dimensions)?),
+            Null => None,
+        };
+        let forest = DynForest::from_bytes(&bytes, dimensions).map_err(r_error)?;
+        Ok(Self { forest })
+    }
+}
+
+/// The columns of the column-major matrix.
+fn columns(x: &RMatrix<f64>) -> Vec<&[f64]> {
+    let (nrows, ncols) = (x.nrows(), x.ncols());
+    (0..ncols)
+        .map(|column| &x.data()[column * nrows..(column + 1) * nrows])
+        .collect()
+}
+
+fn count(name: &str, value: i32) -> Result<usize> {
+    usize::try_from(value).map_err(|_| Error::Other(format!("invalid {} {}", name, value)))
+}
+
+fn r_error(err: extended_isolation_forest::Error) -> Error {
+    Error::Other(err.to_string())
+}
+
+extendr_module! {
+    mod eif;
+    impl EifForest;
+}
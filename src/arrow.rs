@@ -0,0 +1,144 @@
+//! Scoring Arrow record batches, available with the `arrow` feature.
+//!
+//! [`Forest::score_record_batch`] scores every row of a batch with one column per feature, in
+//! the order of the features. The columns are read in place with [`Forest::score_columns`],
+//! only columns containing nulls are copied to replace the nulls by `NaN`.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use arrow_array::{ArrayRef, Float64Array, RecordBatch};
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let x: ArrayRef = Arc::new(Float64Array::from(vec![5.0, 50.0]));
+//! let y: ArrayRef = Arc::new(Float64Array::from(vec![5.0, 50.0]));
+//! let batch = RecordBatch::try_from_iter([("x", x), ("y", y)]).unwrap();
+//! let scores = forest.score_record_batch(&batch).unwrap();
+//! assert!(scores.value(1) > scores.value(0));
+//! ```
+
+use std::borrow::Cow;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float32Type, Float64Type};
+use arrow_array::{Array, ArrowNativeTypeOp, ArrowPrimitiveType, Float64Array, RecordBatch};
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{Error, Forest};
+
+/// Floats stored in Arrow arrays of the primitive type `Arrow`.
+pub trait ArrowFloat: Float + ArrowNativeTypeOp {
+    type Arrow: ArrowPrimitiveType<Native = Self>;
+}
+
+impl ArrowFloat for f32 {
+    type Arrow = Float32Type;
+}
+
+impl ArrowFloat for f64 {
+    type Arrow = Float64Type;
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: ArrowFloat,
+    L: LeafModel<T, N>,
+{
+    /// Score every row of the batch, see the [module documentation](self). The batch must have
+    /// one column per feature, all of them `Float64` for forests of `f64` or `Float32` for
+    /// forests of `f32`. Nulls are scored as missing values.
+    ///
+    /// Fails with [`Error::DimensionMismatch`] unless there are `N` columns and with
+    /// [`Error::UnsupportedColumnType`] for columns of other types.
+    pub fn score_record_batch(&self, batch: &RecordBatch) -> Result<Float64Array, Error> {
+        if batch.num_columns() != N {
+            return Err(Error::DimensionMismatch {
+                expected: N,
+                actual: batch.num_columns(),
+            });
+        }
+        let columns = batch
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(index, column)| float_values(index, column.as_ref()))
+            .collect::<Result<Vec<Cow<[T]>>, Error>>()?;
+        let columns: Vec<&[T]> = columns.iter().map(|column| column.as_ref()).collect();
+        Ok(Float64Array::from(self.score_column_slices(&columns)?))
+    }
+}
+
+/// The values of the column with index `index`, with nulls replaced by `NaN`.
+pub(crate) fn float_values<T: ArrowFloat>(
+    index: usize,
+    column: &dyn Array,
+) -> Result<Cow<'_, [T]>, Error> {
+    let column = column
+        .as_primitive_opt::<T::Arrow>()
+        .ok_or(Error::UnsupportedColumnType { index })?;
+    Ok(if column.null_count() == 0 {
+        Cow::Borrowed(column.values().as_ref())
+    } else {
+        Cow::Owned(
+            column
+                .iter()
+                .map(|value| value.unwrap_or_else(T::nan))
+                .collect(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Array, ArrayRef, Float32Array, Float64Array, Int32Array, RecordBatch};
+
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn score_record_batches() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 64,
+            seed: Some(23),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+
+        let x: ArrayRef = Arc::new(Float64Array::from(vec![Some(3.0), Some(40.0), None]));
+        let y: ArrayRef = Arc::new(Float64Array::from(vec![4.0, 40.0, 5.0]));
+        let batch = RecordBatch::try_from_iter([("x", x), ("y", y.clone())]).unwrap();
+        let scores = forest.score_record_batch(&batch).unwrap();
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores.null_count(), 0);
+        assert_eq!(scores.value(0), forest.score(&[3.0, 4.0]));
+        assert_eq!(scores.value(1), forest.score(&[40.0, 40.0]));
+        assert_eq!(scores.value(2), forest.score(&[f64::NAN, 5.0]));
+
+        let batch = RecordBatch::try_from_iter([("y", y.clone())]).unwrap();
+        assert!(matches!(
+            forest.score_record_batch(&batch),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        let narrow: ArrayRef = Arc::new(Float32Array::from(vec![4.0, 40.0, 5.0]));
+        let integers: ArrayRef = Arc::new(Int32Array::from(vec![4, 40, 5]));
+        for column in [narrow, integers] {
+            let batch = RecordBatch::try_from_iter([("y", y.clone()), ("z", column)]).unwrap();
+            assert!(matches!(
+                forest.score_record_batch(&batch),
+                Err(Error::UnsupportedColumnType { index: 1 })
+            ));
+        }
+    }
+}
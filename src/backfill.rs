@@ -0,0 +1,333 @@
+//! Scoring large files in chunks, resuming after interruptions.
+//!
+//! [`Forest::score_file`] reads samples from a file, writes one score per sample to another file
+//! and records its progress in a checkpoint next to the output after every chunk. Started again
+//! after a crash or a deployment, it continues after the last completed chunk instead of from
+//! the first row, so backfills over billions of historical rows do not start over.
+//!
+//! ```rust
+//! use extended_isolation_forest::backfill::FileFormat;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let dir = std::env::temp_dir().join(format!("eif-backfill-doc-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir).unwrap();
+//! std::fs::write(dir.join("input.csv"), "x,y\n1,2\n3,4\n50,50\n").unwrap();
+//!
+//! let format = FileFormat::Csv { header: true };
+//! let report = forest
+//!     .score_file(dir.join("input.csv"), dir.join("scores.csv"), format, 2)
+//!     .unwrap();
+//! assert_eq!((report.rows, report.resumed_at), (3, 0));
+//! let scores = std::fs::read_to_string(dir.join("scores.csv")).unwrap();
+//! assert_eq!(scores.lines().count(), 4);
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{Error, Forest};
+
+/// The format of the input file of [`Forest::score_file`], one sample per line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileFormat {
+    /// Comma-separated numbers, empty fields are missing values. With `header` the first line
+    /// is skipped and the output starts with the header `score`.
+    Csv { header: bool },
+
+    /// A JSON array of numbers per line. Only available with the `json` feature.
+    #[cfg(feature = "json")]
+    JsonLines,
+}
+
+/// Result of [`Forest::score_file`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BackfillReport {
+    /// Number of scored samples in the output.
+    pub rows: u64,
+
+    /// Number of samples which had already been scored before this call.
+    pub resumed_at: u64,
+}
+
+/// Progress of an interrupted [`Forest::score_file`], stored next to the output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    /// Number of scored samples.
+    pub rows: u64,
+
+    /// Bytes of the input which have been read.
+    pub input_offset: u64,
+
+    /// Bytes of the output which have been written.
+    pub output_len: u64,
+}
+
+impl Checkpoint {
+    /// The path of the checkpoint of the output file.
+    pub fn path(output: &Path) -> PathBuf {
+        let mut name = output.file_name().unwrap_or_default().to_os_string();
+        name.push(".checkpoint");
+        output.with_file_name(name)
+    }
+
+    /// Read the checkpoint of the output file, `None` if there is none because scoring has
+    /// not been started or has completed.
+    pub fn read(output: &Path) -> Result<Option<Self>, Error> {
+        let text = match std::fs::read_to_string(Self::path(output)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::Io(err)),
+        };
+        let numbers: Vec<u64> = text
+            .split_whitespace()
+            .map(|number| number.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| Error::Deserialization(format!("invalid checkpoint {:?}", text)))?;
+        match numbers[..] {
+            [rows, input_offset, output_len] => Ok(Some(Self {
+                rows,
+                input_offset,
+                output_len,
+            })),
+            _ => Err(Error::Deserialization(format!(
+                "invalid checkpoint {:?}",
+                text
+            ))),
+        }
+    }
+
+    /// Replace the checkpoint, so a crash while writing leaves the previous one.
+    fn write(&self, output: &Path) -> Result<(), Error> {
+        let path = Self::path(output);
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+        std::fs::write(
+            &temporary,
+            format!("{} {} {}\n", self.rows, self.input_offset, self.output_len),
+        )
+        .map_err(Error::Io)?;
+        std::fs::rename(&temporary, &path).map_err(Error::Io)
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float + Sync,
+    L: LeafModel<T, N> + Sync,
+{
+    /// Score every sample of the input file and write the scores to the output file, one per
+    /// line in the order of the samples.
+    ///
+    /// The input is processed in chunks of `chunk_size` samples, scored with
+    /// [`Forest::score_slice`]. After every chunk the output is synced to disk and a
+    /// [`Checkpoint`] is written. If a checkpoint exists when this is called, the output is
+    /// truncated to the end of the last completed chunk and scoring resumes after it, the input
+    /// must not have changed. The checkpoint is removed once all samples have been scored.
+    pub fn score_file<P, Q>(
+        &self,
+        input: P,
+        output: Q,
+        format: FileFormat,
+        chunk_size: usize,
+    ) -> Result<BackfillReport, Error>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let output = output.as_ref();
+        let chunk_size = chunk_size.max(1);
+        let mut reader = BufReader::new(File::open(input).map_err(Error::Io)?);
+        let mut writer = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(output)
+            .map_err(Error::Io)?;
+
+        let mut checkpoint = match Checkpoint::read(output)? {
+            Some(checkpoint) => {
+                reader
+                    .seek(SeekFrom::Start(checkpoint.input_offset))
+                    .map_err(Error::Io)?;
+                checkpoint
+            }
+            None => {
+                let mut checkpoint = Checkpoint {
+                    rows: 0,
+                    input_offset: 0,
+                    output_len: 0,
+                };
+                if format == (FileFormat::Csv { header: true }) {
+                    let mut line = String::new();
+                    checkpoint.input_offset =
+                        reader.read_line(&mut line).map_err(Error::Io)? as u64;
+                    writer.set_len(0).map_err(Error::Io)?;
+                    writer.write_all(b"score\n").map_err(Error::Io)?;
+                    checkpoint.output_len = 6;
+                }
+                checkpoint
+            }
+        };
+        let resumed_at = checkpoint.rows;
+        writer.set_len(checkpoint.output_len).map_err(Error::Io)?;
+        writer
+            .seek(SeekFrom::Start(checkpoint.output_len))
+            .map_err(Error::Io)?;
+
+        let mut line = String::new();
+        let mut samples = Vec::with_capacity(chunk_size);
+        loop {
+            samples.clear();
+            let mut read = 0;
+            while samples.len() < chunk_size {
+                line.clear();
+                let bytes = reader.read_line(&mut line).map_err(Error::Io)?;
+                if bytes == 0 {
+                    break;
+                }
+                read += bytes as u64;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let row = checkpoint.rows + samples.len() as u64 + 1;
+                samples.push(
+                    parse_sample::<T, N>(line.trim(), format).map_err(|message| {
+                        Error::Deserialization(format!("sample {}: {}", row, message))
+                    })?,
+                );
+            }
+            if read == 0 {
+                break;
+            }
+
+            let mut text = String::new();
+            for score in self.score_slice(&samples) {
+                text.push_str(&score.to_string());
+                text.push('\n');
+            }
+            let mut buffered = BufWriter::new(&mut writer);
+            buffered.write_all(text.as_bytes()).map_err(Error::Io)?;
+            buffered.flush().map_err(Error::Io)?;
+            drop(buffered);
+            writer.sync_data().map_err(Error::Io)?;
+
+            checkpoint.rows += samples.len() as u64;
+            checkpoint.input_offset += read;
+            checkpoint.output_len += text.len() as u64;
+            checkpoint.write(output)?;
+
+            #[cfg(feature = "tracing")]
+            tracing::info!(rows = checkpoint.rows, "scored chunk");
+        }
+
+        match std::fs::remove_file(Checkpoint::path(output)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(Error::Io(err)),
+            _ => {}
+        }
+        Ok(BackfillReport {
+            rows: checkpoint.rows,
+            resumed_at,
+        })
+    }
+}
+
+fn parse_sample<T: Float, const N: usize>(
+    line: &str,
+    format: FileFormat,
+) -> Result<[T; N], String> {
+    let values: Vec<f64> = match format {
+        FileFormat::Csv { .. } => line
+            .split(',')
+            .map(|field| match field.trim() {
+                "" => Ok(f64::NAN),
+                field => field
+                    .parse()
+                    .map_err(|_| format!("invalid number {:?}", field)),
+            })
+            .collect::<Result<_, _>>()?,
+        #[cfg(feature = "json")]
+        FileFormat::JsonLines => serde_json::from_str::<Vec<Option<f64>>>(line)
+            .map_err(|err| err.to_string())?
+            .into_iter()
+            .map(|value| value.unwrap_or(f64::NAN))
+            .collect(),
+    };
+    if values.len() != N {
+        return Err(format!("expected {} features, but got {}", N, values.len()));
+    }
+    Ok(std::array::from_fn(|i| {
+        T::from(values[i]).unwrap_or_else(T::nan)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checkpoint, FileFormat};
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn resume_after_interruption() {
+        let dir = std::env::temp_dir().join(format!("eif-backfill-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("input.csv"), dir.join("scores.csv"));
+        let lines: Vec<String> = (0..100)
+            .map(|i| {
+                format!(
+                    "{},{}",
+                    i % 10,
+                    if i == 7 {
+                        String::new()
+                    } else {
+                        (i / 10).to_string()
+                    }
+                )
+            })
+            .collect();
+        std::fs::write(&input, format!("a,b\n{}\n", lines.join("\n"))).unwrap();
+
+        let values: Vec<_> = (0..100)
+            .map(|i| [(i % 10) as f64, (i / 10) as f64])
+            .collect();
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+        let format = FileFormat::Csv { header: true };
+        let report = forest.score_file(&input, &output, format, 30).unwrap();
+        assert_eq!((report.rows, report.resumed_at), (100, 0));
+        assert_eq!(Checkpoint::read(&output).unwrap(), None);
+        let complete = std::fs::read_to_string(&output).unwrap();
+        let scores: Vec<&str> = complete.lines().collect();
+        assert_eq!(scores.len(), 101);
+        assert_eq!(scores[3], forest.score(&[2.0, 0.0]).to_string());
+        assert_eq!(scores[8], forest.score(&[6.0, f64::NAN]).to_string());
+
+        // interrupted after the first chunk, with a partially written second chunk
+        let header = "a,b\n".len() as u64;
+        let first_chunk: u64 = lines[..30].iter().map(|line| line.len() as u64 + 1).sum();
+        let output_len: u64 = scores[..31].iter().map(|line| line.len() as u64 + 1).sum();
+        std::fs::write(&output, &complete[..output_len as usize + 5]).unwrap();
+        std::fs::write(
+            Checkpoint::path(&output),
+            format!("30 {} {}", header + first_chunk, output_len),
+        )
+        .unwrap();
+        let report = forest.score_file(&input, &output, format, 30).unwrap();
+        assert_eq!((report.rows, report.resumed_at), (100, 30));
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), complete);
+
+        std::fs::write(&input, "1,2,3\n").unwrap();
+        let format = FileFormat::Csv { header: false };
+        assert!(matches!(
+            forest.score_file(&input, &output, format, 30),
+            Err(Error::Deserialization(message)) if message.starts_with("sample 1:")
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! Low-level primitives for benchmarking the traversal of trees separately from the aggregation
+//! of the path lengths into a score.
+//!
+//! Only available with the `bench` feature. This module is meant for performance work and is not
+//! covered by the semver guarantees of the crate.
+
+use num_traits::Float;
+
+use crate::{determinate_direction, Direction, Forest, Node};
+
+/// A tree stored as a vector of nodes in depth-first order. The root is the first node.
+#[derive(Clone, Debug)]
+pub struct FlatTree<T, const N: usize> {
+    pub nodes: Vec<FlatNode<T, N>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum FlatNode<T, const N: usize> {
+    Internal {
+        n: [T; N],
+        p: [T; N],
+
+        /// Index of the left child in `FlatTree::nodes`.
+        left: usize,
+
+        /// Index of the right child in `FlatTree::nodes`.
+        right: usize,
+
+        /// Number of merged internal nodes above this node, see `Forest::canonicalize`.
+        depth_offset: usize,
+    },
+    External {
+        num_samples: usize,
+        depth_offset: usize,
+    },
+}
+
+impl<T, const N: usize> FlatNode<T, N> {
+    pub fn depth_offset(&self) -> usize {
+        match self {
+            Self::Internal { depth_offset, .. } | Self::External { depth_offset, .. } => {
+                *depth_offset
+            }
+        }
+    }
+}
+
+/// Copy the trees of the forest into their flat representation.
+pub fn flatten<T, const N: usize>(forest: &Forest<T, N>) -> Vec<FlatTree<T, N>>
+where
+    T: Float,
+{
+    forest
+        .trees
+        .iter()
+        .map(|tree| {
+            let mut flat_tree = FlatTree { nodes: vec![] };
+            push_tree(&mut flat_tree, &tree.root);
+            flat_tree
+        })
+        .collect()
+}
+
+/// Appends the nodes of the tree in depth-first order, using an explicit stack so deep trees
+/// can not overflow the call stack.
+fn push_tree<T, const N: usize>(flat_tree: &mut FlatTree<T, N>, root: &Node<T, N>)
+where
+    T: Float,
+{
+    // the nodes with the index of their parent, whose child index is set when they are pushed
+    let mut stack: Vec<(&Node<T, N>, Option<usize>)> = vec![(root, None)];
+    while let Some((node, parent)) = stack.pop() {
+        let index = flat_tree.nodes.len();
+        if let Some(FlatNode::Internal { left, right, .. }) =
+            parent.map(|parent| &mut flat_tree.nodes[parent])
+        {
+            // the left child is pushed first and replaces the placeholder of the parent
+            if *left == usize::MAX {
+                *left = index;
+            } else {
+                *right = index;
+            }
+        }
+        match node {
+            Node::Ex(ex_node) => flat_tree.nodes.push(FlatNode::External {
+                num_samples: ex_node.num_samples,
+                depth_offset: ex_node.depth_offset,
+            }),
+            Node::In(in_node) => {
+                flat_tree.nodes.push(FlatNode::Internal {
+                    n: in_node.n,
+                    p: in_node.p,
+                    left: usize::MAX,
+                    right: usize::MAX,
+                    depth_offset: in_node.depth_offset,
+                });
+                stack.push((&in_node.right, Some(index)));
+                stack.push((&in_node.left, Some(index)));
+            }
+        }
+    }
+}
+
+/// Traverse a flat tree. Returns the index of the reached external node and its depth,
+/// including the depth offsets.
+pub fn traverse_flat<T, const N: usize>(
+    flat_tree: &FlatTree<T, N>,
+    values: &[T; N],
+) -> (usize, usize)
+where
+    T: Float,
+{
+    let mut index = 0;
+    let mut depth = flat_tree.nodes[index].depth_offset();
+    while let FlatNode::Internal {
+        n, p, left, right, ..
+    } = &flat_tree.nodes[index]
+    {
+        index = match determinate_direction(values, n, p) {
+            Direction::Left => *left,
+            Direction::Right => *right,
+        };
+        depth += 1 + flat_tree.nodes[index].depth_offset();
+    }
+    (index, depth)
+}
+
+/// Path length of the sample in a flat tree, including the correction for the samples in the
+/// reached external node.
+pub fn flat_path_length<T, const N: usize>(flat_tree: &FlatTree<T, N>, values: &[T; N]) -> f64
+where
+    T: Float,
+{
+    let (index, depth) = traverse_flat(flat_tree, values);
+    match flat_tree.nodes[index] {
+        FlatNode::External { num_samples, .. } => depth as f64 + crate::c_factor(num_samples),
+        FlatNode::Internal { .. } => unreachable!(),
+    }
+}
+
+/// Uncapped path lengths of the sample in all trees of the forest, without aggregating them.
+pub fn path_lengths<T, const N: usize>(forest: &Forest<T, N>, values: &[T; N]) -> Vec<f64>
+where
+    T: Float,
+{
+    forest
+        .trees
+        .iter()
+        .map(|tree| tree.path_length_with_cap(values, usize::MAX))
+        .collect()
+}
+
+/// Aggregate path lengths of all trees into the anomaly score.
+pub fn score_from_path_lengths<T, const N: usize>(
+    forest: &Forest<T, N>,
+    path_lengths: &[f64],
+) -> f64
+where
+    T: Float,
+{
+    let eh = path_lengths.iter().sum::<f64>() / path_lengths.len() as f64;
+    crate::normalize_path_length(eh, forest.avg_path_length_c)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::{flat_path_length, flatten, path_lengths, score_from_path_lengths};
+    use crate::{ExtensionLevel, Forest, ForestOptions};
+
+    #[test]
+    fn flat_traversal_matches_forest() {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let values: Vec<_> = (0..500)
+            .map(|_| [rng.sample(distribution), rng.sample(distribution)])
+            .collect();
+        let forest: Forest<f64, 2> = Forest::from_slice(
+            values.as_slice(),
+            &ForestOptions {
+                n_trees: 10,
+                sample_size: 100,
+                max_tree_depth: None,
+                extension_level: ExtensionLevel::Fixed(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let flat_trees = flatten(&forest);
+        for sample in [[0.0, 0.0], [3.0, -2.0], [12.0, 8.0]] {
+            let flat: Vec<_> = flat_trees
+                .iter()
+                .map(|flat_tree| flat_path_length(flat_tree, &sample))
+                .collect();
+            assert_eq!(flat, path_lengths(&forest, &sample));
+            assert!(
+                (score_from_path_lengths(&forest, &flat) - forest.score(&sample)).abs() < 1e-12
+            );
+        }
+    }
+}
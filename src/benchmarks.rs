@@ -0,0 +1,247 @@
+//! Evaluating options on labeled anomaly detection benchmarks, available with the `benchmarks`
+//! feature.
+//!
+//! The datasets are not bundled with the crate. Benchmarks in the style of
+//! [ODDS](https://odds.cs.stonybrook.edu/) are loaded from CSV files with one sample per line,
+//! the `N` features followed by the label, `1` marking anomalies. Every configuration of
+//! options is trained on the complete dataset and scores all of its samples, the table reports
+//! the ROC AUC of the scores averaged over the repetitions.
+//!
+//! ```rust,no_run
+//! use extended_isolation_forest::benchmarks::{run, Dataset};
+//! use extended_isolation_forest::{ExtensionLevel, ForestOptions};
+//!
+//! let datasets = vec![
+//!     Dataset::<6>::from_csv("thyroid", "data/thyroid.csv").unwrap(),
+//!     Dataset::<6>::from_csv("mammography", "data/mammography.csv").unwrap(),
+//! ];
+//! let configurations = [
+//!     ("default", ForestOptions::default()),
+//!     (
+//!         "axis-parallel",
+//!         ForestOptions {
+//!             extension_level: ExtensionLevel::Fixed(0),
+//!             ..Default::default()
+//!         },
+//!     ),
+//! ];
+//! let table = run(&datasets, &configurations, 5).unwrap();
+//! println!("{}", table);
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use crate::eval::roc_auc;
+use crate::{Error, Forest, ForestOptions};
+
+/// A labeled benchmark dataset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset<const N: usize> {
+    name: String,
+    samples: Vec<[f64; N]>,
+    labels: Vec<bool>,
+}
+
+impl<const N: usize> Dataset<N> {
+    /// A dataset from samples and their labels, `true` marking anomalies.
+    pub fn new(name: &str, samples: Vec<[f64; N]>, labels: Vec<bool>) -> Result<Self, Error> {
+        if samples.len() != labels.len() {
+            return Err(Error::LabelCountMismatch {
+                expected: samples.len(),
+                actual: labels.len(),
+            });
+        }
+        Ok(Self {
+            name: name.to_string(),
+            samples,
+            labels,
+        })
+    }
+
+    /// Load a dataset from a CSV file with the features of a sample followed by its label on
+    /// every line. A header line is skipped, empty lines are ignored.
+    pub fn from_csv<P: AsRef<Path>>(name: &str, path: P) -> Result<Self, Error> {
+        let text = std::fs::read_to_string(path).map_err(Error::Io)?;
+        Self::parse_csv(name, &text)
+    }
+
+    /// Parse a dataset in the format read by [`Dataset::from_csv`].
+    pub fn parse_csv(name: &str, text: &str) -> Result<Self, Error> {
+        let mut samples = Vec::new();
+        let mut labels = Vec::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != N + 1 {
+                return Err(Error::DimensionMismatch {
+                    expected: N + 1,
+                    actual: fields.len(),
+                });
+            }
+            let values: Result<Vec<f64>, _> = fields.iter().map(|field| field.parse()).collect();
+            let values = match values {
+                Ok(values) => values,
+                // a header has to come first and can not consist of numbers only
+                Err(_) if samples.is_empty() && line_index == 0 => continue,
+                Err(err) => {
+                    return Err(Error::Deserialization(format!(
+                        "line {} of {}: {}",
+                        line_index + 1,
+                        name,
+                        err
+                    )))
+                }
+            };
+            let mut sample = [0.0; N];
+            sample.copy_from_slice(&values[..N]);
+            samples.push(sample);
+            labels.push(values[N] != 0.0);
+        }
+        Self::new(name, samples, labels)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn samples(&self) -> &[[f64; N]] {
+        &self.samples
+    }
+
+    pub fn labels(&self) -> &[bool] {
+        &self.labels
+    }
+
+    /// Fraction of anomalies in the dataset.
+    pub fn contamination(&self) -> f64 {
+        let anomalies = self.labels.iter().filter(|label| **label).count();
+        anomalies as f64 / self.labels.len().max(1) as f64
+    }
+}
+
+/// Mean and standard deviation of the ROC AUC over the repetitions of a benchmark.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Auc {
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+/// The ROC AUC of every configuration on every dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AucTable {
+    /// The names of the configurations, in the order of the columns.
+    pub configurations: Vec<String>,
+
+    /// Per dataset its name and the AUC of every configuration.
+    pub rows: Vec<(String, Vec<Auc>)>,
+}
+
+/// The table in Markdown syntax.
+impl fmt::Display for AucTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "| dataset |")?;
+        for configuration in &self.configurations {
+            write!(f, " {} |", configuration)?;
+        }
+        write!(f, "\n|---|")?;
+        for _ in &self.configurations {
+            write!(f, "---|")?;
+        }
+        for (dataset, aucs) in &self.rows {
+            write!(f, "\n| {} |", dataset)?;
+            for auc in aucs {
+                write!(f, " {:.3} ± {:.3} |", auc.mean, auc.std_dev)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Benchmark every configuration on every dataset, training `repetitions` forests per
+/// configuration and dataset.
+///
+/// The AUC is `NaN` for datasets without anomalies or without normal samples.
+pub fn run<const N: usize>(
+    datasets: &[Dataset<N>],
+    configurations: &[(&str, ForestOptions)],
+    repetitions: usize,
+) -> Result<AucTable, Error> {
+    let mut table = AucTable {
+        configurations: configurations
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        rows: Vec::with_capacity(datasets.len()),
+    };
+    for dataset in datasets {
+        let mut aucs = Vec::with_capacity(configurations.len());
+        for (_, options) in configurations {
+            let mut values = Vec::with_capacity(repetitions);
+            for _ in 0..repetitions {
+                let forest: Forest<f64, N> = Forest::from_slice(&dataset.samples, options)?;
+                let scores: Vec<f64> = dataset
+                    .samples
+                    .iter()
+                    .map(|sample| forest.score(sample))
+                    .collect();
+                values.push(roc_auc(&scores, &dataset.labels).unwrap_or(f64::NAN));
+            }
+            let count = values.len().max(1) as f64;
+            let mean = values.iter().sum::<f64>() / count;
+            let variance = values.iter().map(|auc| (auc - mean).powi(2)).sum::<f64>() / count;
+            aucs.push(Auc {
+                mean,
+                std_dev: variance.sqrt(),
+            });
+        }
+        table.rows.push((dataset.name.clone(), aucs));
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Dataset};
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn benchmark_configurations() {
+        let mut csv = String::from("x,y,label\n");
+        for i in 0..400 {
+            csv.push_str(&format!("{},{},0\n", i % 20, i / 20));
+        }
+        csv.push_str("\n100,100,1\n-100,-100,1\n");
+        let dataset = Dataset::<2>::parse_csv("grid", &csv).unwrap();
+        assert_eq!(dataset.samples().len(), 402);
+        assert!((dataset.contamination() - 2.0 / 402.0).abs() < 1e-12);
+
+        let small = ForestOptions {
+            sample_size: 64,
+            ..Default::default()
+        };
+        let table = run(
+            &[dataset],
+            &[("default", ForestOptions::default()), ("small", small)],
+            2,
+        )
+        .unwrap();
+        assert_eq!(table.rows.len(), 1);
+        assert!(table.rows[0].1.iter().all(|auc| auc.mean > 0.9));
+        let markdown = table.to_string();
+        assert!(markdown.starts_with("| dataset | default | small |"));
+        assert!(markdown.contains("| grid |"));
+
+        assert!(matches!(
+            Dataset::<3>::parse_csv("grid", &csv),
+            Err(Error::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            Dataset::<2>::parse_csv("broken", "1,2,0\n1,x,0\n"),
+            Err(Error::Deserialization(_))
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+//! The gRPC service of `eif-serve --grpc`, available with the `grpc` feature.
+//!
+//! Implements the service `eif.v1.ExtendedIsolationForest` of `proto/eif.proto` for forests of
+//! `N` dimensions. Models are kept in memory by their name, the model given with `--model` is
+//! stored as `default`. Forests are trained on the threads of the blocking pool of the runtime.
+//!
+//! Like the HTTP server, messages larger than 16 MiB are rejected. Forests of more than
+//! 10,000 trees or a sample size above 65,536 are not trained.
+
+use std::collections::BTreeMap;
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use extended_isolation_forest::{Error, ExtensionLevel, Forest, ForestOptions};
+use tonic::{Request, Response, Status};
+
+use proto::extended_isolation_forest_server::{
+    ExtendedIsolationForest, ExtendedIsolationForestServer,
+};
+use proto::{
+    DeleteModelRequest, DeleteModelResponse, ExplainRequest, ExplainResponse, ListModelsRequest,
+    ListModelsResponse, Sample, ScoreRequest, ScoreResponse, TrainRequest, TrainResponse,
+};
+
+mod proto {
+    tonic::include_proto!("eif.v1");
+}
+
+/// The name of the model loaded from `--model`.
+const DEFAULT_MODEL: &str = "default";
+
+/// Forests of more trees are not trained.
+const MAX_TREES: usize = 10_000;
+
+/// Forests of a larger sample size are not trained.
+const MAX_SAMPLE_SIZE: usize = 1 << 16;
+
+pub fn serve<const N: usize>(address: &str, model: Option<&Path>) -> Result<(), Error> {
+    let service = Service::<N>::default();
+    if let Some(path) = model {
+        let forest = super::load::<N>(path)?;
+        service.insert(DEFAULT_MODEL.to_string(), forest);
+    }
+    let address = address
+        .to_socket_addrs()
+        .map_err(Error::Io)?
+        .next()
+        .ok_or_else(|| Error::Io(std::io::Error::other(format!("no address {}", address))))?;
+    eprintln!(
+        "eif-serve: serving gRPC with {} dimensions on {}",
+        N, address
+    );
+    let runtime = tokio::runtime::Runtime::new().map_err(Error::Io)?;
+    runtime
+        .block_on(
+            tonic::transport::Server::builder()
+                .add_service(
+                    ExtendedIsolationForestServer::new(service)
+                        .max_decoding_message_size(super::MAX_BODY_BYTES),
+                )
+                .serve(address),
+        )
+        .map_err(|err| Error::Io(std::io::Error::other(err)))
+}
+
+#[derive(Default)]
+struct Service<const N: usize> {
+    models: RwLock<BTreeMap<String, Arc<Forest<f64, N>>>>,
+}
+
+impl<const N: usize> Service<N> {
+    fn insert(&self, name: String, forest: Forest<f64, N>) {
+        self.write().insert(name, Arc::new(forest));
+    }
+
+    fn model(&self, name: &str) -> Result<Arc<Forest<f64, N>>, Status> {
+        self.read()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("unknown model \"{}\"", name)))
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, Arc<Forest<f64, N>>>> {
+        // the map is never left in an inconsistent state, so poisoning can be ignored
+        self.models
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, Arc<Forest<f64, N>>>> {
+        self.models
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[tonic::async_trait]
+impl<const N: usize> ExtendedIsolationForest for Service<N> {
+    async fn train(
+        &self,
+        request: Request<TrainRequest>,
+    ) -> Result<Response<TrainResponse>, Status> {
+        let request = request.into_inner();
+        if request.model.is_empty() {
+            return Err(Status::invalid_argument("missing model name"));
+        }
+        let options = options(&request)?;
+        let samples = samples::<N>(request.samples)?;
+        let forest = tokio::task::spawn_blocking(move || Forest::from_slice(&samples, &options))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        self.insert(request.model, forest);
+        Ok(Response::new(TrainResponse {}))
+    }
+
+    async fn score(
+        &self,
+        request: Request<ScoreRequest>,
+    ) -> Result<Response<ScoreResponse>, Status> {
+        let request = request.into_inner();
+        let forest = self.model(&request.model)?;
+        let samples = samples::<N>(request.samples)?;
+        let scores = samples.iter().map(|sample| forest.score(sample)).collect();
+        Ok(Response::new(ScoreResponse { scores }))
+    }
+
+    async fn explain(
+        &self,
+        request: Request<ExplainRequest>,
+    ) -> Result<Response<ExplainResponse>, Status> {
+        let request = request.into_inner();
+        let forest = self.model(&request.model)?;
+        let samples = samples::<N>(request.samples)?;
+        let explanations = forest
+            .explain_slice(&samples)
+            .into_iter()
+            .map(|explanation| proto::Explanation {
+                score: explanation.score,
+                contributions: explanation.contributions.to_vec(),
+                path_lengths: explanation.path_lengths,
+            })
+            .collect();
+        Ok(Response::new(ExplainResponse { explanations }))
+    }
+
+    async fn list_models(
+        &self,
+        _request: Request<ListModelsRequest>,
+    ) -> Result<Response<ListModelsResponse>, Status> {
+        let models = self.read().keys().cloned().collect();
+        Ok(Response::new(ListModelsResponse { models }))
+    }
+
+    async fn delete_model(
+        &self,
+        request: Request<DeleteModelRequest>,
+    ) -> Result<Response<DeleteModelResponse>, Status> {
+        let name = request.into_inner().model;
+        match self.write().remove(&name) {
+            Some(_) => Ok(Response::new(DeleteModelResponse {})),
+            None => Err(Status::not_found(format!("unknown model \"{}\"", name))),
+        }
+    }
+}
+
+/// The options of the request, unset options keep the defaults of `ForestOptions`.
+fn options(request: &TrainRequest) -> Result<ForestOptions, Status> {
+    let defaults = ForestOptions::default();
+    let n_trees = request.n_trees.map_or(defaults.n_trees, |n| n as usize);
+    if n_trees > MAX_TREES {
+        return Err(Status::invalid_argument(format!(
+            "at most {} trees are trained, but got {}",
+            MAX_TREES, n_trees
+        )));
+    }
+    let sample_size = request
+        .sample_size
+        .map_or(defaults.sample_size, |n| n as usize);
+    if sample_size > MAX_SAMPLE_SIZE {
+        return Err(Status::invalid_argument(format!(
+            "the sample size is at most {}, but got {}",
+            MAX_SAMPLE_SIZE, sample_size
+        )));
+    }
+    Ok(ForestOptions {
+        n_trees,
+        sample_size,
+        extension_level: request
+            .extension_level
+            .map_or(defaults.extension_level, |level| {
+                ExtensionLevel::Fixed(level as usize)
+            }),
+        seed: request.seed,
+        ..defaults
+    })
+}
+
+fn samples<const N: usize>(samples: Vec<Sample>) -> Result<Vec<[f64; N]>, Status> {
+    samples
+        .into_iter()
+        .enumerate()
+        .map(|(index, sample)| {
+            let len = sample.values.len();
+            sample.values.try_into().map_err(|_| {
+                Status::invalid_argument(format!(
+                    "sample {} has {} features instead of {}",
+                    index, len, N
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::{Code, Request};
+
+    use super::proto::extended_isolation_forest_server::ExtendedIsolationForest;
+    use super::proto::{
+        DeleteModelRequest, ExplainRequest, ListModelsRequest, Sample, ScoreRequest, TrainRequest,
+    };
+    use super::Service;
+
+    fn samples(values: &[[f64; 2]]) -> Vec<Sample> {
+        values
+            .iter()
+            .map(|values| Sample {
+                values: values.to_vec(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn train_score_and_explain() {
+        let service = Service::<2>::default();
+        let training: Vec<[f64; 2]> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let train = TrainRequest {
+            model: "traffic".to_string(),
+            samples: samples(&training),
+            n_trees: Some(50),
+            sample_size: Some(64),
+            extension_level: None,
+            seed: Some(7),
+        };
+        service.train(Request::new(train.clone())).await.unwrap();
+
+        let scores = service
+            .score(Request::new(ScoreRequest {
+                model: "traffic".to_string(),
+                samples: samples(&[[10.0, 10.0], [200.0, 200.0]]),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .scores;
+        assert_eq!(scores.len(), 2);
+        assert!(scores[1] > scores[0]);
+
+        let explanations = service
+            .explain(Request::new(ExplainRequest {
+                model: "traffic".to_string(),
+                samples: samples(&[[200.0, 200.0]]),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .explanations;
+        assert_eq!(explanations[0].score, scores[1]);
+        assert_eq!(explanations[0].contributions.len(), 2);
+        assert_eq!(explanations[0].path_lengths.len(), 50);
+
+        let wrong = ScoreRequest {
+            model: "traffic".to_string(),
+            samples: vec![Sample { values: vec![1.0] }],
+        };
+        let status = service.score(Request::new(wrong)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "sample 0 has 1 features instead of 2");
+        let large = TrainRequest {
+            n_trees: Some(10_001),
+            ..train.clone()
+        };
+        let status = service.train(Request::new(large)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "at most 10000 trees are trained, but got 10001"
+        );
+        let large = TrainRequest {
+            sample_size: Some(u32::MAX),
+            ..train.clone()
+        };
+        let status = service.train(Request::new(large)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let empty = TrainRequest {
+            samples: Vec::new(),
+            ..train
+        };
+        let status = service.train(Request::new(empty)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let models = service
+            .list_models(Request::new(ListModelsRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .models;
+        assert_eq!(models, ["traffic"]);
+        let delete = DeleteModelRequest {
+            model: "traffic".to_string(),
+        };
+        service
+            .delete_model(Request::new(delete.clone()))
+            .await
+            .unwrap();
+        let status = service
+            .delete_model(Request::new(delete))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        let scores = ScoreRequest {
+            model: "traffic".to_string(),
+            samples: Vec::new(),
+        };
+        let status = service.score(Request::new(scores)).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+}
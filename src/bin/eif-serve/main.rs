@@ -0,0 +1,654 @@
+//! Scoring forests over HTTP or gRPC.
+//!
+//! ```text
+//! eif-serve --http 127.0.0.1:8080 --model forest.json --dimensions 3 [--spec spec.json]
+//!     [--probes probes.json] [--workers 8]
+//! eif-serve --grpc 127.0.0.1:50051 --dimensions 3 [--model forest.json]
+//! ```
+//!
+//! Serves a forest stored as JSON or in the binary format of `Forest::write_binary`, the
+//! dimensions of binary forests are read from the file. Both endpoints take a JSON array of
+//! samples, every sample being an array of numbers. With a `FeatureSpec` stored as JSON the
+//! samples may also be objects with the columns of the spec as keys, and its types, defaults
+//! and transforms are applied:
+//!
+//! * `POST /score` returns `{"scores": [...]}`.
+//! * `POST /explain` returns the explanations of `Forest::explain` as an array.
+//! * `GET /health` returns `ok`.
+//!
+//! Requests are answered by a fixed number of worker threads, by default one per CPU, and
+//! connections which wait for a worker queue up in the backlog of the listener. Connections
+//! sending or receiving nothing for 30 seconds are closed, request heads larger than 16 KiB and
+//! bodies larger than 16 MiB are rejected. Bodies are read as they arrive, the `Content-Length`
+//! of a request does not allocate memory up front.
+//!
+//! The model file is checked for changes every second and reloaded, requests which already
+//! started finish with the previous forest. A model which does not load, or which does not
+//! score the probes with valid scores, keeps the previous one active. The probes are the samples
+//! of `--probes`, in the format of the request bodies, and default to the medians and the bounds
+//! of the training data of the forest served at startup.
+//!
+//! With the `grpc` feature, `--grpc` serves the service of `proto/eif.proto` instead, which
+//! trains, scores and explains models of the given dimensions kept in memory by their name, see
+//! the `grpc` module.
+
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use extended_isolation_forest::binary;
+use extended_isolation_forest::dynamic::MAX_DIMENSIONS;
+use extended_isolation_forest::features::FeatureSpec;
+use extended_isolation_forest::swap::SwappableForest;
+use extended_isolation_forest::{Error, Forest};
+
+#[cfg(feature = "grpc")]
+mod grpc;
+
+/// Larger request bodies are rejected.
+const MAX_BODY_BYTES: usize = 16 << 20;
+
+/// Larger request lines and headers are rejected.
+const MAX_HEAD_BYTES: u64 = 16 << 10;
+
+/// Connections are closed when reading or writing blocks for longer.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Accepted connections waiting for a worker, further connections wait in the listener.
+const QUEUED_CONNECTIONS: usize = 64;
+
+const USAGE: &str = "usage: eif-serve --http ADDRESS --model PATH [--dimensions N] [--spec PATH]
+         [--probes PATH] [--workers N]
+       eif-serve --grpc ADDRESS [--model PATH] [--dimensions N]";
+
+#[derive(Debug, PartialEq)]
+enum Protocol {
+    Http,
+    #[cfg(feature = "grpc")]
+    Grpc,
+}
+
+struct Args {
+    protocol: Protocol,
+    address: String,
+    model: Option<PathBuf>,
+    dimensions: Option<usize>,
+    spec: Option<PathBuf>,
+    probes: Option<PathBuf>,
+    workers: Option<usize>,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let dimensions = match (args.dimensions, &args.model) {
+        (Some(dimensions), _) => Ok(dimensions),
+        (None, Some(model)) => binary_dimensions(model),
+        (None, None) => Err(Error::Deserialization(
+            "--dimensions is required without --model".to_string(),
+        )),
+    };
+    let result = dimensions.and_then(|dimensions| dispatch(dimensions, &args));
+    if let Err(err) = result {
+        eprintln!("eif-serve: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let (mut listen, mut model, mut dimensions, mut spec) = (None, None, None, None);
+    let (mut probes, mut workers) = (None, None);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value of {}", arg));
+        match arg.as_str() {
+            "--http" => listen = Some((Protocol::Http, value()?)),
+            #[cfg(feature = "grpc")]
+            "--grpc" => listen = Some((Protocol::Grpc, value()?)),
+            #[cfg(not(feature = "grpc"))]
+            "--grpc" => return Err("--grpc requires the grpc feature".to_string()),
+            "--model" => model = Some(PathBuf::from(value()?)),
+            "--spec" => spec = Some(PathBuf::from(value()?)),
+            "--probes" => probes = Some(PathBuf::from(value()?)),
+            "--workers" => {
+                let value = value()?;
+                workers = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&workers| workers > 0)
+                        .ok_or(format!("invalid number of workers {}", value))?,
+                );
+            }
+            "--dimensions" => {
+                let value = value()?;
+                dimensions = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid number of dimensions {}", value))?,
+                );
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    let (protocol, address) = listen.ok_or("missing --http or --grpc")?;
+    if protocol == Protocol::Http && model.is_none() {
+        return Err("missing --model".to_string());
+    }
+    if protocol != Protocol::Http {
+        let http_only = [
+            ("--spec", spec.is_some()),
+            ("--probes", probes.is_some()),
+            ("--workers", workers.is_some()),
+        ];
+        if let Some((arg, _)) = http_only.iter().find(|(_, given)| *given) {
+            return Err(format!("{} requires --http", arg));
+        }
+    }
+    Ok(Args {
+        protocol,
+        address,
+        model,
+        dimensions,
+        spec,
+        probes,
+        workers,
+    })
+}
+
+/// The dimensions stored in the header of a binary forest.
+fn binary_dimensions(path: &Path) -> Result<usize, Error> {
+    let mut header = [0; 12];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(Error::Io)?;
+    if !header.starts_with(b"EIFB") {
+        return Err(Error::Deserialization(
+            "--dimensions is required for JSON forests".to_string(),
+        ));
+    }
+    binary::dimensions(&header)
+}
+
+/// Serve the forest with its dimensions as the const parameter of the forest, for the
+/// dimensions `DynForest` trains and loads.
+fn dispatch(dimensions: usize, args: &Args) -> Result<(), Error> {
+    const _: () = assert!(
+        MAX_DIMENSIONS == 16,
+        "the literals cover 1 to MAX_DIMENSIONS"
+    );
+    macro_rules! serve_dimensions {
+        ($($n:literal)*) => {
+            match dimensions {
+                0 => Err(Error::EmptyData),
+                $($n => run::<$n>(args),)*
+                _ => Err(Error::TooManyDimensions {
+                    max: MAX_DIMENSIONS,
+                    actual: dimensions,
+                }),
+            }
+        };
+    }
+    serve_dimensions!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16)
+}
+
+fn load<const N: usize>(path: &Path) -> Result<Forest<f64, N>, Error> {
+    let bytes = std::fs::read(path).map_err(Error::Io)?;
+    if bytes.starts_with(b"EIFB") {
+        Forest::from_binary(&bytes)
+    } else {
+        serde_json::from_slice(&bytes).map_err(|err| Error::Deserialization(err.to_string()))
+    }
+}
+
+fn load_spec<const N: usize>(path: &Path) -> Result<FeatureSpec, Error> {
+    let bytes = std::fs::read(path).map_err(Error::Io)?;
+    let spec: FeatureSpec =
+        serde_json::from_slice(&bytes).map_err(|err| Error::Deserialization(err.to_string()))?;
+    spec.check_dimensions::<N>()?;
+    Ok(spec)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+fn run<const N: usize>(args: &Args) -> Result<(), Error> {
+    match (&args.protocol, &args.model) {
+        (Protocol::Http, Some(model)) => serve::<N>(args, model),
+        (Protocol::Http, None) => unreachable!("--http requires --model"),
+        #[cfg(feature = "grpc")]
+        (Protocol::Grpc, model) => grpc::serve::<N>(&args.address, model.as_deref()),
+    }
+}
+
+fn serve<const N: usize>(args: &Args, model: &Path) -> Result<(), Error> {
+    let forest = load::<N>(model)?;
+    let spec = args.spec.as_deref().map(load_spec::<N>).transpose()?;
+    let probes = probes(args.probes.as_deref(), &forest, spec.as_ref())?;
+    let (forest, spec) = (Arc::new(SwappableForest::new(forest)), Arc::new(spec));
+    let listener = TcpListener::bind(&args.address).map_err(Error::Io)?;
+    eprintln!(
+        "eif-serve: serving {} with {} dimensions on {}",
+        model.display(),
+        N,
+        args.address
+    );
+
+    let watched = Arc::clone(&forest);
+    let path = model.to_path_buf();
+    std::thread::spawn(move || {
+        let mut last_modified = modified(&path);
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+            match load::<N>(&path).and_then(|forest| watched.reload(forest, &probes)) {
+                Ok(_) => eprintln!("eif-serve: reloaded {}", path.display()),
+                Err(err) => eprintln!("eif-serve: keeping the previous model: {}", err),
+            }
+        }
+    });
+
+    let workers = args
+        .workers
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |workers| workers.get()));
+    let (sender, receiver) = sync_channel::<TcpStream>(QUEUED_CONNECTIONS);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let receiver = Arc::clone(&receiver);
+        let (forest, spec) = (Arc::clone(&forest), Arc::clone(&spec));
+        std::thread::spawn(move || loop {
+            // the lock is released before responding
+            let Ok(stream) = receiver.lock().unwrap().recv() else {
+                break;
+            };
+            if let Err(err) = respond(stream, &forest, spec.as_ref().as_ref()) {
+                eprintln!("eif-serve: {}", err);
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let timeouts = stream
+            .set_read_timeout(Some(TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)));
+        if let Err(err) = timeouts {
+            eprintln!("eif-serve: {}", err);
+            continue;
+        }
+        // blocks while all workers are busy and the queue is full
+        if sender.send(stream).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The probes validating reloaded forests, read from the file or the medians and the bounds of
+/// the training data of the forest.
+fn probes<const N: usize>(
+    path: Option<&Path>,
+    forest: &Forest<f64, N>,
+    spec: Option<&FeatureSpec>,
+) -> Result<Vec<[f64; N]>, Error> {
+    if let Some(path) = path {
+        let bytes = std::fs::read(path).map_err(Error::Io)?;
+        return parse_samples::<N>(&bytes, spec)
+            .map_err(|message| Error::Deserialization(format!("--probes: {}", message)));
+    }
+    let mut probes = Vec::new();
+    if let Ok(medians) = <[f64; N]>::try_from(forest.training_medians()) {
+        probes.push(medians);
+    }
+    if let Some((min, max)) = forest.training_range() {
+        probes.extend([*min, *max]);
+    }
+    Ok(probes)
+}
+
+fn respond<const N: usize>(
+    mut stream: TcpStream,
+    forest: &SwappableForest<f64, N>,
+    spec: Option<&FeatureSpec>,
+) -> std::io::Result<()> {
+    let (status, body) = match read_and_handle(&stream, forest, spec) {
+        Ok(response) => response,
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            (408, error_json("timed out reading the request"))
+        }
+        Err(err) if err.kind() == ErrorKind::InvalidData => (400, error_json(&err.to_string())),
+        Err(err) => return Err(err),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Read the request from the stream and [`handle`] it.
+fn read_and_handle<const N: usize>(
+    stream: &TcpStream,
+    forest: &SwappableForest<f64, N>,
+    spec: Option<&FeatureSpec>,
+) -> std::io::Result<(u16, String)> {
+    let mut reader = BufReader::new(stream).take(MAX_HEAD_BYTES);
+    let mut request_line = String::new();
+    if !read_head_line(&mut reader, &mut request_line)? {
+        return Ok(head_error(&reader));
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if !read_head_line(&mut reader, &mut header)? {
+            return Ok(head_error(&reader));
+        }
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(length) => content_length = length,
+                    Err(_) => return Ok((400, error_json("invalid Content-Length"))),
+                }
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Ok((413, error_json("request body too large")));
+    }
+
+    // the body grows with the bytes received instead of the announced length
+    reader.set_limit(content_length as u64);
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body)?;
+    if body.len() < content_length {
+        return Ok((400, error_json("incomplete request body")));
+    }
+    Ok(handle(forest, spec, method, target, &body))
+}
+
+/// Read a line of the request head, returns `false` unless the line is complete.
+fn read_head_line<R: BufRead>(reader: &mut R, line: &mut String) -> std::io::Result<bool> {
+    reader.read_line(line)?;
+    Ok(line.ends_with('\n'))
+}
+
+/// The response to an incomplete request head, either too large or ended by the client.
+fn head_error<R>(reader: &std::io::Take<R>) -> (u16, String) {
+    if reader.limit() == 0 {
+        (431, error_json("request head too large"))
+    } else {
+        (400, error_json("incomplete request head"))
+    }
+}
+
+/// The reason phrase of the status codes of the responses.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        413 => "Content Too Large",
+        431 => "Request Header Fields Too Large",
+        // HTTP/1.1 allows empty reason phrases
+        _ => "",
+    }
+}
+
+/// The status code and the body of the response to a request.
+fn handle<const N: usize>(
+    forest: &SwappableForest<f64, N>,
+    spec: Option<&FeatureSpec>,
+    method: &str,
+    target: &str,
+    body: &[u8],
+) -> (u16, String) {
+    match (method, target) {
+        ("GET", "/health") => (200, "\"ok\"".to_string()),
+        ("POST", "/score") | ("POST", "/explain") => {
+            let samples = match parse_samples::<N>(body, spec) {
+                Ok(samples) => samples,
+                Err(message) => return (400, error_json(&message)),
+            };
+            // all samples of a request are scored by the same forest
+            let forest = forest.current();
+            let response = if target == "/score" {
+                let scores: Vec<f64> = samples.iter().map(|sample| forest.score(sample)).collect();
+                serde_json::json!({ "scores": scores })
+            } else {
+                serde_json::json!(forest.explain_slice(&samples))
+            };
+            (200, response.to_string())
+        }
+        _ => (404, error_json("not found")),
+    }
+}
+
+fn parse_samples<const N: usize>(
+    body: &[u8],
+    spec: Option<&FeatureSpec>,
+) -> Result<Vec<[f64; N]>, String> {
+    if let Some(spec) = spec {
+        let samples: Vec<serde_json::Value> = serde_json::from_slice(body)
+            .map_err(|err| format!("expected an array of samples: {}", err))?;
+        return samples
+            .iter()
+            .enumerate()
+            .map(|(index, sample)| {
+                spec.sample_from_json(sample)
+                    .map_err(|err| format!("sample {}: {}", index, err))
+            })
+            .collect();
+    }
+    let samples: Vec<Vec<f64>> = serde_json::from_slice(body)
+        .map_err(|err| format!("expected an array of samples: {}", err))?;
+    samples
+        .into_iter()
+        .enumerate()
+        .map(|(index, sample)| {
+            let len = sample.len();
+            sample
+                .try_into()
+                .map_err(|_| format!("sample {} has {} features instead of {}", index, len, N))
+        })
+        .collect()
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{Shutdown, TcpListener, TcpStream};
+
+    use extended_isolation_forest::eif::{EifForest, EifNode};
+    use extended_isolation_forest::features::{FeatureColumn, FeatureSpec};
+    use extended_isolation_forest::swap::SwappableForest;
+    use extended_isolation_forest::{Forest, ForestOptions};
+
+    use super::{handle, parse_args, probes, respond, MAX_HEAD_BYTES};
+
+    fn forest() -> SwappableForest<f64, 1> {
+        SwappableForest::new(
+            Forest::<f64, 1>::from_eif(&EifForest {
+                sample_size: 8,
+                trees: vec![EifNode::InNode {
+                    n: vec![1.0],
+                    p: vec![5.0],
+                    left: Box::new(EifNode::ExNode { size: 7 }),
+                    right: Box::new(EifNode::ExNode { size: 1 }),
+                }],
+            })
+            .unwrap(),
+        )
+    }
+
+    /// The response to the request, sent over a socket.
+    fn exchange(forest: &SwappableForest<f64, 1>, request: &[u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(request).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        respond(stream, forest, None).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn score_and_explain_requests() {
+        let forest = forest();
+
+        let (status, body) = handle(&forest, None, "POST", "/score", b"[[1.0], [10.0]]");
+        assert_eq!(status, 200);
+        let scores: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(scores["scores"][1].as_f64(), Some(forest.score(&[10.0])));
+
+        let (status, body) = handle(&forest, None, "POST", "/explain", b"[[10.0]]");
+        assert_eq!(status, 200);
+        let explanations: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(explanations[0]["contributions"][0].as_f64(), Some(1.0));
+
+        assert_eq!(
+            handle(&forest, None, "POST", "/score", b"[[1.0, 2.0]]").0,
+            400
+        );
+        assert_eq!(handle(&forest, None, "POST", "/score", b"{}").0, 400);
+        assert_eq!(handle(&forest, None, "GET", "/score", b"").0, 404);
+        assert_eq!(handle(&forest, None, "GET", "/health", b"").0, 200);
+
+        let spec = FeatureSpec {
+            features: vec![FeatureColumn {
+                default: Some(10.0),
+                ..FeatureColumn::number("latency")
+            }],
+        };
+        let requests: [&[u8]; 2] = [br#"[{"latency": "1"}, {}]"#, b"[[1.0], [null]]"];
+        for request in requests {
+            let (status, body) = handle(&forest, Some(&spec), "POST", "/score", request);
+            assert_eq!(status, 200);
+            let scores: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(scores["scores"][0].as_f64(), Some(forest.score(&[1.0])));
+            assert_eq!(scores["scores"][1].as_f64(), Some(forest.score(&[10.0])));
+        }
+        let (status, body) = handle(
+            &forest,
+            Some(&spec),
+            "POST",
+            "/score",
+            br#"[{"latency": "x"}]"#,
+        );
+        assert_eq!(status, 400);
+        assert!(body.contains("sample 0"));
+
+        let args = ["--http", "127.0.0.1:0", "--model", "forest.json"];
+        let args = parse_args(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(args.dimensions, None);
+        assert!(parse_args(["--http".to_string()].into_iter()).is_err());
+        assert!(parse_args(["--http".to_string(), "127.0.0.1:0".to_string()].into_iter()).is_err());
+        let args = [
+            "--http",
+            "127.0.0.1:0",
+            "--model",
+            "forest.json",
+            "--workers",
+            "0",
+        ];
+        assert!(parse_args(args.iter().map(|arg| arg.to_string())).is_err());
+        let args = [
+            "--grpc",
+            "127.0.0.1:0",
+            "--dimensions",
+            "2",
+            "--probes",
+            "probes.json",
+        ];
+        assert!(parse_args(args.iter().map(|arg| arg.to_string())).is_err());
+    }
+
+    #[test]
+    fn read_requests() {
+        let forest = forest();
+        let response = exchange(
+            &forest,
+            b"POST /score HTTP/1.1\r\nContent-Length: 7\r\n\r\n[[1.0]]",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&format!("{{\"scores\":[{}]}}", forest.score(&[1.0]))));
+
+        let responses = [
+            (
+                &b"POST /score HTTP/1.1\r\nContent-Length: 100000000\r\n\r\n"[..],
+                "413 Content Too Large",
+            ),
+            (
+                b"POST /score HTTP/1.1\r\nContent-Length: 100\r\n\r\n[[1.0]]",
+                "400 Bad Request",
+            ),
+            (
+                b"POST /score HTTP/1.1\r\nContent-Length: x\r\n\r\n",
+                "400 Bad Request",
+            ),
+            (b"GET /health HTTP/1.1\r\n", "400 Bad Request"),
+            (b"GET /missing HTTP/1.1\r\n\r\n", "404 Not Found"),
+        ];
+        for (request, status) in responses {
+            let response = exchange(&forest, request);
+            assert!(
+                response.starts_with(&format!("HTTP/1.1 {}\r\n", status)),
+                "{}",
+                response
+            );
+        }
+
+        // a head filling the limit without ending, so the whole request is read
+        let mut request = b"GET /health HTTP/1.1\r\nX-Padding: ".to_vec();
+        request.resize(MAX_HEAD_BYTES as usize, b'a');
+        let response = exchange(&forest, &request);
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    }
+
+    #[test]
+    fn reload_probes() {
+        let values: Vec<[f64; 1]> = (0..100).map(|i| [i as f64]).collect();
+        let forest = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+        let defaults = probes(None, &forest, None).unwrap();
+        assert_eq!(defaults.len(), 3);
+        assert!(defaults.contains(&[0.0]) && defaults.contains(&[99.0]));
+
+        let path =
+            std::env::temp_dir().join(format!("eif-serve-probes-{}.json", std::process::id()));
+        std::fs::write(&path, "[[1.5], [2.5]]").unwrap();
+        assert_eq!(probes(Some(&path), &forest, None).unwrap(), [[1.5], [2.5]]);
+        std::fs::write(&path, "[[1.5, 2.5]]").unwrap();
+        assert!(probes(Some(&path), &forest, None).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
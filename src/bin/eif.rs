@@ -0,0 +1,243 @@
+//! Command line tools for stored forests.
+//!
+//! ```text
+//! eif diff old.bin new.bin [--probes probes.json] [--dimensions N] [--spec spec.json]
+//! ```
+//!
+//! `diff` compares two forests with `Forest::diff` and prints the differences as JSON. Forests
+//! are stored as JSON or in the binary format of `Forest::write_binary`, the dimensions of
+//! binary forests are read from the file. The probes are a JSON array of samples, every sample
+//! being an array of numbers. With a `FeatureSpec` stored as JSON the probes may also be
+//! objects with the columns of the spec as keys, and its types, defaults and transforms are
+//! applied.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use extended_isolation_forest::binary;
+use extended_isolation_forest::dynamic::MAX_DIMENSIONS;
+use extended_isolation_forest::features::FeatureSpec;
+use extended_isolation_forest::{Error, Forest};
+
+const USAGE: &str = "usage: eif diff A B [--probes PATH] [--dimensions N] [--spec PATH]";
+
+#[derive(Clone, Debug, PartialEq)]
+struct DiffArgs {
+    a: PathBuf,
+    b: PathBuf,
+    probes: Option<PathBuf>,
+    dimensions: Option<usize>,
+    spec: Option<PathBuf>,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let args = match args.next().as_deref() {
+        Some("diff") => parse_diff_args(args),
+        Some(command) => Err(format!("unknown command {}", command)),
+        None => Err("missing command".to_string()),
+    };
+    let args = match args {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let dimensions = match args.dimensions {
+        Some(dimensions) => Ok(dimensions),
+        None => binary_dimensions(&args.a),
+    };
+    match dimensions.and_then(|dimensions| dispatch(dimensions, &args)) {
+        Ok(diff) => println!("{}", diff),
+        Err(err) => {
+            eprintln!("eif: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_diff_args(mut args: impl Iterator<Item = String>) -> Result<DiffArgs, String> {
+    let (mut paths, mut probes, mut dimensions, mut spec) = (Vec::new(), None, None, None);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value of {}", arg));
+        match arg.as_str() {
+            "--probes" => probes = Some(PathBuf::from(value()?)),
+            "--spec" => spec = Some(PathBuf::from(value()?)),
+            "--dimensions" => {
+                let value = value()?;
+                dimensions = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid number of dimensions {}", value))?,
+                );
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown argument {}", arg)),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [a, b]: [PathBuf; 2] = paths
+        .try_into()
+        .map_err(|_| "expected the paths of two forests".to_string())?;
+    Ok(DiffArgs {
+        a,
+        b,
+        probes,
+        dimensions,
+        spec,
+    })
+}
+
+/// The dimensions stored in the header of a binary forest.
+fn binary_dimensions(path: &Path) -> Result<usize, Error> {
+    let mut header = [0; 12];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(Error::Io)?;
+    if !header.starts_with(b"EIFB") {
+        return Err(Error::Deserialization(
+            "--dimensions is required for JSON forests".to_string(),
+        ));
+    }
+    binary::dimensions(&header)
+}
+
+/// Compare the forests with their dimensions as the const parameter of the forest, for the
+/// dimensions `DynForest` trains and loads.
+fn dispatch(dimensions: usize, args: &DiffArgs) -> Result<String, Error> {
+    const _: () = assert!(
+        MAX_DIMENSIONS == 16,
+        "the literals cover 1 to MAX_DIMENSIONS"
+    );
+    macro_rules! diff_dimensions {
+        ($($n:literal)*) => {
+            match dimensions {
+                0 => Err(Error::EmptyData),
+                $($n => diff::<$n>(args),)*
+                _ => Err(Error::TooManyDimensions {
+                    max: MAX_DIMENSIONS,
+                    actual: dimensions,
+                }),
+            }
+        };
+    }
+    diff_dimensions!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16)
+}
+
+fn load<const N: usize>(path: &Path) -> Result<Forest<f64, N>, Error> {
+    let bytes = std::fs::read(path).map_err(Error::Io)?;
+    if bytes.starts_with(b"EIFB") {
+        Forest::from_binary(&bytes)
+    } else {
+        serde_json::from_slice(&bytes).map_err(|err| Error::Deserialization(err.to_string()))
+    }
+}
+
+fn read_json<V: serde::de::DeserializeOwned>(path: &Path) -> Result<V, Error> {
+    let bytes = std::fs::read(path).map_err(Error::Io)?;
+    serde_json::from_slice(&bytes).map_err(|err| Error::Deserialization(err.to_string()))
+}
+
+fn diff<const N: usize>(args: &DiffArgs) -> Result<String, Error> {
+    let (a, b) = (load::<N>(&args.a)?, load::<N>(&args.b)?);
+    let spec: Option<FeatureSpec> = args.spec.as_deref().map(read_json).transpose()?;
+    let probes: Vec<[f64; N]> = match (&args.probes, spec) {
+        (Some(path), Some(spec)) => {
+            let probes: Vec<serde_json::Value> = read_json(path)?;
+            probes
+                .iter()
+                .map(|probe| spec.sample_from_json(probe))
+                .collect::<Result<_, _>>()?
+        }
+        (Some(path), None) => {
+            let probes: Vec<Vec<f64>> = read_json(path)?;
+            probes
+                .into_iter()
+                .map(|probe| {
+                    let actual = probe.len();
+                    probe.try_into().map_err(|_| Error::DimensionMismatch {
+                        expected: N,
+                        actual,
+                    })
+                })
+                .collect::<Result<_, _>>()?
+        }
+        (None, _) => Vec::new(),
+    };
+    serde_json::to_string_pretty(&a.diff(&b, &probes))
+        .map_err(|err| Error::Deserialization(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use extended_isolation_forest::{Error, Forest, ForestOptions};
+
+    use super::{dispatch, parse_diff_args, DiffArgs};
+
+    #[test]
+    fn diff_stored_forests() {
+        let args = ["a.bin", "--probes", "probes.json", "b.json"];
+        assert_eq!(
+            parse_diff_args(args.iter().map(|arg| arg.to_string())),
+            Ok(DiffArgs {
+                a: PathBuf::from("a.bin"),
+                b: PathBuf::from("b.json"),
+                probes: Some(PathBuf::from("probes.json")),
+                dimensions: None,
+                spec: None,
+            })
+        );
+        assert!(parse_diff_args(["a.bin".to_string()].into_iter()).is_err());
+
+        let dir = std::env::temp_dir().join(format!("eif-diff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let values: Vec<_> = (0..100)
+            .map(|i| [(i % 10) as f64, (i / 10) as f64])
+            .collect();
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+        let mut binary = Vec::new();
+        forest.write_binary(&mut binary).unwrap();
+        std::fs::write(dir.join("a.bin"), binary).unwrap();
+        std::fs::write(dir.join("b.json"), serde_json::to_vec(&forest).unwrap()).unwrap();
+        std::fs::write(dir.join("probes.json"), "[[1, 2], [50, 50]]").unwrap();
+        std::fs::write(dir.join("wide.json"), "[[1, 2, 3]]").unwrap();
+        std::fs::write(dir.join("objects.json"), r#"[{"y": 2, "x": "1"}]"#).unwrap();
+        std::fs::write(
+            dir.join("spec.json"),
+            r#"{"features": [{"column": "x"}, {"column": "y"}]}"#,
+        )
+        .unwrap();
+
+        let args = DiffArgs {
+            a: dir.join("a.bin"),
+            b: dir.join("b.json"),
+            probes: Some(dir.join("probes.json")),
+            dimensions: None,
+            spec: None,
+        };
+        let diff: serde_json::Value = serde_json::from_str(&dispatch(2, &args).unwrap()).unwrap();
+        assert_eq!(diff["depth_shift"].as_f64(), Some(0.0));
+        assert_eq!(diff["scores"]["num_probes"].as_u64(), Some(2));
+        assert!(dispatch(17, &args).is_err());
+        let with_spec = DiffArgs {
+            probes: Some(dir.join("objects.json")),
+            spec: Some(dir.join("spec.json")),
+            ..args.clone()
+        };
+        let diff: serde_json::Value =
+            serde_json::from_str(&dispatch(2, &with_spec).unwrap()).unwrap();
+        assert_eq!(diff["scores"]["num_probes"].as_u64(), Some(1));
+        let args = DiffArgs {
+            probes: Some(dir.join("wide.json")),
+            ..args
+        };
+        assert!(matches!(
+            dispatch(2, &args),
+            Err(Error::DimensionMismatch { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
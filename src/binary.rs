@@ -0,0 +1,667 @@
+//! A binary file format for large forests, decoded in parallel. Available with the `json`
+//! feature.
+//!
+//! Loading forests with thousands of trees from JSON is dominated by parsing the trees one after
+//! the other. The binary format stores every tree as a separate block located by a table of
+//! offsets, so the blocks can be found without decoding them and are decoded on all available
+//! cores.
+//!
+//! The layout, all integers and floats little-endian:
+//!
+//! * the magic bytes `EIFB`, the format version and the number of dimensions as `u32`,
+//! * the length of the header as `u64` and the header, a JSON object with the options and
+//!   settings of the forest,
+//! * the number of trees as `u64` and the offset of every tree as `u64`, counted from the end
+//!   of the offsets, followed by the offset of the end of the last tree,
+//! * the encoded nodes of every tree.
+//!
+//! With the offsets a tree can be decoded without reading the others, [`MappedForest`] decodes
+//! every tree of a memory-mapped file only when it is first used.
+//!
+//! Every node starts with a tag byte and its depth offset as `u64`. Floats are stored as `f64`,
+//! which is lossless for `f32` forests as well.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let mut bytes = Vec::new();
+//! forest.write_binary(&mut bytes).unwrap();
+//! let restored: Forest<f64, 2> = Forest::from_binary(&bytes).unwrap();
+//! assert_eq!(restored.score(&[50.0, 50.0]), forest.score(&[50.0, 50.0]));
+//! ```
+
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+#[cfg(feature = "mmap")]
+use std::sync::OnceLock;
+
+use num_traits::Float;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::bucket::Buckets;
+use crate::envelope::RangePolicy;
+use crate::leaf::Count;
+use crate::monotone::Monotone;
+use crate::ood::Bounds;
+use crate::provenance::TreeProvenance;
+use crate::suppression::Region;
+#[cfg(feature = "mmap")]
+use crate::{normalization::ScoreMode, SampleAccess};
+use crate::{to_f64, Error, ExNode, Forest, ForestFloat, ForestOptions, InNode, Node, Tree};
+
+const MAGIC: &[u8; 4] = b"EIFB";
+const VERSION: u32 = 2;
+
+const TAG_EX_NODE: u8 = 0;
+const TAG_IN_NODE: u8 = 1;
+
+/// Everything besides the trees.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+struct Header<T, const N: usize> {
+    avg_path_length_c: f64,
+    options: ForestOptions,
+    recursion_cap: Option<usize>,
+    strict_fp: bool,
+    jittered_features: Vec<usize>,
+    training_range: Option<Bounds<T, N>>,
+    range_policy: RangePolicy,
+    #[serde(default)]
+    monotone: Vec<Monotone<T>>,
+    #[serde(default)]
+    constant_features: Vec<usize>,
+    #[serde(default)]
+    training_medians: Vec<T>,
+    #[serde(default)]
+    buckets: Buckets,
+    #[serde(default)]
+    suppressed: Vec<Region<T, N>>,
+    #[serde(default)]
+    provenance: Vec<TreeProvenance>,
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: for<'de> ForestFloat<'de> + DeserializeOwned + Send + Sync,
+{
+    /// Write the forest in the binary format of the [module documentation](self). Fails with
+    /// [`Error::Io`] if the forest can not be written.
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let header = serde_json::to_vec(&Header::<T, N> {
+            avg_path_length_c: self.avg_path_length_c,
+            options: self.options.clone(),
+            recursion_cap: self.recursion_cap,
+            strict_fp: self.strict_fp,
+            jittered_features: self.jittered_features.clone(),
+            training_range: self.training_range.clone(),
+            range_policy: self.range_policy,
+            monotone: self.monotone.clone(),
+            constant_features: self.constant_features.clone(),
+            training_medians: self.training_medians.clone(),
+            buckets: self.buckets,
+            suppressed: self.suppressed.clone(),
+            provenance: self.provenance.clone(),
+        })
+        .map_err(|err| Error::Io(err.into()))?;
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+        buf.extend_from_slice(&(N as u32).to_le_bytes());
+        put_u64(&mut buf, header.len());
+        buf.extend_from_slice(&header);
+        put_u64(&mut buf, self.trees.len());
+        let mut offset = 0;
+        put_u64(&mut buf, offset);
+        for tree in self.trees.iter() {
+            offset += encoded_len(tree);
+            put_u64(&mut buf, offset);
+        }
+        writer.write_all(&buf).map_err(Error::Io)?;
+
+        for tree in self.trees.iter() {
+            buf.clear();
+            encode_tree(tree, &mut buf);
+            debug_assert_eq!(buf.len(), encoded_len(tree));
+            writer.write_all(&buf).map_err(Error::Io)?;
+        }
+        writer.flush().map_err(Error::Io)
+    }
+
+    /// Read a forest written by [`Forest::write_binary`], decoding the trees in parallel on
+    /// all available cores.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, Error> {
+        let (header, blocks) = parse(bytes)?;
+        let blocks: Vec<&[u8]> = blocks.into_iter().map(|block| &bytes[block]).collect();
+
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = blocks.len().div_ceil(threads).max(1);
+        let trees = std::thread::scope(|scope| {
+            let handles: Vec<_> = blocks
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|block| decode_tree(block))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                })
+                .collect();
+            let mut trees = Vec::with_capacity(blocks.len());
+            for handle in handles {
+                trees.extend(handle.join().expect("decoding a tree panicked")?);
+            }
+            Ok::<_, Error>(trees)
+        })?;
+
+        Ok(from_header(header, trees))
+    }
+
+    /// Read a forest from a file written by [`Forest::write_binary`].
+    pub fn from_binary_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_binary(&std::fs::read(path).map_err(Error::Io)?)
+    }
+}
+
+/// The number of dimensions of a forest in the binary format, read from the start of `bytes`
+/// without decoding the header, for choosing the const parameter of the forest to read.
+pub fn dimensions(bytes: &[u8]) -> Result<usize, Error> {
+    let mut reader = Reader { bytes };
+    if reader.take(4)? != MAGIC {
+        return Err(invalid("not a binary forest"));
+    }
+    reader.take(4)?;
+    Ok(u32::from_le_bytes(reader.array()?) as usize)
+}
+
+/// The forest of the header and the decoded trees.
+fn from_header<T, const N: usize>(header: Header<T, N>, trees: Vec<Tree<T, N>>) -> Forest<T, N>
+where
+    T: Float,
+{
+    let mut forest = Forest::from_parts(
+        header.avg_path_length_c,
+        header.options,
+        trees.into_boxed_slice(),
+    );
+    forest.recursion_cap = header.recursion_cap;
+    forest.strict_fp = header.strict_fp;
+    forest.jittered_features = header.jittered_features;
+    forest.training_range = header.training_range;
+    forest.range_policy = header.range_policy;
+    forest.monotone = header.monotone;
+    forest.constant_features = header.constant_features;
+    forest.training_medians = header.training_medians;
+    forest.buckets = header.buckets;
+    forest.suppressed = header.suppressed;
+    forest.provenance = header.provenance;
+    forest
+}
+
+/// Read the header of a binary forest and locate its trees without decoding them, returning the
+/// byte ranges of the encoded trees.
+fn parse<T, const N: usize>(bytes: &[u8]) -> Result<(Header<T, N>, Vec<Range<usize>>), Error>
+where
+    T: DeserializeOwned,
+{
+    let mut reader = Reader { bytes };
+    if reader.take(4)? != MAGIC {
+        return Err(invalid("not a binary forest"));
+    }
+    let version = u32::from_le_bytes(reader.array()?);
+    if version != VERSION {
+        return Err(invalid(format!("unsupported format version {}", version)));
+    }
+    let dimensions = u32::from_le_bytes(reader.array()?) as usize;
+    if dimensions != N {
+        return Err(Error::DimensionMismatch {
+            expected: N,
+            actual: dimensions,
+        });
+    }
+    let header_len = reader.len()?;
+    let header: Header<T, N> = serde_json::from_slice(reader.take(header_len)?)
+        .map_err(|err| Error::Deserialization(err.to_string()))?;
+
+    let num_trees = reader.len()?;
+    let mut blocks = Vec::with_capacity(num_trees.min(reader.bytes.len() / 8));
+    let table_len = num_trees
+        .checked_add(1)
+        .and_then(|offsets| offsets.checked_mul(8))
+        .ok_or_else(|| invalid("too many trees"))?;
+    let mut offsets = Reader {
+        bytes: reader.take(table_len)?,
+    };
+    let start = bytes.len() - reader.bytes.len();
+    let mut previous = offsets.len()?;
+    if previous != 0 {
+        return Err(invalid("the first tree does not start after the offsets"));
+    }
+    for _ in 0..num_trees {
+        let offset = offsets.len()?;
+        if offset < previous || offset > reader.bytes.len() {
+            return Err(invalid("tree offset out of range"));
+        }
+        blocks.push(start + previous..start + offset);
+        previous = offset;
+    }
+    reader.take(previous)?;
+    if !reader.bytes.is_empty() {
+        return Err(invalid("trailing bytes after the last tree"));
+    }
+    Ok((header, blocks))
+}
+
+/// A forest in a memory-mapped file written by [`Forest::write_binary`], available with the
+/// `mmap` feature.
+///
+/// Opening the file reads only the header and the offsets of the trees, every tree is decoded
+/// when a sample is first scored by it and kept in memory afterwards. Processes hosting many
+/// models thereby only hold the trees of the models they actually score.
+///
+/// Samples are scored like by [`Forest::score`], including suppressed regions, monotone
+/// constraints, the range policy and strict mode.
+///
+/// ```rust
+/// use extended_isolation_forest::binary::MappedForest;
+/// use extended_isolation_forest::{Forest, ForestOptions};
+///
+/// let path = std::env::temp_dir().join(format!("eif-mapped-doc-{}.bin", std::process::id()));
+/// let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+/// let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+/// forest.write_binary(std::fs::File::create(&path).unwrap()).unwrap();
+///
+/// let mapped: MappedForest<f64, 2> = MappedForest::open(&path).unwrap();
+/// assert_eq!(mapped.resident_trees(), 0);
+/// assert_eq!(mapped.score(&[50.0, 50.0]).unwrap(), forest.score(&[50.0, 50.0]));
+/// assert_eq!(mapped.resident_trees(), mapped.num_trees());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "mmap")]
+pub struct MappedForest<T, const N: usize> {
+    mmap: memmap2::Mmap,
+
+    /// The forest without its trees, holding the settings used for scoring.
+    settings: Forest<T, N>,
+
+    /// Byte ranges of the encoded trees in the mapping.
+    blocks: Vec<Range<usize>>,
+
+    trees: Box<[OnceLock<Tree<T, N>>]>,
+}
+
+#[cfg(feature = "mmap")]
+impl<T, const N: usize> MappedForest<T, N>
+where
+    T: for<'de> ForestFloat<'de> + DeserializeOwned,
+{
+    /// Map the file into memory and read the header of the forest.
+    ///
+    /// The file must not be modified while it is mapped, trees decoded later would be read from
+    /// the modified file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = std::fs::File::open(path).map_err(Error::Io)?;
+        // SAFETY: the mapping is read-only, modifying the file while it is mapped is documented
+        // as not allowed
+        let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(Error::Io)?;
+        let (header, blocks) = parse(&mmap)?;
+        Ok(Self {
+            settings: from_header(header, Vec::new()),
+            trees: blocks.iter().map(|_| OnceLock::new()).collect(),
+            blocks,
+            mmap,
+        })
+    }
+
+    /// The anomaly score of the sample, see [`Forest::score`]. Fails with
+    /// [`Error::Deserialization`] if a tree decoded for the sample is corrupt.
+    pub fn score<S>(&self, sample: &S) -> Result<f64, Error>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        for index in 0..self.trees.len() {
+            self.tree(index)?;
+        }
+        let trees = self
+            .trees
+            .iter()
+            .map(|tree| tree.get().expect("decoded above"));
+        Ok(self.settings.score_trees(
+            trees,
+            sample,
+            self.settings.recursion_cap(),
+            ScoreMode::Normalized,
+        ))
+    }
+
+    /// The tree with the index, decoded on first use.
+    fn tree(&self, index: usize) -> Result<&Tree<T, N>, Error> {
+        let cell = &self.trees[index];
+        if let Some(tree) = cell.get() {
+            return Ok(tree);
+        }
+        let tree = decode_tree(&self.mmap[self.blocks[index].clone()])?;
+        Ok(cell.get_or_init(|| tree))
+    }
+
+    pub fn options(&self) -> &ForestOptions {
+        self.settings.options()
+    }
+
+    pub fn num_trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Number of trees decoded so far.
+    pub fn resident_trees(&self) -> usize {
+        self.trees
+            .iter()
+            .filter(|tree| tree.get().is_some())
+            .count()
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::Deserialization(message.into())
+}
+
+fn put_u64(buf: &mut Vec<u8>, value: usize) {
+    buf.extend_from_slice(&(value as u64).to_le_bytes());
+}
+
+fn put_floats<T: Float>(buf: &mut Vec<u8>, values: &[T]) {
+    for value in values {
+        buf.extend_from_slice(&to_f64(*value).to_le_bytes());
+    }
+}
+
+/// The bounds of the tree followed by its nodes in pre-order.
+fn encode_tree<T, const N: usize>(tree: &Tree<T, N>, buf: &mut Vec<u8>)
+where
+    T: Float,
+{
+    match &tree.bounds {
+        Some(bounds) => {
+            buf.push(1);
+            put_floats(buf, bounds.min());
+            put_floats(buf, bounds.max());
+        }
+        None => buf.push(0),
+    }
+    for (node, _) in tree.root.iter() {
+        match node {
+            Node::Ex(ex_node) => {
+                buf.push(TAG_EX_NODE);
+                put_u64(buf, ex_node.depth_offset);
+                put_u64(buf, ex_node.num_samples);
+                put_u64(buf, ex_node.samples.len());
+                for sample in &ex_node.samples {
+                    put_floats(buf, sample);
+                }
+            }
+            Node::In(in_node) => {
+                buf.push(TAG_IN_NODE);
+                put_u64(buf, in_node.depth_offset);
+                put_floats(buf, &in_node.n);
+                put_floats(buf, &in_node.p);
+            }
+        }
+    }
+}
+
+/// Number of bytes [`encode_tree`] writes for the tree.
+fn encoded_len<T, const N: usize>(tree: &Tree<T, N>) -> usize {
+    let floats = N * 8;
+    let nodes: usize = tree
+        .root
+        .iter()
+        .map(|(node, _)| match node {
+            Node::Ex(ex_node) => 1 + 3 * 8 + ex_node.samples.len() * floats,
+            Node::In(_) => 1 + 8 + 2 * floats,
+        })
+        .sum();
+    1 + tree.bounds.as_ref().map_or(0, |_| 2 * floats) + nodes
+}
+
+/// An internal node whose children are still being decoded, with the left child once it is
+/// complete.
+struct Pending<T, const N: usize> {
+    n: [T; N],
+    p: [T; N],
+    depth_offset: usize,
+    left: Option<Node<T, N>>,
+}
+
+fn decode_tree<T, const N: usize>(block: &[u8]) -> Result<Tree<T, N>, Error>
+where
+    T: Float,
+{
+    let mut reader = Reader { bytes: block };
+    let bounds = match reader.u8()? {
+        0 => None,
+        _ => Some(Bounds::new(reader.floats()?, reader.floats()?)),
+    };
+
+    let mut pending: Vec<Pending<T, N>> = Vec::new();
+    let root = 'nodes: loop {
+        let tag = reader.u8()?;
+        let depth_offset = reader.len()?;
+        let mut node = match tag {
+            TAG_EX_NODE => {
+                let num_samples = reader.len()?;
+                let samples = (0..reader.len()?)
+                    .map(|_| reader.floats())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Node::Ex(ExNode {
+                    num_samples,
+                    samples,
+                    model: Count,
+                    depth_offset,
+                })
+            }
+            TAG_IN_NODE => {
+                pending.push(Pending {
+                    n: reader.floats()?,
+                    p: reader.floats()?,
+                    depth_offset,
+                    left: None,
+                });
+                continue;
+            }
+            tag => return Err(invalid(format!("unknown node tag {}", tag))),
+        };
+        // attach the complete node to its parent, completing all parents with two children
+        loop {
+            match pending.pop() {
+                None => break 'nodes node,
+                Some(Pending {
+                    left: Some(left),
+                    n,
+                    p,
+                    depth_offset,
+                }) => {
+                    node = Node::In(InNode::new(left, node, n, p, depth_offset));
+                }
+                Some(mut parent) => {
+                    parent.left = Some(node);
+                    pending.push(parent);
+                    break;
+                }
+            }
+        }
+    };
+    if !reader.bytes.is_empty() {
+        return Err(invalid("trailing bytes after the last node of a tree"));
+    }
+    Ok(Tree { root, bounds })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < len {
+            return Err(invalid("unexpected end of the data"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const K: usize>(&mut self) -> Result<[u8; K], Error> {
+        let mut array = [0; K];
+        array.copy_from_slice(self.take(K)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, Error> {
+        usize::try_from(u64::from_le_bytes(self.array()?))
+            .map_err(|_| invalid("length exceeds the address space"))
+    }
+
+    fn floats<T: Float, const N: usize>(&mut self) -> Result<[T; N], Error> {
+        let mut values = [T::zero(); N];
+        for value in values.iter_mut() {
+            *value = T::from(f64::from_le_bytes(self.array()?))
+                .ok_or_else(|| invalid("float out of range"))?;
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dimensions;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn binary_roundtrip() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f32, (i / 20) as f32])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            leaf_samples: 2,
+            ..Default::default()
+        };
+        let mut forest: Forest<f32, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+        forest.set_recursion_cap(Some(5));
+
+        let mut bytes = Vec::new();
+        forest.write_binary(&mut bytes).unwrap();
+        assert_eq!(dimensions(&bytes).unwrap(), 2);
+        assert!(matches!(dimensions(b"{}"), Err(Error::Deserialization(_))));
+        let restored: Forest<f32, 2> = Forest::from_binary(&bytes).unwrap();
+        assert_eq!(restored.options(), forest.options());
+        assert_eq!(restored.recursion_cap(), 5);
+        assert_eq!(restored.training_range(), forest.training_range());
+        for sample in [[3.0, 4.0], [100.0, 7.0], [-2.0, -20.0]] {
+            assert_eq!(restored.score(&sample), forest.score(&sample));
+            assert_eq!(
+                restored.out_of_box_trees(&sample),
+                forest.out_of_box_trees(&sample)
+            );
+        }
+
+        assert!(matches!(
+            Forest::<f32, 3>::from_binary(&bytes),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            Forest::<f32, 2>::from_binary(&bytes[..bytes.len() - 1]),
+            Err(Error::Deserialization(_))
+        ));
+
+        let mut version_1 = bytes.clone();
+        version_1[4..8].copy_from_slice(&1u32.to_le_bytes());
+        assert!(matches!(
+            Forest::<f32, 2>::from_binary(&version_1),
+            Err(Error::Deserialization(_))
+        ));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_trees_are_decoded_on_first_use() {
+        use super::MappedForest;
+        use crate::envelope::RangePolicy;
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            seed: Some(2),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+        forest.set_range_policy(RangePolicy::Clamp);
+        let path = std::env::temp_dir().join(format!("eif-mapped-{}.bin", std::process::id()));
+        forest
+            .write_binary(std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let mapped: MappedForest<f64, 2> = MappedForest::open(&path).unwrap();
+        assert_eq!(mapped.num_trees(), 20);
+        assert_eq!(mapped.options(), forest.options());
+        assert_eq!(mapped.resident_trees(), 0);
+        for sample in [[3.0, 4.0], [100.0, 7.0]] {
+            assert_eq!(mapped.score(&sample).unwrap(), forest.score(&sample));
+        }
+        assert_eq!(mapped.resident_trees(), 20);
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_scores_match_suppressed_and_strict_scores() {
+        use super::MappedForest;
+        use crate::suppression::Region;
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            seed: Some(3),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+        forest.set_suppressed_regions(vec![Region::Box {
+            min: [90.0, 90.0],
+            max: [110.0, 110.0],
+        }]);
+        forest.set_strict_fp(true);
+        let path =
+            std::env::temp_dir().join(format!("eif-mapped-strict-{}.bin", std::process::id()));
+        forest
+            .write_binary(std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let mapped: MappedForest<f64, 2> = MappedForest::open(&path).unwrap();
+        for sample in [[3.0, 4.0], [100.0, 100.0], [-50.0, 7.0]] {
+            assert_eq!(
+                mapped.score(&sample).unwrap().to_bits(),
+                forest.score(&sample).to_bits()
+            );
+        }
+        assert_eq!(mapped.score(&[100.0, 100.0]).unwrap(), 0.0);
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
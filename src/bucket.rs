@@ -0,0 +1,216 @@
+//! Mapping scores to severity buckets for alert routing.
+//!
+//! [`Forest::score_bucket`] sorts a sample into one of the [`Bucket`]s `Info`, `Warn` and
+//! `Critical` by comparing its score to two boundaries stored with the forest. The boundaries
+//! are either set directly or fitted as quantiles of the scores of the training data with
+//! [`Forest::fit_buckets`], so the same fraction of normal traffic ends up in every bucket no
+//! matter how the scores of a particular forest are distributed.
+//!
+//! ```rust
+//! use extended_isolation_forest::bucket::Bucket;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     seed: Some(42),
+//!     ..Default::default()
+//! };
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//! forest.fit_buckets(&values, 0.9, 0.99).unwrap();
+//!
+//! assert_eq!(forest.score_bucket(&[4.5, 4.5]), Bucket::Info);
+//! assert_eq!(forest.score_bucket(&[100.0, -50.0]), Bucket::Critical);
+//! ```
+
+use std::fmt;
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::report::quantile;
+use crate::{Error, Forest, SampleAccess};
+
+/// The severity of a score, ordered from `Info` to `Critical`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Bucket {
+    Info,
+    Warn,
+    Critical,
+}
+
+impl Bucket {
+    /// The lowercase name of the bucket, `info`, `warn` or `critical`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The smallest scores of the `Warn` and `Critical` buckets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Buckets {
+    warn: f64,
+    critical: f64,
+}
+
+impl Default for Buckets {
+    /// Scores of at least `0.6` are warnings and of at least `0.7` critical.
+    fn default() -> Self {
+        Self {
+            warn: 0.6,
+            critical: 0.7,
+        }
+    }
+}
+
+impl Buckets {
+    /// Fails with [`Error::InvalidBuckets`] unless `0 <= warn <= critical <= 1`.
+    pub fn new(warn: f64, critical: f64) -> Result<Self, Error> {
+        if !(0.0 <= warn && warn <= critical && critical <= 1.0) {
+            return Err(Error::InvalidBuckets);
+        }
+        Ok(Self { warn, critical })
+    }
+
+    pub fn warn(&self) -> f64 {
+        self.warn
+    }
+
+    pub fn critical(&self) -> f64 {
+        self.critical
+    }
+
+    /// The bucket of the score, `Info` for `NaN`.
+    pub fn bucket(&self, score: f64) -> Bucket {
+        if score >= self.critical {
+            Bucket::Critical
+        } else if score >= self.warn {
+            Bucket::Warn
+        } else {
+            Bucket::Info
+        }
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Score the sample and sort it into a bucket using the boundaries stored with the forest.
+    pub fn score_bucket<S>(&self, sample: &S) -> Bucket
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.buckets.bucket(self.score(sample))
+    }
+
+    /// The boundaries of the buckets, [`Buckets::default`] unless set or fitted.
+    pub fn buckets(&self) -> Buckets {
+        self.buckets
+    }
+
+    /// Set the boundaries of the buckets. They are stored with the forest.
+    pub fn set_buckets(&mut self, buckets: Buckets) {
+        self.buckets = buckets;
+    }
+
+    /// Fit the boundaries of the buckets to the scores of the training data: the scores of
+    /// the `warn` quantile of the samples and above are warnings, of the `critical` quantile
+    /// and above critical.
+    ///
+    /// Fails with [`Error::EmptyData`] without samples and with [`Error::InvalidBuckets`]
+    /// unless `0 <= warn <= critical <= 1`.
+    pub fn fit_buckets<S>(
+        &mut self,
+        training_data: &[S],
+        warn: f64,
+        critical: f64,
+    ) -> Result<(), Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        Buckets::new(warn, critical)?;
+        if training_data.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        let mut scores: Vec<f64> = training_data
+            .iter()
+            .map(|sample| self.score(sample))
+            .collect();
+        scores.sort_unstable_by(f64::total_cmp);
+        self.buckets = Buckets::new(quantile(&scores, warn), quantile(&scores, critical))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bucket, Buckets};
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn buckets_of_scores() {
+        let buckets = Buckets::new(0.55, 0.65).unwrap();
+        assert_eq!(buckets.bucket(0.5), Bucket::Info);
+        assert_eq!(buckets.bucket(0.55), Bucket::Warn);
+        assert_eq!(buckets.bucket(0.9), Bucket::Critical);
+        assert_eq!(buckets.bucket(f64::NAN), Bucket::Info);
+        assert!(Bucket::Critical > Bucket::Warn);
+        assert_eq!(Bucket::Warn.to_string(), "warn");
+        for (warn, critical) in [(0.7, 0.6), (-0.1, 0.5), (0.5, f64::NAN)] {
+            assert!(matches!(
+                Buckets::new(warn, critical),
+                Err(Error::InvalidBuckets)
+            ));
+        }
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            seed: Some(11),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        assert_eq!(forest.buckets(), Buckets::default());
+        forest.fit_buckets(&values, 0.8, 0.95).unwrap();
+        let counts = |bucket| {
+            values
+                .iter()
+                .filter(|sample| forest.score_bucket(*sample) == bucket)
+                .count()
+        };
+        // tied scores at the boundary are warnings
+        let info = counts(Bucket::Info);
+        assert!(info > 200 && info <= 320, "{}", info);
+        assert!(counts(Bucket::Critical) >= 20);
+        assert!(forest.buckets().warn() <= forest.buckets().critical());
+        assert!(matches!(
+            forest.fit_buckets(&[] as &[[f64; 2]], 0.8, 0.95),
+            Err(Error::EmptyData)
+        ));
+
+        #[cfg(feature = "serde")]
+        {
+            let serialized = serde_json::to_string(&forest).unwrap();
+            let deserialized: Forest<f64, 2> = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.buckets(), forest.buckets());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Scoring within a time budget.
+//!
+//! The latency of [`Forest::score`] grows with the number of trees and their depths, and a
+//! stalled core can delay it arbitrarily. [`Forest::score_within`] checks the clock before
+//! every tree and stops when the budget is used up, returning the score of the trees traversed
+//! so far together with the fraction of the forest they make up. The trees are independent, so
+//! the partial score approximates the full score the better the more trees it includes.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let partial = forest.score_within(&[50.0, 50.0], Duration::from_millis(10));
+//! assert!(partial.completeness > 0.0 && partial.completeness <= 1.0);
+//! assert!(partial.score > 0.5);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::{normalize_path_length, Forest, SampleAccess};
+
+/// The score of a sample by the trees traversed within a budget, see [`Forest::score_within`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PartialScore {
+    /// The score of the sample by the traversed trees.
+    pub score: f64,
+
+    /// Number of trees traversed, at least one.
+    pub trees_scored: usize,
+
+    /// Fraction of the trees of the forest traversed, `1` if the score is complete.
+    pub completeness: f64,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Score the sample with as many trees as fit into the budget, in the order of the trees.
+    /// The first tree is always traversed, even with a zero budget.
+    ///
+    /// Suppressed regions, monotone constraints and the range policy are applied like in
+    /// [`Forest::score`], but the trees are traversed with the regular floating point arithmetic
+    /// even in strict mode.
+    pub fn score_within<S>(&self, sample: &S, budget: Duration) -> PartialScore
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let deadline = Instant::now().checked_add(budget);
+        let adjusted = self.adjusted_sample(sample);
+
+        let max_depth = self.recursion_cap();
+        let (mut path_length, mut trees_scored) = (0.0, 0);
+        for tree in self.trees.iter() {
+            if trees_scored > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            path_length += tree.path_length_with_cap(&adjusted, max_depth);
+            trees_scored += 1;
+        }
+
+        PartialScore {
+            score: if self.is_suppressed(sample) {
+                0.0
+            } else {
+                normalize_path_length(
+                    path_length / trees_scored.max(1) as f64,
+                    self.avg_path_length_c,
+                )
+            },
+            trees_scored,
+            completeness: trees_scored as f64 / self.trees.len().max(1) as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{normalize_path_length, Forest, ForestOptions};
+
+    #[test]
+    fn partial_scores_within_budget() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 40,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let sample = [3.0, 30.0];
+
+        let complete = forest.score_within(&sample, Duration::MAX);
+        assert_eq!(complete.trees_scored, 40);
+        assert_eq!(complete.completeness, 1.0);
+        assert!((complete.score - forest.score(&sample)).abs() < 1e-12);
+
+        let partial = forest.score_within(&sample, Duration::ZERO);
+        assert_eq!(partial.trees_scored, 1);
+        assert_eq!(partial.completeness, 1.0 / 40.0);
+        let first_tree = forest.trees[0].path_length_with_cap(&sample, forest.recursion_cap());
+        assert_eq!(
+            partial.score,
+            normalize_path_length(first_tree, forest.avg_path_length_c)
+        );
+    }
+}
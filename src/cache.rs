@@ -0,0 +1,217 @@
+//! Caching the scores of near-identical samples.
+//!
+//! Telemetry streams often repeat (almost) the same values. A [`CachedScorer`] quantizes the
+//! samples to a grid with a spacing of `tolerance` and keeps the scores of the most recently used
+//! grid cells, so samples falling into a cached cell are scored without traversing the trees.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//! use extended_isolation_forest::cache::CachedScorer;
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let scorer = CachedScorer::new(forest, 0.01, 10_000);
+//! let score = scorer.score(&[4.0, 5.0]);
+//! assert_eq!(scorer.score(&[4.001, 5.0]), score);
+//! assert_eq!(scorer.hits(), 1);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use num_traits::Float;
+
+use crate::{to_f64, Forest, SampleAccess};
+
+/// A forest with a least-recently-used cache of scores.
+pub struct CachedScorer<T, const N: usize> {
+    forest: Forest<T, N>,
+    tolerance: f64,
+    capacity: usize,
+    cache: Mutex<Lru<N>>,
+}
+
+/// Scores by grid cell, with the cells ordered by their last use.
+#[derive(Default)]
+struct Lru<const N: usize> {
+    entries: HashMap<[i64; N], (f64, u64)>,
+    by_use: BTreeMap<u64, [i64; N]>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<T, const N: usize> CachedScorer<T, N>
+where
+    T: Float,
+{
+    /// Cache the scores of up to `capacity` grid cells with a spacing of `tolerance`.
+    ///
+    /// A `tolerance` of zero only shares the scores of identical samples.
+    pub fn new(forest: Forest<T, N>, tolerance: f64, capacity: usize) -> Self {
+        Self {
+            forest,
+            tolerance,
+            capacity,
+            cache: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Score the sample, using the cached score of its grid cell if available.
+    ///
+    /// Samples containing non-finite values are never cached.
+    pub fn score<S>(&self, sample: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let Some(key) = self.key(sample) else {
+            return self.forest.score(sample);
+        };
+
+        if let Some(score) = self.lock().get(&key) {
+            return score;
+        }
+        // score without holding the lock
+        let score = self.forest.score(sample);
+        self.lock().insert(key, score, self.capacity);
+        score
+    }
+
+    fn key<S>(&self, sample: &S) -> Option<[i64; N]>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut key = [0; N];
+        for (i, cell) in key.iter_mut().enumerate() {
+            let value = to_f64(sample.feature(i));
+            if !value.is_finite() {
+                return None;
+            }
+            *cell = if self.tolerance > 0.0 {
+                (value / self.tolerance).round() as i64
+            } else {
+                // normalize -0.0
+                (value + 0.0).to_bits() as i64
+            };
+        }
+        Some(key)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru<N>> {
+        // the cache is never left in an inconsistent state, so poisoning can be ignored
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Number of samples scored from the cache.
+    pub fn hits(&self) -> u64 {
+        self.lock().hits
+    }
+
+    /// Number of samples scored by the forest.
+    pub fn misses(&self) -> u64 {
+        self.lock().misses
+    }
+
+    /// Number of cached scores.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached scores.
+    pub fn clear(&self) {
+        let mut lru = self.lock();
+        lru.entries.clear();
+        lru.by_use.clear();
+    }
+
+    pub fn forest(&self) -> &Forest<T, N> {
+        &self.forest
+    }
+
+    pub fn into_inner(self) -> Forest<T, N> {
+        self.forest
+    }
+}
+
+impl<const N: usize> Lru<N> {
+    fn get(&mut self, key: &[i64; N]) -> Option<f64> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some((score, last_use)) => {
+                self.by_use.remove(last_use);
+                *last_use = self.clock;
+                self.by_use.insert(self.clock, *key);
+                self.hits += 1;
+                Some(*score)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: [i64; N], score: f64, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if let Some((_, last_use)) = self.entries.insert(key, (score, self.clock)) {
+            // another thread scored the same cell in the meantime
+            self.by_use.remove(&last_use);
+        }
+        self.by_use.insert(self.clock, key);
+
+        while self.entries.len() > capacity {
+            match self.by_use.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedScorer;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let values: Vec<_> = (0..100)
+            .map(|i| [(i % 10) as f64, (i / 10) as f64])
+            .collect();
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+        let scorer = CachedScorer::new(forest, 0.1, 2);
+
+        let a = scorer.score(&[1.0, 1.0]);
+        scorer.score(&[5.0, 5.0]);
+        assert_eq!(scorer.score(&[1.02, 0.98]), a);
+        assert_eq!((scorer.hits(), scorer.misses()), (1, 2));
+
+        // evicts [5.0, 5.0], which has been used less recently than [1.0, 1.0]
+        scorer.score(&[20.0, 20.0]);
+        assert_eq!(scorer.len(), 2);
+        scorer.score(&[1.0, 1.0]);
+        scorer.score(&[5.0, 5.0]);
+        assert_eq!((scorer.hits(), scorer.misses()), (2, 4));
+
+        // cached scores are the scores of the forest
+        assert_eq!(
+            scorer.score(&[5.0, 5.0]),
+            scorer.forest().score(&[5.0, 5.0])
+        );
+        scorer.score(&[f64::NAN, 1.0]);
+        assert_eq!(scorer.len(), 2);
+    }
+}
@@ -0,0 +1,433 @@
+//! Removing the parts of trees no sample can reach.
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{
+    determinate_direction, to_f64, Direction, Forest, InNode, Node, SampleAccess, SparseSample,
+};
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Prune the unreachable parts of all trees and normalize the encoding of the hyperplanes.
+    /// Returns the number of removed nodes.
+    ///
+    /// Trees imported from other implementations, or built on data with few distinct values,
+    /// can contain splits repeating a decision taken above them, for example `x ≤ 5` below
+    /// `x ≤ 3`, so that one of their children is unreachable. These splits are removed together
+    /// with the unreachable child, and the remaining child gets a depth offset keeping all path
+    /// lengths the same. Only axis-parallel splits are analyzed.
+    ///
+    /// Zeros are stored as positive zeros, NaNs as the canonical NaN, and intercepts of
+    /// dimensions without a normal component as zero where that makes no difference, so forests
+    /// taking the same decisions are stored identically.
+    ///
+    /// The scores of all dense samples - arrays, vectors, memory-mapped rows - and of
+    /// [`SparseSample`]s stay exactly the same, including samples with missing or infinite
+    /// values.
+    pub fn canonicalize(&mut self) -> usize {
+        let mut removed = 0;
+        for tree in self.trees.iter_mut() {
+            let root = tree.take_root();
+            tree.root = canonicalize_tree(root, &mut removed);
+        }
+        removed
+    }
+}
+
+/// A step of the iterative rebuilding of a tree.
+enum Step<T, const N: usize, L> {
+    /// Rebuild the node reached by the samples of the region, below `depth_offset` levels of
+    /// removed nodes.
+    Visit {
+        node: Node<T, N, L>,
+        region: Region<T, N>,
+        depth_offset: usize,
+    },
+
+    /// Combine the two most recently rebuilt nodes into an internal node.
+    Join {
+        n: [T; N],
+        p: [T; N],
+        depth_offset: usize,
+    },
+}
+
+fn canonicalize_tree<T, L, const N: usize>(
+    root: Node<T, N, L>,
+    removed: &mut usize,
+) -> Node<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    let mut steps = vec![Step::Visit {
+        node: root,
+        region: Region::all(),
+        depth_offset: 0,
+    }];
+    let mut built = vec![];
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Visit {
+                node: Node::Ex(mut ex_node),
+                depth_offset,
+                ..
+            } => {
+                ex_node.depth_offset += depth_offset;
+                built.push(Node::Ex(ex_node));
+            }
+            Step::Visit {
+                node: Node::In(in_node),
+                region,
+                depth_offset,
+            } => {
+                let InNode {
+                    left,
+                    right,
+                    mut n,
+                    mut p,
+                    depth_offset: own_depth_offset,
+                    ..
+                } = in_node;
+                normalize_hyperplane(&mut n, &mut p);
+                let depth_offset = depth_offset + own_depth_offset;
+
+                let (left_region, right_region) = region.split(&n, &p);
+                let (kept, kept_region, dropped) = match (left_region, right_region) {
+                    (Some(left_region), None) => (left, left_region, right),
+                    (None, Some(right_region)) => (right, right_region, left),
+                    (left_region, right_region) => {
+                        steps.push(Step::Join { n, p, depth_offset });
+                        steps.push(Step::Visit {
+                            node: *right,
+                            region: right_region.unwrap_or_else(|| region.clone()),
+                            depth_offset: 0,
+                        });
+                        steps.push(Step::Visit {
+                            node: *left,
+                            region: left_region.unwrap_or(region),
+                            depth_offset: 0,
+                        });
+                        continue;
+                    }
+                };
+                *removed += 1 + dropped.iter().count();
+                dropped.dismantle();
+                steps.push(Step::Visit {
+                    node: *kept,
+                    region: kept_region,
+                    depth_offset: depth_offset + 1,
+                });
+            }
+            Step::Join { n, p, depth_offset } => {
+                let right = built.pop().expect("right child has been rebuilt");
+                let left = built.pop().expect("left child has been rebuilt");
+                built.push(Node::In(InNode::new(left, right, n, p, depth_offset)));
+            }
+        }
+    }
+    built.pop().expect("root has been rebuilt")
+}
+
+fn normalize_hyperplane<T: Float, const N: usize>(n: &mut [T; N], p: &mut [T; N]) {
+    for (n_i, p_i) in n.iter_mut().zip(p.iter_mut()) {
+        // without a normal component the intercept only matters when subtracting it from a
+        // finite value overflows, which the canonical zero never does
+        if n_i.is_zero() && (T::max_value() + p_i.abs()).is_finite() {
+            *p_i = T::zero();
+        }
+        *n_i = canonical_float(*n_i);
+        *p_i = canonical_float(*p_i);
+    }
+}
+
+fn canonical_float<T: Float>(value: T) -> T {
+    if value.is_nan() {
+        T::nan()
+    } else if value.is_zero() {
+        T::zero()
+    } else {
+        value
+    }
+}
+
+/// The inclusive range of values of a dimension, `None` if it is empty.
+type Interval<T> = Option<(T, T)>;
+
+/// What is known about the samples reaching a node from the axis-parallel splits above it.
+///
+/// Per dimension `k`, samples with finite values in all other dimensions and any value but NaN
+/// in `k` - the samples whose side at axis-parallel splits along `k` only depends on their value
+/// in `k` - are restricted to an interval of values. The intervals of dense and sparse samples
+/// are tracked separately as both compute the sides slightly differently. All other samples
+/// are sent to the right by these splits.
+#[derive(Clone)]
+struct Region<T, const N: usize> {
+    dense: [Interval<T>; N],
+    sparse: [Interval<T>; N],
+
+    /// Whether only samples restricted by the intervals reach the node, because they have been
+    /// sent to the left by an axis-parallel split along the dimension.
+    restricted: [bool; N],
+}
+
+impl<T: Float, const N: usize> Region<T, N> {
+    fn all() -> Self {
+        let all = Some((T::neg_infinity(), T::infinity()));
+        Self {
+            dense: [all; N],
+            sparse: [all; N],
+            restricted: [false; N],
+        }
+    }
+
+    /// The regions of the children of the split, `None` for unreachable children.
+    fn split(&self, n: &[T; N], p: &[T; N]) -> (Option<Self>, Option<Self>) {
+        let Some(k) = axis(n, p) else {
+            return (Some(self.clone()), Some(self.clone()));
+        };
+        let (dense_left, dense_right) = split_interval(self.dense[k], |x| {
+            let mut sample = [T::zero(); N];
+            sample[k] = x;
+            goes_left(&sample, n, p)
+        });
+        let (sparse_left, sparse_right) = split_interval(self.sparse[k], |x| {
+            let sample = SparseSample::<T, N>::new([(k, x)]).expect("dimension is in range");
+            goes_left(&sample, n, p)
+        });
+
+        let left = (dense_left.is_some() || sparse_left.is_some()).then(|| {
+            let mut left = self.clone();
+            left.dense[k] = dense_left;
+            left.sparse[k] = sparse_left;
+            left.restricted[k] = true;
+            left
+        });
+        let right =
+            (dense_right.is_some() || sparse_right.is_some() || !self.restricted[k]).then(|| {
+                let mut right = self.clone();
+                right.dense[k] = dense_right;
+                right.sparse[k] = sparse_right;
+                right
+            });
+        (left, right)
+    }
+}
+
+/// The dimension of an axis-parallel split whose side only depends on the value in that
+/// dimension for samples with finite values in all other dimensions.
+fn axis<T: Float, const N: usize>(n: &[T; N], p: &[T; N]) -> Option<usize> {
+    let mut components = (0..N).filter(|i| !n[*i].is_zero());
+    let k = components.next()?;
+    let only_component = components.next().is_none();
+    let finite = n[k].is_finite() && p[k].is_finite();
+    let zero_intercepts = (0..N).all(|i| i == k || p[i].is_zero());
+    (only_component && finite && zero_intercepts).then_some(k)
+}
+
+fn goes_left<T, S, const N: usize>(sample: &S, n: &[T; N], p: &[T; N]) -> bool
+where
+    T: Float,
+    S: SampleAccess<T, N>,
+{
+    matches!(determinate_direction(sample, n, p), Direction::Left)
+}
+
+/// Split the interval into the values going to the left and to the right. `goes_left` is
+/// monotone in the value, so both parts are intervals again.
+fn split_interval<T: Float>(
+    interval: Interval<T>,
+    goes_left: impl Fn(T) -> bool,
+) -> (Interval<T>, Interval<T>) {
+    let Some((lo, hi)) = interval else {
+        return (None, None);
+    };
+    match (goes_left(lo), goes_left(hi)) {
+        (true, true) => (interval, None),
+        (false, false) => (None, interval),
+        (true, false) => {
+            let (last_left, first_right) = boundary(lo, hi, &goes_left);
+            (Some((lo, last_left)), Some((first_right, hi)))
+        }
+        (false, true) => {
+            let (last_right, first_left) = boundary(lo, hi, &goes_left);
+            (Some((first_left, hi)), Some((lo, last_right)))
+        }
+    }
+}
+
+/// The adjacent values `a < b` between `lo` and `hi` where the monotone predicate changes,
+/// `predicate(a) == predicate(lo)` and `predicate(b) == predicate(hi)`.
+///
+/// The search runs over the totally ordered bit patterns of `f64`. Every float type of the
+/// crate converts to `f64` exactly and back monotonically, so the search ends on adjacent
+/// values of `T`.
+fn boundary<T: Float>(lo: T, hi: T, predicate: impl Fn(T) -> bool) -> (T, T) {
+    let at_lo = predicate(lo);
+    let (mut lo, mut hi) = (order_key(to_f64(lo)), order_key(to_f64(hi)));
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if predicate(from_f64(from_order_key(mid))) == at_lo {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (from_f64(from_order_key(lo)), from_f64(from_order_key(hi)))
+}
+
+fn from_f64<T: Float>(value: f64) -> T {
+    T::from(value).unwrap_or_else(T::nan)
+}
+
+/// Maps floats other than NaN to integers in the same order.
+fn order_key(value: f64) -> u64 {
+    let bits = value.to_bits();
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | 1 << 63
+    }
+}
+
+fn from_order_key(key: u64) -> f64 {
+    f64::from_bits(if key >> 63 == 1 {
+        key & !(1 << 63)
+    } else {
+        !key
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+
+    use crate::eif::{EifForest, EifNode};
+    use crate::{Forest, SparseSample};
+
+    /// Interesting values for intercepts and samples.
+    const VALUES: [f64; 12] = [
+        0.0,
+        -0.0,
+        1.0,
+        3.0,
+        -3.0,
+        5.0,
+        3.0000000000000004,
+        1e-310,
+        1e308,
+        -1e308,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+    ];
+
+    fn random_tree(rng: &mut impl Rng, depth: usize) -> EifNode<f64> {
+        if depth == 0 || rng.gen_bool(0.2) {
+            return EifNode::ExNode {
+                size: rng.gen_range(0..4),
+            };
+        }
+        let k = rng.gen_range(0..2);
+        let mut n = vec![*[0.0, -0.0].choose(rng).unwrap(); 2];
+        n[k] = *[1.0, -1.0, 0.5, -2.0, 1e-300].choose(rng).unwrap();
+        if rng.gen_bool(0.1) {
+            n[1 - k] = 0.3;
+        }
+        let mut p: Vec<f64> = (0..2)
+            .map(|_| *[0.0, -0.0, 2.0, 1e308].choose(rng).unwrap())
+            .collect();
+        p[k] = *VALUES[..10].choose(rng).unwrap();
+        EifNode::InNode {
+            n,
+            p,
+            left: Box::new(random_tree(rng, depth - 1)),
+            right: Box::new(random_tree(rng, depth - 1)),
+        }
+    }
+
+    #[test]
+    fn canonicalize_keeps_scores() {
+        let rng = &mut rand::thread_rng();
+        let mut samples: Vec<[f64; 2]> = VALUES
+            .iter()
+            .chain([f64::NAN, 2.9, 4.0, -1e-310].iter())
+            .flat_map(|a| VALUES.iter().map(move |b| [*a, *b]))
+            .collect();
+        samples.extend((0..100).map(|_| [rng.gen_range(-6.0..6.0), rng.gen_range(-6.0..6.0)]));
+
+        let mut removed = 0;
+        for _ in 0..50 {
+            let eif_forest = EifForest {
+                sample_size: 16,
+                trees: (0..4).map(|_| random_tree(rng, 7)).collect(),
+            };
+            let original: Forest<f64, 2> = Forest::from_eif(&eif_forest).unwrap();
+            let mut canonical: Forest<f64, 2> = Forest::from_eif(&eif_forest).unwrap();
+            removed += canonical.canonicalize();
+            assert_eq!(canonical.canonicalize(), 0);
+
+            for sample in &samples {
+                let sparse = SparseSample::<f64, 2>::new([(0, sample[0]), (1, sample[1])]).unwrap();
+                for cap in [0, 1, 2, 3, 5, 8] {
+                    assert_eq!(
+                        canonical.score_with_recursion_cap(sample, cap).to_bits(),
+                        original.score_with_recursion_cap(sample, cap).to_bits(),
+                        "{:?} {}",
+                        sample,
+                        cap
+                    );
+                    assert_eq!(
+                        canonical.score_with_recursion_cap(&sparse, cap).to_bits(),
+                        original.score_with_recursion_cap(&sparse, cap).to_bits(),
+                        "{:?} {}",
+                        sample,
+                        cap
+                    );
+                }
+            }
+        }
+        assert!(removed > 0);
+    }
+
+    #[test]
+    fn remove_repeated_decision() {
+        let leaf = |size| Box::new(EifNode::ExNode { size });
+        let mut forest: Forest<f64, 1> = Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0],
+                p: vec![3.0],
+                left: Box::new(EifNode::InNode {
+                    n: vec![2.0],
+                    p: vec![5.0],
+                    left: Box::new(EifNode::InNode {
+                        n: vec![-1.0],
+                        p: vec![-0.0],
+                        left: leaf(2),
+                        right: leaf(3),
+                    }),
+                    right: leaf(0),
+                }),
+                right: leaf(3),
+            }],
+        })
+        .unwrap();
+        let scores: Vec<_> = [-1.0, 1.0, 4.0]
+            .iter()
+            .map(|x| forest.score(&[*x]))
+            .collect();
+        assert_eq!(forest.canonicalize(), 2);
+        assert_eq!(
+            [-1.0, 1.0, 4.0]
+                .iter()
+                .map(|x| forest.score(&[*x]))
+                .collect::<Vec<_>>(),
+            scores
+        );
+    }
+}
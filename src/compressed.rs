@@ -0,0 +1,356 @@
+//! A compact in-memory representation of forests, for deployments holding many models.
+//!
+//! [`Forest::compress`] encodes every tree into a single byte buffer in pre-order. The normal
+//! vectors of the hyperplanes are quantized to 8-bit weights relative to their largest
+//! component, only the non-zero weights are stored together with the deltas of their feature
+//! indices, and the offsets of the hyperplanes shrink to `f32`. Leaves keep their number of
+//! samples only, so retained samples and leaf models are dropped. The nodes are decoded while a
+//! sample traverses a tree, no tree is ever expanded.
+//!
+//! The quantization moves some samples to the other side of a hyperplane, so compressed scores
+//! differ slightly from those of the forest. [`CompressedForest::drift`] compares both on a set of
+//! samples.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let compressed = forest.compress();
+//! assert!(compressed.score(&[50.0, 50.0]) > compressed.score(&[4.5, 4.5]));
+//!
+//! let report = compressed.drift(&forest, &values);
+//! assert!(report.compression_ratio() > 1.0);
+//! assert!(report.max_abs_drift < 0.1);
+//! ```
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{c_factor, normalize_path_length, to_f64, Forest, Node, SampleAccess};
+
+/// Tag of an encoded leaf.
+const LEAF: u8 = 0;
+
+/// Tag of an encoded internal node.
+const INTERNAL: u8 = 1;
+
+/// The largest magnitude of a quantized weight.
+const MAX_WEIGHT: f64 = i8::MAX as f64;
+
+/// A forest with quantized hyperplanes decoded during traversal, see the
+/// [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedForest<const N: usize> {
+    /// The encoded trees.
+    trees: Vec<Box<[u8]>>,
+
+    /// The average path length of the training data, see `Forest::avg_path_length_c`.
+    avg_path_length_c: f64,
+
+    /// Paths are truncated at this depth, the recursion cap of the compressed forest.
+    max_depth: usize,
+}
+
+/// Differences between the scores of a forest and its compressed representation, see
+/// [`CompressedForest::drift`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DriftReport {
+    /// Number of compared samples.
+    pub samples: usize,
+
+    /// The mean absolute difference of the scores, `0` without samples.
+    pub mean_abs_drift: f64,
+
+    /// The largest absolute difference of the scores, `0` without samples.
+    pub max_abs_drift: f64,
+
+    /// Approximate number of bytes occupied by the trees of the forest.
+    pub forest_bytes: usize,
+
+    /// Number of bytes occupied by the compressed trees.
+    pub compressed_bytes: usize,
+}
+
+impl DriftReport {
+    /// How many times smaller the compressed trees are than those of the forest.
+    pub fn compression_ratio(&self) -> f64 {
+        self.forest_bytes as f64 / self.compressed_bytes.max(1) as f64
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Encode the trees into a [`CompressedForest`] using the current recursion cap.
+    ///
+    /// Suppressed regions, monotone constraints, the range policy and strict floating point
+    /// arithmetic of the forest are not carried over, samples are scored by the trees alone.
+    pub fn compress(&self) -> CompressedForest<N> {
+        CompressedForest {
+            trees: self
+                .trees
+                .iter()
+                .map(|tree| encode(&tree.root).into_boxed_slice())
+                .collect(),
+            avg_path_length_c: self.avg_path_length_c,
+            max_depth: self.recursion_cap(),
+        }
+    }
+
+    /// Approximate number of bytes occupied by the nodes of all trees, including retained
+    /// samples.
+    pub(crate) fn tree_bytes(&self) -> usize {
+        self.trees
+            .iter()
+            .flat_map(|tree| tree.root.iter())
+            .map(|(node, _)| {
+                std::mem::size_of::<Node<T, N, L>>()
+                    + match node {
+                        Node::Ex(ex_node) => {
+                            ex_node.samples.capacity() * std::mem::size_of::<[T; N]>()
+                        }
+                        Node::In(_) => 0,
+                    }
+            })
+            .sum()
+    }
+}
+
+impl<const N: usize> CompressedForest<N> {
+    /// The anomaly score of the sample, see [`Forest::score`].
+    pub fn score<T, S>(&self, sample: &S) -> f64
+    where
+        T: Float,
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let values: [f64; N] = std::array::from_fn(|i| to_f64(sample.feature(i)));
+        let path_length: f64 = self
+            .trees
+            .iter()
+            .map(|tree| self.path_length(tree, &values))
+            .sum();
+        normalize_path_length(
+            path_length / self.trees.len().max(1) as f64,
+            self.avg_path_length_c,
+        )
+    }
+
+    /// Number of bytes occupied by the encoded trees.
+    pub fn memory_bytes(&self) -> usize {
+        self.trees.iter().map(|tree| tree.len()).sum()
+    }
+
+    /// Compare the scores of the samples by the forest and its compressed representation.
+    pub fn drift<T, L, S>(&self, forest: &Forest<T, N, L>, samples: &[S]) -> DriftReport
+    where
+        T: Float,
+        L: LeafModel<T, N>,
+        S: SampleAccess<T, N>,
+    {
+        let (mut sum, mut max) = (0.0, 0.0_f64);
+        for sample in samples.iter() {
+            let drift = (forest.score(sample) - self.score(sample)).abs();
+            sum += drift;
+            max = max.max(drift);
+        }
+        DriftReport {
+            samples: samples.len(),
+            mean_abs_drift: if samples.is_empty() {
+                0.0
+            } else {
+                sum / samples.len() as f64
+            },
+            max_abs_drift: max,
+            forest_bytes: forest.tree_bytes(),
+            compressed_bytes: self.memory_bytes(),
+        }
+    }
+
+    /// Path length of the sample through the encoded tree, like `Tree::path_length_with_cap`.
+    fn path_length(&self, tree: &[u8], values: &[f64; N]) -> f64 {
+        let max_depth = self.max_depth;
+        let (mut position, mut depth) = (0, 0);
+        loop {
+            let tag = tree[position];
+            position += 1;
+            let depth_offset = read_varint(tree, &mut position);
+            if depth_offset > 0 && depth + depth_offset > max_depth {
+                return max_depth as f64;
+            }
+            depth += depth_offset;
+            if tag == LEAF {
+                return depth as f64 + c_factor(read_varint(tree, &mut position));
+            }
+            if depth >= max_depth {
+                return depth as f64;
+            }
+
+            let left_len = read_varint(tree, &mut position);
+            let (mut feature, mut dot) = (0, 0.0);
+            for _ in 0..read_varint(tree, &mut position) {
+                feature += read_varint(tree, &mut position);
+                dot += values[feature] * f64::from(tree[position] as i8);
+                position += 1;
+            }
+            let mut offset = [0; 4];
+            offset.copy_from_slice(&tree[position..position + 4]);
+            position += 4;
+            if dot > f64::from(f32::from_le_bytes(offset)) {
+                position += left_len;
+            }
+            depth += 1;
+        }
+    }
+}
+
+/// A step of the iterative encoding in [`encode`].
+enum EncodeStep<'a, T, const N: usize, L> {
+    /// Encode the subtree of the node.
+    Encode(&'a Node<T, N, L>),
+
+    /// Combine the two most recently encoded subtrees with the internal node.
+    Join(&'a Node<T, N, L>),
+}
+
+/// Encode the tree in pre-order without recursion: the left subtree of an internal node
+/// follows its header directly, the right subtree follows the left one.
+///
+/// A leaf is encoded as its tag, depth offset and number of samples, an internal node as its
+/// tag, depth offset, the length of its left subtree, the number of non-zero weights, the
+/// feature index delta and weight of each of them, and the offset `p · w` of the hyperplane.
+/// All integers are LEB128 varints. A sample `x` goes right if `x · w > p · w`, like
+/// `(x - p) · n > 0` for the original normal vector `n`.
+fn encode<T, const N: usize, L>(root: &Node<T, N, L>) -> Vec<u8>
+where
+    T: Float,
+{
+    let mut steps = vec![EncodeStep::Encode(root)];
+    let mut encoded: Vec<Vec<u8>> = Vec::new();
+    while let Some(step) = steps.pop() {
+        match step {
+            EncodeStep::Encode(Node::Ex(ex_node)) => {
+                let mut bytes = vec![LEAF];
+                write_varint(&mut bytes, ex_node.depth_offset);
+                write_varint(&mut bytes, ex_node.num_samples);
+                encoded.push(bytes);
+            }
+            EncodeStep::Encode(node @ Node::In(in_node)) => {
+                steps.push(EncodeStep::Join(node));
+                steps.push(EncodeStep::Encode(&in_node.right));
+                steps.push(EncodeStep::Encode(&in_node.left));
+            }
+            EncodeStep::Join(Node::In(in_node)) => {
+                let right = encoded.pop().unwrap_or_default();
+                let left = encoded.pop().unwrap_or_default();
+                let scale = in_node
+                    .n
+                    .iter()
+                    .map(|n_i| to_f64(*n_i).abs())
+                    .fold(0.0, f64::max);
+                let weights: Vec<(usize, i8)> = in_node
+                    .n
+                    .iter()
+                    .enumerate()
+                    .filter(|_| scale > 0.0)
+                    .map(|(i, n_i)| (i, (to_f64(*n_i) / scale * MAX_WEIGHT).round() as i8))
+                    .filter(|(_, weight)| *weight != 0)
+                    .collect();
+                let offset: f64 = weights
+                    .iter()
+                    .map(|(i, weight)| to_f64(in_node.p[*i]) * f64::from(*weight))
+                    .sum();
+
+                let mut bytes =
+                    Vec::with_capacity(16 + 2 * weights.len() + left.len() + right.len());
+                bytes.push(INTERNAL);
+                write_varint(&mut bytes, in_node.depth_offset);
+                write_varint(&mut bytes, left.len());
+                write_varint(&mut bytes, weights.len());
+                let mut previous = 0;
+                for (i, weight) in weights.iter() {
+                    write_varint(&mut bytes, i - previous);
+                    bytes.push(*weight as u8);
+                    previous = *i;
+                }
+                bytes.extend_from_slice(&(offset as f32).to_le_bytes());
+                bytes.extend_from_slice(&left);
+                bytes.extend_from_slice(&right);
+                encoded.push(bytes);
+            }
+            EncodeStep::Join(Node::Ex(_)) => unreachable!("only internal nodes are joined"),
+        }
+    }
+    encoded.pop().unwrap_or_default()
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> usize {
+    let (mut value, mut shift) = (0, 0);
+    loop {
+        let byte = bytes[*position];
+        *position += 1;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_varint, write_varint};
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn compressed_scores_follow_forest() {
+        let mut bytes = Vec::new();
+        for value in [0, 127, 128, 300, usize::MAX] {
+            write_varint(&mut bytes, value);
+        }
+        let position = &mut 0;
+        for value in [0, 127, 128, 300, usize::MAX] {
+            assert_eq!(read_varint(&bytes, position), value);
+        }
+        assert_eq!(*position, bytes.len());
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, (i % 3) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            seed: Some(4),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        let compressed = forest.compress();
+        let report = compressed.drift(&forest, &values);
+        assert_eq!(report.samples, 400);
+        assert!(report.mean_abs_drift < 0.01, "{:?}", report);
+        assert!(report.max_abs_drift < 0.05, "{:?}", report);
+        assert!(report.compression_ratio() > 4.0, "{:?}", report);
+        assert_eq!(report.compressed_bytes, compressed.memory_bytes());
+
+        let outlier = [40.0, -20.0, 1.0];
+        assert!((compressed.score(&outlier) - forest.score(&outlier)).abs() < 0.05);
+        assert!(compressed.score(&outlier) > compressed.score(&[10.0, 10.0, 1.0]));
+
+        // truncated paths end at the recursion cap of the forest
+        forest.set_recursion_cap(Some(1));
+        let capped = forest.compress();
+        let expected = forest.score(&outlier);
+        assert!((capped.score(&outlier) - expected).abs() < 0.05);
+    }
+}
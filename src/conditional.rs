@@ -0,0 +1,90 @@
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{Forest, SampleAccess};
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Compute how anomalous the behavior of the sample is given its context. `context` marks
+    /// the context dimensions, like the day of the week, all other dimensions are behavior.
+    ///
+    /// [`Forest::score`] scores the sample jointly, so rare contexts raise the score of samples
+    /// behaving normally for them: weekend traffic is flagged because there are fewer
+    /// weekends than weekdays. Here the path length of the sample is compared to the path
+    /// length expected for the training samples sharing its context, computed like
+    /// [`Forest::score_masked`] with the behavior dimensions unknown. The score is
+    /// `2^(-h / h_context)` for the mean path length `h` of the sample and `h_context` with
+    /// the behavior masked: samples behaving typically for their context score about `0.5`,
+    /// samples isolated faster than their context more.
+    ///
+    /// ```rust
+    /// use extended_isolation_forest::{Forest, ForestOptions};
+    ///
+    /// // weekdays (0) with high and weekends (1) with low traffic
+    /// let values: Vec<_> = (0..500)
+    ///     .map(|i| match i % 5 {
+    ///         0 => [1.0, 20.0 + (i % 11) as f64],
+    ///         _ => [0.0, 100.0 + (i % 21) as f64],
+    ///     })
+    ///     .collect();
+    /// let options = ForestOptions {
+    ///     n_trees: 200,
+    ///     sample_size: 128,
+    ///     ..Default::default()
+    /// };
+    /// let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+    ///
+    /// let context = [true, false];
+    /// let normal_weekend = forest.score_conditional(&[1.0, 25.0], &context);
+    /// assert!(forest.score_conditional(&[1.0, 110.0], &context) > normal_weekend);
+    /// ```
+    pub fn score_conditional<S>(&self, sample: &S, context: &[bool; N]) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let path_length = self.masked_path_length(sample, &[false; N]);
+        let context_path_length = self.masked_path_length(sample, &context.map(|c| !c));
+        if context_path_length > 0.0 {
+            2.0f64.powf(-path_length / context_path_length)
+        } else {
+            0.5
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn rare_contexts_do_not_raise_conditional_scores() {
+        let values: Vec<_> = (0..500)
+            .map(|i| match i % 5 {
+                0 => [1.0, 20.0 + (i % 11) as f64],
+                _ => [0.0, 100.0 + (i % 21) as f64],
+            })
+            .collect();
+        let options = ForestOptions {
+            n_trees: 200,
+            sample_size: 128,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let context = [true, false];
+
+        let weekend = forest.score_conditional(&[1.0, 25.0], &context);
+        let weekday = forest.score_conditional(&[0.0, 110.0], &context);
+        let busy_weekend = forest.score_conditional(&[1.0, 110.0], &context);
+        let joint_gap = forest.score(&[1.0, 25.0]) - forest.score(&[0.0, 110.0]);
+        assert!(joint_gap > 0.0);
+        assert!((weekend - weekday).abs() < joint_gap);
+        assert!(busy_weekend > weekend + 0.05);
+
+        // without context the conditional score compares to the mean path length of all samples
+        let unconditional = forest.score_conditional(&[1.0, 110.0], &[false; 2]);
+        assert!(unconditional > 0.5);
+    }
+}
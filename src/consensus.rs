@@ -0,0 +1,184 @@
+//! Scoring with several independently trained forests.
+//!
+//! The score of a single forest varies with the random choices made while training it, so
+//! samples close to an alerting threshold cross it back and forth when the forest is retrained.
+//! A [`Consensus`] scores with several forests, trained with different options or simply again
+//! from the same data, and reports how much they agree alongside the combined score. Alerting
+//! only when the forests agree keeps the alerts from flapping.
+//!
+//! ```rust
+//! use extended_isolation_forest::consensus::Consensus;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let values: Vec<_> = (0..400).map(|i| [(i % 20) as f64, (i / 20) as f64]).collect();
+//! let options = vec![ForestOptions::default(); 5];
+//! let consensus = Consensus::from_slice(&values, &options).unwrap().with_threshold(0.53);
+//!
+//! let score = consensus.score(&[100.0, 100.0]);
+//! assert!(score.is_anomaly);
+//! assert_eq!(score.agreement, 1.0);
+//! assert!(score.min <= score.score && score.score <= score.max);
+//! ```
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Result of scoring a sample with a [`Consensus`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConsensusScore {
+    /// The mean of the scores of the forests.
+    pub score: f64,
+
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+
+    /// The standard deviation of the scores of the forests.
+    pub std_dev: f64,
+
+    /// The combined score exceeded the threshold.
+    pub is_anomaly: bool,
+
+    /// The fraction of forests deciding like the combined score, in `[0.5, 1]` for an odd
+    /// number of forests.
+    pub agreement: f64,
+}
+
+/// Forests trained independently on the same data, see the [module documentation](self).
+pub struct Consensus<T, const N: usize> {
+    forests: Vec<Forest<T, N>>,
+    threshold: f64,
+}
+
+impl<'de, T, const N: usize> Consensus<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Train one forest per entry of `options` from the same training data.
+    pub fn from_slice<S>(training_data: &[S], options: &[ForestOptions]) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let forests = options
+            .iter()
+            .map(|options| Forest::from_slice(training_data, options))
+            .collect::<Result<_, _>>()?;
+        Self::new(forests)
+    }
+}
+
+impl<T, const N: usize> Consensus<T, N>
+where
+    T: Float,
+{
+    /// Combine forests trained on the same data, failing with [`Error::EmptyData`] without
+    /// any. The threshold is `0.5`, see [`Consensus::with_threshold`].
+    pub fn new(forests: Vec<Forest<T, N>>) -> Result<Self, Error> {
+        if forests.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        Ok(Self {
+            forests,
+            threshold: 0.5,
+        })
+    }
+
+    /// Decide samples scoring above `threshold` to be anomalies.
+    pub fn with_threshold(self, threshold: f64) -> Self {
+        Self { threshold, ..self }
+    }
+
+    /// Score the sample with every forest and combine the scores.
+    pub fn score<S>(&self, sample: &S) -> ConsensusScore
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut scores: Vec<f64> = self
+            .forests
+            .iter()
+            .map(|forest| forest.score(sample))
+            .collect();
+        scores.sort_by(f64::total_cmp);
+
+        let n = scores.len() as f64;
+        let mean = scores.iter().sum::<f64>() / n;
+        let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        let median = (scores[(scores.len() - 1) / 2] + scores[scores.len() / 2]) / 2.0;
+        let is_anomaly = mean > self.threshold;
+        let agreeing = scores
+            .iter()
+            .filter(|score| (**score > self.threshold) == is_anomaly)
+            .count();
+        ConsensusScore {
+            score: mean,
+            median,
+            min: scores[0],
+            max: scores[scores.len() - 1],
+            std_dev: variance.sqrt(),
+            is_anomaly,
+            agreement: agreeing as f64 / n,
+        }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn forests(&self) -> &[Forest<T, N>] {
+        &self.forests
+    }
+
+    pub fn into_inner(self) -> Vec<Forest<T, N>> {
+        self.forests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Consensus;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn forests_agree_on_clear_cases() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = vec![
+            ForestOptions {
+                n_trees: 50,
+                ..Default::default()
+            };
+            4
+        ];
+        let consensus = Consensus::from_slice(values.as_slice(), &options)
+            .unwrap()
+            .with_threshold(0.53);
+        assert_eq!(consensus.forests().len(), 4);
+
+        let outlier = consensus.score(&[200.0, -200.0]);
+        assert!(outlier.is_anomaly);
+        assert_eq!(outlier.agreement, 1.0);
+        let inlier = consensus.score(&[10.0, 10.0]);
+        assert!(!inlier.is_anomaly);
+        assert_eq!(inlier.agreement, 1.0);
+        assert!(inlier.std_dev >= 0.0 && inlier.min <= inlier.median);
+        assert!(inlier.median <= inlier.max);
+
+        let scores: Vec<f64> = consensus
+            .forests()
+            .iter()
+            .map(|forest| forest.score(&[10.0, 10.0]))
+            .collect();
+        let mean = scores.iter().sum::<f64>() / 4.0;
+        assert!((inlier.score - mean).abs() < 1e-12);
+
+        assert!(matches!(
+            Consensus::<f64, 2>::new(Vec::<Forest<f64, 2>>::new()),
+            Err(Error::EmptyData)
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+//! Correlation of the training features, to find redundant features.
+//!
+//! Features which are nearly collinear, like a counter of requests and a counter of responses,
+//! describe the same direction twice. The trees cut along it more often than along the other
+//! features and explanations split the attribution arbitrarily between the redundant features.
+//! [`Correlation`] accumulates the correlation matrix of the samples in a single pass, lists
+//! the [collinear pairs](Correlation::collinear) and [folds](Correlation::fold) every group of
+//! collinear features into a single dimension of a [`Projection`].
+//!
+//! ```rust
+//! use extended_isolation_forest::correlation::Correlation;
+//! use extended_isolation_forest::projection::ProjectedForest;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! // the second feature counts the same events as the first one
+//! let values: Vec<_> = (0..500)
+//!     .map(|i| [(i % 50) as f64, (i % 50) as f64 * 2.0 + 1.0, (i % 7) as f64])
+//!     .collect();
+//! let correlation = Correlation::of(&values);
+//! assert_eq!(correlation.collinear(0.99).len(), 1);
+//! assert_eq!(correlation.groups(0.99), vec![vec![0, 1], vec![2]]);
+//!
+//! let projection = correlation.fold::<f64, 2>(0.99).unwrap();
+//! let forest =
+//!     ProjectedForest::with_projection(projection, &values, &ForestOptions::default()).unwrap();
+//! let anomaly = forest.score(&[200.0, 401.0, 3.0]).unwrap();
+//! assert!(forest.score(&[25.0, 51.0, 3.0]).unwrap() < anomaly);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::projection::{Projection, ProjectionKind};
+use crate::{to_f64, Error, SampleAccess};
+
+/// Streaming accumulator of the Pearson correlation matrix of samples with `N` features.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Correlation<const N: usize> {
+    count: usize,
+    mean: [f64; N],
+
+    /// Sums of the products of the deviations from the mean, row-major.
+    co_moments: Vec<f64>,
+}
+
+/// Two features whose absolute correlation exceeds a threshold, `first < second`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CollinearPair {
+    pub first: usize,
+    pub second: usize,
+
+    /// The correlation coefficient of the features, negative if one falls as the other rises.
+    pub correlation: f64,
+}
+
+impl<const N: usize> Default for Correlation<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Correlation<N> {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: [0.0; N],
+            co_moments: vec![0.0; N * N],
+        }
+    }
+
+    /// Accumulate the correlation of all samples.
+    pub fn of<T, S>(samples: &[S]) -> Self
+    where
+        T: Float,
+        S: SampleAccess<T, N>,
+    {
+        let mut correlation = Self::new();
+        for sample in samples {
+            correlation.push(sample);
+        }
+        correlation
+    }
+
+    /// Add the sample to the accumulated statistics. Samples with missing or infinite features
+    /// are ignored.
+    pub fn push<T, S>(&mut self, sample: &S)
+    where
+        T: Float,
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let values: [f64; N] = std::array::from_fn(|i| to_f64(sample.feature(i)));
+        if values.iter().any(|value| !value.is_finite()) {
+            return;
+        }
+        self.count += 1;
+        let deviations: [f64; N] = std::array::from_fn(|i| values[i] - self.mean[i]);
+        for (mean, deviation) in self.mean.iter_mut().zip(deviations.iter()) {
+            *mean += deviation / self.count as f64;
+        }
+        for (row, deviation) in self.co_moments.chunks_mut(N).zip(deviations.iter()) {
+            for (co_moment, (value, mean)) in
+                row.iter_mut().zip(values.iter().zip(self.mean.iter()))
+            {
+                *co_moment += deviation * (value - mean);
+            }
+        }
+    }
+
+    /// Number of samples accumulated.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The correlation coefficient of the features `i` and `j` in `[-1, 1]`, zero if either
+    /// feature is constant.
+    pub fn coefficient(&self, i: usize, j: usize) -> f64 {
+        let variance = self.co_moments[i * N + i] * self.co_moments[j * N + j];
+        if variance > 0.0 {
+            (self.co_moments[i * N + j] / variance.sqrt()).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// All pairs of features with an absolute correlation of at least `threshold`.
+    pub fn collinear(&self, threshold: f64) -> Vec<CollinearPair> {
+        let mut pairs = Vec::new();
+        for first in 0..N {
+            for second in first + 1..N {
+                let correlation = self.coefficient(first, second);
+                if correlation.abs() >= threshold {
+                    pairs.push(CollinearPair {
+                        first,
+                        second,
+                        correlation,
+                    });
+                }
+            }
+        }
+        pairs
+    }
+
+    /// The features grouped by collinearity: two features are in the same group if a chain of
+    /// collinear pairs connects them. Groups are ordered by their smallest feature.
+    pub fn groups(&self, threshold: f64) -> Vec<Vec<usize>> {
+        let mut group_of: [usize; N] = std::array::from_fn(|i| i);
+        for pair in self.collinear(threshold) {
+            let (keep, merge) = (group_of[pair.first], group_of[pair.second]);
+            for group in group_of.iter_mut() {
+                if *group == merge {
+                    *group = keep;
+                }
+            }
+        }
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for feature in 0..N {
+            match groups
+                .iter_mut()
+                .find(|group| group_of[group[0]] == group_of[feature])
+            {
+                Some(group) => group.push(feature),
+                None => groups.push(vec![feature]),
+            }
+        }
+        groups
+    }
+
+    /// A projection of the samples onto one dimension per [group](Self::groups) of collinear
+    /// features, the mean of the standardized features of the group. Features correlated
+    /// negatively with the first feature of their group are negated. Constant features have
+    /// no weight.
+    ///
+    /// Fails with [`Error::DimensionMismatch`] if the number of groups differs from `K`, or
+    /// with [`Error::EmptyData`] if no sample has been accumulated.
+    pub fn fold<T, const K: usize>(&self, threshold: f64) -> Result<Projection<T, K>, Error>
+    where
+        T: Float,
+    {
+        if self.count == 0 {
+            return Err(Error::EmptyData);
+        }
+        let groups = self.groups(threshold);
+        if groups.len() != K {
+            return Err(Error::DimensionMismatch {
+                expected: groups.len(),
+                actual: K,
+            });
+        }
+        let mut rows = vec![[T::zero(); K]; N];
+        for (dimension, group) in groups.iter().enumerate() {
+            for feature in group {
+                let variance = self.co_moments[feature * N + feature] / self.count as f64;
+                if variance <= 0.0 {
+                    continue;
+                }
+                let sign = self.coefficient(group[0], *feature).signum();
+                let weight = sign / (variance.sqrt() * group.len() as f64);
+                rows[*feature][dimension] = T::from(weight).unwrap_or_else(T::zero);
+            }
+        }
+        Projection::from_weights(ProjectionKind::Folded, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Correlation;
+    use crate::Error;
+
+    #[test]
+    fn streaming_correlation() {
+        let values: Vec<[f64; 4]> = (0..1000)
+            .map(|i| {
+                let x = (i % 100) as f64;
+                [x, 100.0 - 3.0 * x, ((i * 7) % 13) as f64, 5.0]
+            })
+            .collect();
+        let mut correlation = Correlation::new();
+        for sample in values.iter() {
+            correlation.push(sample);
+        }
+        correlation.push(&[f64::NAN, 1.0, 2.0, 3.0]);
+        assert_eq!(correlation.count(), 1000);
+        assert_eq!(correlation, Correlation::of(&values));
+
+        assert!((correlation.coefficient(0, 1) + 1.0).abs() < 1e-9);
+        assert!((correlation.coefficient(2, 2) - 1.0).abs() < 1e-9);
+        assert!(correlation.coefficient(0, 2).abs() < 0.1);
+        assert_eq!(correlation.coefficient(0, 3), 0.0);
+        let pairs = correlation.collinear(0.95);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].first, pairs[0].second), (0, 1));
+        assert_eq!(correlation.groups(0.95), vec![vec![0, 1], vec![2], vec![3]]);
+
+        // both collinear features move the folded dimension in the same direction
+        let projection = correlation.fold::<f64, 3>(0.95).unwrap();
+        let folded = projection.project(&[10.0, 70.0, 0.0, 5.0]).unwrap();
+        let shifted = projection.project(&[11.0, 67.0, 0.0, 5.0]).unwrap();
+        assert!(shifted[0] > folded[0]);
+        assert_eq!((shifted[1], shifted[2]), (folded[1], folded[2]));
+        assert!(matches!(
+            correlation.fold::<f64, 4>(0.95),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 4
+            })
+        ));
+        assert!(matches!(
+            Correlation::<2>::new().fold::<f64, 2>(0.95),
+            Err(Error::EmptyData)
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+//! Search for minimally perturbed, normal versions of anomalous samples.
+//!
+//! [`Forest::counterfactual`] answers "what would have to change for this sample to be normal"
+//! by a greedy coordinate-wise search: in each step every changeable dimension is moved by a few
+//! multiples of a base step in both directions, and the move lowering the score the most per
+//! distance moved is applied. The search stops as soon as the score falls to the target.
+
+use num_traits::Float;
+
+use crate::{to_f64, Forest, SampleAccess};
+
+/// Base step of the search as a fraction of the range of the split intercepts.
+const STEP_FRACTION: f64 = 0.05;
+
+/// Multiples of the base step tried in every search step, to get across plateaus of the score.
+const STEP_MULTIPLES: [f64; 5] = [1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// Restrictions on the changes of the sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraints<T, const N: usize> {
+    /// Dimensions of the sample which may be changed.
+    pub mutable: [bool; N],
+
+    /// Lower limits of the values of the dimensions.
+    pub lower: [Option<T>; N],
+
+    /// Upper limits of the values of the dimensions.
+    pub upper: [Option<T>; N],
+
+    /// Max. number of search steps.
+    pub max_steps: usize,
+}
+
+impl<T, const N: usize> Default for Constraints<T, N>
+where
+    T: Copy,
+{
+    /// All dimensions may be changed without limits, with up to 100 search steps.
+    fn default() -> Self {
+        Self {
+            mutable: [true; N],
+            lower: [None; N],
+            upper: [None; N],
+            max_steps: 100,
+        }
+    }
+}
+
+/// A changed sample found by [`Forest::counterfactual`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counterfactual<T, const N: usize> {
+    /// The changed sample.
+    pub sample: [T; N],
+
+    /// Score of the changed sample.
+    pub score: f64,
+
+    /// Distance to the original sample, as the sum of the per-dimension changes relative to the
+    /// range of the split intercepts.
+    pub distance: f64,
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Search for a changed version of the sample with a score of at most `target_score`.
+    ///
+    /// Returns `None` when no such sample has been found within the constraints.
+    pub fn counterfactual<S>(
+        &self,
+        sample: &S,
+        target_score: f64,
+        constraints: &Constraints<T, N>,
+    ) -> Option<Counterfactual<T, N>>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let ranges: [T; N] = match self.intercept_bounds() {
+            Some((mins, maxs)) => std::array::from_fn(|i| {
+                let range = maxs[i] - mins[i];
+                if range > T::zero() {
+                    range
+                } else {
+                    T::one()
+                }
+            }),
+            None => [T::one(); N],
+        };
+
+        let mut current = Counterfactual {
+            sample: sample.to_array(),
+            score: self.score(sample),
+            distance: 0.0,
+        };
+
+        for _ in 0..constraints.max_steps {
+            if current.score <= target_score {
+                return Some(current);
+            }
+
+            // the move with the largest decrease of the score per distance
+            let mut best: Option<(f64, Counterfactual<T, N>)> = None;
+            for i in (0..N).filter(|i| constraints.mutable[*i]) {
+                for multiple in STEP_MULTIPLES {
+                    for direction in [1.0, -1.0] {
+                        let delta = T::from(direction * multiple * STEP_FRACTION)
+                            .unwrap_or_else(T::zero)
+                            * ranges[i];
+                        let mut value = current.sample[i] + delta;
+                        if let Some(lower) = constraints.lower[i] {
+                            value = value.max(lower);
+                        }
+                        if let Some(upper) = constraints.upper[i] {
+                            value = value.min(upper);
+                        }
+                        let moved = to_f64((value - current.sample[i]).abs() / ranges[i]);
+                        if moved <= 0.0 {
+                            continue;
+                        }
+
+                        let mut candidate = current.sample;
+                        candidate[i] = value;
+                        let score = self.score(&candidate);
+                        let improvement = (current.score - score) / moved;
+                        if improvement > 0.0
+                            && best
+                                .as_ref()
+                                .is_none_or(|(best_improvement, _)| improvement > *best_improvement)
+                        {
+                            best = Some((
+                                improvement,
+                                Counterfactual {
+                                    sample: candidate,
+                                    score,
+                                    distance: current.distance + moved,
+                                },
+                            ));
+                        }
+                    }
+                }
+            }
+
+            match best {
+                Some((_, next)) => current = next,
+                // no move lowers the score
+                None => return None,
+            }
+        }
+        (current.score <= target_score).then_some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::Constraints;
+    use crate::{ExtensionLevel, Forest, ForestOptions};
+
+    fn make_forest() -> Forest<f64, 2> {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let values: Vec<_> = (0..2000)
+            .map(|_| [rng.sample(distribution), rng.sample(distribution)])
+            .collect();
+        Forest::from_slice(
+            values.as_slice(),
+            &ForestOptions {
+                n_trees: 100,
+                sample_size: 200,
+                max_tree_depth: None,
+                extension_level: ExtensionLevel::Fixed(1),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn counterfactual_moves_into_the_data() {
+        let forest = make_forest();
+        let sample = [10.0, 0.0];
+        let target = forest.score(&[0.0, 0.0]) + 0.02;
+        assert!(forest.score(&sample) > target);
+
+        let counterfactual = forest
+            .counterfactual(&sample, target, &Constraints::default())
+            .unwrap();
+        assert!(counterfactual.score <= target);
+        assert!(counterfactual.sample[0] < 4.0);
+        assert!(counterfactual.distance > 0.0);
+    }
+
+    #[test]
+    fn counterfactual_respects_constraints() {
+        let forest = make_forest();
+        let target = forest.score(&[0.0, 0.0]) + 0.02;
+
+        // only the second dimension may change, which can not compensate the first
+        let constraints = Constraints {
+            mutable: [false, true],
+            ..Default::default()
+        };
+        assert!(forest
+            .counterfactual(&[10.0, 0.0], target, &constraints)
+            .is_none());
+    }
+}
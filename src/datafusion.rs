@@ -0,0 +1,197 @@
+//! The `eif_score` SQL function for DataFusion, available with the `datafusion` feature.
+//!
+//! [`register_eif_score`] registers a forest of `f64` as the scalar function
+//! `eif_score(col1, ..., colN)` of a `SessionContext`, taking one argument per feature in the
+//! order of the features. The arguments are coerced to `Float64` and every batch is scored with
+//! [`Forest::score_column_slices`], nulls are scored as missing values.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use datafusion::prelude::SessionContext;
+//! use extended_isolation_forest::datafusion::register_eif_score;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> datafusion::common::Result<()> {
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let ctx = SessionContext::new();
+//! register_eif_score(&ctx, Arc::new(forest));
+//! let scores = ctx
+//!     .sql("SELECT eif_score(x, y) FROM (VALUES (5.0, 5.0), (50.0, 50.0)) AS t (x, y)")
+//!     .await?
+//!     .collect()
+//!     .await?;
+//! assert_eq!(scores[0].num_rows(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, AsArray, Float64Array};
+use datafusion::arrow::datatypes::{DataType, Float64Type};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility,
+};
+use datafusion::prelude::SessionContext;
+
+use crate::Forest;
+
+/// The name of the registered function.
+pub const NAME: &str = "eif_score";
+
+/// Register the forest as the `eif_score` function of the context, see the
+/// [module documentation](self). Replaces functions registered before under this name.
+pub fn register_eif_score<const N: usize>(ctx: &SessionContext, forest: Arc<Forest<f64, N>>) {
+    ctx.register_udf(ScalarUDF::from(EifScore::new(forest)));
+}
+
+/// The `eif_score` function as a DataFusion `ScalarUDFImpl`, for registering it with other
+/// registries or under another name using `ScalarUDF::with_aliases`.
+pub struct EifScore<const N: usize> {
+    forest: Arc<Forest<f64, N>>,
+    signature: Signature,
+}
+
+impl<const N: usize> EifScore<N> {
+    pub fn new(forest: Arc<Forest<f64, N>>) -> Self {
+        Self {
+            forest,
+            signature: Signature::uniform(N, vec![DataType::Float64], Volatility::Immutable),
+        }
+    }
+}
+
+impl<const N: usize> Debug for EifScore<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EifScore")
+            .field("dimensions", &N)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Functions are equal if they score with the same forest.
+impl<const N: usize> PartialEq for EifScore<N> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.forest, &other.forest)
+    }
+}
+
+impl<const N: usize> Eq for EifScore<N> {}
+
+impl<const N: usize> Hash for EifScore<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.forest).hash(state);
+    }
+}
+
+impl<const N: usize> ScalarUDFImpl for EifScore<N> {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Float64)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(&args.args)?;
+        let columns = arrays
+            .iter()
+            .map(|array| {
+                let array = array.as_primitive_opt::<Float64Type>().ok_or_else(|| {
+                    DataFusionError::Internal(format!(
+                        "{} expects Float64 arguments, got {}",
+                        NAME,
+                        array.data_type()
+                    ))
+                })?;
+                Ok(if array.null_count() == 0 {
+                    Cow::Borrowed(array.values().as_ref())
+                } else {
+                    Cow::Owned(
+                        array
+                            .iter()
+                            .map(|value| value.unwrap_or(f64::NAN))
+                            .collect(),
+                    )
+                })
+            })
+            .collect::<Result<Vec<Cow<[f64]>>>>()?;
+        let columns: Vec<&[f64]> = columns.iter().map(|column| column.as_ref()).collect();
+        let scores = self
+            .forest
+            .score_column_slices(&columns)
+            .map_err(|err| DataFusionError::External(Box::new(err)))?;
+        Ok(ColumnarValue::Array(Arc::new(Float64Array::from(scores))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Array, AsArray};
+    use datafusion::arrow::datatypes::Float64Type;
+    use datafusion::prelude::SessionContext;
+
+    use super::register_eif_score;
+    use crate::{Forest, ForestOptions};
+
+    #[tokio::test]
+    async fn score_with_sql() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 64,
+            seed: Some(29),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let expected = [
+            forest.score(&[3.0, 4.0]),
+            forest.score(&[40.0, 40.0]),
+            forest.score(&[f64::NAN, 5.0]),
+        ];
+        let ctx = SessionContext::new();
+        register_eif_score(&ctx, Arc::new(forest));
+
+        let batches = ctx
+            .sql(
+                "SELECT eif_score(x, y) AS score \
+                 FROM (VALUES (3.0, 4), (40.0, 40), (NULL, 5)) AS t (x, y)",
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let scores: Vec<f64> = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Float64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(scores, expected);
+        assert_eq!(batches[0].column(0).null_count(), 0);
+
+        assert!(ctx.sql("SELECT eif_score(1.0)").await.is_err());
+    }
+}
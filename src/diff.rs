@@ -0,0 +1,244 @@
+//! Comparing two forests, for reviewing automatically retrained models.
+//!
+//! [`Forest::diff`] summarizes how a forest differs from another one: its options, the number
+//! of trees, the distribution of the depths at which the trees isolate their training samples
+//! and, on probe samples provided by the caller, its scores. Forests retrained on similar data
+//! have similar depth distributions and highly correlated scores, a large shift points to a
+//! change of the data or the options which should be reviewed before deploying the new model.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     n_trees: 100,
+//!     ..Default::default()
+//! };
+//! let old: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//! let new: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//!
+//! let diff = old.diff(&new, &values);
+//! assert!(!diff.options_changed);
+//! assert!(diff.depth_shift < 0.5);
+//! assert!(diff.scores.unwrap().mean_abs_diff < 0.05);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::eval::scaled_ranks;
+use crate::leaf::LeafModel;
+use crate::{Forest, Node, SampleAccess};
+
+/// Differences between two forests, see [`Forest::diff`]. Pairs hold the value of the forest
+/// `diff` is called on first and of the other forest second.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ForestDiff {
+    /// The forests have been trained with different options.
+    pub options_changed: bool,
+
+    pub n_trees: (usize, usize),
+
+    /// Number of nodes of all trees.
+    pub num_nodes: (usize, usize),
+
+    /// Mean depth at which the training samples of the trees ended in an external node.
+    pub mean_depth: (f64, f64),
+
+    /// Per depth the fraction of the training samples ending in an external node at that depth.
+    pub depth_distribution: (Vec<f64>, Vec<f64>),
+
+    /// The earth mover's distance between the depth distributions, the mean number of levels
+    /// the training samples have to be moved to turn one distribution into the other.
+    pub depth_shift: f64,
+
+    /// Differences of the scores of the probe samples, `None` without probe samples.
+    pub scores: Option<ScoreDiff>,
+}
+
+/// Differences of the scores of two forests on the same probe samples.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScoreDiff {
+    pub num_probes: usize,
+
+    /// Mean of the scores of the other forest minus the scores of this forest.
+    pub mean_shift: f64,
+
+    /// Mean absolute difference of the scores.
+    pub mean_abs_diff: f64,
+
+    /// Largest absolute difference of the scores.
+    pub max_abs_diff: f64,
+
+    /// Index of the probe sample with the largest absolute difference.
+    pub max_abs_diff_probe: usize,
+
+    /// Spearman rank correlation of the scores, `1` if both forests rank the probes equally.
+    pub rank_correlation: f64,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Compare the forest to `other`, scoring the probe samples with both forests. Probes
+    /// should cover normal samples and known anomalies, an empty slice only compares the
+    /// structure.
+    pub fn diff<S>(&self, other: &Self, probes: &[S]) -> ForestDiff
+    where
+        S: SampleAccess<T, N>,
+    {
+        let (nodes, distribution) = self.depth_distribution();
+        let (other_nodes, other_distribution) = other.depth_distribution();
+        let mean_depth = |distribution: &[f64]| {
+            distribution
+                .iter()
+                .enumerate()
+                .map(|(depth, fraction)| depth as f64 * fraction)
+                .sum::<f64>()
+        };
+
+        // the distance between one-dimensional distributions is the area between their CDFs
+        let mut depth_shift = 0.0;
+        let (mut cdf, mut other_cdf) = (0.0, 0.0);
+        for depth in 0..distribution.len().max(other_distribution.len()) {
+            cdf += distribution.get(depth).copied().unwrap_or(0.0);
+            other_cdf += other_distribution.get(depth).copied().unwrap_or(0.0);
+            depth_shift += (cdf - other_cdf).abs();
+        }
+
+        ForestDiff {
+            options_changed: self.options != other.options,
+            n_trees: (self.trees.len(), other.trees.len()),
+            num_nodes: (nodes, other_nodes),
+            mean_depth: (mean_depth(&distribution), mean_depth(&other_distribution)),
+            depth_shift,
+            depth_distribution: (distribution, other_distribution),
+            scores: (!probes.is_empty()).then(|| self.score_diff(other, probes)),
+        }
+    }
+
+    /// The number of nodes and the fraction of training samples per depth of external nodes.
+    fn depth_distribution(&self) -> (usize, Vec<f64>) {
+        let mut nodes = 0;
+        let mut samples_per_depth = Vec::new();
+        for tree in self.trees.iter() {
+            for (node, depth) in tree.root.iter() {
+                nodes += 1;
+                if let Node::Ex(ex_node) = node {
+                    if samples_per_depth.len() <= depth {
+                        samples_per_depth.resize(depth + 1, 0);
+                    }
+                    samples_per_depth[depth] += ex_node.num_samples;
+                }
+            }
+        }
+        let total = samples_per_depth.iter().sum::<usize>().max(1) as f64;
+        (
+            nodes,
+            samples_per_depth
+                .into_iter()
+                .map(|samples| samples as f64 / total)
+                .collect(),
+        )
+    }
+
+    fn score_diff<S>(&self, other: &Self, probes: &[S]) -> ScoreDiff
+    where
+        S: SampleAccess<T, N>,
+    {
+        let scores: Vec<f64> = probes.iter().map(|probe| self.score(probe)).collect();
+        let other_scores: Vec<f64> = probes.iter().map(|probe| other.score(probe)).collect();
+        let num_probes = probes.len() as f64;
+
+        let (mut max_abs_diff, mut max_abs_diff_probe) = (0.0, 0);
+        for (probe, (score, other_score)) in scores.iter().zip(other_scores.iter()).enumerate() {
+            if (other_score - score).abs() > max_abs_diff {
+                max_abs_diff = (other_score - score).abs();
+                max_abs_diff_probe = probe;
+            }
+        }
+
+        let (ranks, other_ranks) = (scaled_ranks(&scores), scaled_ranks(&other_scores));
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / num_probes;
+        let (mean_rank, other_mean_rank) = (mean(&ranks), mean(&other_ranks));
+        let (mut covariance, mut variance, mut other_variance) = (0.0, 0.0, 0.0);
+        for (rank, other_rank) in ranks.iter().zip(other_ranks.iter()) {
+            covariance += (rank - mean_rank) * (other_rank - other_mean_rank);
+            variance += (rank - mean_rank).powi(2);
+            other_variance += (other_rank - other_mean_rank).powi(2);
+        }
+        let rank_correlation = if variance > 0.0 && other_variance > 0.0 {
+            covariance / (variance * other_variance).sqrt()
+        } else {
+            // a constant ranking correlates with nothing but an equally constant one
+            (variance == other_variance) as u8 as f64
+        };
+
+        ScoreDiff {
+            num_probes: probes.len(),
+            mean_shift: mean(&other_scores) - mean(&scores),
+            mean_abs_diff: scores
+                .iter()
+                .zip(other_scores.iter())
+                .map(|(score, other_score)| (other_score - score).abs())
+                .sum::<f64>()
+                / num_probes,
+            max_abs_diff,
+            max_abs_diff_probe,
+            rank_correlation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn diff_of_retrained_forests() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+
+        let same = forest.diff(&forest, &values);
+        assert!(!same.options_changed);
+        assert_eq!(same.depth_shift, 0.0);
+        assert_eq!(same.depth_distribution.0, same.depth_distribution.1);
+        assert!((same.depth_distribution.0.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        let scores = same.scores.unwrap();
+        assert_eq!((scores.num_probes, scores.max_abs_diff), (400, 0.0));
+        assert!((scores.rank_correlation - 1.0).abs() < 1e-9);
+
+        // deeper trees and shifted data
+        let shifted: Vec<_> = values.iter().map(|[x, y]| [x * 10.0 + 100.0, *y]).collect();
+        let retrained: Forest<f64, 2> = Forest::from_slice(
+            &shifted,
+            &ForestOptions {
+                n_trees: 20,
+                sample_size: 256,
+                ..options
+            },
+        )
+        .unwrap();
+        let diff = forest.diff(&retrained, &values);
+        assert!(diff.options_changed);
+        assert_eq!(diff.n_trees, (50, 20));
+        assert!(diff.mean_depth.1 > diff.mean_depth.0 + 1.0);
+        assert!(diff.depth_shift > 1.0);
+        let scores = diff.scores.unwrap();
+        assert!(scores.mean_shift > 0.0);
+        assert!(scores.max_abs_diff >= scores.mean_abs_diff);
+        assert!(forest.diff(&retrained, &[] as &[[f64; 2]]).scores.is_none());
+    }
+}
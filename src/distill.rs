@@ -0,0 +1,446 @@
+//! Distilling a forest into a single decision tree.
+//!
+//! A forest of hundreds of randomly oriented hyperplanes is hard to review. [`Forest::distill`]
+//! fits one small axis-aligned regression tree to the scores of the forest on the training
+//! data, a surrogate model which can be read top to bottom. The tree splits greedily on the
+//! threshold reducing the squared error of the scores most, and [`SurrogateTree::r_squared`]
+//! reports how much of the variation of the scores it reproduces.
+//!
+//! ```rust
+//! use extended_isolation_forest::distill::DistillOptions;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..400).map(|i| [(i % 20) as f64, (i / 20) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let surrogate = forest.distill(&values, &DistillOptions::default()).unwrap();
+//! assert!(surrogate.depth() <= 4);
+//! println!("{}", surrogate.to_text_with_names(&["cpu", "memory"]));
+//! assert!(surrogate.predict(&[0.0, 0.0]) > surrogate.predict(&[10.0, 10.0]));
+//! ```
+
+use std::fmt::{self, Write};
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::{to_f64, Error, Forest, SampleAccess};
+
+/// Size limits of the surrogate tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DistillOptions {
+    /// Max. number of splits from the root to a leaf.
+    pub max_depth: usize,
+
+    /// Min. number of training samples in every leaf.
+    pub min_samples_leaf: usize,
+}
+
+impl Default for DistillOptions {
+    /// Depth 4, at most 16 leaves, with at least 10 samples each.
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            min_samples_leaf: 10,
+        }
+    }
+}
+
+/// A node of a [`SurrogateTree`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SurrogateNode<T> {
+    /// Samples with `x[dimension] <= threshold` continue with the node at index `left`, all
+    /// others, including missing values, with the node at index `right`.
+    Split {
+        dimension: usize,
+        threshold: T,
+        left: usize,
+        right: usize,
+    },
+
+    /// The mean score of the training samples reaching the leaf.
+    Leaf { score: f64, num_samples: usize },
+}
+
+/// An axis-aligned decision tree approximating the scores of a forest, see the
+/// [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SurrogateTree<T, const N: usize> {
+    /// The nodes in pre-order, the root first.
+    nodes: Vec<SurrogateNode<T>>,
+
+    r_squared: f64,
+}
+
+impl<T, const N: usize> SurrogateTree<T, N>
+where
+    T: Float,
+{
+    /// The approximated score of the sample.
+    pub fn predict<S>(&self, sample: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut index = 0;
+        loop {
+            match &self.nodes[index] {
+                SurrogateNode::Split {
+                    dimension,
+                    threshold,
+                    left,
+                    right,
+                } => {
+                    index = if sample.feature(*dimension) <= *threshold {
+                        *left
+                    } else {
+                        *right
+                    }
+                }
+                SurrogateNode::Leaf { score, .. } => return *score,
+            }
+        }
+    }
+
+    /// The nodes in pre-order, the root first.
+    pub fn nodes(&self) -> &[SurrogateNode<T>] {
+        &self.nodes
+    }
+
+    /// The fraction of the variance of the scores of the training data explained by the tree,
+    /// `1` if the tree reproduces them exactly.
+    pub fn r_squared(&self) -> f64 {
+        self.r_squared
+    }
+
+    /// Max. number of splits from the root to a leaf.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut stack = vec![(0, 0)];
+        while let Some((index, level)) = stack.pop() {
+            depth = depth.max(level);
+            if let SurrogateNode::Split { left, right, .. } = &self.nodes[index] {
+                stack.push((*left, level + 1));
+                stack.push((*right, level + 1));
+            }
+        }
+        depth
+    }
+
+    /// The tree as indented `if`/`else` rules, labeling the dimensions with the given names.
+    /// Missing names are replaced by `x[i]`.
+    pub fn to_text_with_names(&self, names: &[&str]) -> String {
+        let labels: Vec<String> = (0..N)
+            .map(|i| match names.get(i) {
+                Some(name) => name.to_string(),
+                None => format!("x[{}]", i),
+            })
+            .collect();
+        let mut text = String::new();
+        // the else branch is written after the whole if branch
+        let mut stack = vec![Line::Node(0, 0)];
+        while let Some(line) = stack.pop() {
+            // writing to a string never fails
+            match line {
+                Line::Node(index, level) => {
+                    let indent = "  ".repeat(level);
+                    match &self.nodes[index] {
+                        SurrogateNode::Split {
+                            dimension,
+                            threshold,
+                            left,
+                            right,
+                        } => {
+                            let _ = writeln!(
+                                text,
+                                "{}if {} <= {}:",
+                                indent,
+                                labels[*dimension],
+                                to_f64(*threshold)
+                            );
+                            stack.push(Line::Node(*right, level + 1));
+                            stack.push(Line::Else(level));
+                            stack.push(Line::Node(*left, level + 1));
+                        }
+                        SurrogateNode::Leaf { score, num_samples } => {
+                            let _ = writeln!(
+                                text,
+                                "{}score {:.4} ({} samples)",
+                                indent, score, num_samples
+                            );
+                        }
+                    }
+                }
+                Line::Else(level) => {
+                    let _ = writeln!(text, "{}else:", "  ".repeat(level));
+                }
+            }
+        }
+        text
+    }
+}
+
+impl<T, const N: usize> fmt::Display for SurrogateTree<T, N>
+where
+    T: Float,
+{
+    /// The tree as indented `if`/`else` rules on `x[0]`, `x[1]`, ...
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text_with_names(&[]))
+    }
+}
+
+/// A line of the text of a surrogate tree still to be written.
+enum Line {
+    Node(usize, usize),
+    Else(usize),
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Fit a surrogate tree to the scores of the forest on the training data, see the
+    /// [module documentation](self). Fails with [`Error::EmptyData`] without samples.
+    pub fn distill<S>(
+        &self,
+        training_data: &[S],
+        options: &DistillOptions,
+    ) -> Result<SurrogateTree<T, N>, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if training_data.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        let samples: Vec<[T; N]> = training_data
+            .iter()
+            .map(|sample| sample.to_array())
+            .collect();
+        let scores: Vec<f64> = samples.iter().map(|sample| self.score(sample)).collect();
+        let min_samples_leaf = options.min_samples_leaf.max(1);
+
+        let mut nodes = Vec::new();
+        // (parent to link, indices of the samples, level)
+        let mut stack = vec![(None, (0..samples.len()).collect::<Vec<_>>(), 0)];
+        while let Some((parent, indices, level)) = stack.pop() {
+            let index = nodes.len();
+            if let Some((parent, is_left)) = parent {
+                if let SurrogateNode::Split { left, right, .. } = &mut nodes[parent] {
+                    *(if is_left { left } else { right }) = index;
+                }
+            }
+            let split = if level < options.max_depth {
+                best_split(&samples, &scores, &indices, min_samples_leaf)
+            } else {
+                None
+            };
+            match split {
+                Some((dimension, threshold)) => {
+                    nodes.push(SurrogateNode::Split {
+                        dimension,
+                        threshold,
+                        left: 0,
+                        right: 0,
+                    });
+                    let (left, right): (Vec<usize>, Vec<usize>) = indices
+                        .iter()
+                        .partition(|i| samples[**i][dimension] <= threshold);
+                    stack.push((Some((index, false)), right, level + 1));
+                    stack.push((Some((index, true)), left, level + 1));
+                }
+                None => nodes.push(SurrogateNode::Leaf {
+                    score: indices.iter().map(|i| scores[*i]).sum::<f64>() / indices.len() as f64,
+                    num_samples: indices.len(),
+                }),
+            }
+        }
+
+        let mut tree = SurrogateTree {
+            nodes,
+            r_squared: 0.0,
+        };
+        let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+        let total: f64 = scores.iter().map(|score| (score - mean).powi(2)).sum();
+        let residual: f64 = samples
+            .iter()
+            .zip(scores.iter())
+            .map(|(sample, score)| (score - tree.predict(sample)).powi(2))
+            .sum();
+        tree.r_squared = if total > 0.0 {
+            1.0 - residual / total
+        } else {
+            1.0
+        };
+        Ok(tree)
+    }
+}
+
+/// The split of the samples reducing the squared error of the scores most, with at least
+/// `min_samples_leaf` samples on each side. Thresholds lie halfway between consecutive values.
+fn best_split<T, const N: usize>(
+    samples: &[[T; N]],
+    scores: &[f64],
+    indices: &[usize],
+    min_samples_leaf: usize,
+) -> Option<(usize, T)>
+where
+    T: Float,
+{
+    let total = Sums::of(indices.iter().map(|i| scores[*i]));
+    (0..N)
+        .filter_map(|dimension| {
+            let column: Vec<(T, f64)> = indices
+                .iter()
+                .map(|i| (samples[*i][dimension], scores[*i]))
+                .collect();
+            best_threshold(column, &total, min_samples_leaf)
+                .map(|(error, threshold)| (error, dimension, threshold))
+        })
+        .filter(|(error, _, _)| *error < total.squared_error())
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, dimension, threshold)| (dimension, threshold))
+}
+
+/// The threshold on the values of one dimension with the smallest squared error of the scores,
+/// as `(error, threshold)`. Samples with missing values always go right.
+fn best_threshold<T>(
+    mut column: Vec<(T, f64)>,
+    total: &Sums,
+    min_samples_leaf: usize,
+) -> Option<(f64, T)>
+where
+    T: Float,
+{
+    let count = column.len();
+    column.retain(|(value, _)| !value.is_nan());
+    column.sort_by(|a, b| {
+        a.0.partial_cmp(&b.0)
+            .expect("missing values have been removed")
+    });
+
+    let two = T::one() + T::one();
+    let mut left = Sums::default();
+    let mut best: Option<(f64, T)> = None;
+    for pair in column.windows(2) {
+        let ((value, score), (next, _)) = (pair[0], pair[1]);
+        left.add(score);
+        let right = Sums {
+            count: count - left.count,
+            sum: total.sum - left.sum,
+            sum_of_squares: total.sum_of_squares - left.sum_of_squares,
+        };
+        if value == next || left.count < min_samples_leaf || right.count < min_samples_leaf {
+            continue;
+        }
+        let error = left.squared_error() + right.squared_error();
+        if best.is_none_or(|(best_error, _)| error < best_error) {
+            best = Some((error, value + (next - value) / two));
+        }
+    }
+    best
+}
+
+/// Running sums of scores for their squared error around the mean.
+#[derive(Clone, Debug, Default)]
+struct Sums {
+    count: usize,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl Sums {
+    fn of(scores: impl Iterator<Item = f64>) -> Self {
+        let mut sums = Self::default();
+        for score in scores {
+            sums.add(score);
+        }
+        sums
+    }
+
+    fn add(&mut self, score: f64) {
+        self.count += 1;
+        self.sum += score;
+        self.sum_of_squares += score * score;
+    }
+
+    fn squared_error(&self) -> f64 {
+        self.sum_of_squares - self.sum * self.sum / self.count.max(1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DistillOptions, SurrogateNode};
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn distill_forest_into_surrogate_tree() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, 1.0])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 40,
+            sample_size: 64,
+            seed: Some(16),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        assert!(matches!(
+            forest.distill(&[] as &[[f64; 3]], &DistillOptions::default()),
+            Err(Error::EmptyData)
+        ));
+
+        let stump = forest
+            .distill(
+                &values,
+                &DistillOptions {
+                    max_depth: 0,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(stump.nodes().len(), 1);
+        assert_eq!(stump.r_squared(), 0.0);
+
+        let options = DistillOptions {
+            max_depth: 3,
+            min_samples_leaf: 20,
+        };
+        let surrogate = forest.distill(&values, &options).unwrap();
+        assert_eq!(surrogate.depth(), 3);
+        assert!(surrogate.r_squared() > 0.5);
+        let mut num_leaves = 0;
+        for node in surrogate.nodes() {
+            match node {
+                SurrogateNode::Split { dimension, .. } => assert_ne!(*dimension, 2),
+                SurrogateNode::Leaf { num_samples, .. } => {
+                    assert!(*num_samples >= 20);
+                    num_leaves += 1;
+                }
+            }
+        }
+        assert_eq!(num_leaves, surrogate.nodes().len() / 2 + 1);
+        // the corners are the most anomalous samples
+        assert!(surrogate.predict(&[0.0, 0.0, 1.0]) > surrogate.predict(&[9.5, 9.5, 1.0]));
+        assert!(surrogate.predict(&[f64::NAN, 0.0, 1.0]).is_finite());
+
+        let text = surrogate.to_string();
+        assert!(text.starts_with("if x["));
+        assert_eq!(text.matches("else:").count(), num_leaves - 1);
+        let named = surrogate.to_text_with_names(&["cpu", "memory"]);
+        assert!(!named.contains("x[0]") && !named.contains("x[1]"));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&surrogate).unwrap();
+            let restored: super::SurrogateTree<f64, 3> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.to_string(), surrogate.to_string());
+        }
+    }
+}
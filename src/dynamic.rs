@@ -0,0 +1,322 @@
+//! Forests of a number of features only known at runtime, like in bindings to other languages.
+//!
+//! [`DynForest`] holds a [`Forest`] of `f64` behind its dimensions, choosing the const parameter
+//! when the forest is trained or loaded. Forests of up to [`MAX_DIMENSIONS`] features can be
+//! trained and loaded, larger forests are converted from a typed `Forest` with `From`. The
+//! samples are passed as flat slices of rows or as one slice per feature.
+//!
+//! ```rust
+//! use extended_isolation_forest::dynamic::DynForest;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let values: Vec<f64> = (0..100).flat_map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest = DynForest::from_rows(&values, 2, &ForestOptions::default()).unwrap();
+//! assert_eq!(forest.dimensions(), 2);
+//!
+//! let scores = forest.score_rows(&[5.0, 5.0, 50.0, 50.0]).unwrap();
+//! assert!(scores[1] > scores[0]);
+//! ```
+
+use std::fmt::{self, Debug, Formatter};
+#[cfg(feature = "json")]
+use std::io::Write;
+
+use crate::{Error, Forest, ForestOptions};
+
+/// Forests of more dimensions are not trained or loaded.
+pub const MAX_DIMENSIONS: usize = 16;
+
+/// Call the generic function with the dimensions as its const parameter.
+macro_rules! dispatch {
+    ($dimensions:expr, $function:ident $args:tt) => {
+        dispatch!(@ $dimensions, $function $args, 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16)
+    };
+    (@ $dimensions:expr, $function:ident $args:tt, $($n:literal)*) => {
+        match $dimensions {
+            0 => Err(Error::EmptyData),
+            $($n => $function::<$n> $args,)*
+            dimensions => Err(Error::TooManyDimensions {
+                max: MAX_DIMENSIONS,
+                actual: dimensions,
+            }),
+        }
+    };
+}
+
+/// A forest of `f64` of any number of dimensions, see the [module documentation](self).
+pub struct DynForest {
+    forest: Box<dyn Model>,
+}
+
+impl DynForest {
+    /// Build a new forest from the rows stored one after the other in `data`, each of them
+    /// with `dimensions` features.
+    ///
+    /// Fails with [`Error::IncompleteRow`] if the length of `data` is not a multiple of the
+    /// dimensions, and with [`Error::TooManyDimensions`] for more than [`MAX_DIMENSIONS`].
+    pub fn from_rows(
+        data: &[f64],
+        dimensions: usize,
+        options: &ForestOptions,
+    ) -> Result<Self, Error> {
+        dispatch!(dimensions, train_rows(data, options))
+    }
+
+    /// Build a new forest from the rows of data stored column-wise, one slice per feature, like
+    /// the columns of an R matrix or a data frame.
+    ///
+    /// Fails with [`Error::ColumnLengthMismatch`] unless all columns have the same length, and
+    /// with [`Error::TooManyDimensions`] for more than [`MAX_DIMENSIONS`] columns.
+    pub fn from_columns(columns: &[&[f64]], options: &ForestOptions) -> Result<Self, Error> {
+        dispatch!(columns.len(), train_columns(columns, options))
+    }
+
+    /// Read a forest stored in the binary format of [`Forest::write_binary`] or as JSON. The
+    /// dimensions of binary forests are read from the data, JSON forests require them.
+    /// Available with the `json` feature.
+    ///
+    /// Fails with [`Error::DimensionMismatch`] if a binary forest has other dimensions than
+    /// given, and with [`Error::Deserialization`] for invalid data.
+    #[cfg(feature = "json")]
+    pub fn from_bytes(bytes: &[u8], dimensions: Option<usize>) -> Result<Self, Error> {
+        if !bytes.starts_with(b"EIFB") {
+            let dimensions = dimensions.ok_or_else(|| {
+                Error::Deserialization("the dimensions of JSON forests are required".to_string())
+            })?;
+            return dispatch!(dimensions, read_json(bytes));
+        }
+        let stored = crate::binary::dimensions(bytes)?;
+        match dimensions {
+            Some(dimensions) if dimensions != stored => Err(Error::DimensionMismatch {
+                expected: dimensions,
+                actual: stored,
+            }),
+            _ => dispatch!(stored, read_binary(bytes)),
+        }
+    }
+
+    /// The number of features of the samples.
+    pub fn dimensions(&self) -> usize {
+        self.forest.dimensions()
+    }
+
+    /// Score the rows stored one after the other in `data` on the threads of the
+    /// [parallelism](Forest::parallelism), in the order of the rows.
+    ///
+    /// Fails with [`Error::IncompleteRow`] if the length of `data` is not a multiple of the
+    /// dimensions.
+    pub fn score_rows(&self, data: &[f64]) -> Result<Vec<f64>, Error> {
+        self.forest.score_rows(data)
+    }
+
+    /// Score the rows of data stored column-wise like [`Forest::score_column_slices`].
+    pub fn score_columns(&self, columns: &[&[f64]]) -> Result<Vec<f64>, Error> {
+        self.forest.score_columns(columns)
+    }
+
+    /// The forest in the binary format of [`Forest::write_binary`], which can be read by
+    /// [`DynForest::from_bytes`]. Available with the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn to_binary(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        self.forest.write_binary(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<const N: usize> From<Forest<f64, N>> for DynForest {
+    fn from(forest: Forest<f64, N>) -> Self {
+        Self {
+            forest: Box::new(forest),
+        }
+    }
+}
+
+impl Debug for DynForest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynForest")
+            .field("dimensions", &self.dimensions())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The operations of forests of any dimensions.
+trait Model: Send + Sync {
+    fn dimensions(&self) -> usize;
+
+    fn score_rows(&self, data: &[f64]) -> Result<Vec<f64>, Error>;
+
+    fn score_columns(&self, columns: &[&[f64]]) -> Result<Vec<f64>, Error>;
+
+    #[cfg(feature = "json")]
+    fn write_binary(&self, writer: &mut dyn Write) -> Result<(), Error>;
+}
+
+impl<const N: usize> Model for Forest<f64, N> {
+    fn dimensions(&self) -> usize {
+        N
+    }
+
+    fn score_rows(&self, data: &[f64]) -> Result<Vec<f64>, Error> {
+        Ok(self.score_slice(&rows::<N>(data)?))
+    }
+
+    fn score_columns(&self, columns: &[&[f64]]) -> Result<Vec<f64>, Error> {
+        self.score_column_slices(columns)
+    }
+
+    #[cfg(feature = "json")]
+    fn write_binary(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        Forest::write_binary(self, writer)
+    }
+}
+
+/// Split the data into rows of `N` features.
+fn rows<const N: usize>(data: &[f64]) -> Result<Vec<[f64; N]>, Error> {
+    if !data.len().is_multiple_of(N) {
+        return Err(Error::IncompleteRow {
+            len: data.len(),
+            dimensions: N,
+        });
+    }
+    Ok(data
+        .chunks_exact(N)
+        .map(|row| row.try_into().expect("rows of N features"))
+        .collect())
+}
+
+fn train_rows<const N: usize>(data: &[f64], options: &ForestOptions) -> Result<DynForest, Error> {
+    Forest::<f64, N>::from_slice(&rows::<N>(data)?, options).map(DynForest::from)
+}
+
+fn train_columns<const N: usize>(
+    columns: &[&[f64]],
+    options: &ForestOptions,
+) -> Result<DynForest, Error> {
+    let num_rows = columns.first().map(|column| column.len()).unwrap_or(0);
+    if columns.iter().any(|column| column.len() != num_rows) {
+        return Err(Error::ColumnLengthMismatch);
+    }
+    let rows: Vec<[f64; N]> = (0..num_rows)
+        .map(|row| std::array::from_fn(|i| columns[i][row]))
+        .collect();
+    Forest::<f64, N>::from_slice(&rows, options).map(DynForest::from)
+}
+
+#[cfg(feature = "json")]
+fn read_binary<const N: usize>(bytes: &[u8]) -> Result<DynForest, Error> {
+    Forest::<f64, N>::from_binary(bytes).map(DynForest::from)
+}
+
+#[cfg(feature = "json")]
+fn read_json<const N: usize>(bytes: &[u8]) -> Result<DynForest, Error> {
+    serde_json::from_slice::<Forest<f64, N>>(bytes)
+        .map(DynForest::from)
+        .map_err(|err| Error::Deserialization(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynForest;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn dyn_forests_score_like_typed_forests() {
+        let values: Vec<[f64; 3]> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, (i % 7) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            sample_size: 64,
+            seed: Some(31),
+            ..Default::default()
+        };
+        let typed: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        let probes = [[3.0, 4.0, 5.0], [40.0, 2.0, 1.0], [10.0, 10.0, 30.0]];
+        let expected: Vec<f64> = probes.iter().map(|probe| typed.score(probe)).collect();
+
+        let flat: Vec<f64> = values.iter().flatten().copied().collect();
+        let columns: Vec<Vec<f64>> = (0..3)
+            .map(|i| values.iter().map(|row| row[i]).collect())
+            .collect();
+        let columns: Vec<&[f64]> = columns.iter().map(|column| column.as_slice()).collect();
+        let flat_probes: Vec<f64> = probes.iter().flatten().copied().collect();
+        let probe_columns: Vec<Vec<f64>> = (0..3)
+            .map(|i| probes.iter().map(|probe| probe[i]).collect())
+            .collect();
+        let probe_columns: Vec<&[f64]> = probe_columns.iter().map(|c| c.as_slice()).collect();
+
+        for forest in [
+            DynForest::from_rows(&flat, 3, &options).unwrap(),
+            DynForest::from_columns(&columns, &options).unwrap(),
+        ] {
+            assert_eq!(forest.dimensions(), 3);
+            assert_eq!(forest.score_rows(&flat_probes).unwrap(), expected);
+            assert_eq!(forest.score_columns(&probe_columns).unwrap(), expected);
+        }
+
+        assert!(matches!(
+            DynForest::from_rows(&flat[1..], 3, &options),
+            Err(Error::IncompleteRow { len, dimensions: 3 }) if len == flat.len() - 1
+        ));
+        assert!(matches!(
+            DynForest::from_rows(&flat, 0, &options),
+            Err(Error::EmptyData)
+        ));
+        assert!(matches!(
+            DynForest::from_rows(&[0.0; 17], 17, &options),
+            Err(Error::TooManyDimensions {
+                max: 16,
+                actual: 17
+            })
+        ));
+        assert!(matches!(
+            DynForest::from_columns(&[columns[0], &columns[1][1..]], &options),
+            Err(Error::ColumnLengthMismatch)
+        ));
+        let forest = DynForest::from(typed);
+        assert!(matches!(
+            forest.score_rows(&[1.0, 2.0]),
+            Err(Error::IncompleteRow { len: 2, .. })
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_dyn_forests() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+        let expected = [forest.score(&[3.0, 4.0]), forest.score(&[40.0, 40.0])];
+
+        let json = serde_json::to_vec(&forest).unwrap();
+        let forest = DynForest::from(forest);
+        let binary = forest.to_binary().unwrap();
+        for (bytes, dimensions) in [(&binary, None), (&binary, Some(2)), (&json, Some(2))] {
+            let loaded = DynForest::from_bytes(bytes, dimensions).unwrap();
+            assert_eq!(loaded.dimensions(), 2);
+            assert_eq!(
+                loaded.score_rows(&[3.0, 4.0, 40.0, 40.0]).unwrap(),
+                expected
+            );
+        }
+
+        assert!(matches!(
+            DynForest::from_bytes(&binary, Some(3)),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            DynForest::from_bytes(&json, None),
+            Err(Error::Deserialization(_))
+        ));
+        assert!(matches!(
+            DynForest::from_bytes(&json, Some(3)),
+            Err(Error::Deserialization(_))
+        ));
+    }
+}
@@ -0,0 +1,279 @@
+//! Import of models trained with the python [eif](https://github.com/sahandha/eif) package.
+//!
+//! The python package does not offer a portable serialization of its trees, so models
+//! have to be converted to a simple nested format first. With the pure-python implementation
+//! of eif (`eif_old.py`), a trained `iForest` can be exported using
+//!
+//! ```python
+//! import json
+//!
+//! def export_node(node):
+//!     if node.ntype == "exNode":
+//!         return {"ntype": "exNode", "size": int(node.size)}
+//!     return {
+//!         "ntype": "inNode",
+//!         "n": node.n.tolist(),
+//!         "p": node.p.tolist(),
+//!         "left": export_node(node.left),
+//!         "right": export_node(node.right),
+//!     }
+//!
+//! with open("forest.json", "w") as f:
+//!     json.dump({
+//!         "sample_size": forest.sample,
+//!         "trees": [export_node(tree.root) for tree in forest.Trees],
+//!     }, f)
+//! ```
+//!
+//! With the `serde` feature enabled, the resulting file deserializes into an [`EifForest`] which
+//! can be converted into a [`Forest`] using [`Forest::from_eif`]:
+//!
+//! ```rust
+//! # #[cfg(feature = "json")]
+//! # {
+//! use extended_isolation_forest::eif::EifForest;
+//! use extended_isolation_forest::Forest;
+//!
+//! let json = r#"{
+//!     "sample_size": 8,
+//!     "trees": [{
+//!         "ntype": "inNode",
+//!         "n": [1.0, 0.0],
+//!         "p": [0.0, 0.0],
+//!         "left": {"ntype": "exNode", "size": 7},
+//!         "right": {"ntype": "exNode", "size": 1}
+//!     }]
+//! }"#;
+//! let eif_forest: EifForest<f64> = serde_json::from_str(json).unwrap();
+//! let forest = Forest::<f64, 2>::from_eif(&eif_forest).unwrap();
+//! assert!(forest.score(&[1.0, 0.0]) > forest.score(&[-1.0, 0.0]));
+//! # }
+//! ```
+//!
+//! eif sends samples to the left child when `(x - p) · n < 0`, while this crate also sends samples
+//! lying exactly on the hyperplane to the left. Scores only differ for such samples.
+
+use std::boxed::Box;
+use std::convert::TryInto;
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::Count;
+use crate::{c_factor, options_of_trees, Error, ExNode, Forest, InNode, Node, Tree};
+
+/// A forest in the exchange format described in the [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EifForest<T> {
+    /// Number of samples used to build each of the trees (`iForest.sample`).
+    pub sample_size: usize,
+
+    /// The root nodes of the trees.
+    pub trees: Vec<EifNode<T>>,
+}
+
+/// A node of a tree exported from eif.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "ntype"))]
+pub enum EifNode<T> {
+    #[cfg_attr(feature = "serde", serde(rename = "exNode"))]
+    ExNode {
+        /// Number of training samples which ended up in this node.
+        size: usize,
+    },
+
+    #[cfg_attr(feature = "serde", serde(rename = "inNode"))]
+    InNode {
+        /// Normal vector of the splitting hyperplane.
+        n: Vec<T>,
+
+        /// Intercept point of the splitting hyperplane.
+        p: Vec<T>,
+
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_depth"))]
+        left: Box<EifNode<T>>,
+
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_depth"))]
+        right: Box<EifNode<T>>,
+    },
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Build a forest from trees exported from the python eif package.
+    ///
+    /// Fails with [`Error::EmptyData`] without trees, with
+    /// [`Error::InsufficientTrainingData`] for a sample size below 2 and when the dimensions of
+    /// the hyperplanes do not match `N`.
+    pub fn from_eif(eif_forest: &EifForest<T>) -> Result<Self, Error> {
+        if eif_forest.trees.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        if eif_forest.sample_size < 2 {
+            return Err(Error::InsufficientTrainingData);
+        }
+        let trees = eif_forest
+            .trees
+            .iter()
+            .map(|root| {
+                Ok(Tree {
+                    root: convert_tree(root)?,
+                    bounds: None,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_boxed_slice();
+
+        Ok(Self::from_parts(
+            c_factor(eif_forest.sample_size),
+            options_of_trees(&trees, eif_forest.sample_size),
+            trees,
+        ))
+    }
+}
+
+/// Converts the tree iteratively, so deep trees can not overflow the stack.
+fn convert_tree<T, const N: usize>(root: &EifNode<T>) -> Result<Node<T, N>, Error>
+where
+    T: Float,
+{
+    // the nodes in depth-first order, converted in reverse so the children of every internal
+    // node are converted before it
+    let mut pending = vec![root];
+    let mut nodes = Vec::new();
+    while let Some(node) = pending.pop() {
+        if let EifNode::InNode { left, right, .. } = node {
+            pending.push(right);
+            pending.push(left);
+        }
+        nodes.push(node);
+    }
+
+    let mut converted: Vec<Node<T, N>> = Vec::new();
+    for node in nodes.into_iter().rev() {
+        let node = match node {
+            EifNode::ExNode { size } => Node::Ex(ExNode {
+                num_samples: *size,
+                samples: Vec::new(),
+                model: Count,
+                depth_offset: 0,
+            }),
+            EifNode::InNode { n, p, .. } => {
+                // the left subtree has been converted last
+                let left = converted.pop().expect("converted left child");
+                let right = converted.pop().expect("converted right child");
+                Node::In(InNode::new(left, right, to_array(n)?, to_array(p)?, 0))
+            }
+        };
+        converted.push(node);
+    }
+    Ok(converted.pop().expect("converted root"))
+}
+
+fn to_array<T, const N: usize>(values: &[T]) -> Result<[T; N], Error>
+where
+    T: Float,
+{
+    values.try_into().map_err(|_| Error::DimensionMismatch {
+        expected: N,
+        actual: values.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EifForest, EifNode};
+    use crate::{Error, ExtensionLevel, Forest};
+
+    fn leaf(size: usize) -> Box<EifNode<f64>> {
+        Box::new(EifNode::ExNode { size })
+    }
+
+    fn make_eif_forest() -> EifForest<f64> {
+        // a single tree, splitting at x = 0 and then at y = 0 on the right side
+        EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0, 0.0],
+                p: vec![0.0, 0.0],
+                left: leaf(6),
+                right: Box::new(EifNode::InNode {
+                    n: vec![0.0, 1.0],
+                    p: vec![0.0, 0.0],
+                    left: leaf(1),
+                    right: leaf(1),
+                }),
+            }],
+        }
+    }
+
+    #[test]
+    fn import_eif_forest() {
+        let forest = Forest::<f64, 2>::from_eif(&make_eif_forest()).unwrap();
+        assert_eq!(forest.options().max_tree_depth, Some(2));
+        assert_eq!(forest.options().extension_level, ExtensionLevel::Fixed(0));
+
+        // the isolated points on the right side are more anomalous than the dense left side
+        assert!(forest.score(&[1.0, 1.0]) > forest.score(&[-1.0, 1.0]));
+        assert!(forest.score(&[1.0, -1.0]) > forest.score(&[-1.0, -1.0]));
+    }
+
+    #[test]
+    fn import_eif_forest_dimension_mismatch() {
+        assert!(matches!(
+            Forest::<f64, 3>::from_eif(&make_eif_forest()),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn import_eif_forest_without_trees_or_samples() {
+        let empty = EifForest::<f64> {
+            trees: Vec::new(),
+            ..make_eif_forest()
+        };
+        assert!(matches!(
+            Forest::<f64, 2>::from_eif(&empty),
+            Err(Error::EmptyData)
+        ));
+        let single_sample = EifForest {
+            sample_size: 1,
+            ..make_eif_forest()
+        };
+        assert!(matches!(
+            Forest::<f64, 2>::from_eif(&single_sample),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_eif_json() {
+        let json = r#"{
+            "sample_size": 8,
+            "trees": [{
+                "ntype": "inNode",
+                "n": [1.0, 0.0],
+                "p": [0.0, 0.0],
+                "left": {"ntype": "exNode", "size": 6},
+                "right": {
+                    "ntype": "inNode",
+                    "n": [0.0, 1.0],
+                    "p": [0.0, 0.0],
+                    "left": {"ntype": "exNode", "size": 1},
+                    "right": {"ntype": "exNode", "size": 1}
+                }
+            }]
+        }"#;
+        let eif_forest: EifForest<f64> = serde_json::from_str(json).unwrap();
+        assert_eq!(eif_forest, make_eif_forest());
+    }
+}
@@ -0,0 +1,157 @@
+//! The range of the training data, and what to do with samples outside of it.
+//!
+//! Forests store the per-dimension minimum and maximum of all their training samples. Outside
+//! of this envelope scores are extrapolations: a sensor glitch reporting a value a thousand
+//! times larger than anything seen during training ends up in the same leaves as a sample just
+//! beyond the largest training value. [`Forest::in_training_range`] tells which dimensions of a
+//! sample are within the envelope, and a [`RangePolicy`] can clamp or count samples outside of
+//! it when scoring.
+//!
+//! ```rust
+//! use extended_isolation_forest::envelope::RangePolicy;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//! assert_eq!(forest.in_training_range(&[4.5, 1e9]), [true, false]);
+//!
+//! forest.set_range_policy(RangePolicy::Warn);
+//! forest.score(&[4.5, 1e9]);
+//! assert_eq!(forest.out_of_range_samples(), 1);
+//! ```
+
+use std::sync::atomic::Ordering;
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::{Forest, SampleAccess};
+
+/// How [`Forest::score`] treats samples outside of the range of the training data.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RangePolicy {
+    /// Score all samples as they are.
+    #[default]
+    Ignore,
+
+    /// Clamp the values of every dimension to the training range before scoring.
+    Clamp,
+
+    /// Score the samples as they are, but count them, see [`Forest::out_of_range_samples`].
+    /// With the `tracing` feature a `WARN` event is emitted for every such sample.
+    Warn,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The per-dimension minimum and maximum of the training data.
+    ///
+    /// `None` for forests converted from other implementations.
+    pub fn training_range(&self) -> Option<(&[T; N], &[T; N])> {
+        self.training_range
+            .as_ref()
+            .map(|bounds| (bounds.min(), bounds.max()))
+    }
+
+    /// Per dimension whether the value of the sample is within the range of the training data.
+    /// Missing values are never within the range, all values are when the range is unknown.
+    pub fn in_training_range<S>(&self, sample: &S) -> [bool; N]
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        match self.training_range() {
+            Some((min, max)) => std::array::from_fn(|i| {
+                let value = sample.feature(i);
+                value >= min[i] && value <= max[i]
+            }),
+            None => [true; N],
+        }
+    }
+
+    pub fn range_policy(&self) -> RangePolicy {
+        self.range_policy
+    }
+
+    /// Set how samples outside of the training range are scored. The policy is stored with the
+    /// forest.
+    pub fn set_range_policy(&mut self, range_policy: RangePolicy) {
+        self.range_policy = range_policy;
+    }
+
+    /// Number of samples outside of the training range scored with [`RangePolicy::Warn`] since
+    /// the forest has been built or loaded.
+    pub fn out_of_range_samples(&self) -> u64 {
+        self.out_of_range_samples.load(Ordering::Relaxed)
+    }
+
+    /// Apply the range policy, returning the clamped sample if it has to be scored instead.
+    pub(crate) fn apply_range_policy<S>(&self, sample: &S) -> Option<[T; N]>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let (min, max) = self.training_range()?;
+        match self.range_policy {
+            RangePolicy::Ignore => None,
+            RangePolicy::Clamp => {
+                let mut clamped = sample.to_array();
+                for i in 0..N {
+                    // missing values are left to the trees
+                    if clamped[i] < min[i] {
+                        clamped[i] = min[i];
+                    } else if clamped[i] > max[i] {
+                        clamped[i] = max[i];
+                    }
+                }
+                Some(clamped)
+            }
+            RangePolicy::Warn => {
+                let in_range = self.in_training_range(sample);
+                if in_range.contains(&false) {
+                    self.out_of_range_samples.fetch_add(1, Ordering::Relaxed);
+
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        dimensions = ?(0..N).filter(|i| !in_range[*i]).collect::<Vec<_>>(),
+                        "scored sample outside of the training range"
+                    );
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangePolicy;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn clamp_samples_to_the_training_range() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let mut forest: Forest<f64, 2> =
+            Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+        assert_eq!(forest.training_range(), Some((&[0.0, 0.0], &[19.0, 19.0])));
+        assert_eq!(forest.in_training_range(&[-1.0, 19.0]), [false, true]);
+        assert_eq!(forest.in_training_range(&[f64::NAN, 1.0]), [false, true]);
+
+        let edge = forest.score(&[19.0, 10.0]);
+        let far = forest.score(&[1e6, 10.0]);
+        forest.set_range_policy(RangePolicy::Clamp);
+        assert_eq!(forest.score(&[1e6, 10.0]), edge);
+        assert_eq!(forest.out_of_range_samples(), 0);
+
+        forest.set_range_policy(RangePolicy::Warn);
+        assert_eq!(forest.score(&[1e6, 10.0]), far);
+        forest.score(&[10.0, 10.0]);
+        assert_eq!(forest.out_of_range_samples(), 1);
+    }
+}
@@ -3,18 +3,98 @@ use std::fmt::Formatter;
 
 #[derive(Debug)]
 pub enum Error {
+    ColumnLengthMismatch,
+    Deserialization(String),
+    DimensionMismatch { expected: usize, actual: usize },
+    EmptyData,
     ExtensionLevelExceedsDimensions,
+    FeatureIndexOutOfRange { index: usize, dimensions: usize },
+    IncompleteRow { len: usize, dimensions: usize },
+    InsufficientCapacity { capacity: usize },
     InsufficientTrainingData,
+    InvalidBuckets,
+    InvalidFraction,
+    InvalidProbeScore { index: usize },
+    Io(std::io::Error),
+    Kafka(String),
+    KeyCountMismatch { expected: usize, actual: usize },
+    LabelCountMismatch { expected: usize, actual: usize },
+    NoExtensionLevel,
+    NotAxisParallel,
+    TooManyDimensions { max: usize, actual: usize },
+    TrainingSample,
+    TreeSampleSizeMismatch { index: usize, actual: usize },
+    UnsupportedColumnType { index: usize },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            Self::ColumnLengthMismatch => write!(f, "all columns must have the same length"),
+            Self::Deserialization(message) => write!(f, "deserialization failed: {}", message),
+            Self::DimensionMismatch { expected, actual } => {
+                write!(f, "expected {} dimensions, but got {}", expected, actual)
+            }
+            Self::EmptyData => write!(f, "no samples given"),
             Self::ExtensionLevelExceedsDimensions => write!(
                 f,
                 "Extension level has to be less than the number of dimensions"
             ),
+            Self::FeatureIndexOutOfRange { index, dimensions } => write!(
+                f,
+                "feature index {} is out of range for {} dimensions",
+                index, dimensions
+            ),
+            Self::IncompleteRow { len, dimensions } => write!(
+                f,
+                "{} values do not split into rows of {} dimensions",
+                len, dimensions
+            ),
+            Self::InsufficientCapacity { capacity } => {
+                write!(f, "more scores than the capacity of {}", capacity)
+            }
             Self::InsufficientTrainingData => write!(f, "insufficient training data"),
+            Self::InvalidBuckets => write!(
+                f,
+                "bucket boundaries must satisfy 0 <= warn <= critical <= 1"
+            ),
+            Self::InvalidFraction => write!(f, "the fraction must be in (0, 1]"),
+            Self::InvalidProbeScore { index } => {
+                write!(f, "invalid score of probe sample {}", index)
+            }
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::Kafka(message) => write!(f, "kafka error: {}", message),
+            Self::KeyCountMismatch { expected, actual } => {
+                write!(f, "expected {} keys, but got {}", expected, actual)
+            }
+            Self::LabelCountMismatch { expected, actual } => {
+                write!(f, "expected {} labels, but got {}", expected, actual)
+            }
+            Self::NoExtensionLevel => write!(f, "the range or list of extension levels is empty"),
+            Self::NotAxisParallel => {
+                write!(f, "the forest contains splits along multiple dimensions")
+            }
+            Self::TooManyDimensions { max, actual } => {
+                write!(
+                    f,
+                    "at most {} dimensions are supported, but got {}",
+                    max, actual
+                )
+            }
+            Self::TrainingSample => write!(
+                f,
+                "the sample is part of the training data of the novelty detector"
+            ),
+            Self::TreeSampleSizeMismatch { index, actual } => write!(
+                f,
+                "tree {} has been built from {} samples instead of the sample size",
+                index, actual
+            ),
+            Self::UnsupportedColumnType { index } => write!(
+                f,
+                "column {} does not store the float type of the forest",
+                index
+            ),
         }
     }
 }
@@ -0,0 +1,231 @@
+//! Evaluation of anomaly scores against labeled data, and of the stability of the scores.
+
+use rand::seq::SliceRandom;
+use rand_distr::{uniform::SampleUniform, Distribution, StandardNormal};
+
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Area under the ROC curve of the scores, with `true` labels marking anomalies.
+///
+/// Computed as the probability that a randomly chosen anomaly has a higher score than a
+/// randomly chosen normal sample, counting ties as one half. Returns `None` if the lengths of
+/// `scores` and `labels` differ or if one of the classes is missing.
+pub fn roc_auc(scores: &[f64], labels: &[bool]) -> Option<f64> {
+    if scores.len() != labels.len() {
+        return None;
+    }
+    let num_anomalies = labels.iter().filter(|label| **label).count();
+    let num_normal = labels.len() - num_anomalies;
+    if num_anomalies == 0 || num_normal == 0 {
+        return None;
+    }
+
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|a, b| scores[*a].total_cmp(&scores[*b]));
+
+    // sum of the ranks of the anomalies, tied scores get the mean of their ranks
+    let mut rank_sum = 0.0;
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && scores[order[end]] == scores[order[start]] {
+            end += 1;
+        }
+        let mean_rank = (start + end + 1) as f64 / 2.0;
+        rank_sum += mean_rank
+            * order[start..end]
+                .iter()
+                .filter(|index| labels[**index])
+                .count() as f64;
+        start = end;
+    }
+
+    let num_anomalies = num_anomalies as f64;
+    Some(
+        (rank_sum - num_anomalies * (num_anomalies + 1.0) / 2.0)
+            / (num_anomalies * num_normal as f64),
+    )
+}
+
+/// Pearson correlation, `0` if either side is constant.
+pub(crate) fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (a, b) in a.iter().zip(b.iter()) {
+        covariance += (a - mean_a) * (b - mean_b);
+        variance_a += (a - mean_a) * (a - mean_a);
+        variance_b += (b - mean_b) * (b - mean_b);
+    }
+    if variance_a > 0.0 && variance_b > 0.0 {
+        covariance / (variance_a * variance_b).sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// How much the scores of samples vary between forests trained on different subsets of the
+/// data, see [`stability`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stability {
+    /// Per sample the mean of its scores.
+    pub mean_scores: Vec<f64>,
+
+    /// Per sample the variance of its scores.
+    pub score_variances: Vec<f64>,
+
+    /// Per sample the difference between its highest and lowest rank, with the ranks scaled to
+    /// `[0, 1]`. A rank churn of `0.5` means the sample moved through half of the ranking.
+    pub rank_churn: Vec<f64>,
+
+    /// The mean of `score_variances`.
+    pub mean_score_variance: f64,
+
+    /// The mean of `rank_churn`.
+    pub mean_rank_churn: f64,
+}
+
+/// Estimate how sensitive the scores are to the randomness of the training.
+///
+/// The data is randomly split into `k` folds and `k` forests are built, each one leaving out
+/// one of the folds. All samples are scored with all forests, and the variation of the scores
+/// and of the ranks of every sample are reported.
+///
+/// Fails with [`Error::InsufficientTrainingData`] for `k < 2` or if the folds left for
+/// training a forest contain less than `sample_size` samples.
+pub fn stability<'de, T, S, const N: usize>(
+    data: &[S],
+    options: &ForestOptions,
+    k: usize,
+) -> Result<Stability, Error>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+    S: SampleAccess<T, N>,
+{
+    if k < 2 {
+        return Err(Error::InsufficientTrainingData);
+    }
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.shuffle(&mut rand::thread_rng());
+    let mut folds = vec![0; data.len()];
+    for (position, index) in order.into_iter().enumerate() {
+        folds[index] = position % k;
+    }
+
+    let mut scores: Vec<Vec<f64>> = Vec::with_capacity(k);
+    for fold in 0..k {
+        let training_data: Vec<&S> = data
+            .iter()
+            .zip(folds.iter())
+            .filter(|(_, sample_fold)| **sample_fold != fold)
+            .map(|(sample, _)| sample)
+            .collect();
+        let forest = Forest::from_slice(training_data.as_slice(), options)?;
+        scores.push(data.iter().map(|sample| forest.score(sample)).collect());
+    }
+    let ranks: Vec<Vec<f64>> = scores.iter().map(|scores| scaled_ranks(scores)).collect();
+
+    let num_forests = k as f64;
+    let mut stability = Stability {
+        mean_scores: Vec::with_capacity(data.len()),
+        score_variances: Vec::with_capacity(data.len()),
+        rank_churn: Vec::with_capacity(data.len()),
+        mean_score_variance: 0.0,
+        mean_rank_churn: 0.0,
+    };
+    for i in 0..data.len() {
+        let mean = scores.iter().map(|scores| scores[i]).sum::<f64>() / num_forests;
+        let variance = scores
+            .iter()
+            .map(|scores| (scores[i] - mean).powi(2))
+            .sum::<f64>()
+            / num_forests;
+        let (min_rank, max_rank) = ranks
+            .iter()
+            .map(|ranks| ranks[i])
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), rank| {
+                (min.min(rank), max.max(rank))
+            });
+        stability.mean_scores.push(mean);
+        stability.score_variances.push(variance);
+        stability.rank_churn.push(max_rank - min_rank);
+    }
+    if !data.is_empty() {
+        let num_samples = data.len() as f64;
+        stability.mean_score_variance = stability.score_variances.iter().sum::<f64>() / num_samples;
+        stability.mean_rank_churn = stability.rank_churn.iter().sum::<f64>() / num_samples;
+    }
+    Ok(stability)
+}
+
+/// Ranks of the scores scaled to `[0, 1]`, tied scores get the mean of their ranks.
+pub(crate) fn scaled_ranks(scores: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|a, b| scores[*a].total_cmp(&scores[*b]));
+    let scale = (scores.len().max(2) - 1) as f64;
+
+    let mut ranks = vec![0.0; scores.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && scores[order[end]] == scores[order[start]] {
+            end += 1;
+        }
+        let mean_rank = (start + end - 1) as f64 / 2.0 / scale;
+        for index in &order[start..end] {
+            ranks[*index] = mean_rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{roc_auc, scaled_ranks, stability};
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn roc_auc_of_scores() {
+        assert_eq!(
+            roc_auc(&[0.1, 0.2, 0.8, 0.9], &[false, false, true, true]),
+            Some(1.0)
+        );
+        assert_eq!(
+            roc_auc(&[0.9, 0.8, 0.2, 0.1], &[false, false, true, true]),
+            Some(0.0)
+        );
+        assert_eq!(roc_auc(&[0.5, 0.5], &[false, true]), Some(0.5));
+        assert_eq!(
+            roc_auc(&[0.1, 0.4, 0.35, 0.8], &[false, false, true, true]),
+            Some(0.75)
+        );
+        assert_eq!(roc_auc(&[0.1, 0.2], &[false, false]), None);
+        assert_eq!(roc_auc(&[0.1], &[false, true]), None);
+    }
+
+    #[test]
+    fn stability_of_scores() {
+        assert_eq!(
+            scaled_ranks(&[0.3, 0.1, 0.3, 0.2]),
+            vec![5.0 / 6.0, 0.0, 5.0 / 6.0, 1.0 / 3.0]
+        );
+
+        let mut values: Vec<_> = (0..200)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        values.push([100.0, 100.0]);
+        let stability = stability(values.as_slice(), &ForestOptions::default(), 4).unwrap();
+        assert_eq!(stability.score_variances.len(), 201);
+        assert!(stability.mean_rank_churn > 0.0 && stability.mean_rank_churn < 1.0);
+        assert!(stability.mean_scores[200] > stability.mean_scores[105]);
+        assert!(stability.rank_churn[200] < stability.mean_rank_churn);
+
+        assert!(matches!(
+            super::stability(values.as_slice(), &ForestOptions::default(), 1),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+}
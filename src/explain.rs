@@ -0,0 +1,298 @@
+//! Explaining the score of a sample.
+//!
+//! An [`Explanation`] attributes the score to the dimensions of the sample and lists the path
+//! length of the sample in every tree. It can be exported as JSON with the `json` feature, or
+//! as a self-contained HTML fragment to embed into alert tickets.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     n_trees: 100,
+//!     ..Default::default()
+//! };
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//!
+//! let explanation = forest.explain(&[4.5, 100.0]);
+//! assert!(explanation.contributions[1] > explanation.contributions[0]);
+//! let html = explanation.to_html();
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{c_factor, normalize_path_length, to_f64, Direction, Forest, Node, SampleAccess};
+
+/// Why a sample received its score.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Explanation<const N: usize> {
+    /// The anomaly score of the sample.
+    pub score: f64,
+
+    /// The share of every dimension in isolating the sample, summing up to `1` unless the
+    /// sample has not been split by any tree.
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    pub contributions: [f64; N],
+
+    /// The path length of the sample in every tree, in the order of the trees.
+    pub path_lengths: Vec<f64>,
+
+    /// Tags of the kind of anomaly, see [`tagging`](crate::tagging). Empty unless the sample
+    /// has been explained with a tagger.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: Vec<String>,
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Explain the score of the sample.
+    ///
+    /// Every split on the path of the sample is credited with how much it narrows down the
+    /// training samples sharing the node with the sample, `log2((n + 1) / (m + 1))` for `n`
+    /// samples at the node and `m` samples at the child the sample continues to. The credit is
+    /// shared by the dimensions in proportion to the absolute components of the normal vector
+    /// of the split.
+    ///
+    /// The number of training samples below the nodes is not stored in the trees but counted
+    /// for every call, use [`Forest::explain_slice`] for explaining many samples.
+    pub fn explain<S>(&self, sample: &S) -> Explanation<N>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.explain_with_counts(sample, &self.node_samples())
+    }
+
+    /// Explain the scores of all samples, see [`Forest::explain`]. The samples are distributed
+    /// over the threads of the [parallelism](Forest::parallelism) and the training samples
+    /// below the nodes are only counted once.
+    pub fn explain_slice<S>(&self, samples: &[S]) -> Vec<Explanation<N>>
+    where
+        T: Sync,
+        S: SampleAccess<T, N> + Sync,
+    {
+        let counts = self.node_samples();
+        self.parallelism
+            .map(samples, |sample| self.explain_with_counts(sample, &counts))
+    }
+
+    /// Number of training samples below every node, by the address of the node.
+    fn node_samples(&self) -> HashMap<usize, usize> {
+        let mut counts = HashMap::new();
+        for tree in self.trees.iter() {
+            // children follow their parents in pre-order, so reversing it counts them first
+            let nodes: Vec<_> = tree.root.iter().map(|(node, _)| node).collect();
+            for node in nodes.into_iter().rev() {
+                let count = match node {
+                    Node::Ex(ex_node) => ex_node.num_samples,
+                    Node::In(in_node) => {
+                        counts[&address(in_node.left.as_ref())]
+                            + counts[&address(in_node.right.as_ref())]
+                    }
+                };
+                counts.insert(address(node), count);
+            }
+        }
+        counts
+    }
+
+    fn explain_with_counts<S>(&self, sample: &S, counts: &HashMap<usize, usize>) -> Explanation<N>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut explanation = match self.apply_monotone_constraints(sample) {
+            Some(adjusted) => self.explain_adjusted(&adjusted, counts),
+            None => self.explain_adjusted(sample, counts),
+        };
+        if self.is_suppressed(sample) {
+            explanation.score = 0.0;
+        }
+        explanation
+    }
+
+    /// Explain the sample after applying the monotone constraints.
+    fn explain_adjusted<S>(&self, sample: &S, counts: &HashMap<usize, usize>) -> Explanation<N>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let max_depth = self.recursion_cap();
+        let mut contributions = [0.0; N];
+        let mut path_lengths = Vec::with_capacity(self.trees.len());
+
+        for tree in self.trees.iter() {
+            let mut node = &tree.root;
+            let mut depth = 0;
+            let path_length = loop {
+                depth = match node.skip_depth_offset(depth, max_depth) {
+                    Some(depth) => depth,
+                    None => break max_depth as f64,
+                };
+                match node {
+                    Node::Ex(ex_node) => break depth as f64 + c_factor(ex_node.num_samples),
+                    Node::In(_) if depth >= max_depth => break depth as f64,
+                    Node::In(in_node) => {
+                        let child = match in_node.direction(sample) {
+                            Direction::Left => in_node.left.as_ref(),
+                            Direction::Right => in_node.right.as_ref(),
+                        };
+                        let norm = in_node.n.iter().fold(0.0, |sum, n_i| sum + abs_f64(*n_i));
+                        if norm > 0.0 {
+                            // bits of information about the position of the sample
+                            let node_samples = counts[&address(node)];
+                            let child_samples = counts[&address(child)];
+                            let separated =
+                                ((node_samples + 1) as f64 / (child_samples + 1) as f64).log2();
+                            for (contribution, n_i) in
+                                contributions.iter_mut().zip(in_node.n.iter())
+                            {
+                                *contribution += separated * abs_f64(*n_i) / norm;
+                            }
+                        }
+                        node = child;
+                        depth += 1;
+                    }
+                }
+            };
+            path_lengths.push(path_length);
+        }
+
+        let total: f64 = contributions.iter().sum();
+        if total > 0.0 {
+            contributions.iter_mut().for_each(|c| *c /= total);
+        }
+        let mean_path_length = path_lengths.iter().sum::<f64>() / path_lengths.len() as f64;
+        Explanation {
+            score: normalize_path_length(mean_path_length, self.avg_path_length_c),
+            contributions,
+            path_lengths,
+            tags: Vec::new(),
+        }
+    }
+}
+
+fn address<T, const N: usize>(node: &Node<T, N>) -> usize {
+    node as *const Node<T, N> as usize
+}
+
+/// The sign of a normal vector component does not matter for the attribution.
+fn abs_f64<T: Float>(n_i: T) -> f64 {
+    to_f64(n_i.abs())
+}
+
+impl<const N: usize> Explanation<N> {
+    /// The explanation as a JSON object with the fields `score`, `contributions`,
+    /// `path_lengths` and `tags`.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("explanations are always serializable")
+    }
+
+    /// A self-contained HTML fragment showing the contributions as a bar chart and the path
+    /// lengths of the trees. The dimensions are labeled by their index.
+    pub fn to_html(&self) -> String {
+        let labels: Vec<String> = (0..N).map(|i| i.to_string()).collect();
+        self.render_html(&labels)
+    }
+
+    /// Like [`Explanation::to_html`], labeling the dimensions with the given names. Missing
+    /// names are replaced by the index of the dimension.
+    pub fn to_html_with_names(&self, names: &[&str]) -> String {
+        let labels: Vec<String> = (0..N)
+            .map(|i| match names.get(i) {
+                Some(name) => escape_html(name),
+                None => i.to_string(),
+            })
+            .collect();
+        self.render_html(&labels)
+    }
+
+    fn render_html(&self, labels: &[String]) -> String {
+        let mut html = String::new();
+        // writing to a string never fails
+        let _ = write!(
+            html,
+            "<div class=\"eif-explanation\" style=\"font-family:sans-serif\">\
+             <p>Anomaly score: <b>{:.4}</b></p>",
+            self.score
+        );
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self.tags.iter().map(|tag| escape_html(tag)).collect();
+            let _ = write!(html, "<p>Tags: {}</p>", tags.join(", "));
+        }
+        html.push_str(
+            "<table style=\"border-collapse:collapse\">\
+             <tr><th style=\"text-align:left\">Dimension</th>\
+             <th style=\"text-align:left\">Contribution</th></tr>",
+        );
+        for (label, contribution) in labels.iter().zip(self.contributions.iter()) {
+            let _ = write!(
+                html,
+                "<tr><td style=\"padding-right:1em\">{}</td><td>\
+                 <div style=\"display:inline-block;background:#c0392b;height:0.8em;width:{:.1}em\">\
+                 </div> {:.1}%</td></tr>",
+                label,
+                contribution * 20.0,
+                contribution * 100.0
+            );
+        }
+        html.push_str("</table><p>Path lengths per tree:</p><ol start=\"0\">");
+        for path_length in &self.path_lengths {
+            let _ = write!(html, "<li>{:.2}</li>", path_length);
+        }
+        html.push_str("</ol></div>");
+        html
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn explain_sample() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, 0.0])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 3> = Forest::from_slice(values.as_slice(), &options).unwrap();
+
+        let sample = [10.0, 30.0, 0.0];
+        let explanation = forest.explain(&sample);
+        assert!((explanation.score - forest.score(&sample)).abs() < 1e-12);
+        assert_eq!(explanation.path_lengths.len(), 50);
+        assert!((explanation.contributions.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(explanation.contributions[1] > explanation.contributions[0]);
+
+        let html = explanation.to_html_with_names(&["x", "<y>"]);
+        assert!(html.contains("&lt;y&gt;") && html.contains("<li>"));
+
+        let explanations = forest.explain_slice(&[sample, [10.0, 10.0, 0.0]]);
+        assert_eq!(explanations[0], explanation);
+        assert!(explanations[1].score < explanation.score);
+
+        #[cfg(feature = "json")]
+        {
+            let json: serde_json::Value = serde_json::from_str(&explanation.to_json()).unwrap();
+            assert_eq!(json["path_lengths"].as_array().unwrap().len(), 50);
+        }
+    }
+}
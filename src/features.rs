@@ -0,0 +1,395 @@
+//! Declarative mapping of input columns to features.
+//!
+//! Parsing the input by hand in the training job and again in the scoring service is a common
+//! source of skew, like a column read in a different order or a missing value replaced by `0`
+//! in one place and by `NaN` in the other. A [`FeatureSpec`] states once which column every
+//! feature is read from, how its values are coerced to numbers, the default of missing values
+//! and an optional transform. It can be stored next to the model and is applied by
+//! [`io::read_samples`](crate::io::read_samples), [`Forest::score_rows`](crate::Forest::score_rows)
+//! and the `--spec` option of the `eif` and `eif-serve` binaries.
+//!
+//! ```rust
+//! use extended_isolation_forest::features::{FeatureColumn, FeatureSpec, RawValue, Transform, ValueType};
+//!
+//! let spec = FeatureSpec {
+//!     features: vec![
+//!         FeatureColumn {
+//!             transform: Some(Transform::Log1p),
+//!             ..FeatureColumn::number("bytes")
+//!         },
+//!         FeatureColumn {
+//!             value_type: ValueType::Boolean,
+//!             default: Some(0.0),
+//!             ..FeatureColumn::number("admin")
+//!         },
+//!     ],
+//! };
+//! let sample: [f64; 2] = spec
+//!     .sample([RawValue::Text("0"), RawValue::Missing])
+//!     .unwrap();
+//! assert_eq!(sample, [0.0, 0.0]);
+//! let sample: [f64; 2] = spec
+//!     .sample([RawValue::Number(99.0), RawValue::Text("yes")])
+//!     .unwrap();
+//! assert_eq!(sample, [100.0_f64.ln(), 1.0]);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// The columns of all features, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeatureSpec {
+    /// The column of every feature, in the order of the features.
+    pub features: Vec<FeatureColumn>,
+}
+
+/// How one feature is read from its column.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeatureColumn {
+    /// The name of the column, or the key of JSON objects.
+    pub column: String,
+
+    #[cfg_attr(feature = "serde", serde(default, rename = "type"))]
+    pub value_type: ValueType,
+
+    /// The value of a missing or empty field, `NaN` if there is none. The transform is applied
+    /// to it like to the values which are present.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub default: Option<f64>,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub transform: Option<Transform>,
+}
+
+/// How the values of a column are coerced to numbers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ValueType {
+    /// Numbers and text parsed as a number.
+    #[default]
+    Number,
+
+    /// `true` and `false`, also as text, `yes` and `no`, `1` and `0`, read as `1` and `0`.
+    Boolean,
+}
+
+/// A function applied to the values of a column.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Transform {
+    /// `ln(1 + x)`, for counts and sizes spanning several orders of magnitude.
+    Log1p,
+
+    Sqrt,
+}
+
+/// A field of an input row before it is coerced to a number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RawValue<'a> {
+    /// The field or key is absent, or `null`.
+    Missing,
+    Text(&'a str),
+    Number(f64),
+    Boolean(bool),
+}
+
+impl FeatureColumn {
+    /// A number read from the column without default or transform.
+    pub fn number(column: impl Into<String>) -> Self {
+        Self {
+            column: column.into(),
+            value_type: ValueType::Number,
+            default: None,
+            transform: None,
+        }
+    }
+
+    /// The value of the field, `None` if it is missing.
+    fn coerce(&self, raw: RawValue) -> Result<Option<f64>, String> {
+        let value = match (self.value_type, raw) {
+            (_, RawValue::Missing) => None,
+            (_, RawValue::Text(text)) if text.trim().is_empty() => None,
+            (ValueType::Number, RawValue::Number(value)) => Some(value),
+            (ValueType::Number, RawValue::Text(text)) => Some(
+                text.trim()
+                    .parse()
+                    .map_err(|_| format!("invalid number {:?}", text))?,
+            ),
+            (ValueType::Boolean, RawValue::Boolean(value)) => Some(if value { 1.0 } else { 0.0 }),
+            (ValueType::Boolean, RawValue::Number(value)) if value == 0.0 || value == 1.0 => {
+                Some(value)
+            }
+            (ValueType::Boolean, RawValue::Text(text)) => {
+                match text.trim().to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "1" => Some(1.0),
+                    "false" | "no" | "0" => Some(0.0),
+                    _ => return Err(format!("invalid boolean {:?}", text)),
+                }
+            }
+            (ValueType::Number, RawValue::Boolean(_)) => return Err("expected a number".into()),
+            (ValueType::Boolean, RawValue::Number(value)) => {
+                return Err(format!("invalid boolean {}", value))
+            }
+        };
+        Ok(value)
+    }
+}
+
+impl Transform {
+    fn apply(&self, value: f64) -> f64 {
+        match self {
+            Self::Log1p => value.ln_1p(),
+            Self::Sqrt => value.sqrt(),
+        }
+    }
+}
+
+impl FeatureSpec {
+    /// Read every feature as a number from the named column.
+    pub fn new<I>(columns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            features: columns.into_iter().map(FeatureColumn::number).collect(),
+        }
+    }
+
+    /// The index of the feature read from the column.
+    pub fn index_of(&self, column: &str) -> Option<usize> {
+        self.features
+            .iter()
+            .position(|feature| feature.column == column)
+    }
+
+    /// Fails with [`Error::DimensionMismatch`] unless the spec has `N` features.
+    pub fn check_dimensions<const N: usize>(&self) -> Result<(), Error> {
+        if self.features.len() != N {
+            return Err(Error::DimensionMismatch {
+                expected: N,
+                actual: self.features.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The sample of the raw values of the features, in the order of the features.
+    ///
+    /// Fails with [`Error::DimensionMismatch`] unless there are `N` features and values, and
+    /// with [`Error::Deserialization`] for values which can not be coerced.
+    pub fn sample<'a, T, const N: usize>(
+        &self,
+        values: impl IntoIterator<Item = RawValue<'a>>,
+    ) -> Result<[T; N], Error>
+    where
+        T: Float,
+    {
+        self.check_dimensions::<N>()?;
+        let values: Vec<RawValue> = values.into_iter().collect();
+        if values.len() != N {
+            return Err(Error::DimensionMismatch {
+                expected: N,
+                actual: values.len(),
+            });
+        }
+        self.convert(|feature| Ok(values[feature]))
+            .map(|(sample, _)| sample)
+            .map_err(Error::Deserialization)
+    }
+
+    /// The sample of a JSON object with the columns as keys, or of an array of the values of
+    /// the features.
+    #[cfg(feature = "json")]
+    pub fn sample_from_json<T, const N: usize>(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<[T; N], Error>
+    where
+        T: Float,
+    {
+        self.check_dimensions::<N>()?;
+        let sample = match value {
+            serde_json::Value::Object(object) => self.json_sample(object),
+            serde_json::Value::Array(values) if values.len() == N => {
+                self.convert(|feature| json_value(&values[feature]))
+            }
+            serde_json::Value::Array(values) => {
+                return Err(Error::DimensionMismatch {
+                    expected: N,
+                    actual: values.len(),
+                })
+            }
+            _ => Err("expected an object or an array".to_string()),
+        };
+        sample
+            .map(|(sample, _)| sample)
+            .map_err(Error::Deserialization)
+    }
+
+    /// Like [`FeatureSpec::sample_from_json`], with the number of missing values.
+    #[cfg(feature = "json")]
+    pub(crate) fn json_sample<T, const N: usize>(
+        &self,
+        object: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<([T; N], usize), String>
+    where
+        T: Float,
+    {
+        self.convert(|feature| match object.get(&self.features[feature].column) {
+            Some(value) => json_value(value),
+            None => Ok(RawValue::Missing),
+        })
+    }
+
+    /// The sample of the raw value of every feature and the number of missing values. The spec
+    /// has `N` features.
+    pub(crate) fn convert<'a, T, const N: usize>(
+        &self,
+        raw: impl Fn(usize) -> Result<RawValue<'a>, String>,
+    ) -> Result<([T; N], usize), String>
+    where
+        T: Float,
+    {
+        let mut missing = 0;
+        let mut values = [f64::NAN; N];
+        for (i, feature) in self.features.iter().enumerate().take(N) {
+            let value = raw(i)
+                .and_then(|raw| feature.coerce(raw))
+                .map_err(|message| format!("column {:?}: {}", feature.column, message))?;
+            if value.is_none() {
+                missing += 1;
+            }
+            let value = value.or(feature.default).unwrap_or(f64::NAN);
+            values[i] = match feature.transform {
+                Some(transform) => transform.apply(value),
+                None => value,
+            };
+        }
+        let sample = values.map(|value| T::from(value).unwrap_or_else(T::nan));
+        Ok((sample, missing))
+    }
+}
+
+/// The raw value of a JSON field.
+#[cfg(feature = "json")]
+fn json_value(value: &serde_json::Value) -> Result<RawValue<'_>, String> {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => Ok(RawValue::Missing),
+        Value::Bool(value) => Ok(RawValue::Boolean(*value)),
+        Value::Number(number) => Ok(number
+            .as_f64()
+            .map(RawValue::Number)
+            .unwrap_or(RawValue::Missing)),
+        Value::String(text) => Ok(RawValue::Text(text)),
+        _ => Err("expected a number".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FeatureColumn, FeatureSpec, RawValue, Transform, ValueType};
+    use crate::Error;
+
+    #[test]
+    fn feature_spec_coerces_values() {
+        let spec = FeatureSpec {
+            features: vec![
+                FeatureColumn {
+                    default: Some(3.0),
+                    transform: Some(Transform::Sqrt),
+                    ..FeatureColumn::number("size")
+                },
+                FeatureColumn {
+                    value_type: ValueType::Boolean,
+                    ..FeatureColumn::number("flag")
+                },
+            ],
+        };
+        assert_eq!(spec.index_of("flag"), Some(1));
+        assert_eq!(spec.index_of("other"), None);
+
+        let sample: [f64; 2] = spec
+            .sample([RawValue::Text(" 16 "), RawValue::Boolean(true)])
+            .unwrap();
+        assert_eq!(sample, [4.0, 1.0]);
+        let sample: [f32; 2] = spec
+            .sample([RawValue::Missing, RawValue::Text("No")])
+            .unwrap();
+        assert_eq!(sample, [3.0_f32.sqrt(), 0.0]);
+        let (sample, missing) = spec.convert::<f64, 2>(|_| Ok(RawValue::Text(""))).unwrap();
+        assert_eq!((sample[0], missing), (3.0_f64.sqrt(), 2));
+        assert!(sample[1].is_nan());
+
+        for values in [
+            [RawValue::Text("four"), RawValue::Boolean(false)],
+            [RawValue::Boolean(true), RawValue::Boolean(false)],
+            [RawValue::Number(1.0), RawValue::Number(2.0)],
+            [RawValue::Number(1.0), RawValue::Text("maybe")],
+        ] {
+            assert!(matches!(
+                spec.sample::<f64, 2>(values),
+                Err(Error::Deserialization(_))
+            ));
+        }
+        assert!(matches!(
+            spec.sample::<f64, 3>([RawValue::Missing; 3]),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            spec.sample::<f64, 2>([RawValue::Missing]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+
+        #[cfg(feature = "json")]
+        {
+            let text = r#"{"features": [
+                {"column": "size", "default": 3, "transform": "sqrt"},
+                {"column": "flag", "type": "boolean"}
+            ]}"#;
+            let loaded: FeatureSpec = serde_json::from_str(text).unwrap();
+            assert_eq!(loaded, spec);
+            let object = serde_json::json!({"size": "9", "flag": false});
+            let sample: [f64; 2] = spec.sample_from_json(&object).unwrap();
+            assert_eq!(sample, [3.0, 0.0]);
+            let array = serde_json::json!([null, "true"]);
+            let sample: [f64; 2] = spec.sample_from_json(&array).unwrap();
+            assert_eq!(sample, [3.0_f64.sqrt(), 1.0]);
+            let object = serde_json::json!({"size": [1]});
+            assert!(matches!(
+                spec.sample_from_json::<f64, 2>(&object),
+                Err(Error::Deserialization(message)) if message.starts_with("column \"size\"")
+            ));
+            assert!(matches!(
+                spec.sample_from_json::<f64, 2>(&serde_json::json!([1])),
+                Err(Error::DimensionMismatch { .. })
+            ));
+            assert!(matches!(
+                spec.sample_from_json::<f64, 2>(&serde_json::json!(1)),
+                Err(Error::Deserialization(_))
+            ));
+        }
+    }
+}
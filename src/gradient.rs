@@ -0,0 +1,110 @@
+use num_traits::Float;
+
+use crate::{to_f64, Forest, Node, SampleAccess};
+
+/// Default step of the finite differences as a fraction of the range of the split intercepts.
+const GRADIENT_STEP_FRACTION: f64 = 0.05;
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Per-dimension minimum and maximum of the intercept points of all splits in the forest.
+    ///
+    /// As the intercepts are sampled from the ranges of the training samples, this approximates
+    /// the range of the training data. Returns `None` for forests without any splits.
+    pub(crate) fn intercept_bounds(&self) -> Option<([T; N], [T; N])> {
+        let mut bounds: Option<([T; N], [T; N])> = None;
+        for tree in self.trees.iter() {
+            for (node, _) in tree.root.iter() {
+                if let Node::In(in_node) = node {
+                    let (mins, maxs) = bounds.get_or_insert((in_node.p, in_node.p));
+                    for i in 0..N {
+                        mins[i] = mins[i].min(in_node.p[i]);
+                        maxs[i] = maxs[i].max(in_node.p[i]);
+                    }
+                }
+            }
+        }
+        bounds
+    }
+
+    /// Gradient of the anomaly score with respect to the features of the sample.
+    ///
+    /// The score of a forest is piecewise constant, so the gradient is approximated by central
+    /// finite differences with a step of 5% of the range of the split intercepts in each
+    /// dimension. This smooths over the individual cuts and shows in which direction the sample
+    /// would become more (positive) or less (negative) anomalous.
+    pub fn score_gradient<S>(&self, sample: &S) -> [f64; N]
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let steps = match self.intercept_bounds() {
+            Some((mins, maxs)) => std::array::from_fn(|i| {
+                let range = maxs[i] - mins[i];
+                if range > T::zero() {
+                    range * T::from(GRADIENT_STEP_FRACTION).unwrap_or_else(T::epsilon)
+                } else {
+                    T::one()
+                }
+            }),
+            None => [T::one(); N],
+        };
+        self.score_gradient_with_steps(sample, &steps)
+    }
+
+    /// Gradient of the anomaly score using central finite differences with the given step
+    /// size for each dimension.
+    pub fn score_gradient_with_steps<S>(&self, sample: &S, steps: &[T; N]) -> [f64; N]
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let values = sample.to_array();
+        std::array::from_fn(|i| {
+            let mut forward = values;
+            forward[i] = forward[i] + steps[i];
+            let mut backward = values;
+            backward[i] = backward[i] - steps[i];
+
+            (self.score(&forward) - self.score(&backward)) / (2.0 * to_f64(steps[i]))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use crate::{ExtensionLevel, Forest, ForestOptions};
+
+    #[test]
+    fn gradient_points_away_from_the_data() {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let values: Vec<_> = (0..2000)
+            .map(|_| [rng.sample(distribution), rng.sample(distribution)])
+            .collect();
+        let forest: Forest<f64, 2> = Forest::from_slice(
+            values.as_slice(),
+            &ForestOptions {
+                n_trees: 100,
+                sample_size: 200,
+                max_tree_depth: None,
+                extension_level: ExtensionLevel::Fixed(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // on the right edge of the data, the score grows to the right
+        let gradient = forest.score_gradient(&[3.8, 0.0]);
+        assert!(gradient[0] > 0.0);
+        assert!(gradient[0].abs() > gradient[1].abs());
+
+        // on the lower edge of the data, the score grows downwards
+        let gradient = forest.score_gradient(&[0.0, -3.8]);
+        assert!(gradient[1] < 0.0);
+        assert!(gradient[1].abs() > gradient[0].abs());
+    }
+}
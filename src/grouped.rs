@@ -0,0 +1,247 @@
+//! One forest per group, for example per customer or per host.
+//!
+//! A [`GroupedForest`] trains a separate forest for every group key using shared options, so
+//! every entity is scored against its own baseline. Samples of groups without a forest are
+//! buffered, and the forest of the group is trained as soon as enough samples have arrived.
+//!
+//! ```rust
+//! use extended_isolation_forest::ForestOptions;
+//! use extended_isolation_forest::grouped::GroupedForest;
+//!
+//! let options = ForestOptions {
+//!     n_trees: 100,
+//!     ..Default::default()
+//! };
+//! let mut forests = GroupedForest::new(options, 100);
+//! for i in 0..100 {
+//!     forests.add_sample("host-a", [(i % 10) as f64, (i / 10) as f64]).unwrap();
+//!     forests.add_sample("host-b", [(i % 10) as f64 * 100.0, (i / 10) as f64]).unwrap();
+//! }
+//!
+//! // normal for the second host, but not for the first one
+//! let sample = [500.0, 5.0];
+//! assert!(forests.score("host-a", &sample).unwrap() > forests.score("host-b", &sample).unwrap());
+//! assert!(forests.score("host-c", &sample).is_none());
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Forests trained per group key with shared options.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "K: Serialize + Eq + Hash, T: Serialize",
+        deserialize = "K: Deserialize<'de> + Eq + Hash, T: Deserialize<'de> + Float"
+    ))
+)]
+pub struct GroupedForest<K, T, const N: usize> {
+    options: ForestOptions,
+
+    /// Number of buffered samples a forest for a new group is trained from.
+    training_size: usize,
+
+    forests: HashMap<K, Forest<T, N>>,
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array::map_of_vecs"))]
+    pending: HashMap<K, Vec<[T; N]>>,
+}
+
+impl<'de, K, T, const N: usize> GroupedForest<K, T, N>
+where
+    K: Eq + Hash + Clone,
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Forests for new groups are trained from their first `training_size` samples.
+    pub fn new(options: ForestOptions, training_size: usize) -> Self {
+        Self {
+            options,
+            training_size,
+            forests: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Train the forests of all groups of the training data. `keys` assigns a group to every
+    /// sample.
+    ///
+    /// Samples of groups with less than `training_size` samples are buffered until more samples
+    /// get added.
+    pub fn from_groups<S>(
+        training_data: &[S],
+        keys: &[K],
+        options: ForestOptions,
+        training_size: usize,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if keys.len() != training_data.len() {
+            return Err(Error::KeyCountMismatch {
+                expected: training_data.len(),
+                actual: keys.len(),
+            });
+        }
+        let mut grouped = Self::new(options, training_size);
+        for (sample, key) in training_data.iter().zip(keys) {
+            grouped
+                .pending
+                .entry(key.clone())
+                .or_default()
+                .push(sample.to_array());
+        }
+        let ready: Vec<K> = grouped
+            .pending
+            .iter()
+            .filter(|(_, samples)| samples.len() >= training_size)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in ready {
+            grouped.train_group(&key)?;
+        }
+        Ok(grouped)
+    }
+
+    /// Add a training sample of the group.
+    ///
+    /// Samples of groups which already have a forest are ignored. Returns `true` when the forest
+    /// of the group has been trained by this call.
+    pub fn add_sample<S>(&mut self, key: K, sample: S) -> Result<bool, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if self.forests.contains_key(&key) {
+            return Ok(false);
+        }
+        let samples = self.pending.entry(key.clone()).or_default();
+        samples.push(sample.to_array());
+        if samples.len() < self.training_size {
+            return Ok(false);
+        }
+        self.train_group(&key)
+    }
+
+    /// Train the forest of the group from its buffered samples, even if there are less than
+    /// `training_size`. Returns `false` if no samples of the group are buffered.
+    ///
+    /// On failure the samples stay buffered.
+    pub fn train_group<Q>(&mut self, key: &Q) -> Result<bool, Error>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let Some((key, samples)) = self.pending.remove_entry(key) else {
+            return Ok(false);
+        };
+        match Forest::from_slice(samples.as_slice(), &self.options) {
+            Ok(forest) => {
+                self.forests.insert(key, forest);
+                Ok(true)
+            }
+            Err(err) => {
+                self.pending.insert(key, samples);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<K, T, const N: usize> GroupedForest<K, T, N>
+where
+    K: Eq + Hash,
+    T: Float,
+{
+    /// Score the sample with the forest of the group. Returns `None` for groups without a
+    /// trained forest.
+    pub fn score<Q, S>(&self, key: &Q, sample: &S) -> Option<f64>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.forests.get(key).map(|forest| forest.score(sample))
+    }
+
+    pub fn forest<Q>(&self, key: &Q) -> Option<&Forest<T, N>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.forests.get(key)
+    }
+
+    /// Add an already trained forest for the group, replacing its buffered samples.
+    pub fn insert_forest(&mut self, key: K, forest: Forest<T, N>) -> Option<Forest<T, N>> {
+        self.pending.remove(&key);
+        self.forests.insert(key, forest)
+    }
+
+    /// Keys of the groups with a trained forest, in arbitrary order.
+    pub fn trained_groups(&self) -> impl Iterator<Item = &K> {
+        self.forests.keys()
+    }
+
+    /// Keys of the groups still waiting for samples, in arbitrary order.
+    pub fn pending_groups(&self) -> impl Iterator<Item = &K> {
+        self.pending.keys()
+    }
+
+    pub fn options(&self) -> &ForestOptions {
+        &self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GroupedForest;
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn groups_are_trained_separately() {
+        let values: Vec<_> = (0..300)
+            .map(|i| [(i % 100) as f64 * if i < 200 { 1.0 } else { 10.0 }, 0.0])
+            .collect();
+        let keys: Vec<_> = (0..300)
+            .map(|i| match i {
+                0..=99 => "a",
+                100..=199 => "b",
+                _ => "c",
+            })
+            .collect();
+        let mut grouped =
+            GroupedForest::from_groups(&values[..250], &keys[..250], ForestOptions::default(), 100)
+                .unwrap();
+
+        // the third group is trained lazily
+        assert_eq!(grouped.pending_groups().collect::<Vec<_>>(), vec![&"c"]);
+        assert!(grouped.score("c", &[500.0, 0.0]).is_none());
+        for (sample, key) in values[250..].iter().zip(&keys[250..]) {
+            grouped.add_sample(*key, *sample).unwrap();
+        }
+        assert_eq!(grouped.trained_groups().count(), 3);
+        assert!(
+            grouped.score("a", &[500.0, 0.0]).unwrap() > grouped.score("c", &[500.0, 0.0]).unwrap()
+        );
+
+        // too few samples to build a forest
+        grouped.add_sample("d", [1.0, 1.0]).unwrap();
+        assert!(matches!(
+            grouped.train_group("d"),
+            Err(Error::InsufficientTrainingData)
+        ));
+        assert_eq!(grouped.pending_groups().collect::<Vec<_>>(), vec![&"d"]);
+    }
+}
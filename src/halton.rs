@@ -0,0 +1,108 @@
+use num_traits::Float;
+
+/// The Halton low-discrepancy sequence, used for choosing hyperplanes with
+/// `Hyperplanes::Halton`.
+///
+/// Every hyperplane of an `N`-dimensional forest consumes one point of a `3 * N`-dimensional
+/// sequence: `N` coordinates rank the dimensions for choosing the active ones, `N` coordinates
+/// are the components of the normal vector and `N` coordinates are the positions of the
+/// intercept within the range of the samples.
+#[derive(Clone, Debug)]
+pub(crate) struct Halton {
+    index: u64,
+    primes: Vec<u64>,
+}
+
+impl Halton {
+    pub fn new(dimensions: usize) -> Self {
+        let mut primes: Vec<u64> = Vec::with_capacity(dimensions);
+        let mut candidate = 2;
+        while primes.len() < dimensions {
+            if primes
+                .iter()
+                .take_while(|prime| *prime * *prime <= candidate)
+                .all(|prime| candidate % prime != 0)
+            {
+                primes.push(candidate);
+            }
+            candidate += 1;
+        }
+        // the first point is the origin in all dimensions
+        Self { index: 1, primes }
+    }
+
+    /// The coordinate of the current point in the dimension, in `[0, 1)`.
+    fn coordinate(&self, dimension: usize) -> f64 {
+        let base = self.primes[dimension];
+        let mut remaining = self.index;
+        let mut fraction = 1.0;
+        let mut value = 0.0;
+        while remaining > 0 {
+            fraction /= base as f64;
+            value += (remaining % base) as f64 * fraction;
+            remaining /= base;
+        }
+        value
+    }
+
+    /// The coordinate rotated by an irrational offset, so a normal component built from it
+    /// can never be exactly zero.
+    fn rotated_coordinate(&self, dimension: usize) -> f64 {
+        let offset = (self.primes[dimension] as f64).sqrt().fract();
+        (self.coordinate(dimension) + offset).fract()
+    }
+
+    /// The next hyperplane for samples within `mins` and `maxs`.
+    pub fn hyperplane<T, const N: usize>(
+        &mut self,
+        mins: &[T; N],
+        maxs: &[T; N],
+        extension_level: usize,
+    ) -> ([T; N], [T; N])
+    where
+        T: Float,
+    {
+        let mut dims: Vec<usize> = (0..N).collect();
+        dims.sort_by(|a, b| self.coordinate(*a).total_cmp(&self.coordinate(*b)));
+
+        let mut n = [T::zero(); N];
+        for i in dims.into_iter().take(extension_level + 1) {
+            n[i] = T::from(2.0 * self.rotated_coordinate(N + i) - 1.0).unwrap_or_else(T::one);
+        }
+
+        let p = std::array::from_fn(|i| {
+            let fraction = T::from(self.coordinate(2 * N + i)).unwrap_or_else(T::zero);
+            mins[i] + (maxs[i] - mins[i]) * fraction
+        });
+
+        self.index += 1;
+        (n, p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Halton;
+
+    #[test]
+    fn halton_sequence() {
+        let mut halton = Halton::new(6);
+        assert_eq!(halton.primes, vec![2, 3, 5, 7, 11, 13]);
+
+        let mut first = vec![];
+        let mut second = vec![];
+        for _ in 0..4 {
+            first.push(halton.coordinate(0));
+            second.push(halton.coordinate(1));
+            halton.index += 1;
+        }
+        assert_eq!(first, vec![0.5, 0.25, 0.75, 0.125]);
+        assert!((second[0] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((second[2] - 1.0 / 9.0).abs() < 1e-12);
+        assert!((second[3] - 4.0 / 9.0).abs() < 1e-12);
+
+        let (n, p) = halton.hyperplane(&[0.0, 10.0], &[1.0, 20.0], 0);
+        assert_eq!(n.iter().filter(|n_i| **n_i != 0.0).count(), 1);
+        assert!((0.0..1.0).contains(&p[0]) && (10.0..20.0).contains(&p[1]));
+    }
+}
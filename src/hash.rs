@@ -0,0 +1,321 @@
+use num_traits::Float;
+
+use crate::bucket::Buckets;
+use crate::envelope::RangePolicy;
+use crate::leaf::LeafModel;
+use crate::monotone::Monotone;
+use crate::suppression::Region;
+use crate::{to_f64, Direction, Forest, Node, SampleAccess};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64bit FNV-1a hasher.
+///
+/// Unlike `std::collections::hash_map::DefaultHasher`, the output is guaranteed to stay stable
+/// across rust versions and platforms.
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Fnv1a {
+    pub fn write_u64(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64)
+    }
+
+    /// Positive and negative zero are hashed identically.
+    pub fn write_float<T: Float>(&mut self, value: T) {
+        let value = to_f64(value);
+        self.write_u64(if value == 0.0 { 0 } else { value.to_bits() })
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// A deterministic hash of the structure of the forest.
+    ///
+    /// The hash only depends on the trees and the settings used for scoring, not on the
+    /// serialization format or the platform. Forests producing different scores have different
+    /// hashes - with the usual probability of collisions of a 64bit hash.
+    ///
+    /// The hashed settings are the recursion cap if it truncates paths of the trees, strict
+    /// arithmetic, the range policy with the training range, the monotone constraints, the
+    /// suppressed regions and the severity buckets. Settings at their defaults are not hashed,
+    /// so forests using none of them keep their hashes.
+    pub fn model_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        hasher.write_usize(N);
+        hasher.write_usize(self.options.sample_size);
+        hasher.write_usize(self.trees.len());
+        let mut max_depth = 0;
+        for tree in self.trees.iter() {
+            for (node, depth) in tree.root.iter() {
+                hash_node(&mut hasher, node);
+                max_depth = max_depth.max(depth);
+            }
+        }
+
+        if self.recursion_cap() < max_depth {
+            hasher.write_u64(3);
+            hasher.write_usize(self.recursion_cap());
+        }
+        if self.strict_fp {
+            hasher.write_u64(4);
+        }
+        if self.range_policy != RangePolicy::Ignore {
+            hasher.write_u64(5);
+            hasher.write_u64(self.range_policy as u64);
+            if let Some(range) = &self.training_range {
+                range.min().iter().for_each(|v| hasher.write_float(*v));
+                range.max().iter().for_each(|v| hasher.write_float(*v));
+            }
+        }
+        if !self.monotone.is_empty() {
+            hasher.write_u64(6);
+            for constraint in self.monotone.iter() {
+                match constraint {
+                    Monotone::Both => hasher.write_u64(0),
+                    Monotone::Above(value) => {
+                        hasher.write_u64(1);
+                        hasher.write_float(*value);
+                    }
+                    Monotone::Below(value) => {
+                        hasher.write_u64(2);
+                        hasher.write_float(*value);
+                    }
+                }
+            }
+        }
+        if !self.suppressed.is_empty() {
+            hasher.write_u64(7);
+            hasher.write_usize(self.suppressed.len());
+            for region in self.suppressed.iter() {
+                hash_region(&mut hasher, region);
+            }
+        }
+        if self.buckets != Buckets::default() {
+            hasher.write_u64(8);
+            hasher.write_float(self.buckets.warn());
+            hasher.write_float(self.buckets.critical());
+        }
+        hasher.finish()
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Per tree a hash of the leaf reached by the sample, in the order of the trees.
+    ///
+    /// The hyperplanes of the trees work like locality-sensitive hashes: close samples reach the
+    /// same leaves in most trees, distant ones in few. The hashes only depend on the path of the
+    /// sample through the tree, so they can be stored and compared with the hashes of other
+    /// samples later, as long as the trees do not change.
+    pub fn hash<S>(&self, sample: &S) -> Vec<u64>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.trees
+            .iter()
+            .map(|tree| {
+                let mut hasher = Fnv1a::default();
+                let mut node = &tree.root;
+                while let Node::In(in_node) = node {
+                    node = match in_node.direction(sample) {
+                        Direction::Left => {
+                            hasher.write_u64(0);
+                            in_node.left.as_ref()
+                        }
+                        Direction::Right => {
+                            hasher.write_u64(1);
+                            in_node.right.as_ref()
+                        }
+                    };
+                }
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    /// The fraction of trees in which both samples reach the same leaf, in `[0, 1]`.
+    ///
+    /// Equals the fraction of equal hashes of [`Forest::hash`], a cheap similarity for finding
+    /// near-duplicates among the samples scored by the forest.
+    pub fn similar_leaf_fraction<A, B>(&self, a: &A, b: &B) -> f64
+    where
+        A: SampleAccess<T, N> + ?Sized,
+        B: SampleAccess<T, N> + ?Sized,
+    {
+        let same = self
+            .trees
+            .iter()
+            .filter(|tree| std::ptr::eq(tree.leaf(a), tree.leaf(b)))
+            .count();
+        same as f64 / self.trees.len().max(1) as f64
+    }
+}
+
+/// Hashes the node without its children, which follow in depth-first order.
+fn hash_node<T, const N: usize>(hasher: &mut Fnv1a, node: &Node<T, N>)
+where
+    T: Float,
+{
+    // only merged nodes hash their offsets, keeping the fingerprints of other forests
+    if node.depth_offset() > 0 {
+        hasher.write_u64(2);
+        hasher.write_usize(node.depth_offset());
+    }
+    match node {
+        Node::Ex(ex_node) => {
+            hasher.write_u64(0);
+            hasher.write_usize(ex_node.num_samples);
+        }
+        Node::In(in_node) => {
+            hasher.write_u64(1);
+            in_node.n.iter().for_each(|v| hasher.write_float(*v));
+            in_node.p.iter().for_each(|v| hasher.write_float(*v));
+        }
+    }
+}
+
+fn hash_region<T, const N: usize>(hasher: &mut Fnv1a, region: &Region<T, N>)
+where
+    T: Float,
+{
+    match region {
+        Region::Box { min, max } => {
+            hasher.write_u64(0);
+            min.iter().for_each(|v| hasher.write_float(*v));
+            max.iter().for_each(|v| hasher.write_float(*v));
+        }
+        Region::Polytope(half_spaces) => {
+            hasher.write_u64(1);
+            hasher.write_usize(half_spaces.len());
+            for half_space in half_spaces.iter() {
+                half_space
+                    .normal
+                    .iter()
+                    .for_each(|v| hasher.write_float(*v));
+                half_space.point.iter().for_each(|v| hasher.write_float(*v));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bucket::Buckets;
+    use crate::eif::{EifForest, EifNode};
+    use crate::envelope::RangePolicy;
+    use crate::monotone::Monotone;
+    use crate::suppression::Region;
+    use crate::{Forest, ForestOptions};
+
+    fn make_forest(threshold: f64) -> Forest<f64, 2> {
+        Forest::from_eif(&EifForest {
+            sample_size: 4,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0, -0.5],
+                p: vec![threshold, 0.0],
+                left: Box::new(EifNode::ExNode { size: 3 }),
+                right: Box::new(EifNode::ExNode { size: 1 }),
+            }],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn model_hash_is_stable() {
+        let forest = make_forest(0.5);
+        assert_eq!(forest.model_hash(), make_forest(0.5).model_hash());
+        assert_ne!(forest.model_hash(), make_forest(0.6).model_hash());
+
+        // the hash must not change between versions of the crate or platforms
+        assert_eq!(forest.model_hash(), 8028386642320909656);
+    }
+
+    #[test]
+    fn model_hash_covers_scoring_settings() {
+        let forest = make_forest(0.5);
+        let mut hashes = vec![forest.model_hash()];
+
+        let mut capped = make_forest(0.5);
+        capped.set_recursion_cap(Some(0));
+        hashes.push(capped.model_hash());
+        let mut strict = make_forest(0.5);
+        strict.set_strict_fp(true);
+        hashes.push(strict.model_hash());
+        let mut clamped = make_forest(0.5);
+        clamped.set_range_policy(RangePolicy::Clamp);
+        hashes.push(clamped.model_hash());
+        let mut monotone = make_forest(0.5);
+        monotone.set_monotone_constraints([Monotone::Above(0.0), Monotone::Both]);
+        hashes.push(monotone.model_hash());
+        let mut suppressed = make_forest(0.5);
+        suppressed.set_suppressed_regions(vec![Region::Box {
+            min: [0.0, 0.0],
+            max: [1.0, 1.0],
+        }]);
+        hashes.push(suppressed.model_hash());
+        let mut bucketed = make_forest(0.5);
+        bucketed.set_buckets(Buckets::new(0.5, 0.8).unwrap());
+        hashes.push(bucketed.model_hash());
+
+        let mut unique = hashes.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), hashes.len());
+
+        // caps beyond the deepest tree do not change the scores
+        let mut uncapped = make_forest(0.5);
+        uncapped.set_recursion_cap(Some(100));
+        assert_eq!(uncapped.model_hash(), forest.model_hash());
+    }
+
+    #[test]
+    fn hash_leaves_of_samples() {
+        let forest = make_forest(0.5);
+        assert_eq!(forest.hash(&[0.0, 0.0]), forest.hash(&[0.2, 0.0]));
+        assert_ne!(forest.hash(&[0.0, 0.0]), forest.hash(&[2.0, 0.0]));
+        assert_eq!(forest.similar_leaf_fraction(&[0.0, 0.0], &[0.2, 0.0]), 1.0);
+        assert_eq!(forest.similar_leaf_fraction(&[0.0, 0.0], &[2.0, 0.0]), 0.0);
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+        let (a, b, c) = ([5.0, 5.0], [5.1, 5.0], [15.0, 15.0]);
+        let same_hashes = |x: &[f64; 2], y: &[f64; 2]| {
+            let count = forest
+                .hash(x)
+                .iter()
+                .zip(forest.hash(y).iter())
+                .filter(|(x, y)| x == y)
+                .count();
+            count as f64 / forest.trees.len() as f64
+        };
+        assert_eq!(forest.similar_leaf_fraction(&a, &b), same_hashes(&a, &b));
+        assert_eq!(forest.similar_leaf_fraction(&a, &c), same_hashes(&a, &c));
+        assert!(forest.similar_leaf_fraction(&a, &b) > forest.similar_leaf_fraction(&a, &c));
+    }
+}
@@ -0,0 +1,158 @@
+//! Score grids over pairs of features, for visualizing what a forest has learned.
+//!
+//! [`Forest::pairwise_heatmaps`] scores, for every pair of features, a regular grid spanning the
+//! training range of both features while the remaining features are held at their training
+//! medians. The grids are stored in a single row-major tensor, which serializes to plain
+//! numbers a dashboard can render without knowing anything about forests.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64, 1.0]).collect();
+//! let forest: Forest<f64, 3> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let heatmaps = forest.pairwise_heatmaps(5).unwrap();
+//! assert_eq!(heatmaps.pairs, [(0, 1), (0, 2), (1, 2)]);
+//! assert_eq!(heatmaps.shape(), [3, 5, 5]);
+//! // the corners of the training range score higher than its center
+//! assert!(heatmaps.score(0, 0, 0) > heatmaps.score(0, 2, 2));
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::{to_f64, Forest};
+
+/// Score grids of all pairs of features, see [`Forest::pairwise_heatmaps`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Heatmaps {
+    /// Number of grid points along each feature.
+    pub resolution: usize,
+
+    /// The pairs `(i, j)` of features with `i < j`, in the order of the grids.
+    pub pairs: Vec<(usize, usize)>,
+
+    /// Per feature the values of the grid points, evenly spaced from the smallest to the
+    /// largest training value.
+    pub axes: Vec<Vec<f64>>,
+
+    /// The scores with the shape [`Heatmaps::shape`]: the score at index `[p, a, b]` is the
+    /// score of feature `i` at `axes[i][a]` and feature `j` at `axes[j][b]` for the pair
+    /// `pairs[p] = (i, j)`.
+    pub scores: Vec<f64>,
+}
+
+impl Heatmaps {
+    /// The dimensions `[pairs, resolution, resolution]` of the score tensor.
+    pub fn shape(&self) -> [usize; 3] {
+        [self.pairs.len(), self.resolution, self.resolution]
+    }
+
+    /// The score of the grid point `(a, b)` of the pair with index `pair`.
+    pub fn score(&self, pair: usize, a: usize, b: usize) -> f64 {
+        self.scores[(pair * self.resolution + a) * self.resolution + b]
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Score grids of `resolution` × `resolution` points for every pair of features, holding
+    /// the remaining features at their [training medians](Forest::training_medians).
+    ///
+    /// Returns `None` for a resolution below 2 and for forests without training range or
+    /// medians, like converted forests.
+    pub fn pairwise_heatmaps(&self, resolution: usize) -> Option<Heatmaps> {
+        let (min, max) = self.training_range()?;
+        if resolution < 2 || self.training_medians.len() != N {
+            return None;
+        }
+        let axes: Vec<Vec<T>> = (0..N)
+            .map(|i| {
+                let step = (max[i] - min[i]) / T::from(resolution - 1)?;
+                Some(
+                    (0..resolution)
+                        .map(|k| min[i] + step * T::from(k).unwrap_or_else(T::zero))
+                        .collect(),
+                )
+            })
+            .collect::<Option<_>>()?;
+        let medians: [T; N] = std::array::from_fn(|i| self.training_medians[i]);
+
+        let pairs: Vec<(usize, usize)> = (0..N)
+            .flat_map(|i| (i + 1..N).map(move |j| (i, j)))
+            .collect();
+        let mut scores = Vec::with_capacity(pairs.len() * resolution * resolution);
+        for (i, j) in pairs.iter().copied() {
+            let mut sample = medians;
+            for a in axes[i].iter() {
+                sample[i] = *a;
+                for b in axes[j].iter() {
+                    sample[j] = *b;
+                    scores.push(self.score(&sample));
+                }
+            }
+        }
+
+        Some(Heatmaps {
+            resolution,
+            pairs,
+            axes: axes
+                .into_iter()
+                .map(|axis| axis.into_iter().map(to_f64).collect())
+                .collect(),
+            scores,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn heatmaps_of_all_pairs() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, (i % 2) as f64, 5.0])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            seed: Some(3),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 4> = Forest::from_slice(&values, &options).unwrap();
+        assert_eq!(forest.training_medians(), &[9.5, 9.5, 0.5, 5.0]);
+
+        let heatmaps = forest.pairwise_heatmaps(4).unwrap();
+        assert_eq!(heatmaps.shape(), [6, 4, 4]);
+        assert_eq!(heatmaps.scores.len(), 6 * 16);
+        assert_eq!(heatmaps.pairs[0], (0, 1));
+        assert_eq!(heatmaps.axes[1], [0.0, 19.0 / 3.0, 38.0 / 3.0, 19.0]);
+        assert_eq!(heatmaps.axes[3], [5.0; 4]);
+        assert_eq!(heatmaps.score(0, 0, 0), forest.score(&[0.0, 0.0, 0.5, 5.0]));
+        assert_eq!(
+            heatmaps.score(0, 1, 3),
+            forest.score(&[19.0 / 3.0, 19.0, 0.5, 5.0])
+        );
+        // pair (1, 2) with the first feature at its median
+        assert_eq!(heatmaps.pairs[3], (1, 2));
+        assert_eq!(
+            heatmaps.score(3, 3, 0),
+            forest.score(&[9.5, 19.0, 0.0, 5.0])
+        );
+        assert!(forest.pairwise_heatmaps(1).is_none());
+
+        #[cfg(feature = "serde")]
+        {
+            let serialized = serde_json::to_value(&heatmaps).unwrap();
+            assert_eq!(serialized["scores"].as_array().unwrap().len(), 96);
+            assert_eq!(serialized["pairs"][5], serde_json::json!([2, 3]));
+        }
+    }
+}
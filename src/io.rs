@@ -0,0 +1,458 @@
+//! Reading and scoring the rows of CSV and NDJSON files.
+//!
+//! [`Forest::score_rows`] is the usual batch workflow as one call: it reads rows, maps their
+//! columns to the features with a [`FeatureSpec`], scores them and writes every row with the
+//! score appended. The rows are processed in batches of [`PipelineOptions::batch_size`], so
+//! memory stays bounded for inputs of any size, and every batch is parsed and scored on the
+//! threads of the [parallelism](Forest::parallelism) of the forest. [`read_samples`] reads the
+//! training data with the same spec.
+//!
+//! ```rust
+//! use extended_isolation_forest::features::FeatureSpec;
+//! use extended_isolation_forest::io::{read_samples, PipelineOptions, RowFormat};
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let spec = FeatureSpec::new(["width", "height"]);
+//! let training: String = (0..100)
+//!     .map(|i| format!("{},{}\n", i / 10, i % 10))
+//!     .fold("height,width\n".to_string(), |csv, row| csv + &row);
+//! let values: Vec<[f64; 2]> = read_samples(training.as_bytes(), &spec, RowFormat::Csv).unwrap();
+//! let forest = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let input = "id,height,width\na,3,4\nb,50,50\nc,,4\n";
+//! let mut output = Vec::new();
+//! let report = forest
+//!     .score_rows(input.as_bytes(), &mut output, &spec, &PipelineOptions::default())
+//!     .unwrap();
+//! assert_eq!((report.rows, report.missing_values), (3, 1));
+//!
+//! let output = String::from_utf8(output).unwrap();
+//! let lines: Vec<&str> = output.lines().collect();
+//! assert_eq!(lines[0], "id,height,width,score");
+//! assert!(lines[2].starts_with("b,50,50,0."));
+//! ```
+
+use std::io::{BufRead, BufWriter, Write};
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::features::{FeatureSpec, RawValue};
+use crate::leaf::LeafModel;
+use crate::{Error, Forest};
+
+/// The format of the rows read and written by [`Forest::score_rows`], one row per line.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RowFormat {
+    /// Comma-separated fields after a header line naming the columns. Fields may be quoted
+    /// with double quotes.
+    #[default]
+    Csv,
+
+    /// A JSON object per line with the columns as keys. Only available with the `json`
+    /// feature.
+    #[cfg(feature = "json")]
+    JsonLines,
+}
+
+/// How [`Forest::score_rows`] reads and writes rows.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PipelineOptions {
+    pub format: RowFormat,
+
+    /// Number of rows held in memory and scored at once.
+    pub batch_size: usize,
+
+    /// The column the score is written to.
+    pub score_column: String,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        Self {
+            format: RowFormat::Csv,
+            batch_size: 4096,
+            score_column: "score".to_string(),
+        }
+    }
+}
+
+/// Result of [`Forest::score_rows`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PipelineReport {
+    /// Number of scored rows.
+    pub rows: u64,
+
+    /// Number of features which were missing in their row, scored with the default of their
+    /// column.
+    pub missing_values: u64,
+}
+
+/// Read the samples of all rows of the input with the features selected by the spec, like
+/// the rows scored by [`Forest::score_rows`]. Empty lines are skipped.
+///
+/// Fails with [`Error::DimensionMismatch`] unless the spec has `N` features, and with
+/// [`Error::Deserialization`] for a missing header or column and the first row which can not
+/// be parsed.
+pub fn read_samples<R, T, const N: usize>(
+    input: R,
+    spec: &FeatureSpec,
+    format: RowFormat,
+) -> Result<Vec<[T; N]>, Error>
+where
+    R: BufRead,
+    T: Float,
+{
+    spec.check_dimensions::<N>()?;
+    let mut lines = input.lines();
+    let reader = RowReader::new(&mut lines, format, spec)?;
+    let mut samples = Vec::new();
+    for line in lines {
+        let line = line.map_err(Error::Io)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (sample, _) = reader.parse(&line, spec).map_err(|message| {
+            Error::Deserialization(format!("row {}: {}", samples.len() + 1, message))
+        })?;
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float + Sync,
+    L: LeafModel<T, N> + Sync,
+{
+    /// Score every row of the input and write it with the score appended to the output, see
+    /// the [module documentation](self). CSV output repeats the header with the score column
+    /// added, JSON objects get the score column as their last key. Empty lines are skipped.
+    ///
+    /// Fails with [`Error::DimensionMismatch`] unless the spec has `N` features, and with
+    /// [`Error::Deserialization`] for a missing header or column and the first row which can
+    /// not be parsed, after the rows before its batch have been written.
+    pub fn score_rows<R, W>(
+        &self,
+        input: R,
+        output: W,
+        spec: &FeatureSpec,
+        options: &PipelineOptions,
+    ) -> Result<PipelineReport, Error>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        spec.check_dimensions::<N>()?;
+        let mut lines = input.lines();
+        let mut writer = BufWriter::new(output);
+        let reader = RowReader::new(&mut lines, options.format, spec)?;
+        match &reader {
+            RowReader::Csv { header, .. } => {
+                writeln!(writer, "{},{}", header, options.score_column).map_err(Error::Io)?
+            }
+            #[cfg(feature = "json")]
+            RowReader::JsonLines => {}
+        }
+        #[cfg(feature = "json")]
+        let score_key = serde_json::to_string(&options.score_column)
+            .map_err(|err| Error::Deserialization(err.to_string()))?;
+
+        let mut report = PipelineReport {
+            rows: 0,
+            missing_values: 0,
+        };
+        let mut batch = Vec::with_capacity(options.batch_size.max(1));
+        loop {
+            batch.clear();
+            while batch.len() < options.batch_size.max(1) {
+                match lines.next().transpose().map_err(Error::Io)? {
+                    Some(line) if line.trim().is_empty() => {}
+                    Some(line) => batch.push(line),
+                    None => break,
+                }
+            }
+            if batch.is_empty() {
+                break;
+            }
+
+            let scored = self.parallelism.map(&batch, |line| {
+                let (sample, missing) = reader.parse::<T, N>(line, spec)?;
+                Ok::<_, String>((self.score(&sample), missing))
+            });
+            for (line, result) in batch.iter().zip(scored) {
+                let (score, missing) = result.map_err(|message| {
+                    Error::Deserialization(format!("row {}: {}", report.rows + 1, message))
+                })?;
+                match reader {
+                    RowReader::Csv { .. } => writeln!(writer, "{},{}", line, score),
+                    #[cfg(feature = "json")]
+                    RowReader::JsonLines => {
+                        // the line has been parsed as an object, so it ends with the brace
+                        let body = line.trim_end().strip_suffix('}').unwrap_or(line).trim_end();
+                        let separator = if body.ends_with('{') { "" } else { "," };
+                        let score = serde_json::Value::from(score);
+                        writeln!(writer, "{}{}{}:{}}}", body, separator, score_key, score)
+                    }
+                }
+                .map_err(Error::Io)?;
+                report.rows += 1;
+                report.missing_values += missing as u64;
+            }
+        }
+        writer.flush().map_err(Error::Io)?;
+        Ok(report)
+    }
+}
+
+/// Reads the features from the rows of one format.
+enum RowReader {
+    Csv {
+        header: String,
+        num_columns: usize,
+
+        /// The index of the column of every feature.
+        indices: Vec<usize>,
+    },
+
+    #[cfg(feature = "json")]
+    JsonLines,
+}
+
+impl RowReader {
+    /// The reader of the rows of the format, consuming the header of CSV input.
+    fn new(
+        lines: &mut impl Iterator<Item = std::io::Result<String>>,
+        format: RowFormat,
+        spec: &FeatureSpec,
+    ) -> Result<Self, Error> {
+        match format {
+            RowFormat::Csv => {
+                let header = lines
+                    .next()
+                    .transpose()
+                    .map_err(Error::Io)?
+                    .ok_or_else(|| Error::Deserialization("missing header".to_string()))?;
+                let columns = split_fields(&header);
+                let indices = spec
+                    .features
+                    .iter()
+                    .map(|feature| {
+                        columns
+                            .iter()
+                            .position(|column| *column == feature.column)
+                            .ok_or_else(|| {
+                                Error::Deserialization(format!(
+                                    "missing column {:?}",
+                                    feature.column
+                                ))
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::Csv {
+                    header,
+                    num_columns: columns.len(),
+                    indices,
+                })
+            }
+            #[cfg(feature = "json")]
+            RowFormat::JsonLines => Ok(Self::JsonLines),
+        }
+    }
+
+    /// The features of the row and the number of missing ones.
+    fn parse<T: Float, const N: usize>(
+        &self,
+        line: &str,
+        spec: &FeatureSpec,
+    ) -> Result<([T; N], usize), String> {
+        match self {
+            Self::Csv {
+                num_columns,
+                indices,
+                ..
+            } => {
+                let fields = split_fields(line);
+                if fields.len() != *num_columns {
+                    return Err(format!(
+                        "expected {} fields, but got {}",
+                        num_columns,
+                        fields.len()
+                    ));
+                }
+                spec.convert(|feature| Ok(RawValue::Text(&fields[indices[feature]])))
+            }
+            #[cfg(feature = "json")]
+            Self::JsonLines => {
+                let object: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(line).map_err(|err| err.to_string())?;
+                spec.json_sample(&object)
+            }
+        }
+    }
+}
+
+/// The fields of a CSV line, with the quotes of quoted fields removed.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_samples, split_fields, PipelineOptions, RowFormat};
+    use crate::features::{FeatureColumn, FeatureSpec};
+    use crate::parallel::Parallelism;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn score_rows_appends_scores() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 64,
+            seed: Some(22),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        assert_eq!(
+            split_fields(r#"a,"b, ""c""",,d"#),
+            ["a", "b, \"c\"", "", "d"]
+        );
+
+        let input = "name,y,x\n\"first, row\",1,2\n\nsecond,40,\nthird,5,6\n";
+        let spec = FeatureSpec::new(["x", "y"]);
+        let samples: Vec<[f64; 2]> = read_samples(input.as_bytes(), &spec, RowFormat::Csv).unwrap();
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[2], [6.0, 5.0]);
+        assert!(samples[1][0].is_nan());
+
+        let score_rows = |forest: &Forest<f64, 2>, batch_size: usize| {
+            let mut output = Vec::new();
+            let options = PipelineOptions {
+                batch_size,
+                ..Default::default()
+            };
+            let report = forest
+                .score_rows(input.as_bytes(), &mut output, &spec, &options)
+                .unwrap();
+            (report, String::from_utf8(output).unwrap())
+        };
+        let (report, output) = score_rows(&forest, 2);
+        assert_eq!((report.rows, report.missing_values), (3, 1));
+        let expected = format!(
+            "name,y,x,score\n\"first, row\",1,2,{}\nsecond,40,,{}\nthird,5,6,{}\n",
+            forest.score(&[2.0, 1.0]),
+            forest.score(&[f64::NAN, 40.0]),
+            forest.score(&[6.0, 5.0])
+        );
+        assert_eq!(output, expected);
+        forest.set_parallelism(Parallelism::sequential());
+        assert_eq!(score_rows(&forest, 1).1, expected);
+
+        let with_default = FeatureSpec {
+            features: vec![
+                FeatureColumn {
+                    default: Some(0.0),
+                    ..FeatureColumn::number("x")
+                },
+                FeatureColumn::number("y"),
+            ],
+        };
+        let mut output = Vec::new();
+        let report = forest
+            .score_rows(
+                input.as_bytes(),
+                &mut output,
+                &with_default,
+                &PipelineOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(report.missing_values, 1);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("second,40,,{}\n", forest.score(&[0.0, 40.0]))));
+
+        let failing = |input: &str, spec: &FeatureSpec| {
+            let mut output = Vec::new();
+            forest.score_rows(
+                input.as_bytes(),
+                &mut output,
+                spec,
+                &PipelineOptions::default(),
+            )
+        };
+        assert!(matches!(
+            failing(input, &FeatureSpec::new(["x"])),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            failing(input, &FeatureSpec::new(["x", "z"])),
+            Err(Error::Deserialization(message)) if message == "missing column \"z\""
+        ));
+        assert!(matches!(
+            failing("x,y\n1,2\n3,four\n", &spec),
+            Err(Error::Deserialization(message))
+                if message == "row 2: column \"y\": invalid number \"four\""
+        ));
+        assert!(matches!(
+            failing("x,y\n1,2,3\n", &spec),
+            Err(Error::Deserialization(message)) if message.starts_with("row 1: expected 2 fields")
+        ));
+        assert!(matches!(failing("", &spec), Err(Error::Deserialization(_))));
+
+        #[cfg(feature = "json")]
+        {
+            let input = "{\"x\": 2, \"y\": \"1\", \"id\": \"a\"}\n{\"y\": null}\n{}\n";
+            let options = PipelineOptions {
+                format: RowFormat::JsonLines,
+                score_column: "anomaly".to_string(),
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            let report = forest
+                .score_rows(input.as_bytes(), &mut output, &spec, &options)
+                .unwrap();
+            assert_eq!((report.rows, report.missing_values), (3, 4));
+            let rows: Vec<serde_json::Value> = String::from_utf8(output)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            assert_eq!(rows[0]["id"], "a");
+            assert_eq!(
+                rows[0]["anomaly"].as_f64().unwrap().to_string(),
+                forest.score(&[2.0, 1.0]).to_string()
+            );
+            assert_eq!(rows[2].as_object().unwrap().len(), 1);
+            assert!(matches!(
+                forest.score_rows("[1, 2]\n".as_bytes(), Vec::new(), &spec, &options),
+                Err(Error::Deserialization(message)) if message.starts_with("row 1:")
+            ));
+            let samples: Vec<[f64; 2]> =
+                read_samples(input.as_bytes(), &spec, RowFormat::JsonLines).unwrap();
+            assert_eq!(samples[0], [2.0, 1.0]);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Java bindings with the JNI, available with the `jni` feature.
+//!
+//! Built as a `cdylib` with the feature enabled, the library implements the native methods of
+//! the class `eif.ExtendedIsolationForest` in the `java` directory, which loads forests in the
+//! binary format or as JSON like [`DynForest::from_bytes`] and scores samples with them. The
+//! forests are boxed [`DynForest`]s passed to Java as `long` handles, which must be released with
+//! `nativeFree` exactly once. The class zeroes the handle of a closed forest, and calls with the
+//! handle 0 throw an `IllegalStateException`. Other errors and panics are thrown as
+//! `RuntimeException`s.
+
+use std::fmt::{self, Display, Formatter};
+
+use jni::errors::ThrowRuntimeExAndDefault;
+use jni::objects::{JByteArray, JClass, JDoubleArray};
+use jni::sys::{jint, jlong};
+use jni::{jni_mangle, jni_str, Env, EnvUnowned};
+
+use crate::dynamic::DynForest;
+use crate::Error;
+
+/// Load a forest from the bytes, JSON requires positive `dimensions`. Returns the handle of the
+/// forest.
+#[jni_mangle("eif.ExtendedIsolationForest")]
+pub fn native_load<'local>(
+    mut env: EnvUnowned<'local>,
+    _class: JClass<'local>,
+    bytes: JByteArray<'local>,
+    dimensions: jint,
+) -> jlong {
+    env.with_env(|env| -> Result<jlong, JniError> {
+        let bytes = env.convert_byte_array(&bytes)?;
+        let dimensions = usize::try_from(dimensions).ok().filter(|&d| d > 0);
+        let forest = DynForest::from_bytes(&bytes, dimensions)?;
+        Ok(Box::into_raw(Box::new(forest)) as jlong)
+    })
+    .resolve::<ThrowRuntimeExAndDefault>()
+}
+
+/// The scores of the samples, stored one after the other in `data`.
+#[jni_mangle("eif.ExtendedIsolationForest")]
+pub fn native_score<'local>(
+    mut env: EnvUnowned<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+    data: JDoubleArray<'local>,
+) -> JDoubleArray<'local> {
+    env.with_env(|env| -> Result<JDoubleArray<'local>, JniError> {
+        // SAFETY: the handles passed by the class are returned by `native_load`, and zeroed by
+        // `close` before they are released while holding the lock of the forest
+        let forest = unsafe { forest(env, handle)? };
+        let mut values = vec![0.0; data.len(env)?];
+        data.get_region(env, 0, &mut values)?;
+        let scores = forest.score_rows(&values)?;
+        let array = JDoubleArray::new(env, scores.len())?;
+        array.set_region(env, 0, &scores)?;
+        Ok(array)
+    })
+    .resolve::<ThrowRuntimeExAndDefault>()
+}
+
+/// The number of features of the samples.
+#[jni_mangle("eif.ExtendedIsolationForest")]
+pub fn native_dimensions<'local>(
+    mut env: EnvUnowned<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) -> jint {
+    env.with_env(|env| -> Result<jint, JniError> {
+        // SAFETY: like in `native_score`
+        let forest = unsafe { forest(env, handle)? };
+        Ok(forest.dimensions() as jint)
+    })
+    .resolve::<ThrowRuntimeExAndDefault>()
+}
+
+/// Release the forest, the handle must not be used afterwards.
+#[jni_mangle("eif.ExtendedIsolationForest")]
+pub fn native_free<'local>(_env: EnvUnowned<'local>, _class: JClass<'local>, handle: jlong) {
+    if handle != 0 {
+        // SAFETY: `close` passes the handle returned by `native_load` once, zeroing it while
+        // holding the lock of the forest, so no other call uses the released forest
+        drop(unsafe { Box::from_raw(handle as *mut DynForest) });
+    }
+}
+
+/// The forest behind a handle returned by `native_load`, throwing an `IllegalStateException`
+/// for the handle 0 of a closed forest.
+///
+/// # Safety
+///
+/// A handle other than 0 must have been returned by `native_load` and must not have been
+/// released with `native_free`, and must not be released while the forest is borrowed.
+unsafe fn forest<'a>(env: &mut Env<'_>, handle: jlong) -> Result<&'a DynForest, JniError> {
+    if handle == 0 {
+        // fails with `JavaException` once the exception is pending
+        env.throw_new(
+            jni_str!("java/lang/IllegalStateException"),
+            jni_str!("the forest is closed"),
+        )?;
+        return Err(JniError::Jni(jni::errors::Error::JavaException));
+    }
+    // SAFETY: the handle points to the live boxed forest created by `native_load`, see the
+    // contract of this function
+    Ok(unsafe { &*(handle as *const DynForest) })
+}
+
+/// The errors thrown to Java.
+#[derive(Debug)]
+enum JniError {
+    Forest(Error),
+    Jni(jni::errors::Error),
+}
+
+impl Display for JniError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Forest(err) => Display::fmt(err, f),
+            Self::Jni(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for JniError {}
+
+impl From<Error> for JniError {
+    fn from(err: Error) -> Self {
+        Self::Forest(err)
+    }
+}
+
+impl From<jni::errors::Error> for JniError {
+    fn from(err: jni::errors::Error) -> Self {
+        Self::Jni(err)
+    }
+}
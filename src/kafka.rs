@@ -0,0 +1,295 @@
+//! Scoring Kafka topics, available with the `kafka` feature.
+//!
+//! [`Forest::score_kafka`] consumes the records of the input topic as a member of a consumer
+//! group, decodes a sample from the payload of every record with a closure, and produces the
+//! payload built by a second closure from the record and its score to the output topic, with
+//! the key of the input record. Records which do not decode are skipped.
+//!
+//! Records are processed in batches. The offsets of a batch are committed once all its scored
+//! records have been delivered, so after a failure the records of the last batch are scored
+//! again: every record is produced at least once.
+//!
+//! ```rust,no_run
+//! use extended_isolation_forest::kafka::KafkaOptions;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let options = KafkaOptions::new("localhost:9092", "eif", "readings", "scored-readings");
+//! forest
+//!     .score_kafka(
+//!         &options,
+//!         |payload| serde_json::from_slice::<[f64; 2]>(payload).ok(),
+//!         |payload, score| {
+//!             format!("{{\"sample\":{},\"score\":{}}}", String::from_utf8_lossy(payload), score)
+//!                 .into_bytes()
+//!         },
+//!     )
+//!     .unwrap();
+//! ```
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use num_traits::Float;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::{DeliveryResult, Message};
+use rdkafka::producer::{BaseProducer, BaseRecord, Producer, ProducerContext};
+use rdkafka::ClientContext;
+
+use crate::leaf::LeafModel;
+use crate::{Error, Forest, SampleAccess};
+
+/// How [`Forest::score_kafka`] consumes and produces records.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KafkaOptions {
+    /// The `bootstrap.servers` of the consumer and the producer.
+    pub brokers: String,
+
+    /// The consumer group, which resumes from the committed offsets.
+    pub group_id: String,
+
+    pub input_topic: String,
+
+    pub output_topic: String,
+
+    /// Further librdkafka properties of the consumer and the producer, like
+    /// `security.protocol` or `auto.offset.reset`.
+    pub properties: Vec<(String, String)>,
+
+    /// Number of records after which the offsets are committed.
+    pub batch_size: usize,
+
+    /// How long to wait for records before committing a partial batch.
+    pub poll_timeout: Duration,
+
+    /// How long to wait for the delivery of the records of a batch.
+    pub delivery_timeout: Duration,
+
+    /// Return after this number of records, or consume forever.
+    pub max_records: Option<u64>,
+}
+
+impl KafkaOptions {
+    /// Options for scoring the input topic into the output topic, with the defaults for the
+    /// other options.
+    pub fn new(
+        brokers: impl Into<String>,
+        group_id: impl Into<String>,
+        input_topic: impl Into<String>,
+        output_topic: impl Into<String>,
+    ) -> Self {
+        Self {
+            brokers: brokers.into(),
+            group_id: group_id.into(),
+            input_topic: input_topic.into(),
+            output_topic: output_topic.into(),
+            properties: Vec::new(),
+            batch_size: 1000,
+            poll_timeout: Duration::from_secs(1),
+            delivery_timeout: Duration::from_secs(30),
+            max_records: None,
+        }
+    }
+
+    /// The configuration shared by the consumer and the producer.
+    fn client_config(&self) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.group_id)
+            // offsets are committed once their batch has been delivered
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false");
+        for (key, value) in &self.properties {
+            config.set(key, value);
+        }
+        config
+    }
+}
+
+/// Result of [`Forest::score_kafka`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KafkaReport {
+    /// Number of scored and produced records.
+    pub records: u64,
+
+    /// Number of records which did not decode.
+    pub skipped: u64,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Score the records of the input topic into the output topic, see the
+    /// [module documentation](self). `decode` returns the sample of a payload or `None` for
+    /// skipping the record, `encode` the payload of the scored record. Records without a
+    /// payload are decoded from an empty payload.
+    ///
+    /// Returns after [`KafkaOptions::max_records`] records. Fails with [`Error::Kafka`] if the
+    /// clients can not be created, a record can not be consumed or delivered, or the offsets
+    /// can not be committed. The offsets of the failed batch are not committed.
+    pub fn score_kafka<S, D, E>(
+        &self,
+        options: &KafkaOptions,
+        mut decode: D,
+        mut encode: E,
+    ) -> Result<KafkaReport, Error>
+    where
+        S: SampleAccess<T, N>,
+        D: FnMut(&[u8]) -> Option<S>,
+        E: FnMut(&[u8], f64) -> Vec<u8>,
+    {
+        let config = options.client_config();
+        let consumer: BaseConsumer = config.create().map_err(kafka_error)?;
+        let producer: BaseProducer<Deliveries> = config
+            .create_with_context(Deliveries::default())
+            .map_err(kafka_error)?;
+        consumer
+            .subscribe(&[options.input_topic.as_str()])
+            .map_err(kafka_error)?;
+
+        let batch_size = options.batch_size.max(1);
+        let mut report = KafkaReport::default();
+        let (mut pending, mut idle) = (0, false);
+        loop {
+            let done = options
+                .max_records
+                .is_some_and(|max| report.records + report.skipped >= max);
+            // partial batches are committed while the topic is idle
+            if pending > 0 && (pending >= batch_size || idle || done) {
+                producer
+                    .flush(options.delivery_timeout)
+                    .map_err(kafka_error)?;
+                producer.context().check()?;
+                consumer
+                    .commit_consumer_state(CommitMode::Sync)
+                    .map_err(kafka_error)?;
+                pending = 0;
+            }
+            if done {
+                return Ok(report);
+            }
+
+            let Some(message) = consumer.poll(options.poll_timeout) else {
+                idle = true;
+                continue;
+            };
+            idle = false;
+            let message = message.map_err(kafka_error)?;
+            let payload = message.payload().unwrap_or_default();
+            match decode(payload) {
+                Some(sample) => {
+                    let scored = encode(payload, self.score(&sample));
+                    let mut record = BaseRecord::<[u8], [u8]>::to(&options.output_topic)
+                        .payload(scored.as_slice());
+                    if let Some(key) = message.key() {
+                        record = record.key(key);
+                    }
+                    send(&producer, record)?;
+                    report.records += 1;
+                }
+                None => report.skipped += 1,
+            }
+            consumer
+                .store_offset_from_message(&message)
+                .map_err(kafka_error)?;
+            pending += 1;
+        }
+    }
+}
+
+fn kafka_error(err: KafkaError) -> Error {
+    Error::Kafka(err.to_string())
+}
+
+/// Send the record, waiting for space in the queue of the producer.
+fn send(
+    producer: &BaseProducer<Deliveries>,
+    mut record: BaseRecord<[u8], [u8]>,
+) -> Result<(), Error> {
+    loop {
+        match producer.send(record) {
+            Ok(()) => {
+                producer.poll(Duration::ZERO);
+                return Ok(());
+            }
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                record = returned;
+                producer.poll(Duration::from_millis(100));
+            }
+            Err((err, _)) => return Err(kafka_error(err)),
+        }
+    }
+}
+
+/// Keeps the first failed delivery of the producer.
+#[derive(Default)]
+struct Deliveries {
+    failure: Mutex<Option<KafkaError>>,
+}
+
+impl Deliveries {
+    fn check(&self) -> Result<(), Error> {
+        match self.lock().take() {
+            Some(err) => Err(kafka_error(err)),
+            None => Ok(()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<KafkaError>> {
+        // the failure is replaced as a whole, so poisoning can be ignored
+        self.failure
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ClientContext for Deliveries {}
+
+impl ProducerContext for Deliveries {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+        if let Err((err, _)) = result {
+            self.lock().get_or_insert_with(|| err.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::KafkaOptions;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn kafka_options() {
+        let mut options = KafkaOptions::new("localhost:9092", "eif", "in", "out");
+        options
+            .properties
+            .push(("auto.offset.reset".to_string(), "earliest".to_string()));
+        let config = options.client_config();
+        assert_eq!(config.get("bootstrap.servers"), Some("localhost:9092"));
+        assert_eq!(config.get("group.id"), Some("eif"));
+        assert_eq!(config.get("enable.auto.commit"), Some("false"));
+        assert_eq!(config.get("auto.offset.reset"), Some("earliest"));
+
+        // invalid properties fail when the clients are created
+        let values: Vec<_> = (0..100).map(|i| [i as f64]).collect();
+        let forest: Forest<f64, 1> =
+            Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+        options
+            .properties
+            .push(("no.such.property".to_string(), "1".to_string()));
+        options.poll_timeout = Duration::from_millis(10);
+        let result = forest.score_kafka(&options, |_| Some([0.0]), |_, _| Vec::new());
+        assert!(matches!(result, Err(Error::Kafka(_))));
+    }
+}
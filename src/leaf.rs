@@ -0,0 +1,484 @@
+//! Statistics stored in the leaves of the trees.
+//!
+//! Leaves always know how many training samples reached them, which is all the path lengths
+//! need. A [`LeafModel`] summarizes these samples further when the tree is built, for scoring
+//! modes looking at where a sample lies within its leaf. Forests built with
+//! [`Forest::from_slice_with_leaves`] store a model in every leaf, all other constructors use
+//! the plain [`Count`].
+//!
+//! ```rust
+//! use extended_isolation_forest::leaf::GaussianLeaf;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2, GaussianLeaf<2>> =
+//!     Forest::from_slice_with_leaves(&values, &ForestOptions::default()).unwrap();
+//!
+//! assert!(forest.score(&[50.0, 50.0]) > forest.score(&[5.0, 5.0]));
+//! assert!(forest.gaussian_tail_score(&[50.0, 50.0]) > forest.gaussian_tail_score(&[5.0, 5.0]));
+//! ```
+//!
+//! [`Forest::debug_score`] lists the leaves a sample reaches together with their depths and
+//! models. With the bounding boxes of [`BoxLeaf`] it shows which leaves moved after retraining
+//! and thereby changed the score of a sample:
+//!
+//! ```rust
+//! use extended_isolation_forest::leaf::BoxLeaf;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2, BoxLeaf<2>> =
+//!     Forest::from_slice_with_leaves(&values, &ForestOptions::default()).unwrap();
+//!
+//! let debug = forest.debug_score(&[50.0, 50.0]);
+//! assert_eq!(debug.leaves.len(), forest.options().n_trees);
+//! for hit in debug.leaves.iter() {
+//!     // the sample lies outside of the training samples of every leaf it reaches
+//!     assert!(hit.model.max()[0] < 50.0 || hit.model.max()[1] < 50.0);
+//! }
+//! ```
+
+use num_traits::Float;
+use rand::{distributions::uniform::SampleUniform, seq::index};
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::split::ExtendedSplit;
+use crate::{
+    normalize_path_length, to_f64, Direction, Error, ExNode, Forest, ForestFloat, ForestOptions,
+    Node, SampleAccess, Tree,
+};
+
+/// A summary of the training samples reaching a leaf.
+///
+/// The default value is stored in leaves of forests deserialized from files written before the
+/// model was known.
+pub trait LeafModel<T, const N: usize>: Default {
+    /// Summarize the samples of a leaf. Leaves can be empty.
+    fn fit<S>(samples: &[S]) -> Self
+    where
+        S: SampleAccess<T, N>;
+}
+
+/// The default leaf model, storing nothing besides the number of samples every leaf keeps.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Count;
+
+impl<T, const N: usize> LeafModel<T, N> for Count {
+    fn fit<S>(_samples: &[S]) -> Self
+    where
+        S: SampleAccess<T, N>,
+    {
+        Count
+    }
+}
+
+/// The per-dimension mean and variance of the samples of a leaf.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GaussianLeaf<const N: usize> {
+    count: usize,
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    mean: [f64; N],
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    variance: [f64; N],
+}
+
+impl<const N: usize> GaussianLeaf<N> {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> &[f64; N] {
+        &self.mean
+    }
+
+    /// The population variance of every dimension.
+    pub fn variance(&self) -> &[f64; N] {
+        &self.variance
+    }
+}
+
+impl<const N: usize> Default for GaussianLeaf<N> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: [0.0; N],
+            variance: [0.0; N],
+        }
+    }
+}
+
+impl<T, const N: usize> LeafModel<T, N> for GaussianLeaf<N>
+where
+    T: Float,
+{
+    fn fit<S>(samples: &[S]) -> Self
+    where
+        S: SampleAccess<T, N>,
+    {
+        let mut leaf = Self {
+            count: samples.len(),
+            ..Default::default()
+        };
+        if samples.is_empty() {
+            return leaf;
+        }
+        // Welford's algorithm
+        for (k, sample) in samples.iter().enumerate() {
+            for i in 0..N {
+                let value = to_f64(sample.feature(i));
+                let delta = value - leaf.mean[i];
+                leaf.mean[i] += delta / (k + 1) as f64;
+                leaf.variance[i] += delta * (value - leaf.mean[i]);
+            }
+        }
+        leaf.variance
+            .iter_mut()
+            .for_each(|variance| *variance /= samples.len() as f64);
+        leaf
+    }
+}
+
+/// The bounding box of the samples of a leaf.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BoxLeaf<const N: usize> {
+    count: usize,
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    min: [f64; N],
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    max: [f64; N],
+}
+
+impl<const N: usize> BoxLeaf<N> {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The smallest value of every dimension, `0` for empty leaves.
+    pub fn min(&self) -> &[f64; N] {
+        &self.min
+    }
+
+    /// The largest value of every dimension, `0` for empty leaves.
+    pub fn max(&self) -> &[f64; N] {
+        &self.max
+    }
+}
+
+impl<const N: usize> Default for BoxLeaf<N> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            min: [0.0; N],
+            max: [0.0; N],
+        }
+    }
+}
+
+impl<T, const N: usize> LeafModel<T, N> for BoxLeaf<N>
+where
+    T: Float,
+{
+    fn fit<S>(samples: &[S]) -> Self
+    where
+        S: SampleAccess<T, N>,
+    {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut leaf = Self {
+            count: samples.len(),
+            min: [f64::INFINITY; N],
+            max: [f64::NEG_INFINITY; N],
+        };
+        for sample in samples.iter() {
+            for i in 0..N {
+                let value = to_f64(sample.feature(i));
+                leaf.min[i] = leaf.min[i].min(value);
+                leaf.max[i] = leaf.max[i].max(value);
+            }
+        }
+        leaf
+    }
+}
+
+/// The leaf a sample reaches in one tree, see [`Forest::debug_score`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeafHit<'a, L> {
+    /// Index of the tree.
+    pub tree: usize,
+
+    /// Depth of the leaf, including the internal nodes merged into depth offsets.
+    pub depth: usize,
+
+    /// Number of training samples which reached the leaf.
+    pub num_samples: usize,
+
+    /// The path length of the sample in the tree, truncated at the recursion cap.
+    pub path_length: f64,
+
+    pub model: &'a L,
+}
+
+/// The leaves behind the score of a sample, see [`Forest::debug_score`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreDebug<'a, L> {
+    /// The score of the sample, computed from the path lengths of the leaves.
+    pub score: f64,
+
+    /// The leaf reached in every tree, in the order of the trees.
+    pub leaves: Vec<LeafHit<'a, L>>,
+}
+
+impl<'de, T, const N: usize, L> Forest<T, N, L>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+    L: LeafModel<T, N>,
+{
+    /// Build a new forest from the given training data, storing the leaf model `L` in every
+    /// leaf.
+    pub fn from_slice_with_leaves<S>(
+        training_data: &[S],
+        options: &ForestOptions,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        Self::train_with_strategy(
+            training_data.len(),
+            |i| &training_data[i],
+            |rng| index::sample(rng, training_data.len(), options.sample_size).into_vec(),
+            options,
+            ExtendedSplit::new(options.hyperplanes),
+        )
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The models of the leaves reached by the sample, one per tree in the order of the trees.
+    pub fn leaf_models<S>(&self, sample: &S) -> Vec<&L>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.trees
+            .iter()
+            .map(|tree| &tree.leaf(sample).model)
+            .collect()
+    }
+
+    /// The leaves the sample reaches together with their depths and [models](LeafModel), for
+    /// finding out why a sample scores the way it does.
+    ///
+    /// Suppressed regions, monotone constraints and the range policy are applied like in
+    /// [`Forest::score`], but the path lengths are computed with the regular floating point
+    /// arithmetic even in strict mode.
+    pub fn debug_score<S>(&self, sample: &S) -> ScoreDebug<'_, L>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let adjusted = self.adjusted_sample(sample);
+        let max_depth = self.recursion_cap();
+        let leaves: Vec<_> = self
+            .trees
+            .iter()
+            .enumerate()
+            .map(|(index, tree)| {
+                let (leaf, depth) = leaf_with_depth(tree, &adjusted);
+                LeafHit {
+                    tree: index,
+                    depth,
+                    num_samples: leaf.num_samples,
+                    path_length: tree.path_length_with_cap(&adjusted, max_depth),
+                    model: &leaf.model,
+                }
+            })
+            .collect();
+        let path_length: f64 = leaves.iter().map(|hit| hit.path_length).sum();
+        ScoreDebug {
+            score: if self.is_suppressed(sample) {
+                0.0
+            } else {
+                normalize_path_length(
+                    path_length / leaves.len().max(1) as f64,
+                    self.avg_path_length_c,
+                )
+            },
+            leaves,
+        }
+    }
+}
+
+/// The leaf reached by the sample and its depth, ignoring the recursion cap.
+fn leaf_with_depth<'a, T, S, const N: usize, L>(
+    tree: &'a Tree<T, N, L>,
+    sample: &S,
+) -> (&'a ExNode<T, N, L>, usize)
+where
+    T: Float,
+    S: SampleAccess<T, N> + ?Sized,
+    L: LeafModel<T, N>,
+{
+    let mut node = &tree.root;
+    let mut depth = 0;
+    loop {
+        depth += node.depth_offset();
+        match node {
+            Node::Ex(ex_node) => return (ex_node, depth),
+            Node::In(in_node) => {
+                node = match in_node.direction(sample) {
+                    Direction::Left => in_node.left.as_ref(),
+                    Direction::Right => in_node.right.as_ref(),
+                };
+                depth += 1;
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Forest<T, N, GaussianLeaf<N>>
+where
+    T: Float,
+{
+    /// How far the sample lies in the tails of the leaves it reaches, in `[0, 1]`.
+    ///
+    /// Every dimension of every leaf contributes `1 - exp(-z² / 2)` for the distance `z` of the
+    /// sample to the mean of the leaf in units of its standard deviation, which is about `0.29`
+    /// on average for normally distributed samples. In dimensions without variance within a
+    /// leaf the contribution is `0` for samples at the mean and `1` otherwise. Empty leaves are
+    /// skipped.
+    pub fn gaussian_tail_score<S>(&self, sample: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut sum = 0.0;
+        let mut count = 0;
+        for leaf in self.leaf_models(sample) {
+            if leaf.count == 0 {
+                continue;
+            }
+            for i in 0..N {
+                let distance = to_f64(sample.feature(i)) - leaf.mean[i];
+                sum += if leaf.variance[i] > 0.0 {
+                    1.0 - (-distance * distance / leaf.variance[i] / 2.0).exp()
+                } else if distance == 0.0 {
+                    0.0
+                } else {
+                    1.0
+                };
+                count += 1;
+            }
+        }
+        if count == 0 {
+            0.0
+        } else {
+            sum / count as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoxLeaf, GaussianLeaf, LeafModel};
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn gaussian_leaves() {
+        let leaf: GaussianLeaf<2> = LeafModel::<f64, 2>::fit(&[[1.0, 5.0], [3.0, 5.0]]);
+        assert_eq!(leaf.count(), 2);
+        assert_eq!(leaf.mean(), &[2.0, 5.0]);
+        assert_eq!(leaf.variance(), &[1.0, 0.0]);
+        let empty: GaussianLeaf<2> = LeafModel::<f64, 2>::fit::<[f64; 2]>(&[]);
+        assert_eq!(empty, GaussianLeaf::default());
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let forest: Forest<f64, 2, GaussianLeaf<2>> =
+            Forest::from_slice_with_leaves(values.as_slice(), &ForestOptions::default()).unwrap();
+        assert_eq!(forest.leaf_models(&[3.0, 4.0]).len(), 20);
+        assert!(
+            forest.gaussian_tail_score(&[100.0, 100.0]) > forest.gaussian_tail_score(&[9.5, 9.5])
+        );
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&forest).unwrap();
+            let restored: Forest<f64, 2, GaussianLeaf<2>> = serde_json::from_str(&json).unwrap();
+            let counts = |forest: &Forest<f64, 2, GaussianLeaf<2>>| -> Vec<usize> {
+                forest
+                    .leaf_models(&[3.0, 4.0])
+                    .iter()
+                    .map(|leaf| leaf.count())
+                    .collect()
+            };
+            assert_eq!(counts(&restored), counts(&forest));
+        }
+    }
+
+    #[test]
+    fn debug_scores_with_box_leaves() {
+        let leaf: BoxLeaf<2> = LeafModel::<f64, 2>::fit(&[[1.0, 5.0], [3.0, 4.0]]);
+        assert_eq!(leaf.count(), 2);
+        assert_eq!(leaf.min(), &[1.0, 4.0]);
+        assert_eq!(leaf.max(), &[3.0, 5.0]);
+        let empty: BoxLeaf<2> = LeafModel::<f64, 2>::fit::<[f64; 2]>(&[]);
+        assert_eq!(empty, BoxLeaf::default());
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 30,
+            sample_size: 64,
+            seed: Some(6),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2, BoxLeaf<2>> =
+            Forest::from_slice_with_leaves(values.as_slice(), &options).unwrap();
+        for sample in [[9.5, 9.5], [100.0, -3.0]] {
+            let debug = forest.debug_score(&sample);
+            assert_eq!(debug.leaves.len(), 30);
+            assert!((debug.score - forest.score(&sample)).abs() < 1e-12);
+            for (index, hit) in debug.leaves.iter().enumerate() {
+                assert_eq!(hit.tree, index);
+                assert_eq!(hit.model.count(), hit.num_samples);
+                assert!(hit.path_length >= hit.depth as f64);
+            }
+        }
+        let outlier = forest.debug_score(&[100.0, -3.0]);
+        let normal = forest.debug_score(&[9.5, 9.5]);
+        let mean_depth = |debug: &super::ScoreDebug<'_, BoxLeaf<2>>| {
+            debug.leaves.iter().map(|hit| hit.depth).sum::<usize>() as f64 / 30.0
+        };
+        assert!(mean_depth(&outlier) < mean_depth(&normal));
+        let depths: Vec<usize> = normal.leaves.iter().map(|hit| hit.depth).collect();
+
+        // truncated paths keep the depth of the leaf
+        forest.set_recursion_cap(Some(1));
+        let capped = forest.debug_score(&[9.5, 9.5]);
+        assert!(capped.leaves.iter().all(|hit| hit.path_length <= 1.0));
+        assert_eq!(
+            capped
+                .leaves
+                .iter()
+                .map(|hit| hit.depth)
+                .collect::<Vec<_>>(),
+            depths
+        );
+    }
+}
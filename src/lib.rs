@@ -4,16 +4,33 @@
 //! and implemented in [https://github.com/sahandha/eif](https://github.com/sahandha/eif). For a detailed description see the paper or the
 //! github repository.
 //!
-//! This crate requires rust >= 1.51 as it makes use of `min_const_generics`.
+//! This crate requires rust >= 1.87. The `arrow`, `parquet`, `grpc` and `napi` features require
+//! rust >= 1.88 and the `datafusion` feature rust >= 1.94, following their dependencies.
 //!
-//! Includes optional serde support with the `serde` feature.
+//! Includes optional serde support with the `serde` feature. The `nalgebra` feature allows using
+//! statically sized `nalgebra` vectors as samples and enables PCA whitening of the training data.
+//! The `json` feature enables loading forests serialized as JSON from files and a binary format
+//! for large forests decoded in parallel, the `mmap` feature training from memory-mapped
+//! datasets. The `benchmarks` feature evaluates options on labeled benchmark datasets. The `arrow`
+//! feature scores Arrow record batches, the `parquet` feature trains from Parquet files and the
+//! `datafusion` feature registers forests as the SQL function `eif_score` of DataFusion. The `napi`
+//! feature exports Node.js bindings, the `jni` feature Java bindings. The `kafka` feature scores
+//! the records of Kafka topics.
+//!
+//! serde handles trees up to 256 levels deep, deeper trees fail to serialize and are stored in
+//! the binary format instead. Parsing JSON with `serde_json` is limited to trees of about 60
+//! levels by its recursion limit.
+//!
+//! With the `tracing` feature enabled, training emits [tracing](https://docs.rs/tracing) spans and
+//! per-tree events containing timings and node counts on the `DEBUG` level. A sample of the
+//! scores is reported as events on the `TRACE` level, see `Forest::set_score_trace_interval`.
 //!
 //! ## Example
 //!
 //! ```rust
 //! use rand::distributions::Uniform;
 //! use rand::Rng;
-//! use extended_isolation_forest::{Forest, ForestOptions};
+//! use extended_isolation_forest::{ExtensionLevel, Forest, ForestOptions};
 //!
 //! fn make_f64_forest() -> Forest<f64, 3> {
 //!     let rng = &mut rand::thread_rng();
@@ -27,7 +44,8 @@
 //!         n_trees: 150,
 //!         sample_size: 200,
 //!         max_tree_depth: None,
-//!         extension_level: 1,
+//!         extension_level: ExtensionLevel::Fixed(1),
+//!         ..Default::default()
 //!     };
 //!     Forest::from_slice(values.as_slice(), &options).unwrap()
 //! }
@@ -47,24 +65,130 @@
 //! ```
 
 use std::boxed::Box;
+use std::cmp::Ordering;
 use std::result::Result;
+use std::sync::{atomic::AtomicU64, OnceLock};
 
-use num_traits::{Float, FloatConst};
+use num_traits::Float;
 use rand::{
     distributions::{uniform::SampleUniform, Uniform},
-    rngs::ThreadRng,
-    seq::{IteratorRandom, SliceRandom},
+    seq::{index, IteratorRandom},
     Rng,
 };
 use rand_distr::{Distribution, StandardNormal};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::bucket::Buckets;
+use crate::envelope::RangePolicy;
 pub use crate::error::Error;
+use crate::halton::Halton;
+use crate::leaf::{Count, LeafModel};
+use crate::monotone::Monotone;
+use crate::normalization::ScoreMode;
+use crate::ood::Bounds;
+use crate::parallel::Parallelism;
+use crate::provenance::TreeProvenance;
+use crate::sample::ColumnRow;
+pub use crate::sample::{SampleAccess, SparseSample};
+use crate::seed::TreeRng;
+use crate::split::{ExtendedSplit, SplitStrategy};
+use crate::suppression::Region;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod backfill;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "benchmarks")]
+pub mod benchmarks;
+#[cfg(feature = "json")]
+pub mod binary;
+pub mod bucket;
+pub mod budget;
+pub mod cache;
+mod canonical;
+pub mod compressed;
+mod conditional;
+pub mod consensus;
+pub mod correlation;
+pub mod counterfactual;
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
+pub mod diff;
+pub mod distill;
+pub mod dynamic;
+pub mod eif;
+pub mod envelope;
 mod error;
+pub mod eval;
+pub mod explain;
+pub mod features;
+mod gradient;
+pub mod grouped;
+mod halton;
+mod hash;
+pub mod heatmap;
+pub mod io;
+#[cfg(feature = "jni")]
+pub mod jni;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod leaf;
+mod many;
+mod masked;
+pub mod mixed;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod monitor;
+pub mod monotone;
+#[cfg(feature = "napi")]
+pub mod napi;
+mod neighbors;
+pub mod normalization;
+pub mod novelty;
+pub mod online;
+pub mod ood;
+pub mod parallel;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "nalgebra")]
+pub mod pca;
+pub mod plan;
+pub mod profile;
+pub mod projection;
+pub mod provenance;
+pub mod pruning;
+pub mod refresh;
+pub mod registry;
+pub mod report;
+mod reservoir;
+mod sample;
+pub mod sample_size;
+pub mod seasonal;
+pub mod seed;
+pub mod sensitivity;
 #[cfg(feature = "serde")]
 mod serde_array;
+#[cfg(feature = "serde")]
+mod serde_depth;
+pub mod sink;
+pub mod sklearn;
+pub mod split;
+#[cfg(feature = "json")]
+pub mod state;
+mod stratified;
+mod strict;
+pub mod suppression;
+pub mod swap;
+pub mod tagging;
+mod text;
+pub mod throughput;
+pub mod tiered;
+pub mod tuning;
+pub mod validation;
+pub mod vote;
+pub mod what_if;
 
 #[cfg(not(feature = "serde"))]
 pub trait ForestFloat<'de>: Float {}
@@ -75,7 +199,13 @@ pub trait ForestFloat<'de>: Float + Serialize + Deserialize<'de> {}
 impl<'de> ForestFloat<'de> for f32 {}
 impl<'de> ForestFloat<'de> for f64 {}
 
-#[derive(Clone, Eq, PartialEq)]
+/// Lossless for the floats supported by the forest.
+fn to_f64<T: Float>(value: T) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ForestOptions {
     /// `n_trees` is the number of trees to be created.
     pub n_trees: usize,
@@ -89,8 +219,45 @@ pub struct ForestOptions {
     pub max_tree_depth: Option<usize>,
 
     /// `extension_level` specifies degree of freedom in choosing the hyperplanes for dividing up
-    /// data. Must be smaller than the dimension n of the dataset.
-    pub extension_level: usize,
+    /// data. Must be smaller than the dimension n of the dataset. Each tree can draw its own
+    /// extension level from a range or a list of levels.
+    pub extension_level: ExtensionLevel,
+
+    /// `leaf_samples` is the max. number of training samples retained at each external node,
+    /// used by [`Forest::nearest_training_examples`]. By default no samples are retained.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub leaf_samples: usize,
+
+    /// `hyperplanes` selects how the hyperplanes splitting the data are chosen.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hyperplanes: Hyperplanes,
+
+    /// `integer_jitter` adds uniform noise in `[-0.5, 0.5)` to features which only take integer
+    /// values in the training data, so heavily quantized features do not produce degenerate
+    /// splits. The noise is drawn anew for every tree and only applied during training.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub integer_jitter: bool,
+
+    /// `collapse_duplicates` stores nodes whose training samples are all identical as a leaf
+    /// with the depth offset of the chain of splits down to the max. depth instead of building
+    /// the chain, which shrinks trees built from low-cardinality data considerably. The
+    /// duplicates keep their scores, other samples reaching the leaf are no longer isolated by
+    /// the splits of the chain and score as normal as the duplicates.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub collapse_duplicates: bool,
+
+    /// `seed` makes training reproducible: every tree draws its random numbers from a
+    /// generator derived from the seed and the index of the tree, see [`seed`]. Without a seed
+    /// the thread-local generator is used.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+
+    /// `drop_constant_features` excludes features which are (nearly) constant in the training
+    /// data from the hyperplanes, so they do not use up the non-zero components allowed by the
+    /// extension level. The detected features are stored with the forest either way, see
+    /// [`Forest::constant_features`]. Only applies to random hyperplanes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub drop_constant_features: bool,
 }
 
 impl Default for ForestOptions {
@@ -99,340 +266,2345 @@ impl Default for ForestOptions {
             n_trees: 20,
             sample_size: 20,
             max_tree_depth: None,
-            extension_level: 0,
+            extension_level: ExtensionLevel::Fixed(0),
+            leaf_samples: 0,
+            hyperplanes: Hyperplanes::Random,
+            integer_jitter: false,
+            collapse_duplicates: false,
+            seed: None,
+            drop_constant_features: false,
         }
     }
 }
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Forest<T, const N: usize> {
-    /// Multiplicative factor used in computing the anomaly scores.
-    avg_path_length_c: f64,
+/// The extension level of the trees of a forest.
+///
+/// Mixing extension levels in one forest makes it more robust when the best level for the data
+/// is not known.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum ExtensionLevel {
+    /// All trees use the same extension level.
+    Fixed(usize),
 
-    trees: Box<[Tree<T, N>]>,
+    /// Each tree draws its extension level uniformly from `min..=max`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "serialize_range",
+            deserialize_with = "deserialize_range"
+        )
+    )]
+    Range { min: usize, max: usize },
+
+    /// Each tree draws one of the listed extension levels.
+    List(Vec<usize>),
 }
 
-impl<'de, T, const N: usize> Forest<T, N>
+/// Ranges are always written as maps, formats storing structs as arrays could not tell them
+/// apart from lists of two levels otherwise.
+#[cfg(feature = "serde")]
+fn serialize_range<S>(min: &usize, max: &usize, serializer: S) -> Result<S::Ok, S::Error>
 where
-    T: ForestFloat<'de> + SampleUniform + Default,
-    StandardNormal: Distribution<T>,
+    S: serde::Serializer,
 {
-    /// Build a new forest from the given training data
-    pub fn from_slice(training_data: &[[T; N]], options: &ForestOptions) -> Result<Self, Error> {
-        if training_data.len() < options.sample_size || N == 0 {
-            return Err(Error::InsufficientTrainingData);
-        } else if options.extension_level > (N - 1) {
-            return Err(Error::ExtensionLevelExceedsDimensions);
-        }
+    use serde::ser::SerializeMap;
 
-        let max_tree_depth = if let Some(mdt) = options.max_tree_depth {
-            mdt
-        } else {
-            (options.sample_size as f64).log2().ceil() as usize
-        };
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry("min", min)?;
+    map.serialize_entry("max", max)?;
+    map.end()
+}
 
-        // build the trees
-        let rng = &mut rand::thread_rng();
-        let trees = (0..options.n_trees)
-            .map(|_| {
-                let tree_sample: Vec<_> = training_data
-                    .choose_multiple(rng, options.sample_size)
-                    .collect();
+#[cfg(feature = "serde")]
+fn deserialize_range<'de, D>(deserializer: D) -> Result<(usize, usize), D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let map = std::collections::BTreeMap::<String, usize>::deserialize(deserializer)?;
+    match (map.get("min"), map.get("max"), map.len()) {
+        (Some(min), Some(max), 2) => Ok((*min, *max)),
+        _ => Err(serde::de::Error::custom(
+            "expected the fields `min` and `max`",
+        )),
+    }
+}
 
-                Tree::new(
-                    tree_sample.as_slice(),
-                    rng,
-                    max_tree_depth,
-                    options.extension_level,
-                )
-            })
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+impl ExtensionLevel {
+    /// The highest extension level a tree can draw. `None` if no level can be drawn.
+    pub fn max_level(&self) -> Option<usize> {
+        match self {
+            Self::Fixed(level) => Some(*level),
+            Self::Range { min, max } => (min <= max).then_some(*max),
+            Self::List(levels) => levels.iter().max().copied(),
+        }
+    }
 
-        Ok(Self {
-            avg_path_length_c: c_factor(options.sample_size),
-            trees,
-        })
+    /// Draw the extension level of a tree. Must only be called when `max_level` is not `None`.
+    fn sample(&self, rng: &mut TreeRng) -> usize {
+        match self {
+            Self::Fixed(level) => *level,
+            Self::Range { min, max } => rng.gen_range(*min..=*max),
+            Self::List(levels) => levels[rng.gen_range(0..levels.len())],
+        }
     }
+}
 
-    /// Compute anomaly score for an item, with a recursion cap (default: 2x max_tree_depth)
-    pub fn score(&self, values: &[T; N]) -> f64 {
-        // Use a conservative cap: 2x the average tree depth
-        let default_cap = (self.avg_path_length_c.ceil() as usize) * 2;
-        self.score_with_recursion_cap(values, default_cap)
+impl Default for ExtensionLevel {
+    fn default() -> Self {
+        Self::Fixed(0)
     }
+}
 
-    /// Compute anomaly score for an item, with explicit recursion cap
-    pub fn score_with_recursion_cap(&self, values: &[T; N], max_depth: usize) -> f64 {
-        let path_length: f64 = self.trees.iter().map(|tree| tree.path_length_with_cap(values, max_depth)).sum();
-        let eh = path_length / self.trees.len() as f64;
-        2.0_f64.powf(-eh / self.avg_path_length_c)
+impl From<usize> for ExtensionLevel {
+    fn from(level: usize) -> Self {
+        Self::Fixed(level)
     }
 }
 
+/// How the normal vectors and intercepts of the hyperplanes are chosen.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-enum Node<T, const N: usize> {
-    Ex(ExNode),
-    In(InNode<T, N>),
+pub enum Hyperplanes {
+    /// Gaussian normal vectors and uniformly distributed intercepts drawn from the random number
+    /// generator, as described in the paper.
+    #[default]
+    Random,
+
+    /// Normal vectors and intercepts taken from the Halton low-discrepancy sequence, which
+    /// distributes the cuts more evenly. The normal vector components are uniformly distributed
+    /// in `[-1, 1]`. The hyperplanes do not depend on the random number generator, only the
+    /// subsampling of the training data for each tree does.
+    Halton,
 }
 
+// `remote = "Self"` turns the derived implementations into inherent functions, which the
+// implementations below wrap to restore the options of models serialized without them.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-struct InNode<T, const N: usize> {
-    /// Left child node.
-    left: Box<Node<T, N>>,
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        remote = "Self",
+        bound(
+            serialize = "T: Serialize, L: Serialize",
+            deserialize = "T: Deserialize<'de> + Float, L: Deserialize<'de>"
+        )
+    )
+)]
+pub struct Forest<T, const N: usize, L: LeafModel<T, N> = Count> {
+    /// Multiplicative factor used in computing the anomaly scores.
+    avg_path_length_c: f64,
 
-    /// Right child node.
-    right: Box<Node<T, N>>,
+    /// Options the forest has been built with. `max_tree_depth` is always set.
+    ///
+    /// Missing in models serialized by version 0.2.3 and earlier, the options are restored
+    /// from the trees then, see `options_of_trees`.
+    #[cfg_attr(feature = "serde", serde(default = "missing_options"))]
+    options: ForestOptions,
 
-    /// Normal vector at the root of this tree, which is used in
-    /// creating hyperplanes for splitting criteria
-    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
-    n: [T; N],
+    /// Overrides the default recursion cap used by `score`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    recursion_cap: Option<usize>,
 
-    /// Intercept point through which the hyperplane passes.
-    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
-    p: [T; N],
-}
+    /// Score with platform independent arithmetic, see `Forest::set_strict_fp`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    strict_fp: bool,
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-struct ExNode {
-    /// Size of the dataset present at the node.
-    num_samples: usize,
+    /// `c_factor` values for all leaf sizes, computed on first use in strict mode.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    strict_c_factors: OnceLock<Vec<f64>>,
+
+    /// Features which have been jittered during training, see `ForestOptions::integer_jitter`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    jittered_features: Vec<usize>,
+
+    /// The per-dimension range of the training data, unknown for converted forests.
+    #[cfg_attr(feature = "serde", serde(default))]
+    training_range: Option<Bounds<T, N>>,
+
+    /// How samples outside of `training_range` are scored.
+    #[cfg_attr(feature = "serde", serde(default))]
+    range_policy: RangePolicy,
+
+    /// The constraint of every dimension, empty unless set, see `Forest::set_monotone_constraints`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    monotone: Vec<Monotone<T>>,
+
+    /// The features which are (nearly) constant in `training_range`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    constant_features: Vec<usize>,
+
+    /// The medians of the features of the training data, empty if unknown.
+    #[cfg_attr(feature = "serde", serde(default))]
+    training_medians: Vec<T>,
+
+    /// The boundaries of the severity buckets, see `Forest::score_bucket`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    buckets: Buckets,
+
+    /// Regions scored as normal, see `Forest::set_suppressed_regions`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    suppressed: Vec<Region<T, N>>,
+
+    /// The origin of every tree, empty if unknown, see `Forest::tree_provenance`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    provenance: Vec<TreeProvenance>,
+
+    /// The threads used for batch scoring, see `Forest::set_parallelism`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    parallelism: Parallelism,
+
+    /// Samples outside of `training_range` scored with `RangePolicy::Warn`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    out_of_range_samples: AtomicU64,
+
+    /// The scores reported as events, see `Forest::set_score_trace_interval`.
+    #[cfg(feature = "tracing")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    score_tracing: ScoreTracing,
+
+    trees: Box<[Tree<T, N, L>]>,
 }
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-struct Tree<T, const N: usize> {
-    root: Node<T, N>,
+#[cfg(feature = "serde")]
+impl<T, const N: usize, L> Serialize for Forest<T, N, L>
+where
+    T: Serialize,
+    L: LeafModel<T, N> + Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Forest::serialize(self, serializer)
+    }
 }
 
-impl<'de, T, const N: usize> Tree<T, N>
+#[cfg(feature = "serde")]
+impl<'de, T, const N: usize, L> Deserialize<'de> for Forest<T, N, L>
 where
-    T: ForestFloat<'de> + SampleUniform + Default,
-    StandardNormal: Distribution<T>,
+    T: Float + Deserialize<'de>,
+    L: LeafModel<T, N> + Deserialize<'de>,
 {
-    pub fn new(
-        samples: &[&[T; N]],
-        rng: &mut ThreadRng,
-        max_tree_depth: usize,
-        extension_level: usize,
-    ) -> Self {
-        Self {
-            root: make_node(samples, rng, 0, max_tree_depth, extension_level),
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut forest = Forest::deserialize(deserializer)?;
+        if forest.options.n_trees == 0 && !forest.trees.is_empty() {
+            let sample_size = forest.trees[0].root.num_samples();
+            forest.options = options_of_trees(&forest.trees, sample_size);
         }
+        Ok(forest)
     }
+}
 
-    pub fn path_length_with_cap(&self, values: &[T; N], max_depth: usize) -> f64 {
-        path_length_recurse(&self.root, values, 0, max_depth)
+/// Marks the options as missing, a forest always has trees.
+#[cfg(feature = "serde")]
+fn missing_options() -> ForestOptions {
+    ForestOptions {
+        n_trees: 0,
+        ..Default::default()
     }
 }
 
-fn path_length_recurse<T, const N: usize>(
-    node: &Node<T, N>,
-    values: &[T; N],
-    depth: usize,
-    max_depth: usize,
-) -> f64
+/// The options of a forest of `trees` built from `sample_size` samples each, see
+/// `Forest::from_trees`.
+fn options_of_trees<T, const N: usize, L>(
+    trees: &[Tree<T, N, L>],
+    sample_size: usize,
+) -> ForestOptions
 where
     T: Float,
+    L: LeafModel<T, N>,
 {
-    if depth >= max_depth {
-        // Cap reached: treat as external node with 1 sample (shortest possible path)
-        return 0.0;
+    let max_tree_depth = trees.iter().map(|tree| tree.root.depth()).max();
+    let extension_level = trees
+        .iter()
+        .flat_map(|tree| tree.root.iter())
+        .filter_map(|(node, _)| match node {
+            Node::In(in_node) => Some(in_node.n.iter().filter(|n| !n.is_zero()).count()),
+            Node::Ex(_) => None,
+        })
+        .max()
+        .unwrap_or(0)
+        .saturating_sub(1);
+    ForestOptions {
+        n_trees: trees.len(),
+        sample_size,
+        max_tree_depth: Some(max_tree_depth.unwrap_or(0)),
+        extension_level: ExtensionLevel::Fixed(extension_level),
+        ..Default::default()
     }
-    match node {
-        Node::Ex(ex_node) => {
-            if ex_node.num_samples <= 1 {
-                0.0
-            } else {
-                c_factor(ex_node.num_samples)
-            }
+}
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the given training data
+    pub fn from_slice<S>(training_data: &[S], options: &ForestOptions) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        Self::train(
+            training_data.len(),
+            |i| &training_data[i],
+            |rng| index::sample(rng, training_data.len(), options.sample_size).into_vec(),
+            options,
+        )
+    }
+
+    /// Build a new forest from training data stored column-wise, one slice per feature.
+    ///
+    /// The rows are read directly from the columns, no transposed copy of the data is created.
+    /// All columns must have the same length.
+    pub fn from_columns(columns: &[&[T]; N], options: &ForestOptions) -> Result<Self, Error> {
+        let num_rows = columns.first().map(|column| column.len()).unwrap_or(0);
+        if columns.iter().any(|column| column.len() != num_rows) {
+            return Err(Error::ColumnLengthMismatch);
         }
-        Node::In(in_node) => {
-            1.0 + path_length_recurse(
-                match determinate_direction(values, &in_node.n, &in_node.p) {
-                    Direction::Left => in_node.left.as_ref(),
-                    Direction::Right => in_node.right.as_ref(),
-                },
-                values,
-                depth + 1,
-                max_depth,
-            )
+        Self::train(
+            num_rows,
+            |row| ColumnRow { columns, row },
+            |rng| index::sample(rng, num_rows, options.sample_size).into_vec(),
+            options,
+        )
+    }
+
+    /// Build a new forest from training data split into shards, like per-day partitions, which
+    /// are not concatenated. The trees are assigned to the shards round-robin, tree `i` is built
+    /// from a subsample of shard `i % shards.len()`, so every shard is represented as long as
+    /// there are at least as many trees as shards. The training range and medians are computed
+    /// over all shards.
+    ///
+    /// Every shard must contain at least `sample_size` samples.
+    pub fn from_shards<S>(shards: &[&[S]], options: &ForestOptions) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if shards.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        if shards.iter().any(|shard| shard.len() < options.sample_size) {
+            return Err(Error::InsufficientTrainingData);
         }
+        // offsets[k] is the index of the first sample of shard k in the concatenated shards
+        let offsets: Vec<usize> = shards
+            .iter()
+            .scan(0, |offset, shard| {
+                let start = *offset;
+                *offset += shard.len();
+                Some(start)
+            })
+            .collect();
+        let num_rows = shards.iter().map(|shard| shard.len()).sum();
+        // the trees are built in order, so the rows of tree `i` are selected in the `i`-th call
+        let mut next_tree = 0;
+        Self::train(
+            num_rows,
+            |row| {
+                let shard = offsets.partition_point(|offset| *offset <= row) - 1;
+                &shards[shard][row - offsets[shard]]
+            },
+            |rng| {
+                let shard = next_tree % shards.len();
+                next_tree += 1;
+                index::sample(rng, shards[shard].len(), options.sample_size)
+                    .into_iter()
+                    .map(|row| offsets[shard] + row)
+                    .collect()
+            },
+            options,
+        )
+    }
+
+    /// Build a new forest from `num_rows` samples provided by `get_sample`. `select_rows`
+    /// chooses the `sample_size` rows used for building a tree.
+    fn train<S, F, R>(
+        num_rows: usize,
+        get_sample: F,
+        select_rows: R,
+        options: &ForestOptions,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+        F: Fn(usize) -> S,
+        R: FnMut(&mut TreeRng) -> Vec<usize>,
+    {
+        Self::train_with_strategy(
+            num_rows,
+            get_sample,
+            select_rows,
+            options,
+            ExtendedSplit::new(options.hyperplanes),
+        )
     }
 }
 
-fn make_node<'de, T, const N: usize>(
-    samples: &[&[T; N]],
-    rng: &mut ThreadRng,
-    current_tree_depth: usize,
-    max_tree_depth: usize,
-    extension_level: usize,
-) -> Node<T, N>
+impl<'de, T, const N: usize, L> Forest<T, N, L>
 where
     T: ForestFloat<'de> + SampleUniform + Default,
     StandardNormal: Distribution<T>,
+    L: LeafModel<T, N>,
 {
-    let num_samples = samples.len();
-    if current_tree_depth >= max_tree_depth || num_samples <= 1 {
-        Node::Ex(ExNode { num_samples })
-    } else {
-        // randomly select an intercept point p ~ ∈ IR |samples| in
-        // the range of the samples
-        let p = {
-            let mut maxs = *samples[0];
-            let mut mins = *samples[0];
-            samples.iter().skip(1).for_each(|s| {
-                s.iter().enumerate().for_each(|(i, v)| {
-                    maxs[i] = if *v > maxs[i] { *v } else { maxs[i] };
-                    mins[i] = if *v < mins[i] { *v } else { mins[i] };
+    /// Like [`Forest::train`], with the hyperplanes chosen by `split` and the leaf model `L`.
+    fn train_with_strategy<S, F, R, P>(
+        num_rows: usize,
+        get_sample: F,
+        mut select_rows: R,
+        options: &ForestOptions,
+        split: P,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+        F: Fn(usize) -> S,
+        R: FnMut(&mut TreeRng) -> Vec<usize>,
+        P: SplitStrategy<T, N>,
+    {
+        Self::train_with_builder(num_rows, &get_sample, options, split, |mut builder| {
+            (0..options.n_trees)
+                .map(|tree_index| {
+                    let rng = &mut TreeRng::new(options.seed, tree_index);
+                    let mut tree_sample: Vec<_> =
+                        select_rows(rng).into_iter().map(&get_sample).collect();
+                    let extension_level = options.extension_level.sample(rng);
+                    builder.build(tree_index, tree_sample.as_mut_slice(), extension_level, rng)
                 })
-            });
+                .collect()
+        })
+    }
 
-            // randomly pick an intercept point using a uniform distribution
-            let mut p = [T::zero(); N];
-            mins.iter()
-                .zip(maxs.iter())
-                .zip(p.iter_mut())
-                .for_each(|((min_val, max_val), p_i)| {
-                    *p_i = if min_val == max_val {
-                        // sampling with lower and upper bound being equal panics
-                        *min_val
-                    } else {
-                        rng.sample(Uniform::new(*min_val, *max_val))
-                    }
-                });
-            p
+    /// Prepare the training on `num_rows` samples and build the trees with `build_trees`.
+    fn train_with_builder<S, F, P, B>(
+        num_rows: usize,
+        get_sample: F,
+        options: &ForestOptions,
+        mut split: P,
+        build_trees: B,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+        F: Fn(usize) -> S,
+        P: SplitStrategy<T, N>,
+        B: FnOnce(TreeBuilder<'_, T, P>) -> Vec<Tree<T, N, L>>,
+    {
+        let max_tree_depth = Self::validate_options(num_rows, options)?;
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "train_forest",
+            n_trees = options.n_trees,
+            sample_size = options.sample_size,
+            dimensions = N,
+        )
+        .entered();
+
+        let jittered_features = if options.integer_jitter {
+            integer_features(num_rows, &get_sample)
+        } else {
+            Vec::new()
         };
+        let training_range = Bounds::of((0..num_rows).map(&get_sample));
+        let training_medians = training_medians(num_rows, &get_sample);
+        if options.drop_constant_features {
+            if let Some(range) = &training_range {
+                split.exclude_features(&constant_features(range));
+            }
+        }
 
-        // Efficiently generate a sparse random normal vector. Only
-        // `active_dims = extension_level + 1` coordinates receive a non-zero
-        // component; the rest are guaranteed to be 0.  For high-dimensional
-        // data this avoids sampling N Gaussian numbers at every node.
+        let builder = TreeBuilder::new(options, max_tree_depth, &jittered_features, split);
+        let trees = build_trees(builder).into_boxed_slice();
 
-        let mut n = [T::zero(); N];
-        let active_dims = extension_level + 1; // must be ≤ N
+        Ok(Self::with_trees(
+            options,
+            max_tree_depth,
+            jittered_features,
+            training_range,
+            training_medians,
+            trees,
+        ))
+    }
 
-        // Choose the active dimensions uniformly without replacement.
-        for idx in (0..N).choose_multiple(rng, active_dims) {
-            n[idx] = rng.sample(StandardNormal);
+    /// Check the options for training with `num_rows` samples, returning the max. tree depth.
+    fn validate_options(num_rows: usize, options: &ForestOptions) -> Result<usize, Error> {
+        if num_rows < options.sample_size || options.sample_size < 2 || N == 0 {
+            return Err(Error::InsufficientTrainingData);
         }
+        match options.extension_level.max_level() {
+            None => return Err(Error::NoExtensionLevel),
+            Some(level) if level > (N - 1) => return Err(Error::ExtensionLevelExceedsDimensions),
+            Some(_) => (),
+        }
+
+        Ok(if let Some(mdt) = options.max_tree_depth {
+            mdt
+        } else {
+            (options.sample_size as f64).log2().ceil() as usize
+        })
+    }
+}
 
-        let mut samples_left = vec![];
-        let mut samples_right = vec![];
+/// Builds the trees of a forest one after the other, sharing the state of the hyperplane
+/// sequence between them.
+struct TreeBuilder<'a, T: SampleUniform, P> {
+    max_tree_depth: usize,
+    leaf_samples: usize,
+    collapse_duplicates: bool,
+    jittered_features: &'a [usize],
+    jitter: Uniform<T>,
+    split: P,
+}
 
-        for sample in samples {
-            match determinate_direction(sample, &n, &p) {
-                Direction::Left => samples_left.push(*sample),
-                Direction::Right => samples_right.push(*sample),
-            }
+impl<'a, 'de, T, P> TreeBuilder<'a, T, P>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    fn new(
+        options: &ForestOptions,
+        max_tree_depth: usize,
+        jittered_features: &'a [usize],
+        split: P,
+    ) -> Self {
+        let half = T::one() / (T::one() + T::one());
+        Self {
+            max_tree_depth,
+            leaf_samples: options.leaf_samples,
+            collapse_duplicates: options.collapse_duplicates,
+            jittered_features,
+            jitter: Uniform::new(-half, half),
+            split,
         }
+    }
+
+    fn build<S, L, const N: usize>(
+        &mut self,
+        tree_index: usize,
+        tree_sample: &mut [S],
+        extension_level: usize,
+        rng: &mut TreeRng,
+    ) -> Tree<T, N, L>
+    where
+        S: SampleAccess<T, N>,
+        P: SplitStrategy<T, N>,
+        L: LeafModel<T, N>,
+    {
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
 
-        Node::In(InNode {
-            left: Box::new(make_node(
-                samples_left.as_slice(),
+        let tree = if self.jittered_features.is_empty() {
+            Tree::new(
+                tree_sample,
                 rng,
-                current_tree_depth + 1,
-                max_tree_depth,
+                &mut self.split,
+                self.max_tree_depth,
                 extension_level,
-            )),
-            right: Box::new(make_node(
-                samples_right.as_slice(),
+                self.leaf_samples,
+                self.collapse_duplicates,
+            )
+        } else {
+            let mut jittered_sample: Vec<_> = tree_sample
+                .iter()
+                .map(|sample| {
+                    let mut values = sample.to_array();
+                    for &i in self.jittered_features {
+                        values[i] = values[i] + rng.sample(&self.jitter);
+                    }
+                    values
+                })
+                .collect();
+            Tree::new(
+                jittered_sample.as_mut_slice(),
                 rng,
-                current_tree_depth + 1,
-                max_tree_depth,
+                &mut self.split,
+                self.max_tree_depth,
                 extension_level,
-            )),
-            n,
-            p,
-        })
+                self.leaf_samples,
+                self.collapse_duplicates,
+            )
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            tree = tree_index,
+            nodes = tree.root.num_nodes(),
+            depth = tree.root.depth(),
+            elapsed_us = started.elapsed().as_micros() as u64,
+            "built tree"
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = tree_index;
+
+        tree
     }
 }
 
-/// Average path length of unsuccessful search in a binary search tree given n points
-/// n: Number of data points for the BST.
-///
-/// Returns the average path length of unsuccessful search in a BST
-fn c_factor(n: usize) -> f64 {
-    2.0 * ((n as f64 - 1.0).log(f64::E()) + 0.5772156649) - (2.0 * (n as f64 - 1.0) / n as f64)
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Build a forest from trees trained elsewhere, like on distributed workers or with a
+    /// custom sampler, so they can be scored and serialized like a trained forest. Every tree
+    /// must have been built from `sample_size` samples, which normalizes the path lengths.
+    ///
+    /// The max. tree depth and the extension level of the options are those of the deepest
+    /// tree and of the hyperplane with the most non-zero components. The training range is the
+    /// union of the bounds of the trees, the training medians and provenance are unknown.
+    ///
+    /// Fails with [`Error::EmptyData`] without trees and with
+    /// [`Error::TreeSampleSizeMismatch`] for the first tree built from a different number of
+    /// samples.
+    ///
+    /// ```rust
+    /// use extended_isolation_forest::{Forest, Tree};
+    ///
+    /// let values: Vec<_> = (0..256).map(|i| [(i % 16) as f64, (i / 16) as f64]).collect();
+    /// let trees: Vec<Tree<f64, 2>> = values
+    ///     .chunks(64)
+    ///     .enumerate()
+    ///     .map(|(seed, chunk)| Tree::new_seeded(chunk, 1, 6, seed as u64).unwrap())
+    ///     .collect();
+    ///
+    /// let forest = Forest::from_trees(trees, 64).unwrap();
+    /// assert_eq!(forest.options().n_trees, 4);
+    /// assert!(forest.score(&[50.0, 50.0]) > forest.score(&[7.5, 7.5]));
+    /// ```
+    pub fn from_trees(trees: Vec<Tree<T, N, L>>, sample_size: usize) -> Result<Self, Error> {
+        if trees.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        if let Some((index, tree)) = trees
+            .iter()
+            .enumerate()
+            .find(|(_, tree)| tree.root.num_samples() != sample_size)
+        {
+            return Err(Error::TreeSampleSizeMismatch {
+                index,
+                actual: tree.root.num_samples(),
+            });
+        }
+
+        let bounds: Option<Vec<[T; N]>> = trees
+            .iter()
+            .map(|tree| {
+                tree.bounds
+                    .as_ref()
+                    .map(|bounds| [*bounds.min(), *bounds.max()])
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|corners| corners.concat());
+        let options = options_of_trees(&trees, sample_size);
+
+        let mut forest = Self::with_trees(
+            &options,
+            options.max_tree_depth.unwrap_or(0),
+            Vec::new(),
+            bounds.and_then(Bounds::of),
+            Vec::new(),
+            trees.into_boxed_slice(),
+        );
+        forest.provenance = Vec::new();
+        Ok(forest)
+    }
+
+    /// A forest of freshly built trees.
+    fn with_trees(
+        options: &ForestOptions,
+        max_tree_depth: usize,
+        jittered_features: Vec<usize>,
+        training_range: Option<Bounds<T, N>>,
+        training_medians: Vec<T>,
+        trees: Box<[Tree<T, N, L>]>,
+    ) -> Self {
+        let provenance = vec![TreeProvenance::now(0, options.seed); trees.len()];
+        let mut forest = Self::from_parts(
+            c_factor(options.sample_size),
+            ForestOptions {
+                max_tree_depth: Some(max_tree_depth),
+                ..options.clone()
+            },
+            trees,
+        );
+        forest.jittered_features = jittered_features;
+        forest.constant_features = training_range
+            .as_ref()
+            .map(constant_features)
+            .unwrap_or_default();
+        forest.training_range = training_range;
+        forest.training_medians = training_medians;
+        forest.provenance = provenance;
+        forest
+    }
+
+    /// A forest of the trees with the default settings and no training statistics, for
+    /// building, converting and reading forests.
+    pub(crate) fn from_parts(
+        avg_path_length_c: f64,
+        options: ForestOptions,
+        trees: Box<[Tree<T, N, L>]>,
+    ) -> Self {
+        Self {
+            avg_path_length_c,
+            options,
+            recursion_cap: None,
+            strict_fp: false,
+            strict_c_factors: OnceLock::new(),
+            jittered_features: Vec::new(),
+            constant_features: Vec::new(),
+            training_range: None,
+            training_medians: Vec::new(),
+            buckets: Buckets::default(),
+            suppressed: Vec::new(),
+            provenance: Vec::new(),
+            parallelism: Parallelism::default(),
+            range_policy: RangePolicy::Ignore,
+            monotone: Vec::new(),
+            out_of_range_samples: AtomicU64::new(0),
+            #[cfg(feature = "tracing")]
+            score_tracing: ScoreTracing::default(),
+            trees,
+        }
+    }
 }
 
-enum Direction {
-    Left,
-    Right,
+/// Reports every `interval`-th score as a `TRACE` event, none for an interval of 0.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default)]
+pub(crate) struct ScoreTracing {
+    interval: u64,
+    scores: AtomicU64,
+}
+
+#[cfg(feature = "tracing")]
+impl ScoreTracing {
+    fn trace(&self, path_length: f64, score: f64) {
+        if self.interval > 0
+            && self
+                .scores
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+                .is_multiple_of(self.interval)
+        {
+            tracing::trace!(path_length, score, "scored sample");
+        }
+    }
+}
+
+/// Features whose range is at most this fraction of their largest absolute value are constant.
+const CONSTANT_FEATURE_TOLERANCE: f64 = 1e-9;
+
+/// The features which are constant in the range of the training data, up to rounding errors.
+fn constant_features<T: Float, const N: usize>(range: &Bounds<T, N>) -> Vec<usize> {
+    (0..N)
+        .filter(|i| {
+            let (min, max) = (to_f64(range.min()[*i]), to_f64(range.max()[*i]));
+            max - min <= CONSTANT_FEATURE_TOLERANCE * min.abs().max(max.abs())
+        })
+        .collect()
 }
 
-fn determinate_direction<T, const N: usize>(sample: &[T; N], n: &[T; N], p: &[T; N]) -> Direction
+/// The medians of the features of all rows, ignoring missing values. The median of a feature
+/// without any value is `NaN`.
+fn training_medians<T, S, const N: usize>(
+    num_rows: usize,
+    get_sample: impl Fn(usize) -> S,
+) -> Vec<T>
 where
     T: Float,
+    S: SampleAccess<T, N>,
 {
-    let direction_value = sample
-        .iter()
-        .zip(p.iter())
-        .map(|(sample_val, p_val)| *sample_val - *p_val)
-        .zip(n.iter())
-        .fold(T::zero(), |sum, (sp_val, n_val)| sum + sp_val * (*n_val));
+    let mut values = Vec::with_capacity(num_rows);
+    (0..N)
+        .map(|i| {
+            values.clear();
+            values.extend(
+                (0..num_rows)
+                    .map(|row| get_sample(row).feature(i))
+                    .filter(|value| !value.is_nan()),
+            );
+            if values.is_empty() {
+                return T::nan();
+            }
+            values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let n = values.len();
+            (values[(n - 1) / 2] + values[n / 2]) / (T::one() + T::one())
+        })
+        .collect()
+}
 
-    if direction_value <= T::zero() {
-        Direction::Left
-    } else {
-        Direction::Right
+/// The features with only finite integer values in all rows.
+fn integer_features<T, S, const N: usize>(
+    num_rows: usize,
+    get_sample: impl Fn(usize) -> S,
+) -> Vec<usize>
+where
+    T: Float,
+    S: SampleAccess<T, N>,
+{
+    let mut integer = [true; N];
+    for row in 0..num_rows {
+        let sample = get_sample(row);
+        for (i, is_integer) in integer.iter_mut().enumerate() {
+            let value = sample.feature(i);
+            *is_integer &= value.is_finite() && value.fract() == T::zero();
+        }
     }
+    (0..N).filter(|i| integer[*i]).collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use rand::distributions::Uniform;
-    use rand::Rng;
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The options the forest has been built with.
+    ///
+    /// `max_tree_depth` is always set to the depth limit which has been used during training.
+    pub fn options(&self) -> &ForestOptions {
+        &self.options
+    }
 
-    use crate::{Forest, ForestOptions};
+    /// The features which have been jittered during training because they only took integer
+    /// values, see [`ForestOptions::integer_jitter`].
+    pub fn jittered_features(&self) -> &[usize] {
+        &self.jittered_features
+    }
 
-    fn make_f64_forest() -> Forest<f64, 3> {
-        let rng = &mut rand::thread_rng();
-        let distribution = Uniform::new(-4., 4.);
-        let distribution2 = Uniform::new(10., 50.);
+    /// The features which have been (nearly) constant in the training data, excluded from the
+    /// hyperplanes with [`ForestOptions::drop_constant_features`]. Empty for converted forests.
+    pub fn constant_features(&self) -> &[usize] {
+        &self.constant_features
+    }
 
-        let values: Vec<_> = (0..6000)
-            .map(|_| {
-                [
-                    rng.sample(distribution),
-                    rng.sample(distribution),
-                    rng.sample(distribution2),
-                ]
-            })
-            .collect();
+    /// The medians of the features of the training data. Empty for converted forests.
+    pub fn training_medians(&self) -> &[T] {
+        &self.training_medians
+    }
 
-        let options = ForestOptions {
-            n_trees: 150,
-            sample_size: 200,
-            max_tree_depth: None,
-            extension_level: 1,
-        };
-        Forest::from_slice(values.as_slice(), &options).unwrap()
+    /// The recursion cap used by [`Forest::score`].
+    ///
+    /// Defaults to the max. tree depth used during training, so paths are never truncated.
+    pub fn recursion_cap(&self) -> usize {
+        self.recursion_cap
+            .or(self.options.max_tree_depth)
+            .unwrap_or(usize::MAX)
     }
 
-    fn assert_anomalies_forest_3d_f64(forest: &Forest<f64, 3>) {
-        // no anomaly
-        assert!(forest.score(&[1.0, 3.0, 25.0]) < 0.52);
-        assert!(forest.score(&[-1.0, 3.0, 25.0]) < 0.52);
+    /// Override the recursion cap used by [`Forest::score`]. `None` restores the default.
+    pub fn set_recursion_cap(&mut self, recursion_cap: Option<usize>) {
+        self.recursion_cap = recursion_cap;
+    }
 
-        // anomalies
-        assert!(forest.score(&[-12.0, 6.0, 25.0]) > 0.5);
-        assert!(forest.score(&[-1.0, 2.0, 60.0]) > 0.5);
-        assert!(forest.score(&[-1.0, 2.0, 0.0]) > 0.5);
+    /// Whether the forest scores in strict mode, see [`Forest::set_strict_fp`].
+    pub fn strict_fp(&self) -> bool {
+        self.strict_fp
     }
 
-    #[test]
-    fn score_forest_3d_f64() {
-        let forest = make_f64_forest();
-        assert_anomalies_forest_3d_f64(&forest);
+    /// Enable scoring with bit-identical results on all platforms, for example to compare
+    /// scores computed on edge devices and servers.
+    ///
+    /// The logarithms and powers provided by the math library of the platform are replaced by
+    /// computations using only basic floating point operations, which are slower and may differ
+    /// from the default scores in the last bits. The mode is stored with the forest and applies
+    /// to [`Forest::score`] and [`Forest::score_with_recursion_cap`].
+    pub fn set_strict_fp(&mut self, strict_fp: bool) {
+        self.strict_fp = strict_fp;
     }
 
-    #[cfg(feature = "serde")]
-    #[test]
+    /// Report the path length and score of every `interval`-th scored sample as an event on
+    /// the `TRACE` level, starting with the next one. An interval of 0, the default, reports
+    /// none, as an event per score slows down scoring even when it is filtered out.
+    ///
+    /// The interval is not serialized.
+    #[cfg(feature = "tracing")]
+    pub fn set_score_trace_interval(&mut self, interval: u64) {
+        self.score_tracing = ScoreTracing {
+            interval,
+            scores: AtomicU64::new(0),
+        };
+    }
+
+    /// Compute anomaly score for an item, with the recursion cap of the forest.
+    ///
+    /// Traversing the trees is not recursive, the cap is only a scoring policy truncating
+    /// long paths.
+    pub fn score<S>(&self, values: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.score_with_recursion_cap(values, self.recursion_cap())
+    }
+
+    /// Compute anomaly score for an item following every path to its external node, ignoring
+    /// the recursion cap set by [`Forest::set_recursion_cap`] and the max. tree depth.
+    ///
+    /// Trees loaded from other implementations may be deeper than their stored depth limit, the
+    /// scores of their deep samples then match the reference implementation only without cap.
+    pub fn score_exact<S>(&self, values: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.score_with_recursion_cap(values, usize::MAX)
+    }
+
+    /// Compute anomaly score for an item, with explicit recursion cap. Paths are truncated
+    /// at internal nodes at a depth of `max_depth`.
+    pub fn score_with_recursion_cap<S>(&self, values: &S, max_depth: usize) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.score_with_mode(values, max_depth, ScoreMode::Normalized)
+    }
+
+    pub(crate) fn score_with_mode<S>(&self, values: &S, max_depth: usize, mode: ScoreMode) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.score_trees(self.trees.iter(), values, max_depth, mode)
+    }
+
+    /// Score the sample on the trees with the settings of this forest, like suppressed regions
+    /// and strict mode. The trees are the trees of the forest or those decoded by a
+    /// `MappedForest`, which holds its settings in a forest without trees.
+    pub(crate) fn score_trees<'a, S, I>(
+        &self,
+        trees: I,
+        values: &S,
+        max_depth: usize,
+        mode: ScoreMode,
+    ) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+        I: IntoIterator<Item = &'a Tree<T, N, L>>,
+        T: 'a,
+        L: 'a,
+    {
+        if self.is_suppressed(values) {
+            return mode.convert(f64::INFINITY, 0.0);
+        }
+        if let Some(adjusted) = self.apply_monotone_constraints(values) {
+            return self.score_with_range_policy(trees, &adjusted, max_depth, mode);
+        }
+        self.score_with_range_policy(trees, values, max_depth, mode)
+    }
+
+    /// The sample the trees see, after applying the monotone constraints and the range policy.
+    pub(crate) fn adjusted_sample<S>(&self, values: &S) -> [T; N]
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut adjusted = self
+            .apply_monotone_constraints(values)
+            .unwrap_or_else(|| values.to_array());
+        if self.range_policy != RangePolicy::Ignore {
+            if let Some(clamped) = self.apply_range_policy(&adjusted) {
+                adjusted = clamped;
+            }
+        }
+        adjusted
+    }
+
+    /// Score every row of data stored column-wise, one slice per feature, in the order of the
+    /// rows.
+    ///
+    /// The trees read the features of a row from the columns as they need them, no rows are
+    /// assembled. The values of columnar formats like Arrow can be passed without copying them,
+    /// missing values must be replaced by `NaN` before. All columns must have the same length.
+    pub fn score_columns(&self, columns: &[&[T]; N]) -> Result<Vec<f64>, Error> {
+        let num_rows = columns.first().map(|column| column.len()).unwrap_or(0);
+        if columns.iter().any(|column| column.len() != num_rows) {
+            return Err(Error::ColumnLengthMismatch);
+        }
+        Ok((0..num_rows)
+            .map(|row| self.score(&ColumnRow { columns, row }))
+            .collect())
+    }
+
+    /// [`Forest::score_columns`] for a number of columns only known at runtime, like the
+    /// arguments of a SQL function. Fails with [`Error::DimensionMismatch`] unless there is one
+    /// column per feature.
+    pub fn score_column_slices(&self, columns: &[&[T]]) -> Result<Vec<f64>, Error> {
+        let columns: &[&[T]; N] = columns.try_into().map_err(|_| Error::DimensionMismatch {
+            expected: N,
+            actual: columns.len(),
+        })?;
+        self.score_columns(columns)
+    }
+
+    fn score_with_range_policy<'a, S, I>(
+        &self,
+        trees: I,
+        values: &S,
+        max_depth: usize,
+        mode: ScoreMode,
+    ) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+        I: IntoIterator<Item = &'a Tree<T, N, L>>,
+        T: 'a,
+        L: 'a,
+    {
+        if self.range_policy != RangePolicy::Ignore {
+            if let Some(clamped) = self.apply_range_policy(values) {
+                return self.score_in_range(trees, &clamped, max_depth, mode);
+            }
+        }
+        self.score_in_range(trees, values, max_depth, mode)
+    }
+
+    fn score_in_range<'a, S, I>(
+        &self,
+        trees: I,
+        values: &S,
+        max_depth: usize,
+        mode: ScoreMode,
+    ) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+        I: IntoIterator<Item = &'a Tree<T, N, L>>,
+        T: 'a,
+        L: 'a,
+    {
+        if self.strict_fp {
+            let (eh, score) = self.strict_score(trees, values, max_depth);
+            return mode.convert(eh, score);
+        }
+        let (path_length, num_trees) =
+            trees.into_iter().fold((0.0, 0), |(sum, num_trees), tree| {
+                (
+                    sum + tree.path_length_with_cap(values, max_depth),
+                    num_trees + 1,
+                )
+            });
+        let eh = path_length / num_trees as f64;
+        let score = normalize_path_length(eh, self.avg_path_length_c);
+
+        #[cfg(feature = "tracing")]
+        self.score_tracing.trace(eh, score);
+
+        mode.convert(eh, score)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize, L: Serialize",
+        deserialize = "T: Deserialize<'de> + Float, L: Deserialize<'de> + Default"
+    ))
+)]
+enum Node<T, const N: usize, L = Count> {
+    Ex(ExNode<T, N, L>),
+    In(InNode<T, N, L>),
+}
+
+impl<T, const N: usize, L> Node<T, N, L> {
+    /// Number of internal nodes which have been merged into this node, see
+    /// `Forest::canonicalize`.
+    fn depth_offset(&self) -> usize {
+        match self {
+            Self::Ex(ex_node) => ex_node.depth_offset,
+            Self::In(in_node) => in_node.depth_offset,
+        }
+    }
+
+    /// The depth of the node entered at `depth`, below the internal nodes merged into its depth
+    /// offset. `None` if a path truncated at `max_depth` ends within the merged nodes, the path
+    /// length is `max_depth` then.
+    fn skip_depth_offset(&self, depth: usize, max_depth: usize) -> Option<usize> {
+        let depth_offset = self.depth_offset();
+        if depth_offset > 0 && depth + depth_offset > max_depth {
+            None
+        } else {
+            Some(depth + depth_offset)
+        }
+    }
+
+    /// Iterate over all nodes of the subtree starting at this node together with their depth
+    /// relative to the node this node has been entered at, including the depth offsets.
+    fn iter(&self) -> impl Iterator<Item = (&Self, usize)> {
+        let mut stack = vec![(self, self.depth_offset())];
+        std::iter::from_fn(move || {
+            let (node, depth) = stack.pop()?;
+            if let Self::In(in_node) = node {
+                for child in [in_node.right.as_ref(), in_node.left.as_ref()] {
+                    stack.push((child, depth + 1 + child.depth_offset()));
+                }
+            }
+            Some((node, depth))
+        })
+    }
+
+    /// Dismantle the subtree iteratively, the default recursive drop could overflow the stack
+    /// for deep trees.
+    fn dismantle(self) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            if let Node::In(in_node) = node {
+                stack.push(*in_node.left);
+                stack.push(*in_node.right);
+            }
+        }
+    }
+
+    /// Depth of the deepest external node below this node.
+    fn depth(&self) -> usize {
+        self.iter().map(|(_, depth)| depth).max().unwrap_or(0)
+    }
+
+    /// Number of nodes in the subtree starting at this node.
+    #[cfg(feature = "tracing")]
+    fn num_nodes(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Number of training samples which passed this node.
+    fn num_samples(&self) -> usize {
+        self.iter()
+            .map(|(node, _)| match node {
+                Self::Ex(ex_node) => ex_node.num_samples,
+                Self::In(_) => 0,
+            })
+            .sum()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize, L: Serialize",
+        deserialize = "T: Deserialize<'de> + Float, L: Deserialize<'de> + Default"
+    ))
+)]
+#[cfg_attr(feature = "serde", serde(from = "InNodeFields<T, N, L>"))]
+struct InNode<T, const N: usize, L = Count> {
+    /// Left child node.
+    #[cfg_attr(feature = "serde", serde(with = "serde_depth"))]
+    left: Box<Node<T, N, L>>,
+
+    /// Right child node.
+    #[cfg_attr(feature = "serde", serde(with = "serde_depth"))]
+    right: Box<Node<T, N, L>>,
+
+    /// Normal vector at the root of this tree, which is used in
+    /// creating hyperplanes for splitting criteria
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    n: [T; N],
+
+    /// Intercept point through which the hyperplane passes.
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    p: [T; N],
+
+    /// The dot product of `n` and `p`, see `SampleAccess::direction_with_offset`. Computed
+    /// when the node is built or deserialized.
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    offset: T,
+
+    /// Number of internal nodes merged into this node, see `Forest::canonicalize`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    depth_offset: usize,
+}
+
+impl<T, const N: usize, L> InNode<T, N, L>
+where
+    T: Float,
+{
+    fn new(
+        left: Node<T, N, L>,
+        right: Node<T, N, L>,
+        n: [T; N],
+        p: [T; N],
+        depth_offset: usize,
+    ) -> Self {
+        let offset = hyperplane_offset(&n, &p);
+        Self {
+            left: Box::new(left),
+            right: Box::new(right),
+            n,
+            p,
+            offset,
+            depth_offset,
+        }
+    }
+
+    /// The side of the hyperplane of the node the sample lies on.
+    #[inline]
+    fn direction<S>(&self, sample: &S) -> Direction
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        if sample.direction_with_offset(&self.n, &self.p, self.offset) <= T::zero() {
+            Direction::Left
+        } else {
+            Direction::Right
+        }
+    }
+}
+
+/// The serialized fields of an `InNode`, which computes its offset when deserialized.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Float, L: Deserialize<'de> + Default"))]
+struct InNodeFields<T, const N: usize, L> {
+    #[serde(with = "serde_depth")]
+    left: Box<Node<T, N, L>>,
+
+    #[serde(with = "serde_depth")]
+    right: Box<Node<T, N, L>>,
+
+    #[serde(with = "serde_array")]
+    n: [T; N],
+
+    #[serde(with = "serde_array")]
+    p: [T; N],
+
+    #[serde(default)]
+    depth_offset: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<T, const N: usize, L> From<InNodeFields<T, N, L>> for InNode<T, N, L>
+where
+    T: Float,
+{
+    fn from(fields: InNodeFields<T, N, L>) -> Self {
+        Self::new(
+            *fields.left,
+            *fields.right,
+            fields.n,
+            fields.p,
+            fields.depth_offset,
+        )
+    }
+}
+
+/// The dot product of the normal vector and the intercept point of a hyperplane, summing only
+/// the non-zero components of the normal vector.
+fn hyperplane_offset<T, const N: usize>(n: &[T; N], p: &[T; N]) -> T
+where
+    T: Float,
+{
+    n.iter()
+        .zip(p.iter())
+        .filter(|(n_i, _)| !n_i.is_zero())
+        .fold(T::zero(), |sum, (n_i, p_i)| sum + *n_i * *p_i)
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize, L: Serialize",
+        deserialize = "T: Deserialize<'de>, L: Deserialize<'de> + Default"
+    ))
+)]
+struct ExNode<T, const N: usize, L = Count> {
+    /// Size of the dataset present at the node.
+    num_samples: usize,
+
+    /// Training samples retained at the node, at most `ForestOptions::leaf_samples`.
+    #[cfg_attr(feature = "serde", serde(default, with = "serde_array::vec"))]
+    samples: Vec<[T; N]>,
+
+    /// Summary of the samples, see `leaf::LeafModel`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    model: L,
+
+    /// Number of internal nodes merged into this node, see `Forest::canonicalize`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    depth_offset: usize,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize, L: Serialize",
+        deserialize = "T: Deserialize<'de> + Float, L: Deserialize<'de>"
+    ))
+)]
+/// A single isolation tree, the building block of a [`Forest`].
+///
+/// Trees can be built and used on their own for custom ensembles, like trees trained on
+/// different feature sets or weighted combinations of trees:
+///
+/// ```rust
+/// use extended_isolation_forest::{score_from_path_length, Tree};
+///
+/// let values: Vec<_> = (0..256).map(|i| [(i % 16) as f64, (i / 16) as f64]).collect();
+/// let trees: Vec<Tree<f64, 2>> = (0..10)
+///     .map(|seed| Tree::new_seeded(&values, 1, 8, seed).unwrap())
+///     .collect();
+///
+/// let mean_path_length = |sample: &[f64; 2]| {
+///     trees.iter().map(|tree| tree.path_length(sample)).sum::<f64>() / trees.len() as f64
+/// };
+/// let anomaly = score_from_path_length(mean_path_length(&[50.0, 50.0]), values.len());
+/// let normal = score_from_path_length(mean_path_length(&[7.5, 7.5]), values.len());
+/// assert!(anomaly > normal);
+/// ```
+pub struct Tree<T, const N: usize, L: LeafModel<T, N> = Count> {
+    root: Node<T, N, L>,
+
+    /// Bounding box of the samples the tree has been built from, unknown for trees converted
+    /// from other implementations.
+    #[cfg_attr(feature = "serde", serde(default))]
+    bounds: Option<Bounds<T, N>>,
+}
+
+impl<'de, T, const N: usize, L> Tree<T, N, L>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+    L: LeafModel<T, N>,
+{
+    /// Build a tree from all of the samples with hyperplanes of the given extension level,
+    /// drawing the random numbers from a generator seeded with `seed`. Subtrees at `max_depth`
+    /// become external nodes, the forest uses `ceil(log2(samples.len()))`.
+    ///
+    /// Fails with [`Error::InsufficientTrainingData`] for less than two samples and with
+    /// [`Error::ExtensionLevelExceedsDimensions`] unless `extension_level < N`.
+    pub fn new_seeded<S>(
+        samples: &[S],
+        extension_level: usize,
+        max_depth: usize,
+        seed: u64,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if samples.len() < 2 || N == 0 {
+            return Err(Error::InsufficientTrainingData);
+        }
+        if extension_level >= N {
+            return Err(Error::ExtensionLevelExceedsDimensions);
+        }
+        let defaults = ForestOptions::default();
+        let mut samples: Vec<&S> = samples.iter().collect();
+        Ok(Self::new(
+            samples.as_mut_slice(),
+            &mut TreeRng::from_seed(seed),
+            &mut ExtendedSplit::new(defaults.hyperplanes),
+            max_depth,
+            extension_level,
+            defaults.leaf_samples,
+            defaults.collapse_duplicates,
+        ))
+    }
+
+    pub(crate) fn new<S>(
+        samples: &mut [S],
+        rng: &mut TreeRng,
+        split: &mut impl SplitStrategy<T, N>,
+        max_tree_depth: usize,
+        extension_level: usize,
+        leaf_samples: usize,
+        collapse_duplicates: bool,
+    ) -> Self
+    where
+        S: SampleAccess<T, N>,
+    {
+        Self {
+            bounds: Bounds::of(samples.iter()),
+            root: make_tree(
+                samples,
+                rng,
+                split,
+                max_tree_depth,
+                extension_level,
+                leaf_samples,
+                collapse_duplicates,
+            ),
+        }
+    }
+}
+
+impl<T, const N: usize, L: LeafModel<T, N>> Drop for Tree<T, N, L> {
+    /// Dismantle the tree iteratively, the default recursive drop could overflow the stack
+    /// for deep trees.
+    fn drop(&mut self) {
+        self.take_root().dismantle();
+    }
+}
+
+impl<T, const N: usize, L: LeafModel<T, N>> Tree<T, N, L> {
+    /// Move the root out of the tree, leaving an empty external node.
+    fn take_root(&mut self) -> Node<T, N, L> {
+        std::mem::replace(
+            &mut self.root,
+            Node::Ex(ExNode {
+                num_samples: 0,
+                samples: Vec::new(),
+                model: L::default(),
+                depth_offset: 0,
+            }),
+        )
+    }
+}
+
+impl<T, const N: usize, L> Tree<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The external node reached by the sample.
+    fn leaf<S>(&self, values: &S) -> &ExNode<T, N, L>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Ex(ex_node) => return ex_node,
+                Node::In(in_node) => {
+                    node = match in_node.direction(values) {
+                        Direction::Left => in_node.left.as_ref(),
+                        Direction::Right => in_node.right.as_ref(),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Path length of the sample: the depth of the external node it reaches plus the
+    /// average path length of the training samples in that node, see [`c_factor`].
+    pub fn path_length<S>(&self, values: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.path_length_with_cap(values, usize::MAX)
+    }
+
+    /// Path length of the sample. The traversal stops at internal nodes at `max_depth`,
+    /// in which case the path is truncated at that depth.
+    pub fn path_length_with_cap<S>(&self, values: &S, max_depth: usize) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let mut node = &self.root;
+        let mut depth = 0;
+        loop {
+            depth = match node.skip_depth_offset(depth, max_depth) {
+                Some(depth) => depth,
+                None => return max_depth as f64,
+            };
+            match node {
+                Node::Ex(ex_node) => return depth as f64 + c_factor(ex_node.num_samples),
+                Node::In(_) if depth >= max_depth => return depth as f64,
+                Node::In(in_node) => {
+                    node = match in_node.direction(values) {
+                        Direction::Left => in_node.left.as_ref(),
+                        Direction::Right => in_node.right.as_ref(),
+                    };
+                    depth += 1;
+                }
+            }
+        }
+    }
+}
+
+/// A step of the iterative tree construction in [`make_tree`].
+enum BuildStep<T, const N: usize> {
+    /// Build the subtree of the samples in `start..end` of the sample buffer.
+    Build {
+        start: usize,
+        end: usize,
+        depth: usize,
+    },
+
+    /// Combine the two most recently built subtrees into an internal node.
+    Join { n: [T; N], p: [T; N] },
+}
+
+/// Build a tree from the samples without recursion, using an explicit stack of [`BuildStep`]s.
+/// The depth of the tree is therefore not limited by the size of the call stack.
+fn make_tree<T, S, P, L, const N: usize>(
+    samples: &mut [S],
+    rng: &mut TreeRng,
+    split: &mut P,
+    max_tree_depth: usize,
+    extension_level: usize,
+    leaf_samples: usize,
+    collapse_duplicates: bool,
+) -> Node<T, N, L>
+where
+    T: Float,
+    S: SampleAccess<T, N>,
+    P: SplitStrategy<T, N>,
+    L: LeafModel<T, N>,
+{
+    let mut steps = vec![BuildStep::Build {
+        start: 0,
+        end: samples.len(),
+        depth: 0,
+    }];
+    let mut built = vec![];
+
+    while let Some(step) = steps.pop() {
+        match step {
+            BuildStep::Build { start, end, depth } => {
+                let node_samples = &mut samples[start..end];
+                // every split sends identical samples the same way, the chain of splits down to
+                // the max. depth is stored as the depth offset of the leaf instead
+                let identical = collapse_duplicates
+                    && node_samples.len() > 1
+                    && node_samples[1..]
+                        .iter()
+                        .all(|sample| sample.to_array() == node_samples[0].to_array());
+                if depth >= max_tree_depth || node_samples.len() <= 1 || identical {
+                    let samples = index::sample(
+                        rng,
+                        node_samples.len(),
+                        leaf_samples.min(node_samples.len()),
+                    )
+                    .into_iter()
+                    .map(|i| node_samples[i].to_array())
+                    .collect();
+                    built.push(Node::Ex(ExNode {
+                        num_samples: node_samples.len(),
+                        samples,
+                        model: L::fit(node_samples),
+                        depth_offset: if identical {
+                            max_tree_depth.saturating_sub(depth)
+                        } else {
+                            0
+                        },
+                    }));
+                } else {
+                    let (n, p) = split.hyperplane(node_samples, extension_level, rng);
+                    let split = start + partition(node_samples, &n, &p);
+
+                    // The left subtree is built first, the join step finds it below the
+                    // right subtree.
+                    steps.push(BuildStep::Join { n, p });
+                    steps.push(BuildStep::Build {
+                        start: split,
+                        end,
+                        depth: depth + 1,
+                    });
+                    steps.push(BuildStep::Build {
+                        start,
+                        end: split,
+                        depth: depth + 1,
+                    });
+                }
+            }
+            BuildStep::Join { n, p } => {
+                let right = built.pop().expect("right subtree has been built");
+                let left = built.pop().expect("left subtree has been built");
+                built.push(Node::In(InNode::new(left, right, n, p, 0)));
+            }
+        }
+    }
+    built.pop().expect("root has been built")
+}
+
+/// Randomly choose the normal vector `n` and the intercept point `p` of the hyperplane
+/// splitting the samples. The Halton sequence replaces the random number generator when given.
+fn make_hyperplane<'de, T, S, const N: usize>(
+    samples: &[S],
+    rng: &mut TreeRng,
+    halton: &mut Option<Halton>,
+    extension_level: usize,
+    features: Option<&[usize]>,
+) -> ([T; N], [T; N])
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+    S: SampleAccess<T, N>,
+{
+    // randomly select an intercept point p ~ ∈ IR |samples| in
+    // the range of the samples
+    let mut maxs = samples[0].to_array();
+    let mut mins = maxs;
+    samples.iter().skip(1).for_each(|s| {
+        (0..N).for_each(|i| {
+            let v = s.feature(i);
+            maxs[i] = if v > maxs[i] { v } else { maxs[i] };
+            mins[i] = if v < mins[i] { v } else { mins[i] };
+        })
+    });
+    if let Some(halton) = halton {
+        return halton.hyperplane(&mins, &maxs, extension_level);
+    }
+
+    let p = {
+        // randomly pick an intercept point using a uniform distribution
+        let unit = Uniform::new(T::zero(), T::one());
+        let mut p = [T::zero(); N];
+        mins.iter()
+            .zip(maxs.iter())
+            .zip(p.iter_mut())
+            .for_each(|((min_val, max_val), p_i)| {
+                *p_i = intercept_in_range(*min_val, *max_val, rng.sample(&unit));
+            });
+        p
+    };
+
+    // Efficiently generate a sparse random normal vector. Only
+    // `active_dims = extension_level + 1` coordinates receive a non-zero
+    // component; the rest are guaranteed to be 0.  For high-dimensional
+    // data this avoids sampling N Gaussian numbers at every node.
+
+    let mut n = [T::zero(); N];
+    let active_dims = extension_level + 1; // must be ≤ N
+
+    // Choose the active dimensions uniformly without replacement.
+    match features {
+        Some(features) => {
+            for idx in features.iter().choose_multiple(rng, active_dims) {
+                n[*idx] = rng.sample(StandardNormal);
+            }
+        }
+        None => {
+            for idx in (0..N).choose_multiple(rng, active_dims) {
+                n[idx] = rng.sample(StandardNormal);
+            }
+        }
+    }
+
+    (n, p)
+}
+
+/// The intercept at `fraction` of the range `[min, max]`.
+///
+/// `Uniform::new(min, max)` panics for equal bounds and takes extremely long to set up for
+/// bounds only a few ulps apart, so a fraction of the range is sampled instead. Rounding may
+/// overshoot `max`, which is clamped.
+fn intercept_in_range<T: Float>(min: T, max: T, fraction: T) -> T {
+    (min + (max - min) * fraction).min(max)
+}
+
+/// Partition the samples in place, quicksort-style: the samples going to the left
+/// child are moved to the front. This way building a tree does not allocate besides
+/// the nodes themselves.
+///
+/// Returns the number of samples going to the left child.
+fn partition<T, S, const N: usize>(samples: &mut [S], n: &[T; N], p: &[T; N]) -> usize
+where
+    T: Float,
+    S: SampleAccess<T, N>,
+{
+    let offset = hyperplane_offset(n, p);
+    let mut split = 0;
+    for i in 0..samples.len() {
+        if samples[i].direction_with_offset(n, p, offset) <= T::zero() {
+            samples.swap(i, split);
+            split += 1;
+        }
+    }
+    split
+}
+
+/// Average path length of unsuccessful search in a binary search tree given n points
+/// n: Number of data points for the BST.
+///
+/// Returns the average path length of unsuccessful search in a BST. This is also the length of
+/// the path added for the samples of an external node of a tree which have not been isolated
+/// further, and the normalization factor of the anomaly score.
+///
+/// The result is `0` for `n <= 1`. The harmonic number used in the computation is summed up
+/// exactly for small `n` and approximated by its asymptotic expansion for larger `n`.
+pub fn c_factor(n: usize) -> f64 {
+    if n <= 1 {
+        return 0.0;
+    }
+    2.0 * harmonic_number(n - 1) - (2.0 * (n as f64 - 1.0) / n as f64)
+}
+
+/// Convert the mean path length of a sample over all trees into its anomaly score.
+///
+/// `sample_size` is the number of samples each tree has been built from. This is the conversion
+/// used by [`Forest::score`], so path lengths aggregated differently - for example using the
+/// median, or combined from multiple forests - result in scores consistent with the crate.
+pub fn score_from_path_length(mean_path_length: f64, sample_size: usize) -> f64 {
+    normalize_path_length(mean_path_length, c_factor(sample_size))
+}
+
+/// `avg_path_length_c` is the `c_factor` of the sample size of the trees.
+fn normalize_path_length(mean_path_length: f64, avg_path_length_c: f64) -> f64 {
+    2.0_f64.powf(-mean_path_length / avg_path_length_c)
+}
+
+/// Up to this value harmonic numbers are summed up exactly.
+const HARMONIC_NUMBER_EXACT_LIMIT: usize = 64;
+
+const EULER_MASCHERONI: f64 = 0.577_215_664_901_532_9;
+
+/// The `i`-th harmonic number `1 + 1/2 + ... + 1/i`.
+fn harmonic_number(i: usize) -> f64 {
+    if i <= HARMONIC_NUMBER_EXACT_LIMIT {
+        (1..=i).map(|k| 1.0 / k as f64).sum()
+    } else {
+        let x = i as f64;
+        let x2 = x * x;
+        x.ln() + EULER_MASCHERONI + 1.0 / (2.0 * x) - 1.0 / (12.0 * x2) + 1.0 / (120.0 * x2 * x2)
+    }
+}
+
+enum Direction {
+    Left,
+    Right,
+}
+
+fn determinate_direction<T, S, const N: usize>(sample: &S, n: &[T; N], p: &[T; N]) -> Direction
+where
+    T: Float,
+    S: SampleAccess<T, N> + ?Sized,
+{
+    if sample.direction(n, p) <= T::zero() {
+        Direction::Left
+    } else {
+        Direction::Right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::seed::TreeRng;
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use crate::split::SplitStrategy;
+    use crate::{
+        c_factor, intercept_in_range, normalize_path_length, score_from_path_length, Error,
+        ExtensionLevel, Forest, ForestOptions, Hyperplanes, Node, SampleAccess, Tree,
+    };
+
+    fn make_f64_forest() -> Forest<f64, 3> {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let distribution2 = Uniform::new(10., 50.);
+
+        let values: Vec<_> = (0..6000)
+            .map(|_| {
+                [
+                    rng.sample(distribution),
+                    rng.sample(distribution),
+                    rng.sample(distribution2),
+                ]
+            })
+            .collect();
+
+        let options = ForestOptions {
+            n_trees: 150,
+            sample_size: 200,
+            max_tree_depth: None,
+            extension_level: ExtensionLevel::Fixed(1),
+            ..Default::default()
+        };
+        Forest::from_slice(values.as_slice(), &options).unwrap()
+    }
+
+    fn assert_anomalies_forest_3d_f64(forest: &Forest<f64, 3>) {
+        // no anomaly
+        assert!(forest.score(&[1.0, 3.0, 25.0]) < 0.52);
+        assert!(forest.score(&[-1.0, 3.0, 25.0]) < 0.52);
+
+        // anomalies
+        assert!(forest.score(&[-12.0, 6.0, 25.0]) > 0.5);
+        assert!(forest.score(&[-1.0, 2.0, 60.0]) > 0.5);
+        assert!(forest.score(&[-1.0, 2.0, 0.0]) > 0.5);
+    }
+
+    #[test]
+    fn score_forest_3d_f64() {
+        let forest = make_f64_forest();
+        assert_anomalies_forest_3d_f64(&forest);
+    }
+
+    #[test]
+    fn trees_contain_all_samples() {
+        let forest = make_f64_forest();
+        for tree in forest.trees.iter() {
+            assert_eq!(tree.root.num_samples(), 200);
+        }
+    }
+
+    /// Cuts the first dimension left of all samples.
+    struct LeftOfAll;
+
+    impl SplitStrategy<f64, 2> for LeftOfAll {
+        fn hyperplane<S: SampleAccess<f64, 2>>(
+            &mut self,
+            samples: &[S],
+            _extension_level: usize,
+            _rng: &mut TreeRng,
+        ) -> ([f64; 2], [f64; 2]) {
+            let min = samples
+                .iter()
+                .map(|sample| sample.feature(0))
+                .fold(f64::INFINITY, f64::min);
+            ([1.0, 0.0], [min - 1.0, 0.0])
+        }
+    }
+
+    #[test]
+    fn deep_trees_do_not_overflow_the_stack() {
+        // the samples are never separated, so every tree grows to the max. depth
+        let values: Vec<_> = (0..10).map(|i| [1.0, i as f64]).collect();
+        let forest = Forest::from_slice_with_strategy(
+            values.as_slice(),
+            &ForestOptions {
+                n_trees: 2,
+                sample_size: 10,
+                max_tree_depth: Some(200_000),
+                ..Default::default()
+            },
+            LeftOfAll,
+        )
+        .unwrap();
+
+        assert_eq!(forest.trees[0].root.depth(), 200_000);
+        assert!(forest.score_with_recursion_cap(&[1.0, 2.0], usize::MAX) < 0.01);
+
+        let exported = forest.to_sklearn().unwrap();
+        assert_eq!(exported.estimators[0].n_node_samples.len(), 400_001);
+        assert_eq!(exported.estimators[0].n_node_samples[0], 10);
+        assert_ne!(forest.model_hash(), 0);
+        #[cfg(feature = "bench")]
+        assert_eq!(crate::bench::flatten(&forest)[0].nodes.len(), 400_001);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialized_trees_are_limited_in_depth() {
+        let make_forest = |max_tree_depth| {
+            let values: Vec<_> = (0..10).map(|i| [1.0, i as f64]).collect();
+            let options = ForestOptions {
+                n_trees: 1,
+                sample_size: 10,
+                max_tree_depth: Some(max_tree_depth),
+                ..Default::default()
+            };
+            Forest::from_slice_with_strategy(values.as_slice(), &options, LeftOfAll).unwrap()
+        };
+
+        let forest = make_forest(crate::serde_depth::MAX_DEPTH);
+        let value = serde_json::to_value(&forest).unwrap();
+        let restored: Forest<f64, 2> = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            restored.trees[0].root.depth(),
+            crate::serde_depth::MAX_DEPTH
+        );
+
+        let error = serde_json::to_string(&make_forest(20_000)).unwrap_err();
+        assert!(error.to_string().contains("binary format"));
+
+        // one more level below the root
+        let mut deeper = value;
+        let root = deeper["trees"][0]["root"].take();
+        deeper["trees"][0]["root"] = serde_json::json!({"In": {
+            "left": root,
+            "right": {"Ex": {"num_samples": 0}},
+            "n": [1.0, 0.0],
+            "p": [0.0, 0.0],
+        }});
+        assert!(serde_json::from_value::<Forest<f64, 2>>(deeper).is_err());
+    }
+
+    #[test]
+    fn identical_samples_end_in_one_leaf() {
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 64,
+            max_tree_depth: Some(50),
+            extension_level: ExtensionLevel::Fixed(1),
+            collapse_duplicates: true,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(vec![[1.0, 2.0]; 64].as_slice(), &options).unwrap();
+        for tree in forest.trees.iter() {
+            assert_eq!(tree.root.iter().count(), 1);
+            assert_eq!(tree.root.depth(), 50);
+        }
+        assert_eq!(
+            forest.score(&[1.0, 2.0]),
+            normalize_path_length(50.0 + c_factor(64), forest.avg_path_length_c)
+        );
+        assert_eq!(
+            forest.score_with_recursion_cap(&[1.0, 2.0], 10),
+            normalize_path_length(10.0, forest.avg_path_length_c)
+        );
+
+        // low-cardinality data, every leaf holding more than one sample ends at the max. depth
+        let values: Vec<_> = (0..256).map(|i| [(i % 3) as f64, (i % 2) as f64]).collect();
+        let num_nodes = |options: &ForestOptions| -> usize {
+            let forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), options).unwrap();
+            for tree in forest.trees.iter() {
+                assert_eq!(tree.root.num_samples(), 64);
+                assert!(tree.root.iter().all(|(node, depth)| match node {
+                    Node::Ex(ex_node) if ex_node.num_samples > 1 => depth == 50,
+                    _ => depth <= 50,
+                }));
+            }
+            forest
+                .trees
+                .iter()
+                .map(|tree| tree.root.iter().count())
+                .sum()
+        };
+        let chains = ForestOptions {
+            collapse_duplicates: false,
+            ..options.clone()
+        };
+        assert!(num_nodes(&options) * 10 < num_nodes(&chains));
+    }
+
+    #[test]
+    fn recursion_cap_defaults_to_max_tree_depth() {
+        let mut forest = make_f64_forest();
+        assert_eq!(forest.recursion_cap(), 8);
+
+        let sample = [1.0, 3.0, 25.0];
+        let score = forest.score(&sample);
+        assert_eq!(score, forest.score_with_recursion_cap(&sample, usize::MAX));
+
+        // truncating all paths at the root
+        forest.set_recursion_cap(Some(0));
+        assert_eq!(forest.score(&sample), 1.0);
+        assert_eq!(forest.score_exact(&sample), score);
+
+        forest.set_recursion_cap(None);
+        assert_eq!(forest.score(&sample), score);
+    }
+
+    #[test]
+    fn c_factor_small_n() {
+        assert_eq!(c_factor(0), 0.0);
+        assert_eq!(c_factor(1), 0.0);
+        assert_eq!(c_factor(2), 1.0);
+        assert!((c_factor(3) - 5.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn c_factor_is_continuous() {
+        // switch from summing up the harmonic number to its approximation
+        let exact: f64 = (1..=65).map(|k| 1.0 / k as f64).sum();
+        let expected = 2.0 * exact - 2.0 * 65.0 / 66.0;
+        assert!((c_factor(66) - expected).abs() < 1e-12);
+        assert!(c_factor(65) < c_factor(66));
+    }
+
+    #[test]
+    fn score_from_mean_path_length() {
+        assert_eq!(score_from_path_length(0.0, 256), 1.0);
+        assert_eq!(score_from_path_length(c_factor(256), 256), 0.5);
+    }
+
+    #[test]
+    fn score_forest_from_columns() {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let distribution2 = Uniform::new(10., 50.);
+
+        let x: Vec<f64> = (0..6000).map(|_| rng.sample(distribution)).collect();
+        let y: Vec<f64> = (0..6000).map(|_| rng.sample(distribution)).collect();
+        let z: Vec<f64> = (0..6000).map(|_| rng.sample(distribution2)).collect();
+
+        let options = ForestOptions {
+            n_trees: 150,
+            sample_size: 200,
+            max_tree_depth: None,
+            extension_level: ExtensionLevel::Fixed(1),
+            ..Default::default()
+        };
+        let forest = Forest::from_columns(&[&x, &y, &z], &options).unwrap();
+        assert_anomalies_forest_3d_f64(&forest);
+
+        let scores = forest
+            .score_columns(&[&x[..10], &y[..10], &z[..10]])
+            .unwrap();
+        assert_eq!(scores.len(), 10);
+        assert_eq!(scores[3], forest.score(&[x[3], y[3], z[3]]));
+        assert!(matches!(
+            forest.score_columns(&[&x, &y[1..], &z]),
+            Err(Error::ColumnLengthMismatch)
+        ));
+        assert_eq!(
+            forest
+                .score_column_slices(&[&x[..10], &y[..10], &z[..10]])
+                .unwrap(),
+            scores
+        );
+        assert!(matches!(
+            forest.score_column_slices(&[&x, &y]),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+
+        assert!(matches!(
+            Forest::from_columns(&[&x, &y, &z[1..]], &options),
+            Err(Error::ColumnLengthMismatch)
+        ));
+    }
+
+    #[test]
+    fn train_trees_round_robin_on_shards() {
+        let monday: Vec<_> = (0..300)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let tuesday: Vec<_> = (0..200)
+            .map(|i| [(i % 20) as f64 + 100.0, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            sample_size: 64,
+            seed: Some(17),
+            ..Default::default()
+        };
+        let forest = Forest::from_shards(&[&monday, &tuesday], &options).unwrap();
+        for (index, tree) in forest.trees.iter().enumerate() {
+            let bounds = tree.bounds.as_ref().unwrap();
+            if index % 2 == 0 {
+                assert!(bounds.max()[0] < 20.0);
+            } else {
+                assert!(bounds.min()[0] >= 100.0);
+            }
+        }
+        let (min, max) = forest.training_range().unwrap();
+        assert_eq!((min[0], max[0]), (0.0, 119.0));
+        assert_eq!(max[1], 14.0);
+        // both shards are normal, the gap between them is not
+        assert!(forest.score(&[10.0, 5.0]) < forest.score(&[60.0, 5.0]));
+        assert!(forest.score(&[110.0, 5.0]) < forest.score(&[60.0, 5.0]));
+
+        assert!(matches!(
+            Forest::<f64, 2>::from_shards(&[] as &[&[[f64; 2]]], &options),
+            Err(Error::EmptyData)
+        ));
+        assert!(matches!(
+            Forest::from_shards(&[&monday, &tuesday[..10]], &options),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+
+    #[test]
+    fn train_standalone_trees() {
+        let values: Vec<_> = (0..100)
+            .map(|i| [(i % 10) as f64, (i / 10) as f64])
+            .collect();
+        let a: Tree<f64, 2> = Tree::new_seeded(&values, 1, 7, 3).unwrap();
+        let b: Tree<f64, 2> = Tree::new_seeded(&values, 1, 7, 3).unwrap();
+        let c: Tree<f64, 2> = Tree::new_seeded(&values, 1, 7, 4).unwrap();
+        let path_lengths = |tree: &Tree<f64, 2>| -> Vec<f64> {
+            values
+                .iter()
+                .map(|sample| tree.path_length(sample))
+                .collect()
+        };
+        assert_eq!(path_lengths(&a), path_lengths(&b));
+        assert_ne!(path_lengths(&a), path_lengths(&c));
+        let trees: Vec<Tree<f64, 2>> = (0..20)
+            .map(|seed| Tree::new_seeded(&values, 1, 7, seed).unwrap())
+            .collect();
+        let mean_path_length = |sample: &[f64; 2]| -> f64 {
+            trees
+                .iter()
+                .map(|tree| tree.path_length(sample))
+                .sum::<f64>()
+                / 20.0
+        };
+        assert!(mean_path_length(&[100.0, 100.0]) < mean_path_length(&[4.5, 4.5]));
+        assert_eq!(a.path_length_with_cap(&[4.5, 4.5], 1), 1.0);
+
+        assert!(matches!(
+            Tree::<f64, 2>::new_seeded(&values[..1], 1, 7, 3),
+            Err(Error::InsufficientTrainingData)
+        ));
+        assert!(matches!(
+            Tree::<f64, 2>::new_seeded(&values, 2, 7, 3),
+            Err(Error::ExtensionLevelExceedsDimensions)
+        ));
+    }
+
+    #[test]
+    fn forest_from_trees() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let trees: Vec<Tree<f64, 2>> = values
+            .chunks(40)
+            .enumerate()
+            .map(|(seed, chunk)| Tree::new_seeded(chunk, 1, 8, seed as u64).unwrap())
+            .collect();
+        let mean_path_length = |sample: &[f64; 2]| -> f64 {
+            trees
+                .iter()
+                .map(|tree| tree.path_length(sample))
+                .sum::<f64>()
+                / trees.len() as f64
+        };
+        let expected = score_from_path_length(mean_path_length(&[30.0, 5.0]), 40);
+
+        let forest = Forest::from_trees(trees, 40).unwrap();
+        assert!((forest.score(&[30.0, 5.0]) - expected).abs() < 1e-12);
+        assert_eq!(forest.options().n_trees, 10);
+        assert_eq!(forest.options().extension_level, ExtensionLevel::Fixed(1));
+        assert!(forest.recursion_cap() <= 8);
+        let (min, max) = forest.training_range().unwrap();
+        assert_eq!((min, max), (&[0.0, 0.0], &[19.0, 19.0]));
+        assert_eq!(forest.tree_provenance(), vec![Default::default(); 10]);
+
+        let axis_parallel: Vec<Tree<f64, 2>> = (0..3)
+            .map(|seed| Tree::new_seeded(&values[..40], 0, 8, seed).unwrap())
+            .collect();
+        let forest = Forest::from_trees(axis_parallel, 40).unwrap();
+        assert_eq!(forest.options().extension_level, ExtensionLevel::Fixed(0));
+
+        let mixed = vec![
+            Tree::new_seeded(&values[..40], 1, 8, 0).unwrap(),
+            Tree::new_seeded(&values[..30], 1, 8, 1).unwrap(),
+        ];
+        assert!(matches!(
+            Forest::<f64, 2>::from_trees(mixed, 40),
+            Err(Error::TreeSampleSizeMismatch {
+                index: 1,
+                actual: 30
+            })
+        ));
+        assert!(matches!(
+            Forest::<f64, 2>::from_trees(Vec::new(), 40),
+            Err(Error::EmptyData)
+        ));
+    }
+
+    #[test]
+    fn mixed_extension_levels() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, (i % 7) as f64])
+            .collect();
+        for extension_level in [
+            ExtensionLevel::Range { min: 0, max: 2 },
+            ExtensionLevel::List(vec![0, 2]),
+        ] {
+            let options = ForestOptions {
+                extension_level,
+                ..Default::default()
+            };
+            let forest = Forest::from_slice(values.as_slice(), &options).unwrap();
+            assert!(forest.score(&[100.0, 100.0, 3.0]) > forest.score(&[10.0, 10.0, 3.0]));
+        }
+
+        for extension_level in [
+            ExtensionLevel::List(vec![]),
+            ExtensionLevel::Range { min: 2, max: 1 },
+        ] {
+            let options = ForestOptions {
+                extension_level,
+                ..Default::default()
+            };
+            assert!(matches!(
+                Forest::<f64, 3>::from_slice(values.as_slice(), &options),
+                Err(Error::NoExtensionLevel)
+            ));
+        }
+        let options = ForestOptions {
+            extension_level: ExtensionLevel::List(vec![1, 3]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Forest::<f64, 3>::from_slice(values.as_slice(), &options),
+            Err(Error::ExtensionLevelExceedsDimensions)
+        ));
+
+        // forests serialized with a plain number as extension level can still be read
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::from_str::<ExtensionLevel>("1").unwrap(),
+            ExtensionLevel::Fixed(1)
+        );
+    }
+
+    #[test]
+    fn score_forest_with_halton_hyperplanes() {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let distribution2 = Uniform::new(10., 50.);
+
+        let values: Vec<_> = (0..6000)
+            .map(|_| {
+                [
+                    rng.sample(distribution),
+                    rng.sample(distribution),
+                    rng.sample(distribution2),
+                ]
+            })
+            .collect();
+
+        let options = ForestOptions {
+            n_trees: 150,
+            sample_size: 200,
+            extension_level: ExtensionLevel::Fixed(1),
+            hyperplanes: Hyperplanes::Halton,
+            ..Default::default()
+        };
+        let forest = Forest::from_slice(values.as_slice(), &options).unwrap();
+        assert_anomalies_forest_3d_f64(&forest);
+    }
+
+    #[test]
+    fn jitter_integer_features() {
+        let rng = &mut rand::thread_rng();
+        let values: Vec<_> = (0..1000)
+            .map(|i| [(i % 3) as f64, rng.sample(Uniform::new(0.0, 1.0))])
+            .collect();
+
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 100,
+            integer_jitter: true,
+            ..Default::default()
+        };
+        let forest = Forest::from_slice(values.as_slice(), &options).unwrap();
+        assert_eq!(forest.jittered_features(), &[0]);
+        assert!(forest.score(&[10.0, 0.5]) > forest.score(&[1.0, 0.5]));
+
+        let forest = Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+        assert!(forest.jittered_features().is_empty());
+    }
+
+    #[test]
+    fn drop_constant_features() {
+        let values: Vec<_> = (0..200)
+            .map(|i| {
+                [
+                    (i % 10) as f64,
+                    1e6 + (i % 2) as f64 * 1e-6,
+                    (i / 10) as f64,
+                ]
+            })
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            sample_size: 64,
+            extension_level: ExtensionLevel::Fixed(1),
+            drop_constant_features: true,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 3> = Forest::from_slice(values.as_slice(), &options).unwrap();
+        assert_eq!(forest.constant_features(), &[1]);
+        // both non-zero components are spent on the varying features
+        for tree in forest.trees.iter() {
+            for (node, _) in tree.root.iter() {
+                if let Node::In(in_node) = node {
+                    assert_eq!(in_node.n[1], 0.0);
+                    assert!(in_node.n[0] != 0.0 && in_node.n[2] != 0.0);
+                }
+            }
+        }
+
+        let options = ForestOptions {
+            drop_constant_features: false,
+            ..options
+        };
+        let forest: Forest<f64, 3> = Forest::from_slice(values.as_slice(), &options).unwrap();
+        assert_eq!(forest.constant_features(), &[1]);
+        assert!(forest.trees.iter().any(|tree| tree
+            .root
+            .iter()
+            .any(|(node, _)| matches!(node, Node::In(in_node) if in_node.n[1] != 0.0))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
     fn serialize_forest_3d_f64() {
         let forest = make_f64_forest();
         let forest_json = serde_json::to_string(&forest).unwrap();
         let forest2 = serde_json::from_str(forest_json.as_str()).unwrap();
         assert_anomalies_forest_3d_f64(&forest2);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_forest_without_options() {
+        // serialized with version 0.2.3, before the options have been stored
+        let json = r#"{"avg_path_length_c":1.8516559071362195,"trees":[{"root":{"In":{"left":{"In":{"left":{"Ex":{"num_samples":1}},"right":{"Ex":{"num_samples":1}},"n":[-0.6315895528698844,-1.539628263066526],"p":[0.3628765860284735,2.6559363070974897]}},"right":{"In":{"left":{"Ex":{"num_samples":1}},"right":{"Ex":{"num_samples":1}},"n":[0.9303068433385816,-0.7359859497896188],"p":[2.3926262350237772,2.0]}},"n":[0.1172116956876433,-0.5091378386030487],"p":[1.686736960675331,2.074507441951721]}}},{"root":{"In":{"left":{"Ex":{"num_samples":1}},"right":{"In":{"left":{"Ex":{"num_samples":2}},"right":{"Ex":{"num_samples":1}},"n":[1.0417733658039472,-1.356997415952332],"p":[1.0048103492815044,3.0]}},"n":[-0.482583427864971,1.0439739560760288],"p":[1.0650141849155175,1.4430678918552893]}}}]}"#;
+        let forest: Forest<f64, 2> = serde_json::from_str(json).unwrap();
+
+        let options = forest.options();
+        assert_eq!(options.n_trees, 2);
+        assert_eq!(options.sample_size, 4);
+        assert_eq!(options.max_tree_depth, Some(2));
+        assert_eq!(options.extension_level, ExtensionLevel::Fixed(1));
+
+        // the scores computed by version 0.2.3
+        assert!((forest.score(&[1.0, 1.0]) - 0.5703479706665715).abs() < 1e-12);
+        assert!((forest.score(&[9.0, 9.0]) - 0.472991352569295).abs() < 1e-12);
+
+        let json = serde_json::to_string(&forest).unwrap();
+        let restored: Forest<f64, 2> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.options(), options);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn score_events_are_sampled() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        /// Counts the events on the `TRACE` level.
+        struct CountingSubscriber(Arc<AtomicUsize>);
+
+        impl tracing::Subscriber for CountingSubscriber {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+                tracing::span::Id::from_u64(1)
+            }
+            fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+            fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+            fn event(&self, event: &tracing::Event<'_>) {
+                if *event.metadata().level() == tracing::Level::TRACE {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            fn enter(&self, _: &tracing::span::Id) {}
+            fn exit(&self, _: &tracing::span::Id) {}
+        }
+
+        let mut forest = make_f64_forest();
+        let events = Arc::new(AtomicUsize::new(0));
+        let score_all = |forest: &Forest<f64, 3>| {
+            tracing::subscriber::with_default(CountingSubscriber(events.clone()), || {
+                (0..100).for_each(|i| {
+                    forest.score(&[i as f64, 0.0, 0.0]);
+                })
+            });
+            events.swap(0, Ordering::Relaxed)
+        };
+
+        assert_eq!(score_all(&forest), 0);
+        forest.set_score_trace_interval(10);
+        assert_eq!(score_all(&forest), 10);
+        forest.set_score_trace_interval(1);
+        assert_eq!(score_all(&forest), 100);
+        forest.set_score_trace_interval(0);
+        assert_eq!(score_all(&forest), 0);
+    }
+
+    #[test]
+    fn intercepts_between_almost_equal_bounds() {
+        let min = 1.0_f64;
+        let max = f64::from_bits(min.to_bits() + 2);
+        for fraction in [0.0, 0.3, 0.5, 1.0 - f64::EPSILON] {
+            let intercept = intercept_in_range(min, max, fraction);
+            assert!(min <= intercept && intercept <= max);
+        }
+        assert_eq!(intercept_in_range(min, min, 0.7), min);
+
+        // used to hang while setting up the uniform distribution of every split
+        let values: Vec<_> = (0..64)
+            .map(|i| [f64::from_bits(min.to_bits() + i % 3), 0.0])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+        assert!(forest.score(&values[0]).is_finite());
+    }
 }
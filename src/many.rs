@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::distributions::uniform::SampleUniform;
+use rand::seq::index;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::parallel::Parallelism;
+use crate::seed::TreeRng;
+use crate::split::ExtendedSplit;
+use crate::{Error, Forest, ForestFloat, ForestOptions};
+
+/// Groups handed to a thread at once, small forests train too fast to hand them out one by one.
+const GROUPS_PER_CHUNK: usize = 64;
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default + Send + Sync,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a forest for every group of training data like [`Forest::from_slice`], for
+    /// example a baseline per device. The groups are distributed over all available cores and
+    /// every thread reuses its buffers for the subsamples of the trees.
+    ///
+    /// Every group gets its own result, so groups with too few samples do not prevent training
+    /// the others. With duplicate keys the forest of the last group is kept.
+    pub fn train_many<K>(
+        groups: &[(K, &[[T; N]])],
+        options: &ForestOptions,
+    ) -> HashMap<K, Result<Self, Error>>
+    where
+        K: Eq + Hash + Clone + Sync + Send,
+    {
+        let parallelism = Parallelism {
+            chunk_size: Some(GROUPS_PER_CHUNK),
+            ..Default::default()
+        };
+        parallelism
+            .map_with(groups, Vec::new, |tree_sample, (key, training_data)| {
+                (
+                    key.clone(),
+                    Self::train_group(training_data, options, tree_sample),
+                )
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn train_group<'a>(
+        training_data: &'a [[T; N]],
+        options: &ForestOptions,
+        tree_sample: &mut Vec<&'a [T; N]>,
+    ) -> Result<Self, Error> {
+        let split = ExtendedSplit::new(options.hyperplanes);
+        Self::train_with_builder(
+            training_data.len(),
+            |i| &training_data[i],
+            options,
+            split,
+            |mut builder| {
+                (0..options.n_trees)
+                    .map(|tree_index| {
+                        let rng = &mut TreeRng::new(options.seed, tree_index);
+                        tree_sample.clear();
+                        tree_sample.extend(
+                            index::sample(rng, training_data.len(), options.sample_size)
+                                .into_iter()
+                                .map(|i| &training_data[i]),
+                        );
+                        let extension_level = options.extension_level.sample(rng);
+                        builder.build(tree_index, tree_sample.as_mut_slice(), extension_level, rng)
+                    })
+                    .collect()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn train_forests_per_group() {
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 16,
+            seed: Some(9),
+            ..Default::default()
+        };
+        let data: Vec<Vec<[f64; 2]>> = (0..200)
+            .map(|device| {
+                (0..20 + device % 5)
+                    .map(|i| [(i + device) as f64, (i % 4) as f64])
+                    .collect()
+            })
+            .collect();
+        let mut groups: Vec<(usize, &[[f64; 2]])> = data
+            .iter()
+            .enumerate()
+            .map(|(device, values)| (device, values.as_slice()))
+            .collect();
+        let too_small = [[1.0, 2.0]; 3];
+        groups.push((1000, &too_small));
+
+        let forests = Forest::train_many(&groups, &options);
+        assert_eq!(forests.len(), 201);
+        assert!(matches!(
+            forests[&1000],
+            Err(Error::InsufficientTrainingData)
+        ));
+        for device in [0, 57, 199] {
+            let forest = forests[&device].as_ref().unwrap();
+            let single: Forest<f64, 2> = Forest::from_slice(&data[device], &options).unwrap();
+            for sample in [[3.0, 1.0], [100.0, -1.0]] {
+                assert_eq!(forest.score(&sample), single.score(&sample));
+            }
+        }
+    }
+}
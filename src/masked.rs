@@ -0,0 +1,180 @@
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{
+    c_factor, envelope::RangePolicy, normalize_path_length, Direction, Forest, Node, SampleAccess,
+};
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Compute the anomaly score of the sample ignoring the dimensions set in `mask`, for
+    /// questions like how anomalous a sample is apart from one feature.
+    ///
+    /// Masked dimensions are treated as unknown: at a split involving any of them the sample
+    /// descends into both children, weighted by the share of the training samples which went
+    /// each way, and the path length is the weighted mean of the reached leaves. The values of
+    /// masked dimensions are never read. The recursion cap, the monotone constraints and the
+    /// range policy apply as in [`Forest::score`], strict mode does not.
+    ///
+    /// ```rust
+    /// use extended_isolation_forest::{Forest, ForestOptions};
+    ///
+    /// let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+    /// let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+    ///
+    /// let sample = [4.5, 100.0];
+    /// assert!(forest.score_masked(&sample, &[false, true]) < forest.score(&sample));
+    /// ```
+    pub fn score_masked<S>(&self, sample: &S, mask: &[bool; N]) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        normalize_path_length(
+            self.masked_path_length(sample, mask),
+            self.avg_path_length_c,
+        )
+    }
+
+    /// The mean path length of the sample over all trees, with the masked dimensions unknown.
+    pub(crate) fn masked_path_length<S>(&self, sample: &S, mask: &[bool; N]) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let adjusted = self
+            .apply_monotone_constraints(sample)
+            .unwrap_or_else(|| sample.to_array());
+        let mut adjusted = match self.range_policy {
+            RangePolicy::Ignore => adjusted,
+            _ => self.apply_range_policy(&adjusted).unwrap_or(adjusted),
+        };
+        // splits not involving a masked dimension multiply it by zero, which must not be `NaN`
+        for i in 0..N {
+            if mask[i] {
+                adjusted[i] = T::zero();
+            }
+        }
+
+        let max_depth = self.recursion_cap();
+        let path_length: f64 = self
+            .trees
+            .iter()
+            .map(|tree| tree_path_length(&tree.root, &adjusted, mask, max_depth))
+            .sum();
+        path_length / self.trees.len() as f64
+    }
+}
+
+/// The expected path length of the sample, descending into both children of splits involving
+/// masked dimensions.
+fn tree_path_length<T, L, const N: usize>(
+    root: &Node<T, N, L>,
+    sample: &[T; N],
+    mask: &[bool; N],
+    max_depth: usize,
+) -> f64
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    let mut path_length = 0.0;
+    let mut stack = vec![(root, 0, 1.0)];
+    while let Some((node, depth, weight)) = stack.pop() {
+        let depth = match node.skip_depth_offset(depth, max_depth) {
+            Some(depth) => depth,
+            None => {
+                path_length += weight * max_depth as f64;
+                continue;
+            }
+        };
+        match node {
+            Node::Ex(ex_node) => {
+                path_length += weight * (depth as f64 + c_factor(ex_node.num_samples));
+            }
+            Node::In(_) if depth >= max_depth => path_length += weight * depth as f64,
+            Node::In(in_node) => {
+                let masked = (0..N).any(|i| mask[i] && in_node.n[i] != T::zero());
+                if !masked {
+                    let child = match in_node.direction(sample) {
+                        Direction::Left => in_node.left.as_ref(),
+                        Direction::Right => in_node.right.as_ref(),
+                    };
+                    stack.push((child, depth + 1, weight));
+                    continue;
+                }
+                let left = in_node.left.num_samples() as f64;
+                let right = in_node.right.num_samples() as f64;
+                let left_share = if left + right > 0.0 {
+                    left / (left + right)
+                } else {
+                    0.5
+                };
+                stack.push((in_node.left.as_ref(), depth + 1, weight * left_share));
+                stack.push((
+                    in_node.right.as_ref(),
+                    depth + 1,
+                    weight * (1.0 - left_share),
+                ));
+            }
+        }
+    }
+    path_length
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eif::{EifForest, EifNode};
+    use crate::{c_factor, normalize_path_length, Forest, ForestOptions};
+
+    #[test]
+    fn masked_dimensions_descend_into_both_children() {
+        let leaf = |size| Box::new(EifNode::ExNode { size });
+        let forest: Forest<f64, 2> = Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![0.0, 1.0],
+                p: vec![0.0, 5.0],
+                left: Box::new(EifNode::InNode {
+                    n: vec![1.0, 0.0],
+                    p: vec![2.0, 0.0],
+                    left: leaf(5),
+                    right: leaf(1),
+                }),
+                right: leaf(2),
+            }],
+        })
+        .unwrap();
+
+        // the first split is skipped with the shares 6 / 8 and 2 / 8
+        let expected = 0.75 * (2.0 + c_factor(5)) + 0.25 * (1.0 + c_factor(2));
+        assert_eq!(
+            forest.score_masked(&[1.0, f64::NAN], &[false, true]),
+            normalize_path_length(expected, c_factor(8))
+        );
+        let sample = [1.0, 1.0];
+        assert_eq!(
+            forest.score_masked(&sample, &[false; 2]),
+            forest.score(&sample)
+        );
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 64,
+            extension_level: 1.into(),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let sample = [10.0, 1000.0];
+        assert_eq!(
+            forest.score_masked(&sample, &[false; 2]),
+            forest.score(&sample)
+        );
+        assert!(forest.score_masked(&sample, &[false, true]) < forest.score(&[10.0, 10.0]) + 0.1);
+        assert!(forest.score_masked(&sample, &[false, true]) < forest.score(&sample));
+    }
+}
@@ -0,0 +1,494 @@
+//! Forests for samples with continuous and categorical features.
+//!
+//! One-hot encoding categorical features creates many sparse dimensions, which extended
+//! isolation forests handle poorly. A [`MixedForest`] splits on categorical features directly:
+//! each internal node either splits the continuous features with a hyperplane, like a
+//! [`Forest`](crate::Forest), or sends a random subset of the categories of one categorical
+//! feature to the left. The kind of split is chosen with a probability proportional to the number
+//! of continuous and categorical features.
+//!
+//! Categories are identified by `u32` codes. A category which has not been seen at a categorical
+//! split during training is isolated by the split, as if it reached an external node below it.
+//!
+//! ```rust
+//! use extended_isolation_forest::ForestOptions;
+//! use extended_isolation_forest::mixed::{MixedForest, MixedSample};
+//!
+//! // temperature and pressure, plus the id of the sensor model
+//! let samples: Vec<_> = (0..200)
+//!     .map(|i| MixedSample {
+//!         continuous: [20.0 + (i % 10) as f64, 1000.0 + (i / 20) as f64],
+//!         categorical: [i % 3],
+//!     })
+//!     .collect();
+//! let forest = MixedForest::from_slice(&samples, &ForestOptions::default()).unwrap();
+//!
+//! let known_model = forest.score(&MixedSample { continuous: [25.0, 1005.0], categorical: [1] });
+//! let unknown_model = forest.score(&MixedSample { continuous: [25.0, 1005.0], categorical: [7] });
+//! assert!(unknown_model > known_model);
+//! ```
+
+use rand::{distributions::uniform::SampleUniform, seq::index, seq::SliceRandom, Rng};
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use num_traits::Float;
+
+use crate::seed::TreeRng;
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{
+    c_factor, determinate_direction, make_hyperplane, normalize_path_length, partition, Direction,
+    Error, ForestFloat, ForestOptions, Halton, Hyperplanes, SampleAccess,
+};
+
+/// A sample with `N` continuous and `C` categorical features.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MixedSample<T, const N: usize, const C: usize> {
+    pub continuous: [T; N],
+    pub categorical: [u32; C],
+}
+
+impl<T, const N: usize, const C: usize> SampleAccess<T, N> for MixedSample<T, N, C>
+where
+    T: Copy,
+{
+    #[inline]
+    fn feature(&self, i: usize) -> T {
+        self.continuous[i]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MixedForest<T, const N: usize, const C: usize> {
+    /// Multiplicative factor used in computing the anomaly scores.
+    avg_path_length_c: f64,
+
+    /// Options the forest has been built with. `max_tree_depth` is always set.
+    options: ForestOptions,
+
+    trees: Vec<MixedTree<T, N>>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct MixedTree<T, const N: usize> {
+    root: MixedNode<T, N>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum MixedNode<T, const N: usize> {
+    Ex {
+        num_samples: usize,
+    },
+    Hyperplane {
+        #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+        n: [T; N],
+        #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+        p: [T; N],
+        left: Box<MixedNode<T, N>>,
+        right: Box<MixedNode<T, N>>,
+    },
+    Categories {
+        /// Index of the categorical feature.
+        feature: usize,
+
+        /// Sorted categories going to the left.
+        left_categories: Vec<u32>,
+
+        /// Sorted categories going to the right.
+        right_categories: Vec<u32>,
+        left: Box<MixedNode<T, N>>,
+        right: Box<MixedNode<T, N>>,
+    },
+}
+
+/// How an internal node splits the samples.
+enum MixedSplit<T, const N: usize> {
+    Hyperplane {
+        n: [T; N],
+        p: [T; N],
+    },
+    Categories {
+        feature: usize,
+        left_categories: Vec<u32>,
+        right_categories: Vec<u32>,
+    },
+}
+
+/// A step of the iterative tree construction, see `BuildStep` of [`Forest`](crate::Forest).
+enum MixedBuildStep<T, const N: usize> {
+    Build {
+        start: usize,
+        end: usize,
+        depth: usize,
+    },
+    Join(MixedSplit<T, N>),
+}
+
+impl<'de, T, const N: usize, const C: usize> MixedForest<T, N, C>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the given training data.
+    ///
+    /// The extension level only applies to the continuous features. `leaf_samples` and
+    /// `collapse_duplicates` are not supported and ignored.
+    pub fn from_slice(
+        training_data: &[MixedSample<T, N, C>],
+        options: &ForestOptions,
+    ) -> Result<Self, Error> {
+        if training_data.len() < options.sample_size || options.sample_size < 2 || N + C == 0 {
+            return Err(Error::InsufficientTrainingData);
+        }
+        match options.extension_level.max_level() {
+            None => return Err(Error::NoExtensionLevel),
+            Some(level) if N > 0 && level > (N - 1) => {
+                return Err(Error::ExtensionLevelExceedsDimensions)
+            }
+            Some(_) => (),
+        }
+
+        let max_tree_depth = options
+            .max_tree_depth
+            .unwrap_or_else(|| (options.sample_size as f64).log2().ceil() as usize);
+
+        let mut halton = match options.hyperplanes {
+            Hyperplanes::Random => None,
+            Hyperplanes::Halton => Some(Halton::new(3 * N)),
+        };
+        let trees = (0..options.n_trees)
+            .map(|tree_index| {
+                let rng = &mut TreeRng::new(options.seed, tree_index);
+                let mut tree_sample: Vec<_> =
+                    index::sample(rng, training_data.len(), options.sample_size)
+                        .into_iter()
+                        .map(|i| &training_data[i])
+                        .collect();
+                let extension_level = options.extension_level.sample(rng);
+                MixedTree {
+                    root: make_mixed_tree(
+                        tree_sample.as_mut_slice(),
+                        rng,
+                        &mut halton,
+                        max_tree_depth,
+                        extension_level,
+                    ),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            avg_path_length_c: c_factor(options.sample_size),
+            options: ForestOptions {
+                max_tree_depth: Some(max_tree_depth),
+                ..options.clone()
+            },
+            trees,
+        })
+    }
+}
+
+impl<T, const N: usize, const C: usize> MixedForest<T, N, C>
+where
+    T: Float,
+{
+    /// The options the forest has been built with.
+    pub fn options(&self) -> &ForestOptions {
+        &self.options
+    }
+
+    /// Compute the anomaly score of a sample.
+    pub fn score(&self, sample: &MixedSample<T, N, C>) -> f64 {
+        let path_length: f64 = self.trees.iter().map(|tree| tree.path_length(sample)).sum();
+        normalize_path_length(
+            path_length / self.trees.len() as f64,
+            self.avg_path_length_c,
+        )
+    }
+}
+
+impl<T, const N: usize> MixedTree<T, N>
+where
+    T: Float,
+{
+    fn path_length<const C: usize>(&self, sample: &MixedSample<T, N, C>) -> f64 {
+        let mut node = &self.root;
+        let mut depth = 0;
+        loop {
+            node = match node {
+                MixedNode::Ex { num_samples } => return depth as f64 + c_factor(*num_samples),
+                MixedNode::Hyperplane {
+                    n, p, left, right, ..
+                } => match determinate_direction(sample, n, p) {
+                    Direction::Left => left,
+                    Direction::Right => right,
+                },
+                MixedNode::Categories {
+                    feature,
+                    left_categories,
+                    right_categories,
+                    left,
+                    right,
+                } => {
+                    let category = sample.categorical[*feature];
+                    if left_categories.binary_search(&category).is_ok() {
+                        left
+                    } else if right_categories.binary_search(&category).is_ok() {
+                        right
+                    } else {
+                        // unseen category
+                        return (depth + 1) as f64;
+                    }
+                }
+            };
+            depth += 1;
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for MixedTree<T, N> {
+    /// Dismantle the tree iteratively, the default recursive drop could overflow the stack
+    /// for deep trees.
+    fn drop(&mut self) {
+        let mut stack = vec![std::mem::replace(
+            &mut self.root,
+            MixedNode::Ex { num_samples: 0 },
+        )];
+        while let Some(node) = stack.pop() {
+            match node {
+                MixedNode::Ex { .. } => (),
+                MixedNode::Hyperplane { left, right, .. }
+                | MixedNode::Categories { left, right, .. } => {
+                    stack.push(*left);
+                    stack.push(*right);
+                }
+            }
+        }
+    }
+}
+
+/// Build a tree without recursion, like `make_tree` does for [`Forest`](crate::Forest).
+fn make_mixed_tree<'de, T, const N: usize, const C: usize>(
+    samples: &mut [&MixedSample<T, N, C>],
+    rng: &mut TreeRng,
+    halton: &mut Option<Halton>,
+    max_tree_depth: usize,
+    extension_level: usize,
+) -> MixedNode<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    let mut steps = vec![MixedBuildStep::Build {
+        start: 0,
+        end: samples.len(),
+        depth: 0,
+    }];
+    let mut built = vec![];
+
+    while let Some(step) = steps.pop() {
+        match step {
+            MixedBuildStep::Build { start, end, depth } => {
+                let node_samples = &mut samples[start..end];
+                let split = if depth >= max_tree_depth || node_samples.len() <= 1 {
+                    None
+                } else {
+                    choose_split(node_samples, rng, halton, extension_level)
+                };
+                let Some(split) = split else {
+                    built.push(MixedNode::Ex {
+                        num_samples: node_samples.len(),
+                    });
+                    continue;
+                };
+
+                let num_left = match &split {
+                    MixedSplit::Hyperplane { n, p } => partition(node_samples, n, p),
+                    MixedSplit::Categories {
+                        feature,
+                        left_categories,
+                        ..
+                    } => partition_categories(node_samples, *feature, left_categories),
+                };
+                let split_index = start + num_left;
+
+                // The left subtree is built first, the join step finds it below the
+                // right subtree.
+                steps.push(MixedBuildStep::Join(split));
+                steps.push(MixedBuildStep::Build {
+                    start: split_index,
+                    end,
+                    depth: depth + 1,
+                });
+                steps.push(MixedBuildStep::Build {
+                    start,
+                    end: split_index,
+                    depth: depth + 1,
+                });
+            }
+            MixedBuildStep::Join(split) => {
+                let right = Box::new(built.pop().expect("right subtree has been built"));
+                let left = Box::new(built.pop().expect("left subtree has been built"));
+                built.push(match split {
+                    MixedSplit::Hyperplane { n, p } => MixedNode::Hyperplane { n, p, left, right },
+                    MixedSplit::Categories {
+                        feature,
+                        left_categories,
+                        right_categories,
+                    } => MixedNode::Categories {
+                        feature,
+                        left_categories,
+                        right_categories,
+                        left,
+                        right,
+                    },
+                });
+            }
+        }
+    }
+    built.pop().expect("root has been built")
+}
+
+/// Choose the split of a node. A random feature decides between a hyperplane and a categorical
+/// split. Categorical features with a single category at the node can not split it, in which
+/// case another categorical feature or a hyperplane is used.
+///
+/// Returns `None` when the node can not be split.
+fn choose_split<'de, T, const N: usize, const C: usize>(
+    samples: &[&MixedSample<T, N, C>],
+    rng: &mut TreeRng,
+    halton: &mut Option<Halton>,
+    extension_level: usize,
+) -> Option<MixedSplit<T, N>>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    let feature = rng.gen_range(0..N + C);
+    if feature >= N {
+        let mut features: Vec<usize> = (0..C).collect();
+        features.shuffle(rng);
+        // prefer the randomly chosen feature
+        features.sort_by_key(|candidate| *candidate != feature - N);
+
+        for feature in features {
+            let mut categories: Vec<u32> = samples
+                .iter()
+                .map(|sample| sample.categorical[feature])
+                .collect();
+            categories.sort_unstable();
+            categories.dedup();
+            if categories.len() < 2 {
+                continue;
+            }
+
+            categories.shuffle(rng);
+            let num_left = rng.gen_range(1..categories.len());
+            let mut right_categories = categories.split_off(num_left);
+            categories.sort_unstable();
+            right_categories.sort_unstable();
+            return Some(MixedSplit::Categories {
+                feature,
+                left_categories: categories,
+                right_categories,
+            });
+        }
+    }
+    if N == 0 {
+        return None;
+    }
+    let (n, p) = make_hyperplane(samples, rng, halton, extension_level, None);
+    Some(MixedSplit::Hyperplane { n, p })
+}
+
+/// Move the samples with a category in `left_categories` to the front. Returns their number.
+fn partition_categories<T, const N: usize, const C: usize>(
+    samples: &mut [&MixedSample<T, N, C>],
+    feature: usize,
+    left_categories: &[u32],
+) -> usize {
+    let mut split = 0;
+    for i in 0..samples.len() {
+        if left_categories
+            .binary_search(&samples[i].categorical[feature])
+            .is_ok()
+        {
+            samples.swap(i, split);
+            split += 1;
+        }
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MixedForest, MixedSample};
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn categories_are_split() {
+        // only categorical features
+        let samples: Vec<_> = (0..300)
+            .map(|i| MixedSample {
+                continuous: [],
+                categorical: [if i == 0 { 9 } else { i % 3 }, i % 2],
+            })
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let forest: MixedForest<f64, 0, 2> = MixedForest::from_slice(&samples, &options).unwrap();
+        let rare = forest.score(&MixedSample {
+            continuous: [],
+            categorical: [9, 0],
+        });
+        let common = forest.score(&MixedSample {
+            continuous: [],
+            categorical: [1, 0],
+        });
+        assert!(rare > common);
+
+        assert!(matches!(
+            MixedForest::<f64, 0, 0>::from_slice(&[], &options),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+
+    #[test]
+    fn continuous_and_categorical_features() {
+        let samples: Vec<_> = (0..400)
+            .map(|i| MixedSample {
+                continuous: [(i % 20) as f64, (i / 20) as f64],
+                categorical: [i % 4],
+            })
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 128,
+            extension_level: 1.into(),
+            ..Default::default()
+        };
+        let forest = MixedForest::from_slice(&samples, &options).unwrap();
+
+        let normal = forest.score(&MixedSample {
+            continuous: [10.0, 10.0],
+            categorical: [2],
+        });
+        assert!(
+            forest.score(&MixedSample {
+                continuous: [100.0, 10.0],
+                categorical: [2],
+            }) > normal
+        );
+        assert!(
+            forest.score(&MixedSample {
+                continuous: [10.0, 10.0],
+                categorical: [42],
+            }) > normal
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Training from memory-mapped datasets, available with the `mmap` feature.
+//!
+//! Datasets larger than the main memory can be stored in a packed binary file: the rows follow
+//! each other without any header or padding, every row consisting of its `N` features as
+//! little-endian `f32` or `f64` values. The file is mapped into memory and the forest reads the
+//! rows it samples directly from the mapping, so the dataset is never copied.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//! use extended_isolation_forest::mmap::{write_packed, MmapDataset};
+//!
+//! let path = std::env::temp_dir().join(format!("eif-doc-{}.bin", std::process::id()));
+//! let values: Vec<[f64; 2]> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! write_packed(&path, &values).unwrap();
+//!
+//! let dataset: MmapDataset<f64, 2> = MmapDataset::open(&path).unwrap();
+//! let forest = Forest::from_mmap(&dataset, &ForestOptions::default()).unwrap();
+//! assert!(forest.score(&[50.0, 50.0]) > forest.score(&[5.0, 5.0]));
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use memmap2::Mmap;
+use num_traits::Float;
+use rand::{distributions::uniform::SampleUniform, seq::index};
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Floats which can be stored in packed datasets.
+pub trait PackedFloat: Float {
+    /// Number of bytes of a stored value.
+    const SIZE: usize;
+
+    /// Read a value from exactly `SIZE` little-endian bytes.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Append the little-endian bytes of the value.
+    fn write_le(self, writer: &mut impl Write) -> std::io::Result<()>;
+}
+
+impl PackedFloat for f32 {
+    const SIZE: usize = 4;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(bytes);
+        f32::from_le_bytes(buf)
+    }
+
+    fn write_le(self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl PackedFloat for f64 {
+    const SIZE: usize = 8;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        f64::from_le_bytes(buf)
+    }
+
+    fn write_le(self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+/// A packed dataset mapped into memory.
+pub struct MmapDataset<T, const N: usize> {
+    mmap: Mmap,
+    num_rows: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T, const N: usize> MmapDataset<T, N>
+where
+    T: PackedFloat,
+{
+    /// Map the packed dataset in the file into memory.
+    ///
+    /// The file must not be modified while it is mapped, the rows would change while training.
+    /// Files whose size is not a multiple of the size of a row are rejected.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(Error::Io)?;
+        // SAFETY: the mapping is read-only, modifying the file while it is mapped is documented
+        // as not allowed
+        let mmap = unsafe { Mmap::map(&file) }.map_err(Error::Io)?;
+
+        let row_size = N * T::SIZE;
+        if row_size == 0 || mmap.len() % row_size != 0 {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "file size of {} bytes is not a multiple of the row size of {} bytes",
+                    mmap.len(),
+                    row_size
+                ),
+            )));
+        }
+        Ok(Self {
+            num_rows: mmap.len() / row_size,
+            mmap,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Number of rows of the dataset.
+    pub fn len(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_rows == 0
+    }
+
+    /// The row with the index `i`, read from the mapping without copying.
+    pub fn row(&self, i: usize) -> Option<PackedRow<'_, T, N>> {
+        let row_size = N * T::SIZE;
+        (i < self.num_rows).then(|| PackedRow {
+            bytes: &self.mmap[i * row_size..(i + 1) * row_size],
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// A row of a packed dataset.
+#[derive(Clone, Copy)]
+pub struct PackedRow<'a, T, const N: usize> {
+    bytes: &'a [u8],
+    _marker: PhantomData<T>,
+}
+
+impl<T, const N: usize> SampleAccess<T, N> for PackedRow<'_, T, N>
+where
+    T: PackedFloat,
+{
+    #[inline]
+    fn feature(&self, i: usize) -> T {
+        T::from_le_slice(&self.bytes[i * T::SIZE..(i + 1) * T::SIZE])
+    }
+}
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + PackedFloat + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the rows of a memory-mapped dataset.
+    pub fn from_mmap(dataset: &MmapDataset<T, N>, options: &ForestOptions) -> Result<Self, Error> {
+        let num_rows = dataset.len();
+        Self::train(
+            num_rows,
+            |i| dataset.row(i).expect("sampled rows are within the dataset"),
+            |rng| index::sample(rng, num_rows, options.sample_size).into_vec(),
+            options,
+        )
+    }
+}
+
+/// Write the samples to a file in the packed format read by [`MmapDataset`].
+pub fn write_packed<T, S, P, const N: usize>(path: P, samples: &[S]) -> Result<(), Error>
+where
+    T: PackedFloat,
+    S: SampleAccess<T, N>,
+    P: AsRef<Path>,
+{
+    let mut writer = BufWriter::new(File::create(path).map_err(Error::Io)?);
+    for sample in samples {
+        for i in 0..N {
+            sample.feature(i).write_le(&mut writer).map_err(Error::Io)?;
+        }
+    }
+    writer.flush().map_err(Error::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_packed, MmapDataset};
+    use crate::{Error, Forest, ForestOptions, SampleAccess};
+
+    #[test]
+    fn train_from_packed_file() {
+        let path = std::env::temp_dir().join(format!("eif-mmap-{}.bin", std::process::id()));
+        let values: Vec<[f32; 3]> = (0..400)
+            .map(|i| [(i % 20) as f32, (i / 20) as f32, 0.5])
+            .collect();
+        write_packed(&path, &values).unwrap();
+
+        let dataset: MmapDataset<f32, 3> = MmapDataset::open(&path).unwrap();
+        assert_eq!(dataset.len(), 400);
+        assert_eq!(dataset.row(21).unwrap().to_array(), [1.0, 1.0, 0.5]);
+        assert!(dataset.row(400).is_none());
+
+        let forest = Forest::from_mmap(&dataset, &ForestOptions::default()).unwrap();
+        assert!(forest.score(&[100.0, 100.0, 0.5]) > forest.score(&[10.0, 10.0, 0.5]));
+
+        // a partial row at the end
+        assert!(matches!(
+            MmapDataset::<f32, 7>::open(&path),
+            Err(Error::Io(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
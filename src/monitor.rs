@@ -0,0 +1,253 @@
+//! Streaming anomaly monitoring with pluggable metrics.
+//!
+//! An [`AnomalyMonitor`] scores a stream of samples against a threshold and reports every scored
+//! sample and raised alert to a [`MetricsSink`]. Sinks take `&self`, so they can forward to the
+//! (usually atomic) counters of a metrics system and the monitor can be shared between threads.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//! use extended_isolation_forest::monitor::{AnomalyMonitor, CountingSink};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let monitor = AnomalyMonitor::with_sink(forest, 0.6, CountingSink::default());
+//! monitor.observe(&[4.0, 5.0]);
+//! monitor.observe(&[40.0, -30.0]);
+//!
+//! assert_eq!(monitor.sink().samples_scored(), 2);
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Forest, SampleAccess};
+
+/// Receives the metrics of an [`AnomalyMonitor`].
+pub trait MetricsSink {
+    /// Called for every scored sample.
+    fn sample_scored(&self, score: f64);
+
+    /// Called for every sample with a score above the threshold of the monitor.
+    fn alert_raised(&self, score: f64);
+}
+
+/// Discards all metrics.
+impl MetricsSink for () {
+    fn sample_scored(&self, _score: f64) {}
+
+    fn alert_raised(&self, _score: f64) {}
+}
+
+/// Prometheus-style counters and a cumulative histogram of the scores.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CountingSink {
+    samples_scored: AtomicU64,
+    alerts_raised: AtomicU64,
+
+    /// Sum of all scores, stored as the bits of a `f64`.
+    score_sum: AtomicU64,
+
+    /// Upper bounds of the histogram buckets in ascending order.
+    bucket_bounds: Vec<f64>,
+    bucket_counts: Vec<AtomicU64>,
+}
+
+impl CountingSink {
+    /// Create a sink using the given upper bounds for the histogram buckets.
+    ///
+    /// The bounds get sorted. Scores larger than the largest bound are only counted in
+    /// `samples_scored`, which corresponds to the implicit `+Inf` bucket of prometheus.
+    pub fn new(mut bucket_bounds: Vec<f64>) -> Self {
+        bucket_bounds.sort_by(|a, b| a.total_cmp(b));
+        let bucket_counts = bucket_bounds.iter().map(|_| AtomicU64::new(0)).collect();
+        Self {
+            samples_scored: AtomicU64::new(0),
+            alerts_raised: AtomicU64::new(0),
+            score_sum: AtomicU64::new(0.0_f64.to_bits()),
+            bucket_bounds,
+            bucket_counts,
+        }
+    }
+
+    pub fn samples_scored(&self) -> u64 {
+        self.samples_scored.load(Ordering::Relaxed)
+    }
+
+    pub fn alerts_raised(&self) -> u64 {
+        self.alerts_raised.load(Ordering::Relaxed)
+    }
+
+    pub fn score_sum(&self) -> f64 {
+        f64::from_bits(self.score_sum.load(Ordering::Relaxed))
+    }
+
+    /// The histogram as pairs of upper bound and cumulative count.
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.bucket_bounds
+            .iter()
+            .zip(self.bucket_counts.iter())
+            .map(|(bound, count)| (*bound, count.load(Ordering::Relaxed)))
+    }
+}
+
+impl Default for CountingSink {
+    /// Buckets in steps of 0.1 from 0.1 to 1.0.
+    fn default() -> Self {
+        Self::new((1..=10).map(|i| i as f64 / 10.0).collect())
+    }
+}
+
+impl MetricsSink for CountingSink {
+    fn sample_scored(&self, score: f64) {
+        self.samples_scored.fetch_add(1, Ordering::Relaxed);
+
+        let mut current = self.score_sum.load(Ordering::Relaxed);
+        while let Err(actual) = self.score_sum.compare_exchange_weak(
+            current,
+            (f64::from_bits(current) + score).to_bits(),
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            current = actual;
+        }
+
+        self.bucket_bounds
+            .iter()
+            .zip(self.bucket_counts.iter())
+            .filter(|(bound, _)| score <= **bound)
+            .for_each(|(_, count)| {
+                count.fetch_add(1, Ordering::Relaxed);
+            });
+    }
+
+    fn alert_raised(&self, _score: f64) {
+        self.alerts_raised.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Result of observing a sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observation {
+    pub score: f64,
+
+    /// The score exceeded the threshold of the monitor.
+    pub is_anomaly: bool,
+}
+
+/// Scores samples against a threshold and reports to a [`MetricsSink`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize, M: Serialize",
+        deserialize = "T: Deserialize<'de> + Float, M: Deserialize<'de>"
+    ))
+)]
+pub struct AnomalyMonitor<T, const N: usize, M = ()> {
+    forest: Forest<T, N>,
+    threshold: f64,
+    sink: M,
+}
+
+impl<T, const N: usize> AnomalyMonitor<T, N, ()>
+where
+    T: Float,
+{
+    /// Create a monitor without metrics.
+    pub fn new(forest: Forest<T, N>, threshold: f64) -> Self {
+        Self::with_sink(forest, threshold, ())
+    }
+}
+
+impl<T, const N: usize, M> AnomalyMonitor<T, N, M>
+where
+    T: Float,
+    M: MetricsSink,
+{
+    /// Create a monitor reporting to `sink`. Samples with a score above `threshold` raise an alert.
+    pub fn with_sink(forest: Forest<T, N>, threshold: f64, sink: M) -> Self {
+        Self {
+            forest,
+            threshold,
+            sink,
+        }
+    }
+
+    /// Score a sample and report it to the sink.
+    pub fn observe<S>(&self, values: &S) -> Observation
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let score = self.forest.score(values);
+        self.sink.sample_scored(score);
+
+        let is_anomaly = score > self.threshold;
+        if is_anomaly {
+            self.sink.alert_raised(score);
+        }
+        Observation { score, is_anomaly }
+    }
+
+    pub fn forest(&self) -> &Forest<T, N> {
+        &self.forest
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn sink(&self) -> &M {
+        &self.sink
+    }
+
+    pub fn into_inner(self) -> (Forest<T, N>, M) {
+        (self.forest, self.sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnomalyMonitor, CountingSink, MetricsSink};
+    use crate::eif::{EifForest, EifNode};
+    use crate::Forest;
+
+    #[test]
+    fn counting_sink_buckets() {
+        let sink = CountingSink::new(vec![0.5, 0.25, 1.0]);
+        [0.1, 0.3, 0.3, 0.7, 1.5]
+            .iter()
+            .for_each(|score| sink.sample_scored(*score));
+
+        assert_eq!(sink.samples_scored(), 5);
+        assert!((sink.score_sum() - 2.9).abs() < 1e-9);
+        assert_eq!(
+            sink.buckets().collect::<Vec<_>>(),
+            vec![(0.25, 1), (0.5, 3), (1.0, 4)]
+        );
+    }
+
+    #[test]
+    fn monitor_raises_alerts() {
+        let forest: Forest<f64, 1> = Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0],
+                p: vec![5.0],
+                left: Box::new(EifNode::ExNode { size: 7 }),
+                right: Box::new(EifNode::ExNode { size: 1 }),
+            }],
+        })
+        .unwrap();
+        let monitor = AnomalyMonitor::with_sink(forest, 0.6, CountingSink::default());
+
+        assert!(!monitor.observe(&[1.0]).is_anomaly);
+        assert!(monitor.observe(&[10.0]).is_anomaly);
+        assert_eq!(monitor.sink().samples_scored(), 2);
+        assert_eq!(monitor.sink().alerts_raised(), 1);
+    }
+}
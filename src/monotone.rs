@@ -0,0 +1,128 @@
+//! Dimensions in which only one direction is anomalous.
+//!
+//! Isolation forests flag a latency far below the usual values as readily as one far above,
+//! although only high latencies are a problem. A [`Monotone`] constraint declares the side of a
+//! baseline on which values can be anomalous. Values on the other side are moved to the
+//! baseline before scoring and explaining, so they neither raise the score nor show up in the
+//! contributions of an [`Explanation`](crate::explain::Explanation).
+//!
+//! ```rust
+//! use extended_isolation_forest::monotone::Monotone;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! forest.set_monotone_constraints([Monotone::Both, Monotone::Above(4.5)]);
+//! assert_eq!(forest.score(&[4.5, -100.0]), forest.score(&[4.5, 4.5]));
+//! assert!(forest.score(&[4.5, 100.0]) > forest.score(&[4.5, 4.5]));
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::{Forest, SampleAccess};
+
+/// The side of a baseline on which the values of a dimension can be anomalous.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Monotone<T> {
+    /// Values on both sides can be anomalous.
+    #[default]
+    Both,
+
+    /// Only values above the baseline can be anomalous.
+    Above(T),
+
+    /// Only values below the baseline can be anomalous.
+    Below(T),
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The constraint of every dimension, [`Monotone::Both`] unless set.
+    pub fn monotone_constraints(&self) -> [Monotone<T>; N] {
+        std::array::from_fn(|i| self.monotone.get(i).copied().unwrap_or_default())
+    }
+
+    /// Set the constraint of every dimension. The constraints are stored with the forest and
+    /// apply to [`Forest::score`] and [`Forest::explain`], before the range policy.
+    pub fn set_monotone_constraints(&mut self, constraints: [Monotone<T>; N]) {
+        self.monotone = if constraints.iter().all(|c| *c == Monotone::Both) {
+            Vec::new()
+        } else {
+            constraints.to_vec()
+        };
+    }
+
+    /// The sample with the values on the safe side of the baselines moved to the baselines,
+    /// `None` without constraints.
+    pub(crate) fn apply_monotone_constraints<S>(&self, sample: &S) -> Option<[T; N]>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        if self.monotone.is_empty() {
+            return None;
+        }
+        let mut adjusted = sample.to_array();
+        for (value, constraint) in adjusted.iter_mut().zip(self.monotone.iter()) {
+            // missing values are left to the trees
+            match *constraint {
+                Monotone::Both => {}
+                Monotone::Above(baseline) if *value < baseline => *value = baseline,
+                Monotone::Below(baseline) if *value > baseline => *value = baseline,
+                Monotone::Above(_) | Monotone::Below(_) => {}
+            }
+        }
+        Some(adjusted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Monotone;
+    use crate::envelope::RangePolicy;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn safe_direction_does_not_raise_scores() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let low = [-100.0, 10.0];
+        let unconstrained = forest.score(&low);
+
+        forest.set_monotone_constraints([Monotone::Above(10.0), Monotone::Both]);
+        assert_eq!(
+            forest.monotone_constraints(),
+            [Monotone::Above(10.0), Monotone::Both]
+        );
+        assert_eq!(forest.score(&low), forest.score(&[10.0, 10.0]));
+        assert!(forest.score(&low) < unconstrained);
+        assert!(forest.score(&[100.0, 10.0]) > forest.score(&[10.0, 10.0]));
+        assert!(forest.score(&[f64::NAN, 10.0]).is_finite());
+
+        let explanation = forest.explain(&[-100.0, 100.0]);
+        assert!(explanation.contributions[1] > explanation.contributions[0]);
+        assert_eq!(explanation.score, forest.score(&[-100.0, 100.0]));
+
+        forest.set_monotone_constraints([Monotone::Below(10.0), Monotone::Below(0.0)]);
+        forest.set_range_policy(RangePolicy::Clamp);
+        assert_eq!(forest.score(&[100.0, -50.0]), forest.score(&[10.0, 0.0]));
+
+        forest.set_monotone_constraints([Monotone::Both; 2]);
+        forest.set_range_policy(RangePolicy::Ignore);
+        assert_eq!(forest.score(&low), unconstrained);
+    }
+}
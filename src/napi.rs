@@ -0,0 +1,145 @@
+//! Node.js bindings with napi-rs, available with the `napi` feature.
+//!
+//! Built as a `cdylib` with the feature enabled, the library is a Node.js addon exporting
+//! `trainForest(rows, options)`, `loadFromBuffer(buffer, dimensions)` and the class `Forest`
+//! with `score(rows)`, `toBuffer()` and `dimensions`. The samples are arrays of numbers, the
+//! buffers contain forests in the binary format or as JSON like [`DynForest::from_bytes`]. The
+//! package in the `node` directory builds and loads the addon.
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::dynamic::DynForest;
+use crate::{Error, ExtensionLevel, ForestOptions};
+
+/// The options of `trainForest`, missing options keep the defaults of [`ForestOptions`].
+#[napi(object)]
+pub struct TrainOptions {
+    pub n_trees: Option<u32>,
+    pub sample_size: Option<u32>,
+    pub extension_level: Option<u32>,
+    pub seed: Option<i64>,
+}
+
+/// A forest of any number of dimensions, exported as the class `Forest`.
+#[napi(js_name = "Forest")]
+pub struct JsForest {
+    forest: DynForest,
+}
+
+#[napi]
+impl JsForest {
+    /// The scores of the samples, which must have the features of the forest.
+    #[napi]
+    pub fn score(&self, rows: Vec<Vec<f64>>) -> napi::Result<Vec<f64>> {
+        let data = concat(&rows, self.forest.dimensions())?;
+        Ok(self.forest.score_rows(&data)?)
+    }
+
+    /// The forest in the binary format, for `loadFromBuffer`.
+    #[napi]
+    pub fn to_buffer(&self) -> napi::Result<Buffer> {
+        Ok(self.forest.to_binary()?.into())
+    }
+
+    /// The number of features of the samples.
+    #[napi(getter)]
+    pub fn dimensions(&self) -> u32 {
+        self.forest.dimensions() as u32
+    }
+}
+
+/// Train a forest on the samples, which must all have the same number of features.
+#[napi(js_name = "trainForest")]
+pub fn train_forest(rows: Vec<Vec<f64>>, options: Option<TrainOptions>) -> napi::Result<JsForest> {
+    let (data, dimensions) = flatten(&rows)?;
+    let options = options.map(forest_options).transpose()?.unwrap_or_default();
+    let forest = DynForest::from_rows(&data, dimensions, &options)?;
+    Ok(JsForest { forest })
+}
+
+/// Load a forest from the binary format or from JSON, which requires the dimensions.
+#[napi(js_name = "loadFromBuffer")]
+pub fn load_from_buffer(buffer: Buffer, dimensions: Option<u32>) -> napi::Result<JsForest> {
+    let dimensions = dimensions.map(|dimensions| dimensions as usize);
+    let forest = DynForest::from_bytes(&buffer, dimensions)?;
+    Ok(JsForest { forest })
+}
+
+fn forest_options(options: TrainOptions) -> napi::Result<ForestOptions> {
+    let defaults = ForestOptions::default();
+    let seed = options
+        .seed
+        .map(|seed| {
+            u64::try_from(seed)
+                .map_err(|_| napi::Error::from_reason(format!("invalid seed {}", seed)))
+        })
+        .transpose()?;
+    Ok(ForestOptions {
+        n_trees: options.n_trees.map_or(defaults.n_trees, |n| n as usize),
+        sample_size: options
+            .sample_size
+            .map_or(defaults.sample_size, |n| n as usize),
+        extension_level: options
+            .extension_level
+            .map_or(defaults.extension_level, |level| {
+                ExtensionLevel::Fixed(level as usize)
+            }),
+        seed,
+        ..defaults
+    })
+}
+
+/// The rows one after the other and their number of features.
+fn flatten(rows: &[Vec<f64>]) -> Result<(Vec<f64>, usize), Error> {
+    let dimensions = rows.first().map(|row| row.len()).ok_or(Error::EmptyData)?;
+    Ok((concat(rows, dimensions)?, dimensions))
+}
+
+/// The rows one after the other, which must all have `dimensions` features.
+fn concat(rows: &[Vec<f64>], dimensions: usize) -> Result<Vec<f64>, Error> {
+    if let Some(row) = rows.iter().find(|row| row.len() != dimensions) {
+        return Err(Error::DimensionMismatch {
+            expected: dimensions,
+            actual: row.len(),
+        });
+    }
+    Ok(rows.concat())
+}
+
+impl From<Error> for napi::Error {
+    fn from(err: Error) -> Self {
+        napi::Error::from_reason(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{concat, flatten};
+    use crate::Error;
+
+    #[test]
+    fn flatten_rows() {
+        let (data, dimensions) = flatten(&[vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap();
+        assert_eq!(data, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(dimensions, 2);
+        assert!(matches!(flatten(&[]), Err(Error::EmptyData)));
+        assert!(matches!(
+            flatten(&[vec![1.0, 2.0], vec![3.0]]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+
+        // rows of a multiple of the features are not split into several samples
+        assert!(matches!(
+            concat(&[vec![1.0, 2.0, 3.0, 4.0]], 2),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 4
+            })
+        ));
+        assert_eq!(concat(&[], 2).unwrap(), Vec::<f64>::new());
+    }
+}
@@ -0,0 +1,90 @@
+use std::cmp::Ordering;
+
+use num_traits::Float;
+
+use crate::{Forest, SampleAccess};
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Up to `k` training samples resembling the sample, nearest first.
+    ///
+    /// The candidates are the training samples retained at the external nodes the sample reaches
+    /// in the trees, which are ordered by their euclidean distance to the sample. Returns no
+    /// samples unless the forest has been trained with `ForestOptions::leaf_samples`.
+    pub fn nearest_training_examples<S>(&self, sample: &S, k: usize) -> Vec<[T; N]>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let values = sample.to_array();
+        let mut candidates: Vec<_> = self
+            .trees
+            .iter()
+            .flat_map(|tree| tree.leaf(sample).samples.iter())
+            .map(|candidate| {
+                let distance = values
+                    .iter()
+                    .zip(candidate)
+                    .fold(T::zero(), |sum, (a, b)| sum + (*a - *b) * (*a - *b));
+                (distance, *candidate)
+            })
+            .collect();
+
+        // the same training sample can be retained in several trees
+        candidates.sort_by(|(a_distance, a), (b_distance, b)| {
+            a_distance
+                .partial_cmp(b_distance)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| {
+                    a.iter()
+                        .zip(b)
+                        .map(|(a, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                        .find(|ordering| ordering.is_ne())
+                        .unwrap_or(Ordering::Equal)
+                })
+        });
+        candidates.dedup_by(|(_, a), (_, b)| a == b);
+
+        candidates
+            .into_iter()
+            .take(k)
+            .map(|(_, candidate)| candidate)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn nearest_training_examples_from_leaves() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+        assert!(forest.nearest_training_examples(&[3.0, 4.0], 5).is_empty());
+
+        let forest: Forest<f64, 2> = Forest::from_slice(
+            values.as_slice(),
+            &ForestOptions {
+                n_trees: 50,
+                sample_size: 100,
+                leaf_samples: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let examples = forest.nearest_training_examples(&[3.2, 4.1], 5);
+        assert_eq!(examples.len(), 5);
+        assert!(examples.iter().all(|example| values.contains(example)));
+        assert!(examples.windows(2).all(|pair| pair[0] != pair[1]));
+
+        // the nearest candidate is close to the sample
+        let [x, y] = examples[0];
+        assert!((x - 3.2).abs() <= 3.0 && (y - 4.1).abs() <= 3.0);
+    }
+}
@@ -0,0 +1,101 @@
+//! Scores on other scales than the bounded anomaly score.
+//!
+//! [`Forest::score`] maps the mean path length of a sample to `(0, 1]`, compressing the
+//! differences between clearly normal samples into a narrow band below `0.5`. Statistical tests
+//! downstream often work better on the path lengths themselves, or on the unbounded log-odds of
+//! the score. [`Forest::score_as`] returns the scale selected by a [`ScoreMode`].
+//!
+//! ```rust
+//! use extended_isolation_forest::normalization::ScoreMode;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let sample = [50.0, 50.0];
+//! let path_length = forest.score_as(&sample, ScoreMode::PathLength);
+//! assert!(path_length < forest.score_as(&[5.0, 5.0], ScoreMode::PathLength));
+//! assert_eq!(forest.score_as(&sample, ScoreMode::Normalized), forest.score(&sample));
+//! assert!(forest.score_as(&sample, ScoreMode::LogOdds) > 0.0);
+//! ```
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{Forest, SampleAccess};
+
+/// The scale of the values returned by [`Forest::score_as`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ScoreMode {
+    /// The mean path length `E[h]` of the sample over all trees, smaller for anomalies.
+    PathLength,
+
+    /// The anomaly score `2^(-E[h] / c)` in `(0, 1]` of [`Forest::score`], where `c` is the
+    /// average path length of the sample size.
+    #[default]
+    Normalized,
+
+    /// The log-odds `ln(s / (1 - s))` of the anomaly score `s`, zero for a score of `0.5` and
+    /// infinite for samples isolated at the root.
+    LogOdds,
+}
+
+impl ScoreMode {
+    /// The value on this scale of a sample with the mean path length and anomaly score.
+    pub(crate) fn convert(self, mean_path_length: f64, score: f64) -> f64 {
+        match self {
+            Self::PathLength => mean_path_length,
+            Self::Normalized => score,
+            Self::LogOdds => (score / (1.0 - score)).ln(),
+        }
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Score the sample like [`Forest::score`], returning the value on the scale of `mode`.
+    pub fn score_as<S>(&self, values: &S, mode: ScoreMode) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.score_with_mode(values, self.recursion_cap(), mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScoreMode;
+    use crate::{c_factor, Forest, ForestOptions};
+
+    #[test]
+    fn modes_are_consistent() {
+        let values: Vec<_> = (0..100)
+            .map(|i| [(i % 10) as f64, (i / 10) as f64])
+            .collect();
+        let options = ForestOptions {
+            sample_size: 64,
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let c = c_factor(64);
+
+        for strict_fp in [false, true] {
+            forest.set_strict_fp(strict_fp);
+            for sample in [[1.0, 2.0], [4.5, 4.5], [-30.0, 100.0]] {
+                let score = forest.score(&sample);
+                let path_length = forest.score_as(&sample, ScoreMode::PathLength);
+                assert!((2.0f64.powf(-path_length / c) - score).abs() < 1e-12);
+                assert_eq!(forest.score_as(&sample, ScoreMode::default()), score);
+                let log_odds = forest.score_as(&sample, ScoreMode::LogOdds);
+                assert!((1.0 / (1.0 + (-log_odds).exp()) - score).abs() < 1e-12);
+            }
+        }
+        assert!(
+            forest.score_as(&[-30.0, 100.0], ScoreMode::PathLength)
+                < forest.score_as(&[4.5, 4.5], ScoreMode::PathLength)
+        );
+    }
+}
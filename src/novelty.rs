@@ -0,0 +1,216 @@
+//! Novelty detection on clean training data.
+//!
+//! Isolation forests are used in two ways which call for different thresholds:
+//!
+//! * In outlier detection the training data itself is searched for anomalies. It is
+//!   contaminated, the forest is built from it and thresholds are calibrated on the scores of
+//!   the training samples, for example with [`Forest::fit_buckets`]. This is what a plain
+//!   [`Forest`] does.
+//! * In novelty detection the forest is fitted on data known to be clean and only new samples
+//!   are scored. Training samples tend to score lower than new samples from the same
+//!   distribution, as the trees were built around them, so their scores must not be mixed with
+//!   those of new data when calibrating thresholds.
+//!
+//! A [`NoveltyDetector`] makes novelty detection explicit: it remembers a fingerprint of every
+//! training sample and refuses to score them, or counts them with
+//! [`TrainingSamplePolicy::Warn`].
+//!
+//! ```rust
+//! use extended_isolation_forest::novelty::NoveltyDetector;
+//! use extended_isolation_forest::{Error, ForestOptions};
+//!
+//! let clean: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let detector = NoveltyDetector::fit(&clean, &ForestOptions::default()).unwrap();
+//!
+//! assert!(detector.score(&[50.0, 50.0]).unwrap() > detector.score(&[4.5, 4.5]).unwrap());
+//! assert!(matches!(detector.score(&[3.0, 4.0]), Err(Error::TrainingSample)));
+//! ```
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::Fnv1a;
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// How a [`NoveltyDetector`] treats samples of its training data.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TrainingSamplePolicy {
+    /// Fail with [`Error::TrainingSample`].
+    #[default]
+    Refuse,
+
+    /// Score the samples, but count them, see [`NoveltyDetector::training_samples_scored`].
+    /// With the `tracing` feature a `WARN` event is emitted for every such sample.
+    Warn,
+}
+
+/// A forest fitted on clean data for scoring new samples, see the
+/// [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize",
+        deserialize = "T: Deserialize<'de> + Float"
+    ))
+)]
+pub struct NoveltyDetector<T, const N: usize> {
+    forest: Forest<T, N>,
+
+    /// Hashes of the values of all training samples.
+    fingerprints: HashSet<u64>,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    policy: TrainingSamplePolicy,
+
+    /// Training samples scored with `TrainingSamplePolicy::Warn`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    training_samples_scored: AtomicU64,
+}
+
+impl<'de, T, const N: usize> NoveltyDetector<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a forest from clean training data like [`Forest::from_slice`] and remember the
+    /// training samples.
+    pub fn fit<S>(clean_data: &[S], options: &ForestOptions) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        Ok(Self {
+            forest: Forest::from_slice(clean_data, options)?,
+            fingerprints: clean_data.iter().map(fingerprint).collect(),
+            policy: TrainingSamplePolicy::default(),
+            training_samples_scored: AtomicU64::new(0),
+        })
+    }
+}
+
+impl<T, const N: usize> NoveltyDetector<T, N>
+where
+    T: Float,
+{
+    /// The anomaly score of a new sample, see [`Forest::score`].
+    ///
+    /// Fails with [`Error::TrainingSample`] for samples of the training data unless the policy
+    /// is [`TrainingSamplePolicy::Warn`]. Samples are recognized by a 64 bit hash of their
+    /// values, so with the usual tiny probability of collisions a new sample is refused as well.
+    pub fn score<S>(&self, sample: &S) -> Result<f64, Error>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        if self.is_training_sample(sample) {
+            match self.policy {
+                TrainingSamplePolicy::Refuse => return Err(Error::TrainingSample),
+                TrainingSamplePolicy::Warn => {
+                    self.training_samples_scored.fetch_add(1, Ordering::Relaxed);
+
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("scored a training sample of a novelty detector");
+                }
+            }
+        }
+        Ok(self.forest.score(sample))
+    }
+
+    /// Whether the sample has the same values as a sample of the training data.
+    pub fn is_training_sample<S>(&self, sample: &S) -> bool
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.fingerprints.contains(&fingerprint(sample))
+    }
+
+    pub fn policy(&self) -> TrainingSamplePolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: TrainingSamplePolicy) {
+        self.policy = policy;
+    }
+
+    /// Number of training samples scored with [`TrainingSamplePolicy::Warn`] since the
+    /// detector has been fitted or loaded.
+    pub fn training_samples_scored(&self) -> u64 {
+        self.training_samples_scored.load(Ordering::Relaxed)
+    }
+
+    /// The forest, for scoring without the check for training samples.
+    pub fn forest(&self) -> &Forest<T, N> {
+        &self.forest
+    }
+
+    pub fn into_forest(self) -> Forest<T, N> {
+        self.forest
+    }
+}
+
+fn fingerprint<T, S, const N: usize>(sample: &S) -> u64
+where
+    T: Float,
+    S: SampleAccess<T, N> + ?Sized,
+{
+    let mut hasher = Fnv1a::default();
+    for i in 0..N {
+        hasher.write_float(sample.feature(i));
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NoveltyDetector, TrainingSamplePolicy};
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn novelty_detector_refuses_training_samples() {
+        let clean: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 30,
+            sample_size: 64,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let mut detector = NoveltyDetector::fit(&clean, &options).unwrap();
+        assert_eq!(detector.policy(), TrainingSamplePolicy::Refuse);
+        assert!(detector.is_training_sample(&[3.0, 7.0]));
+        assert!(detector.is_training_sample(&[-0.0, 0.0]));
+        assert!(!detector.is_training_sample(&[3.5, 7.0]));
+        assert!(matches!(
+            detector.score(&[3.0, 7.0]),
+            Err(Error::TrainingSample)
+        ));
+        assert_eq!(
+            detector.score(&[3.5, 7.0]).unwrap(),
+            detector.forest().score(&[3.5, 7.0])
+        );
+
+        detector.set_policy(TrainingSamplePolicy::Warn);
+        assert_eq!(
+            detector.score(&[3.0, 7.0]).unwrap(),
+            detector.forest().score(&[3.0, 7.0])
+        );
+        detector.score(&[100.0, 7.0]).unwrap();
+        assert_eq!(detector.training_samples_scored(), 1);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&detector).unwrap();
+            let restored: NoveltyDetector<f64, 2> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.policy(), TrainingSamplePolicy::Warn);
+            assert!(restored.is_training_sample(&[19.0, 19.0]));
+            assert_eq!(restored.training_samples_scored(), 0);
+        }
+    }
+}
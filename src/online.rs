@@ -0,0 +1,316 @@
+//! A forest following a stream, forgetting old samples with exponential decay.
+//!
+//! Concepts drift: what is normal today may not have been normal a month ago, and retraining on
+//! a fixed window treats all samples in the window equally and everything before it as
+//! forgotten. A [`DecayedForest`] keeps a subsample of the stream per tree in which the weight
+//! of a sample halves with every half-life of its age. New samples replace old ones in each
+//! subsample as they arrive, and a tree is rebuilt once a part of its subsample has been
+//! replaced, so only the affected trees are rebuilt and scoring continues meanwhile.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use extended_isolation_forest::online::DecayedForest;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let mut forest = DecayedForest::new(ForestOptions::default(), Duration::from_secs(600)).unwrap();
+//! for t in 0..1000 {
+//!     forest.observe(t, [(t % 10) as f64]).unwrap();
+//! }
+//! assert!(forest.score(&[100.0]).unwrap() > forest.score(&[5.0]).unwrap());
+//! ```
+
+use std::time::Duration;
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand::Rng;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ood::Bounds;
+use crate::provenance::TreeProvenance;
+use crate::seed::TreeRng;
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::split::{ExtendedSplit, SplitStrategy};
+use crate::{
+    constant_features, training_medians, Error, Forest, ForestFloat, ForestOptions, SampleAccess,
+    TreeBuilder,
+};
+
+/// The subsample of a tree with the sampling keys of its samples.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+struct Reservoir<T, const N: usize> {
+    keys: Vec<f64>,
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array::vec"))]
+    samples: Vec<[T; N]>,
+
+    /// Index of the sample with the smallest key, replaced next.
+    min: usize,
+
+    /// Samples replaced since the tree has been built.
+    replaced: usize,
+}
+
+impl<T, const N: usize> Reservoir<T, N> {
+    fn update_min(&mut self) {
+        self.min = (0..self.keys.len())
+            .min_by(|a, b| self.keys[*a].total_cmp(&self.keys[*b]))
+            .unwrap_or(0);
+    }
+}
+
+/// A forest trained online on a stream of timestamped samples, see the
+/// [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize",
+        deserialize = "T: Deserialize<'de> + Float"
+    ))
+)]
+pub struct DecayedForest<T, const N: usize> {
+    options: ForestOptions,
+
+    /// Decay rate per second, `ln(2)` divided by the half-life.
+    decay: f64,
+
+    /// Trees are rebuilt after this many samples of their subsample have been replaced.
+    rebuild_after: usize,
+
+    reservoirs: Vec<Reservoir<T, N>>,
+    forest: Option<Forest<T, N>>,
+}
+
+impl<'de, T, const N: usize> DecayedForest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// An empty forest with the weight of samples halving every `half_life`. Trees are rebuilt
+    /// after a tenth of their subsample has been replaced, see
+    /// [`DecayedForest::with_rebuild_after`].
+    pub fn new(options: ForestOptions, half_life: Duration) -> Result<Self, Error> {
+        Forest::<T, N>::validate_options(options.sample_size, &options)?;
+        let reservoirs = (0..options.n_trees)
+            .map(|_| Reservoir {
+                keys: Vec::with_capacity(options.sample_size),
+                samples: Vec::with_capacity(options.sample_size),
+                min: 0,
+                replaced: 0,
+            })
+            .collect();
+        Ok(Self {
+            decay: std::f64::consts::LN_2 / half_life.as_secs_f64().max(f64::MIN_POSITIVE),
+            rebuild_after: (options.sample_size / 10).max(1),
+            options,
+            reservoirs,
+            forest: None,
+        })
+    }
+
+    /// Rebuild trees after `replacements` samples of their subsample have been replaced, at
+    /// least one. Larger values rebuild less often and follow the stream with more delay.
+    pub fn with_rebuild_after(self, replacements: usize) -> Self {
+        Self {
+            rebuild_after: replacements.max(1),
+            ..self
+        }
+    }
+
+    /// Add a sample of the stream with its timestamp in seconds, returning the number of trees
+    /// rebuilt. The forest is built once `sample_size` samples have been observed.
+    ///
+    /// Every tree samples the stream independently, weighting samples by `2^(t / half_life)`
+    /// for their timestamp `t`: the subsamples are weighted samples without replacement of all
+    /// samples seen so far, drawn with the Gumbel-top-k trick.
+    pub fn observe<S>(&mut self, timestamp: u64, sample: S) -> Result<usize, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let sample = sample.to_array();
+        let rng = &mut rand::thread_rng();
+        let log_weight = self.decay * timestamp as f64;
+
+        let mut rebuild = Vec::new();
+        for (tree, reservoir) in self.reservoirs.iter_mut().enumerate() {
+            let key = log_weight + gumbel(rng);
+            if reservoir.samples.len() < self.options.sample_size {
+                reservoir.keys.push(key);
+                reservoir.samples.push(sample);
+                reservoir.update_min();
+                continue;
+            }
+            if key <= reservoir.keys[reservoir.min] {
+                continue;
+            }
+            reservoir.keys[reservoir.min] = key;
+            reservoir.samples[reservoir.min] = sample;
+            reservoir.update_min();
+            reservoir.replaced += 1;
+            if reservoir.replaced >= self.rebuild_after {
+                rebuild.push(tree);
+            }
+        }
+
+        if self.forest.is_none() {
+            if self
+                .reservoirs
+                .iter()
+                .any(|reservoir| reservoir.samples.len() < self.options.sample_size)
+            {
+                return Ok(0);
+            }
+            let rows: Vec<&[T; N]> = self
+                .reservoirs
+                .iter()
+                .flat_map(|reservoir| reservoir.samples.iter())
+                .collect();
+            let sample_size = self.options.sample_size;
+            let mut next_tree = 0;
+            self.forest = Some(Forest::train(
+                rows.len(),
+                |i| rows[i],
+                |_| {
+                    let start = next_tree * sample_size;
+                    next_tree += 1;
+                    (start..start + sample_size).collect()
+                },
+                &self.options,
+            )?);
+            self.reservoirs
+                .iter_mut()
+                .for_each(|reservoir| reservoir.replaced = 0);
+            return Ok(self.reservoirs.len());
+        }
+
+        let Some(forest) = self.forest.as_mut() else {
+            return Ok(0);
+        };
+        if rebuild.is_empty() {
+            return Ok(0);
+        }
+        let max_tree_depth = forest.recursion_cap();
+        let mut split = ExtendedSplit::new(forest.options.hyperplanes);
+        if forest.options.drop_constant_features {
+            SplitStrategy::<T, N>::exclude_features(&mut split, &forest.constant_features);
+        }
+        let mut builder = TreeBuilder::new(
+            &forest.options,
+            max_tree_depth,
+            &forest.jittered_features,
+            split,
+        );
+        for &tree in &rebuild {
+            let reservoir = &mut self.reservoirs[tree];
+            let mut samples = reservoir.samples.clone();
+            let rng = &mut TreeRng::thread();
+            let extension_level = forest.options.extension_level.sample(rng);
+            forest.trees[tree] = builder.build(tree, samples.as_mut_slice(), extension_level, rng);
+            if let Some(provenance) = forest.provenance.get_mut(tree) {
+                *provenance = TreeProvenance::now(provenance.generation, None);
+            }
+            reservoir.replaced = 0;
+        }
+        forest.training_range = Bounds::of(
+            self.reservoirs
+                .iter()
+                .flat_map(|reservoir| reservoir.samples.iter()),
+        );
+        forest.constant_features = forest
+            .training_range
+            .as_ref()
+            .map(constant_features)
+            .unwrap_or_default();
+        let samples: Vec<&[T; N]> = self
+            .reservoirs
+            .iter()
+            .flat_map(|reservoir| reservoir.samples.iter())
+            .collect();
+        forest.training_medians = training_medians(samples.len(), |i| samples[i]);
+        Ok(rebuild.len())
+    }
+}
+
+impl<T, const N: usize> DecayedForest<T, N>
+where
+    T: Float,
+{
+    /// Score the sample with the current trees. Returns `None` until the forest has been built.
+    pub fn score<S>(&self, sample: &S) -> Option<f64>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.forest.as_ref().map(|forest| forest.score(sample))
+    }
+
+    /// The current forest, `None` until `sample_size` samples have been observed.
+    pub fn forest(&self) -> Option<&Forest<T, N>> {
+        self.forest.as_ref()
+    }
+
+    /// The half-life of the weight of samples.
+    pub fn half_life(&self) -> Duration {
+        Duration::from_secs_f64(std::f64::consts::LN_2 / self.decay)
+    }
+}
+
+/// A standard Gumbel distributed value.
+fn gumbel(rng: &mut impl Rng) -> f64 {
+    // in `(0, 1]`, so the logarithms are finite
+    let unit = 1.0 - rng.gen::<f64>();
+    -(-unit.ln()).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::DecayedForest;
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn old_samples_are_forgotten() {
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 32,
+            ..Default::default()
+        };
+        let mut forest = DecayedForest::new(options, Duration::from_secs(100)).unwrap();
+        assert_eq!(forest.half_life(), Duration::from_secs(100));
+        for t in 0..31 {
+            assert_eq!(forest.observe(t, [(t % 8) as f64]).unwrap(), 0);
+        }
+        assert!(forest.score(&[1.0]).is_none());
+        assert_eq!(forest.observe(31, [7.0]).unwrap(), 50);
+
+        // the stream moves from around 0 to around 1000
+        let mut rebuilt = 0;
+        for t in 32..2000 {
+            rebuilt += forest.observe(t, [1000.0 + (t % 8) as f64]).unwrap();
+        }
+        assert!(rebuilt > 50);
+        assert!(forest.score(&[3.0]).unwrap() > forest.score(&[1003.0]).unwrap());
+        let (min, _) = forest.forest().unwrap().training_range().unwrap();
+        assert!(min[0] >= 1000.0);
+
+        assert!(matches!(
+            DecayedForest::<f64, 1>::new(
+                ForestOptions {
+                    extension_level: 1.into(),
+                    ..Default::default()
+                },
+                Duration::from_secs(1)
+            ),
+            Err(Error::ExtensionLevelExceedsDimensions)
+        ));
+    }
+}
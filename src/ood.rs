@@ -0,0 +1,177 @@
+//! Detecting samples outside of the training data of the trees.
+//!
+//! Every tree stores the bounding box of the samples it has been built from. Far outside of
+//! these boxes all samples end up in the same few leaves and the scores say little about how
+//! anomalous they are, [`Forest::score_with_ood`] reports how many trees see the sample
+//! outside of their box along with the score.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! assert_eq!(forest.score_with_ood(&[4.5, 4.5]).out_of_box, 0.0);
+//! assert_eq!(forest.score_with_ood(&[4.5, 1000.0]).out_of_box, 1.0);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{Forest, SampleAccess};
+
+/// The bounding box of the training samples of a tree.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub(crate) struct Bounds<T, const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    min: [T; N],
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    max: [T; N],
+}
+
+impl<T, const N: usize> Bounds<T, N>
+where
+    T: Float,
+{
+    /// The bounding box of the samples, `None` if there are no samples.
+    pub(crate) fn of<S>(samples: impl IntoIterator<Item = S>) -> Option<Self>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let mut samples = samples.into_iter();
+        let first = samples.next()?.to_array();
+        let mut bounds = Self {
+            min: first,
+            max: first,
+        };
+        for sample in samples {
+            for i in 0..N {
+                let value = sample.feature(i);
+                bounds.min[i] = bounds.min[i].min(value);
+                bounds.max[i] = bounds.max[i].max(value);
+            }
+        }
+        Some(bounds)
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn new(min: [T; N], max: [T; N]) -> Self {
+        Self { min, max }
+    }
+
+    pub(crate) fn min(&self) -> &[T; N] {
+        &self.min
+    }
+
+    pub(crate) fn max(&self) -> &[T; N] {
+        &self.max
+    }
+
+    /// Missing values are never within the box.
+    pub(crate) fn contains<S>(&self, sample: &S) -> bool
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        (0..N).all(|i| {
+            let value = sample.feature(i);
+            value >= self.min[i] && value <= self.max[i]
+        })
+    }
+}
+
+/// An anomaly score together with the share of trees which have not seen anything like the
+/// sample during training.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OodScore {
+    /// The score of [`Forest::score`].
+    pub score: f64,
+
+    /// The fraction of trees with the sample outside of the bounding box of their training
+    /// samples, in `[0, 1]`.
+    pub out_of_box: f64,
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Per tree whether the sample lies outside of the bounding box of the samples the tree has
+    /// been built from, in the order of the trees.
+    ///
+    /// Trees of forests converted from other implementations do not know their bounding box and
+    /// are never flagged.
+    pub fn out_of_box_trees<S>(&self, sample: &S) -> Vec<bool>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.trees
+            .iter()
+            .map(|tree| {
+                tree.bounds
+                    .as_ref()
+                    .is_some_and(|bounds| !bounds.contains(sample))
+            })
+            .collect()
+    }
+
+    /// Score the sample and report the fraction of trees seeing it outside of their training
+    /// samples, see [`Forest::out_of_box_trees`]. Scores of samples outside of most boxes
+    /// should not be trusted.
+    pub fn score_with_ood<S>(&self, sample: &S) -> OodScore
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let out_of_box = self
+            .out_of_box_trees(sample)
+            .into_iter()
+            .filter(|flag| *flag)
+            .count();
+        OodScore {
+            score: self.score(sample),
+            out_of_box: out_of_box as f64 / self.trees.len().max(1) as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bounds;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn flag_samples_outside_of_the_trees() {
+        let bounds = Bounds::of([[0.0, 1.0], [2.0, -1.0], [1.0, 0.0]]).unwrap();
+        assert!(bounds.contains(&[2.0, 1.0]));
+        assert!(!bounds.contains(&[2.5, 0.0]));
+        assert!(!bounds.contains(&[f64::NAN, 0.0]));
+        assert!(Bounds::<f64, 2>::of(Vec::<[f64; 2]>::new()).is_none());
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            sample_size: 32,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+
+        // the corner is only within the subsamples of some of the trees
+        let corner = forest.score_with_ood(&[19.0, 19.0]);
+        assert!(corner.out_of_box > 0.0 && corner.out_of_box < 1.0);
+        assert_eq!(corner.score, forest.score(&[19.0, 19.0]));
+        assert_eq!(forest.score_with_ood(&[10.0, 10.0]).out_of_box, 0.0);
+        assert!(forest
+            .out_of_box_trees(&[-5.0, 10.0])
+            .iter()
+            .all(|flag| *flag));
+    }
+}
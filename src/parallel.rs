@@ -0,0 +1,312 @@
+//! How training and batch scoring use threads.
+//!
+//! By default the trees of [`Forest::from_slice_with_parallelism`] are built, and the samples
+//! of batch operations like [`Forest::score_slice`] and [`Forest::explain_slice`] are scored,
+//! on one scoped thread per available core. Applications which already manage their threads,
+//! for example in the global pool of rayon or an async runtime, select [`Threads::None`] to
+//! keep all work on the calling thread and distribute the calls themselves, or limit the
+//! forest to a fixed number of threads.
+//!
+//! Work is handed out in chunks of [`Parallelism::chunk_size`] items, threads which are done
+//! with a chunk take the next one. Seeded forests are the same for every parallelism.
+//!
+//! ```rust
+//! use extended_isolation_forest::parallel::{Parallelism, Threads};
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let parallelism = Parallelism {
+//!     threads: Threads::Fixed(2),
+//!     chunk_size: Some(16),
+//! };
+//! let forest: Forest<f64, 2> =
+//!     Forest::from_slice_with_parallelism(&values, &ForestOptions::default(), parallelism)
+//!         .unwrap();
+//!
+//! let scores = forest.score_slice(&values);
+//! assert_eq!(scores[17], forest.score(&values[17]));
+//! ```
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand::seq::index;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::leaf::LeafModel;
+use crate::seed::TreeRng;
+use crate::split::ExtendedSplit;
+use crate::{Error, Forest, ForestFloat, ForestOptions, Hyperplanes, SampleAccess, TreeBuilder};
+
+/// The threads used by a [`Parallelism`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Threads {
+    /// All work is done on the calling thread.
+    None,
+
+    /// One thread per core, as reported by `std::thread::available_parallelism`.
+    #[default]
+    Available,
+
+    /// A fixed number of threads, `0` and `1` work on the calling thread.
+    Fixed(usize),
+}
+
+/// The threads used for training and batch scoring, see the [module documentation](self).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Parallelism {
+    pub threads: Threads,
+
+    /// Number of items, trees or samples, handed to a thread at once. By default the items are
+    /// split evenly over the threads.
+    pub chunk_size: Option<usize>,
+}
+
+impl Parallelism {
+    /// All work on the calling thread.
+    pub fn sequential() -> Self {
+        Self {
+            threads: Threads::None,
+            chunk_size: None,
+        }
+    }
+
+    /// The number of threads work is distributed over.
+    pub fn num_threads(&self) -> usize {
+        match self.threads {
+            Threads::None => 1,
+            Threads::Available => std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+            Threads::Fixed(threads) => threads.max(1),
+        }
+    }
+
+    /// Apply `f` to all items, returning the results in the order of the items.
+    pub(crate) fn map<I, O, F>(&self, items: &[I], f: F) -> Vec<O>
+    where
+        I: Sync,
+        O: Send,
+        F: Fn(&I) -> O + Sync,
+    {
+        self.map_with(items, || (), |_, item| f(item))
+    }
+
+    /// Like [`Parallelism::map`], passing the state created by `init` once per thread to `f`,
+    /// for example buffers reused for all items of the thread.
+    pub(crate) fn map_with<I, O, St, F, G>(&self, items: &[I], init: G, f: F) -> Vec<O>
+    where
+        I: Sync,
+        O: Send,
+        G: Fn() -> St + Sync,
+        F: Fn(&mut St, &I) -> O + Sync,
+    {
+        let threads = self.num_threads();
+        let chunk_size = self
+            .chunk_size
+            .unwrap_or_else(|| items.len().div_ceil(threads))
+            .max(1);
+        if threads <= 1 || items.len() <= chunk_size {
+            let state = &mut init();
+            return items.iter().map(|item| f(state, item)).collect();
+        }
+
+        let chunks: Vec<&[I]> = items.chunks(chunk_size).collect();
+        let next_chunk = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads.min(chunks.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let state = &mut init();
+                        let mut done = Vec::new();
+                        loop {
+                            let chunk = next_chunk.fetch_add(1, Ordering::Relaxed);
+                            let Some(items) = chunks.get(chunk) else {
+                                return done;
+                            };
+                            let results: Vec<_> = items.iter().map(|item| f(state, item)).collect();
+                            done.push((chunk, results));
+                        }
+                    })
+                })
+                .collect();
+            let mut results: Vec<Vec<O>> = chunks.iter().map(|_| Vec::new()).collect();
+            for handle in handles {
+                let done = handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                for (chunk, chunk_results) in done {
+                    results[chunk] = chunk_results;
+                }
+            }
+            results.into_iter().flatten().collect()
+        })
+    }
+}
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default + Send,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest like [`Forest::from_slice`], building the trees on the threads of
+    /// `parallelism`. The forest keeps the parallelism for batch scoring.
+    ///
+    /// With [`Hyperplanes::Halton`] the trees share the sequence of hyperplanes and are built
+    /// one after the other.
+    pub fn from_slice_with_parallelism<S>(
+        training_data: &[S],
+        options: &ForestOptions,
+        parallelism: Parallelism,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N> + Sync,
+    {
+        if options.hyperplanes == Hyperplanes::Halton || parallelism.num_threads() <= 1 {
+            let mut forest = Self::from_slice(training_data, options)?;
+            forest.parallelism = parallelism;
+            return Ok(forest);
+        }
+
+        let split = ExtendedSplit::new(options.hyperplanes);
+        let mut forest = Self::train_with_builder(
+            training_data.len(),
+            |i| &training_data[i],
+            options,
+            split,
+            |builder| {
+                // every tree gets a new builder, only the copied split strategy is shared
+                let (max_tree_depth, jittered_features, split) = (
+                    builder.max_tree_depth,
+                    builder.jittered_features,
+                    builder.split,
+                );
+                let tree_indices: Vec<usize> = (0..options.n_trees).collect();
+                parallelism.map(&tree_indices, |tree_index| {
+                    let mut builder =
+                        TreeBuilder::new(options, max_tree_depth, jittered_features, split.clone());
+                    let rng = &mut TreeRng::new(options.seed, *tree_index);
+                    let mut tree_sample: Vec<_> =
+                        index::sample(rng, training_data.len(), options.sample_size)
+                            .into_iter()
+                            .map(|i| &training_data[i])
+                            .collect();
+                    let extension_level = options.extension_level.sample(rng);
+                    builder.build(
+                        *tree_index,
+                        tree_sample.as_mut_slice(),
+                        extension_level,
+                        rng,
+                    )
+                })
+            },
+        )?;
+        forest.parallelism = parallelism;
+        Ok(forest)
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The threads used for batch scoring, [`Threads::Available`] unless set. The parallelism
+    /// is not stored with the forest.
+    pub fn parallelism(&self) -> Parallelism {
+        self.parallelism
+    }
+
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    /// Score all samples on the threads of the [parallelism](Forest::parallelism), in the
+    /// order of the samples.
+    pub fn score_slice<S>(&self, samples: &[S]) -> Vec<f64>
+    where
+        T: Sync,
+        L: Sync,
+        S: SampleAccess<T, N> + Sync,
+    {
+        self.parallelism.map(samples, |sample| self.score(sample))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{Parallelism, Threads};
+    use crate::{Forest, ForestOptions, Hyperplanes};
+
+    #[test]
+    fn parallel_training_and_scoring() {
+        let items: Vec<usize> = (0..100).collect();
+        let calls = AtomicUsize::new(0);
+        for parallelism in [
+            Parallelism::sequential(),
+            Parallelism::default(),
+            Parallelism {
+                threads: Threads::Fixed(3),
+                chunk_size: Some(7),
+            },
+        ] {
+            let doubled = parallelism.map(&items, |item| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                item * 2
+            });
+            assert_eq!(
+                doubled,
+                items.iter().map(|item| item * 2).collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 300);
+        assert_eq!(Parallelism::sequential().num_threads(), 1);
+        assert_eq!(
+            Parallelism {
+                threads: Threads::Fixed(0),
+                chunk_size: None
+            }
+            .num_threads(),
+            1
+        );
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 30,
+            sample_size: 64,
+            integer_jitter: true,
+            seed: Some(5),
+            ..Default::default()
+        };
+        let sequential: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let parallelism = Parallelism {
+            threads: Threads::Fixed(4),
+            chunk_size: Some(4),
+        };
+        let parallel: Forest<f64, 2> =
+            Forest::from_slice_with_parallelism(&values, &options, parallelism).unwrap();
+        assert_eq!(parallel.parallelism(), parallelism);
+        let scores = parallel.score_slice(&values);
+        for (sample, score) in values.iter().zip(scores.iter()) {
+            assert_eq!(*score, sequential.score(sample));
+        }
+
+        let halton = ForestOptions {
+            hyperplanes: Hyperplanes::Halton,
+            ..options
+        };
+        let forest: Forest<f64, 2> =
+            Forest::from_slice_with_parallelism(&values, &halton, parallelism).unwrap();
+        assert_eq!(
+            forest.score(&[3.0, 4.0]),
+            Forest::<f64, 2>::from_slice(&values, &halton)
+                .unwrap()
+                .score(&[3.0, 4.0])
+        );
+    }
+}
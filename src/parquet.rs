@@ -0,0 +1,180 @@
+//! Training from Parquet files, available with the `parquet` feature.
+//!
+//! [`Forest::from_parquet`] reads only the columns of the features and passes the rows of every
+//! record batch to [`Forest::from_stream`], so the file is read once and only the subsamples of
+//! the trees are kept in memory. Snappy-compressed files are supported, other codecs require
+//! enabling the corresponding features of the `parquet` crate.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use arrow_array::{ArrayRef, Float64Array, RecordBatch};
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//! use parquet::arrow::ArrowWriter;
+//!
+//! let path = std::env::temp_dir().join(format!("eif-doc-{}.parquet", std::process::id()));
+//! let x: ArrayRef = Arc::new(Float64Array::from_iter_values((0..1000).map(|i| (i % 10) as f64)));
+//! let y: ArrayRef = Arc::new(Float64Array::from_iter_values((0..1000).map(|i| (i / 100) as f64)));
+//! let batch = RecordBatch::try_from_iter([("x", x), ("y", y)]).unwrap();
+//! let mut writer = ArrowWriter::try_new(std::fs::File::create(&path).unwrap(), batch.schema(), None).unwrap();
+//! writer.write(&batch).unwrap();
+//! writer.close().unwrap();
+//!
+//! let forest: Forest<f64, 2> =
+//!     Forest::from_parquet(&path, &["x", "y"], &ForestOptions::default()).unwrap();
+//! assert!(forest.score(&[50.0, 50.0]) > forest.score(&[5.0, 5.0]));
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::path::Path;
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::arrow::{float_values, ArrowFloat};
+use crate::{Error, Forest, ForestFloat, ForestOptions};
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + ArrowFloat + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the top-level `columns` of a Parquet file, one per feature in
+    /// the order of the features, see the [module documentation](self). The columns must be
+    /// `Float64` for forests of `f64` or `Float32` for forests of `f32`, nulls are read as
+    /// missing values.
+    ///
+    /// Fails with [`Error::Io`] if the file can not be opened, with [`Error::Deserialization`]
+    /// for missing columns and files which can not be decoded, and with
+    /// [`Error::UnsupportedColumnType`] for columns of other types, indexed by their feature.
+    pub fn from_parquet(
+        path: impl AsRef<Path>,
+        columns: &[&str; N],
+        options: &ForestOptions,
+    ) -> Result<Self, Error> {
+        let file = File::open(path).map_err(Error::Io)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .map_err(|err| Error::Deserialization(err.to_string()))?;
+        let projection = ProjectionMask::columns(builder.parquet_schema(), columns.iter().copied());
+        let reader = builder
+            .with_projection(projection)
+            .build()
+            .map_err(|err| Error::Deserialization(err.to_string()))?;
+        // the projected columns keep the order of the file
+        let schema = reader.schema();
+        let indices = columns
+            .iter()
+            .map(|name| {
+                schema
+                    .index_of(name)
+                    .map_err(|_| Error::Deserialization(format!("missing column \"{}\"", name)))
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        let mut failure = None;
+        let rows = reader
+            .map(|batch| {
+                batch
+                    .map_err(|err| Error::Deserialization(err.to_string()))
+                    .and_then(|batch| batch_rows(&batch, &indices))
+            })
+            .map_while(|rows| rows.map_err(|err| failure = Some(err)).ok())
+            .flatten();
+        let forest = Self::from_stream(rows, options);
+        match failure {
+            Some(err) => Err(err),
+            None => forest,
+        }
+    }
+}
+
+/// The rows of the batch, reading feature `i` from the column `indices[i]`.
+fn batch_rows<T: ArrowFloat, const N: usize>(
+    batch: &RecordBatch,
+    indices: &[usize],
+) -> Result<Vec<[T; N]>, Error> {
+    let columns = indices
+        .iter()
+        .enumerate()
+        .map(|(feature, &index)| float_values(feature, batch.column(index).as_ref()))
+        .collect::<Result<Vec<Cow<[T]>>, Error>>()?;
+    Ok((0..batch.num_rows())
+        .map(|row| std::array::from_fn(|feature| columns[feature][row]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Float64Array, Int32Array, RecordBatch};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn train_from_parquet() {
+        let path = std::env::temp_dir().join(format!("eif-test-{}.parquet", std::process::id()));
+        let id: ArrayRef = Arc::new(Int32Array::from_iter_values(0..10_000));
+        let y: ArrayRef = Arc::new(Float64Array::from_iter((0..10_000).map(|i| {
+            if i == 7 {
+                None
+            } else {
+                Some((i / 100) as f64)
+            }
+        })));
+        let x: ArrayRef = Arc::new(Float64Array::from_iter_values(
+            (0..10_000).map(|i| (i % 100) as f64),
+        ));
+        let batch = RecordBatch::try_from_iter([("id", id), ("y", y), ("x", x)]).unwrap();
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_row_count(Some(3000))
+            .build();
+        let file = File::create(&path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let options = ForestOptions::default();
+        let forest: Forest<f64, 2> = Forest::from_parquet(&path, &["x", "y"], &options).unwrap();
+        assert!(forest.score(&[500.0, 50.0]) > forest.score(&[50.0, 50.0]));
+        assert!(forest.score(&[50.0, 500.0]) > forest.score(&[50.0, 50.0]));
+
+        assert!(matches!(
+            Forest::<f64, 2>::from_parquet(&path, &["x", "z"], &options),
+            Err(Error::Deserialization(message)) if message == "missing column \"z\""
+        ));
+        assert!(matches!(
+            Forest::<f64, 2>::from_parquet(&path, &["x", "id"], &options),
+            Err(Error::UnsupportedColumnType { index: 1 })
+        ));
+        let small = ForestOptions {
+            sample_size: 20_000,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Forest::<f64, 2>::from_parquet(&path, &["x", "y"], &small),
+            Err(Error::InsufficientTrainingData)
+        ));
+
+        std::fs::write(&path, b"not parquet").unwrap();
+        assert!(matches!(
+            Forest::<f64, 2>::from_parquet(&path, &["x", "y"], &options),
+            Err(Error::Deserialization(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            Forest::<f64, 2>::from_parquet(&path, &["x", "y"], &options),
+            Err(Error::Io(_))
+        ));
+    }
+}
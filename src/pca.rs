@@ -0,0 +1,220 @@
+//! PCA whitening of the samples before building a forest, available with the `nalgebra`
+//! feature.
+//!
+//! The intercepts of the hyperplanes are chosen within the ranges of the samples along the
+//! axes. For strongly correlated features these ranges are much wider than the data, so many
+//! cuts miss it. A [`WhitenedForest`] rotates the samples onto their principal components and
+//! scales them to unit variance before building the forest. The fitted transform is stored
+//! with the forest and applied to every scored sample.
+//!
+//! ```rust
+//! use extended_isolation_forest::ForestOptions;
+//! use extended_isolation_forest::pca::WhitenedForest;
+//!
+//! // the second feature mostly follows the first one
+//! let values: Vec<_> = (0..500)
+//!     .map(|i| {
+//!         let x = (i % 50) as f64;
+//!         [x, 2.0 * x + (i % 7) as f64]
+//!     })
+//!     .collect();
+//! let forest = WhitenedForest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! // within the ranges of both features, but far off the correlation
+//! assert!(forest.score(&[45.0, 10.0]) > forest.score(&[25.0, 53.0]));
+//! ```
+
+use nalgebra::DMatrix;
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{to_f64, Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Principal components with a variance below this fraction of the largest variance are
+/// dropped instead of being scaled up.
+const MIN_RELATIVE_VARIANCE: f64 = 1e-12;
+
+/// Centering, rotation onto the principal components and scaling to unit variance.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub struct Whitening<T, const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    mean: [T; N],
+
+    /// One row per principal component, scaled by the inverse of its standard deviation.
+    /// Ordered by decreasing variance.
+    #[cfg_attr(feature = "serde", serde(with = "serde_array::vec"))]
+    components: Vec<[T; N]>,
+}
+
+impl<T, const N: usize> Whitening<T, N>
+where
+    T: Float,
+{
+    /// Fit the transform to the samples.
+    ///
+    /// Components without variance, for example of constant features, are mapped to zero.
+    pub fn fit<S>(samples: &[S]) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if samples.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        let num_samples = samples.len() as f64;
+        let mut mean = [0.0; N];
+        for sample in samples {
+            for (i, m) in mean.iter_mut().enumerate() {
+                *m += to_f64(sample.feature(i)) / num_samples;
+            }
+        }
+
+        let mut covariance = DMatrix::<f64>::zeros(N, N);
+        for sample in samples {
+            let centered: [f64; N] = std::array::from_fn(|i| to_f64(sample.feature(i)) - mean[i]);
+            for i in 0..N {
+                for j in 0..N {
+                    covariance[(i, j)] += centered[i] * centered[j] / num_samples;
+                }
+            }
+        }
+
+        let eigen = covariance.symmetric_eigen();
+        let max_variance = eigen.eigenvalues.iter().copied().fold(0.0, f64::max);
+        let mut order: Vec<usize> = (0..N).collect();
+        order.sort_by(|a, b| eigen.eigenvalues[*b].total_cmp(&eigen.eigenvalues[*a]));
+
+        let components = order
+            .into_iter()
+            .map(|k| {
+                let variance = eigen.eigenvalues[k];
+                let scale = if variance > max_variance * MIN_RELATIVE_VARIANCE {
+                    variance.sqrt().recip()
+                } else {
+                    0.0
+                };
+                std::array::from_fn(|i| {
+                    T::from(eigen.eigenvectors[(i, k)] * scale).unwrap_or_else(T::zero)
+                })
+            })
+            .collect();
+        Ok(Self {
+            mean: mean.map(|m| T::from(m).unwrap_or_else(T::zero)),
+            components,
+        })
+    }
+
+    /// Apply the transform to the sample.
+    pub fn transform<S>(&self, sample: &S) -> [T; N]
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let centered: [T; N] = std::array::from_fn(|i| sample.feature(i) - self.mean[i]);
+        let mut transformed = [T::zero(); N];
+        for (value, component) in transformed.iter_mut().zip(self.components.iter()) {
+            *value = centered
+                .iter()
+                .zip(component.iter())
+                .fold(T::zero(), |sum, (c, w)| sum + *c * *w);
+        }
+        transformed
+    }
+
+    /// The mean of the samples the transform has been fitted to.
+    pub fn mean(&self) -> &[T; N] {
+        &self.mean
+    }
+}
+
+/// A forest built from whitened samples, see the [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize",
+        deserialize = "T: Deserialize<'de> + Float"
+    ))
+)]
+pub struct WhitenedForest<T, const N: usize> {
+    whitening: Whitening<T, N>,
+    forest: Forest<T, N>,
+}
+
+impl<'de, T, const N: usize> WhitenedForest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Fit the whitening to the training data and build a forest from the whitened samples.
+    pub fn from_slice<S>(training_data: &[S], options: &ForestOptions) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let whitening = Whitening::fit(training_data)?;
+        let whitened: Vec<_> = training_data
+            .iter()
+            .map(|sample| whitening.transform(sample))
+            .collect();
+        let forest = Forest::from_slice(whitened.as_slice(), options)?;
+        Ok(Self { whitening, forest })
+    }
+}
+
+impl<T, const N: usize> WhitenedForest<T, N>
+where
+    T: Float,
+{
+    pub fn score<S>(&self, sample: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.forest.score(&self.whitening.transform(sample))
+    }
+
+    pub fn whitening(&self) -> &Whitening<T, N> {
+        &self.whitening
+    }
+
+    /// The forest built from the whitened samples. Samples passed to it directly, and the
+    /// samples it returns, are in the whitened space.
+    pub fn forest(&self) -> &Forest<T, N> {
+        &self.forest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Whitening;
+
+    #[test]
+    fn whitened_samples_are_uncorrelated() {
+        let values: Vec<[f64; 3]> = (0..1000)
+            .map(|i| {
+                let x = (i % 100) as f64;
+                let y = ((i * 7) % 13) as f64;
+                [x, 3.0 * x - y, 5.0]
+            })
+            .collect();
+        let whitening = Whitening::fit(values.as_slice()).unwrap();
+        assert!((whitening.mean()[0] - 49.5).abs() < 1e-9);
+
+        let whitened: Vec<_> = values.iter().map(|v| whitening.transform(v)).collect();
+        for i in 0..3 {
+            for j in 0..3 {
+                let covariance = whitened.iter().map(|w| w[i] * w[j]).sum::<f64>() / 1000.0;
+                // the constant feature has no variance
+                let expected = if i == j && i < 2 { 1.0 } else { 0.0 };
+                assert!((covariance - expected).abs() < 1e-6, "{} {}", i, j);
+            }
+        }
+    }
+}
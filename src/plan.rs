@@ -0,0 +1,226 @@
+//! Training in two phases, choosing the subsamples of the trees first and building the trees
+//! from them later.
+//!
+//! A [`ForestPlan`] holds copies of the subsamples of all trees. With the `serde` feature it
+//! can be stored to audit which samples a forest has been built from, or sent to the machine
+//! building the trees instead of the complete training data.
+//!
+//! ```rust
+//! use extended_isolation_forest::plan::ForestPlan;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let plan = ForestPlan::subsample(&values, &ForestOptions::default()).unwrap();
+//! assert_eq!(plan.subsamples().len(), ForestOptions::default().n_trees);
+//!
+//! let forest = plan.build().unwrap();
+//! assert!(forest.score(&[50.0, 50.0]) > forest.score(&[5.0, 5.0]));
+//! ```
+
+use rand::{distributions::uniform::SampleUniform, seq::index};
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ood::Bounds;
+use crate::seed::TreeRng;
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::split::{ExtendedSplit, SplitStrategy};
+use crate::{
+    constant_features, integer_features, training_medians, Error, Forest, ForestFloat,
+    ForestOptions, SampleAccess, TreeBuilder,
+};
+
+/// The samples a single tree is built from.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub struct Subsample<T, const N: usize> {
+    extension_level: usize,
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array::vec"))]
+    samples: Vec<[T; N]>,
+}
+
+impl<T, const N: usize> Subsample<T, N> {
+    /// The extension level drawn for the tree.
+    pub fn extension_level(&self) -> usize {
+        self.extension_level
+    }
+
+    pub fn samples(&self) -> &[[T; N]] {
+        &self.samples
+    }
+}
+
+/// The subsamples of all trees of a forest, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub struct ForestPlan<T, const N: usize> {
+    /// The options of the forest, `max_tree_depth` is always set.
+    options: ForestOptions,
+    jittered_features: Vec<usize>,
+    training_range: Option<Bounds<T, N>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    training_medians: Vec<T>,
+    subsamples: Vec<Subsample<T, N>>,
+}
+
+impl<'de, T, const N: usize> ForestPlan<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Choose the subsamples of all trees the way [`Forest::from_slice`] does.
+    pub fn subsample<S>(training_data: &[S], options: &ForestOptions) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let max_tree_depth = Forest::<T, N>::validate_options(training_data.len(), options)?;
+        let subsamples = (0..options.n_trees)
+            .map(|tree_index| {
+                let rng = &mut TreeRng::new(options.seed, tree_index);
+                Subsample {
+                    extension_level: options.extension_level.sample(rng),
+                    samples: index::sample(rng, training_data.len(), options.sample_size)
+                        .into_iter()
+                        .map(|i| training_data[i].to_array())
+                        .collect(),
+                }
+            })
+            .collect();
+        Ok(Self {
+            options: ForestOptions {
+                max_tree_depth: Some(max_tree_depth),
+                ..options.clone()
+            },
+            jittered_features: if options.integer_jitter {
+                integer_features(training_data.len(), |i| &training_data[i])
+            } else {
+                Vec::new()
+            },
+            training_range: Bounds::of(training_data.iter()),
+            training_medians: training_medians(training_data.len(), |i| &training_data[i]),
+            subsamples,
+        })
+    }
+
+    /// Build the trees from the subsamples.
+    ///
+    /// Fails for plans which have been modified after deserializing them so that a subsample
+    /// contains less than two samples or an extension level exceeds the dimensions.
+    pub fn build(&self) -> Result<Forest<T, N>, Error> {
+        if self
+            .subsamples
+            .iter()
+            .any(|subsample| subsample.samples.len() < 2)
+        {
+            return Err(Error::InsufficientTrainingData);
+        }
+        if self
+            .subsamples
+            .iter()
+            .any(|subsample| subsample.extension_level > N.saturating_sub(1))
+        {
+            return Err(Error::ExtensionLevelExceedsDimensions);
+        }
+        let max_tree_depth = self.options.max_tree_depth.unwrap_or(0);
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "build_planned_forest",
+            n_trees = self.subsamples.len(),
+            dimensions = N,
+        )
+        .entered();
+
+        let mut split = ExtendedSplit::new(self.options.hyperplanes);
+        if let (true, Some(range)) = (self.options.drop_constant_features, &self.training_range) {
+            SplitStrategy::<T, N>::exclude_features(&mut split, &constant_features(range));
+        }
+        let mut builder = TreeBuilder::new(
+            &self.options,
+            max_tree_depth,
+            &self.jittered_features,
+            split,
+        );
+        let trees = self
+            .subsamples
+            .iter()
+            .enumerate()
+            .map(|(tree_index, subsample)| {
+                let mut samples = subsample.samples.clone();
+                let rng = &mut TreeRng::new(self.options.seed, tree_index);
+                builder.build(tree_index, &mut samples, subsample.extension_level, rng)
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        Ok(Forest::with_trees(
+            &self.options,
+            max_tree_depth,
+            self.jittered_features.clone(),
+            self.training_range.clone(),
+            self.training_medians.clone(),
+            trees,
+        ))
+    }
+}
+
+impl<T, const N: usize> ForestPlan<T, N> {
+    pub fn options(&self) -> &ForestOptions {
+        &self.options
+    }
+
+    /// The subsamples, one per tree in the order of the trees.
+    pub fn subsamples(&self) -> &[Subsample<T, N>] {
+        &self.subsamples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForestPlan;
+    use crate::ForestOptions;
+
+    #[test]
+    fn build_forest_from_plan() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let plan: ForestPlan<f64, 2> = ForestPlan::subsample(values.as_slice(), &options).unwrap();
+        assert_eq!(plan.subsamples().len(), 50);
+        assert_eq!(plan.options().max_tree_depth, Some(6));
+        assert!(plan.subsamples().iter().all(|subsample| {
+            subsample.samples().len() == 64
+                && subsample
+                    .samples()
+                    .iter()
+                    .all(|sample| values.contains(sample))
+        }));
+
+        let forest = plan.build().unwrap();
+        assert_eq!(forest.options(), plan.options());
+        assert!(forest.score(&[100.0, 100.0]) > forest.score(&[10.0, 10.0]));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&plan).unwrap();
+            let restored: ForestPlan<f64, 2> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored, plan);
+        }
+    }
+}
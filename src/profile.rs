@@ -0,0 +1,142 @@
+//! How quickly the trees isolate a sample.
+//!
+//! A [`DepthProfile`] lists for every depth the fraction of trees which have isolated the
+//! sample up to that depth, and the score the sample would get if the paths were truncated
+//! there. Anomalies reach their final score at small depths, normal samples only close to the
+//! max. tree depth. Plotting the profiles of normal samples and anomalies shows from which depth on
+//! deeper trees stop separating them, which helps choosing `ForestOptions::max_tree_depth`.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let profile = forest.depth_profile(&[50.0, 50.0]);
+//! assert_eq!(profile.scores.last(), Some(&forest.score(&[50.0, 50.0])));
+//! ```
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{c_factor, normalize_path_length, Direction, Forest, Node, SampleAccess};
+
+/// The isolation of a sample by depth, see [`Forest::depth_profile`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthProfile {
+    /// Per depth `d` the fraction of trees in which the sample reached an external node at a
+    /// depth of at most `d`.
+    pub isolated: Vec<f64>,
+
+    /// Per depth `d` the score of the sample with the paths truncated at internal nodes at
+    /// depth `d`, as computed by [`Forest::score_with_recursion_cap`].
+    pub scores: Vec<f64>,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The isolation of the sample by depth, from the root up to the depth at which the last
+    /// tree isolated the sample or the recursion cap of the forest.
+    ///
+    /// The last score equals the score of [`Forest::score`] unless the forest scores in strict
+    /// mode or applies a range policy.
+    pub fn depth_profile<S>(&self, sample: &S) -> DepthProfile
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let max_depth = self.recursion_cap();
+        // per tree the depth of the reached external node and its path length, `None` if the
+        // path is truncated by the recursion cap
+        let leaves: Vec<(usize, Option<f64>)> = self
+            .trees
+            .iter()
+            .map(|tree| {
+                let mut node = &tree.root;
+                let mut depth = 0;
+                loop {
+                    depth = match node.skip_depth_offset(depth, max_depth) {
+                        Some(depth) => depth,
+                        None => break (max_depth, None),
+                    };
+                    match node {
+                        Node::Ex(ex_node) => {
+                            break (depth, Some(depth as f64 + c_factor(ex_node.num_samples)))
+                        }
+                        Node::In(_) if depth >= max_depth => break (depth, None),
+                        Node::In(in_node) => {
+                            node = match in_node.direction(sample) {
+                                Direction::Left => in_node.left.as_ref(),
+                                Direction::Right => in_node.right.as_ref(),
+                            };
+                            depth += 1;
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        let deepest = leaves.iter().map(|(depth, _)| *depth).max().unwrap_or(0);
+        let num_trees = leaves.len().max(1) as f64;
+        let mut profile = DepthProfile {
+            isolated: Vec::with_capacity(deepest + 1),
+            scores: Vec::with_capacity(deepest + 1),
+        };
+        for cap in 0..=deepest {
+            let mut isolated = 0;
+            let mut path_length = 0.0;
+            for (depth, leaf_path_length) in &leaves {
+                match leaf_path_length {
+                    Some(leaf_path_length) if *depth <= cap => {
+                        isolated += 1;
+                        path_length += leaf_path_length;
+                    }
+                    _ => path_length += cap as f64,
+                }
+            }
+            profile.isolated.push(isolated as f64 / num_trees);
+            profile.scores.push(normalize_path_length(
+                path_length / num_trees,
+                self.avg_path_length_c,
+            ));
+        }
+        profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn profile_of_anomaly_and_normal_sample() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 64,
+            seed: Some(8),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+
+        let anomaly = forest.depth_profile(&[100.0, 100.0]);
+        let normal = forest.depth_profile(&[9.5, 9.5]);
+        for profile in [&anomaly, &normal] {
+            assert_eq!(profile.isolated.len(), profile.scores.len());
+            assert!(profile.isolated.windows(2).all(|w| w[0] <= w[1]));
+            assert_eq!(profile.scores[0], 1.0);
+        }
+        assert!(anomaly.isolated[2] > normal.isolated[2]);
+        assert!((normal.scores.last().unwrap() - forest.score(&[9.5, 9.5])).abs() < 1e-12);
+        for depth in 0..=3 {
+            assert_eq!(
+                anomaly.scores[depth],
+                forest.score_with_recursion_cap(&[100.0, 100.0], depth)
+            );
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Random projection of very high-dimensional samples before building a forest.
+//!
+//! Building and scoring trees on thousands of raw features is slow. A [`ProjectedForest`]
+//! multiplies every sample with a random matrix reducing it to `K` dimensions, which
+//! approximately preserves the distances between samples, and builds the forest on the
+//! projected samples. The matrix is stored together with the forest, so scoring applies the
+//! same projection.
+//!
+//! ```rust
+//! use extended_isolation_forest::ForestOptions;
+//! use extended_isolation_forest::projection::{ProjectedForest, ProjectionKind};
+//!
+//! let rows: Vec<Vec<f64>> = (0..200)
+//!     .map(|i| (0..1000).map(|j| ((i * j) % 7) as f64).collect())
+//!     .collect();
+//! let forest: ProjectedForest<f64, 16> =
+//!     ProjectedForest::from_rows(&rows, ProjectionKind::Sparse, &ForestOptions::default()).unwrap();
+//!
+//! let score = forest.score(&rows[0]).unwrap();
+//! assert!(forest.score(&vec![100.0; 1000]).unwrap() > score);
+//! ```
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand::Rng;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{Error, Forest, ForestFloat, ForestOptions};
+
+/// The max. number of dimensions samples can be projected to.
+pub const MAX_DIMENSIONS: usize = 64;
+
+/// How the entries of the projection matrix are drawn.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ProjectionKind {
+    /// Entries drawn from a standard normal distribution.
+    #[default]
+    Gaussian,
+
+    /// The sparse projection of Achlioptas: entries are `+1` or `-1` with a probability of
+    /// `1/6` each and zero otherwise, so about a third of the input features are read for each
+    /// projected dimension.
+    Sparse,
+
+    /// Every input feature contributes to a single dimension. Drawn by [`Projection::new`],
+    /// feature `i` is added to dimension `i % K`. [`Correlation::fold`] averages groups of
+    /// collinear features instead.
+    ///
+    /// [`Correlation::fold`]: crate::correlation::Correlation::fold
+    Folded,
+}
+
+/// A random linear map from samples with any number of features to `K` dimensions.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub struct Projection<T, const K: usize> {
+    kind: ProjectionKind,
+
+    /// The contribution of every input feature to the projected dimensions.
+    #[cfg_attr(feature = "serde", serde(with = "serde_array::vec"))]
+    rows: Vec<[T; K]>,
+}
+
+impl<T, const K: usize> Projection<T, K>
+where
+    T: Float,
+    StandardNormal: Distribution<T>,
+{
+    /// Draw a projection of samples with `input_dimensions` features.
+    pub fn new(input_dimensions: usize, kind: ProjectionKind) -> Result<Self, Error> {
+        if K > MAX_DIMENSIONS {
+            return Err(Error::TooManyDimensions {
+                max: MAX_DIMENSIONS,
+                actual: K,
+            });
+        }
+        let rng = &mut rand::thread_rng();
+        let k = T::from(K).unwrap_or_else(T::one);
+        let rows = (0..input_dimensions)
+            .map(|i| match kind {
+                ProjectionKind::Gaussian => {
+                    let scale = k.sqrt().recip();
+                    std::array::from_fn(|_| rng.sample::<T, _>(StandardNormal) * scale)
+                }
+                ProjectionKind::Sparse => {
+                    let scale = (T::from(3.0).unwrap_or_else(T::one) / k).sqrt();
+                    std::array::from_fn(|_| match rng.gen_range(0..6) {
+                        0 => scale,
+                        1 => -scale,
+                        _ => T::zero(),
+                    })
+                }
+                ProjectionKind::Folded => {
+                    std::array::from_fn(|j| if j == i % K { T::one() } else { T::zero() })
+                }
+            })
+            .collect();
+        Ok(Self { kind, rows })
+    }
+}
+
+impl<T, const K: usize> Projection<T, K>
+where
+    T: Float,
+{
+    /// A projection with the given contributions of every input feature.
+    pub(crate) fn from_weights(kind: ProjectionKind, rows: Vec<[T; K]>) -> Result<Self, Error> {
+        if K > MAX_DIMENSIONS {
+            return Err(Error::TooManyDimensions {
+                max: MAX_DIMENSIONS,
+                actual: K,
+            });
+        }
+        Ok(Self { kind, rows })
+    }
+
+    pub fn kind(&self) -> ProjectionKind {
+        self.kind
+    }
+
+    /// Number of features of the samples the projection accepts.
+    pub fn input_dimensions(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Project the sample, which must have exactly `input_dimensions` features.
+    pub fn project(&self, sample: &[T]) -> Result<[T; K], Error> {
+        if sample.len() != self.rows.len() {
+            return Err(Error::DimensionMismatch {
+                expected: self.rows.len(),
+                actual: sample.len(),
+            });
+        }
+        let mut projected = [T::zero(); K];
+        for (value, row) in sample.iter().zip(self.rows.iter()) {
+            if value.is_zero() {
+                continue;
+            }
+            for (target, weight) in projected.iter_mut().zip(row.iter()) {
+                *target = *target + *value * *weight;
+            }
+        }
+        Ok(projected)
+    }
+}
+
+/// A forest built from randomly projected samples, see the [module documentation](self).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize",
+        deserialize = "T: Deserialize<'de> + Float"
+    ))
+)]
+pub struct ProjectedForest<T, const K: usize> {
+    projection: Projection<T, K>,
+    forest: Forest<T, K>,
+}
+
+impl<'de, T, const K: usize> ProjectedForest<T, K>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Draw a projection for the rows and build a forest from the projected rows. All rows must
+    /// have the same number of features.
+    pub fn from_rows<R>(
+        rows: &[R],
+        kind: ProjectionKind,
+        options: &ForestOptions,
+    ) -> Result<Self, Error>
+    where
+        R: AsRef<[T]>,
+    {
+        let input_dimensions = rows.first().ok_or(Error::EmptyData)?.as_ref().len();
+        Self::with_projection(Projection::new(input_dimensions, kind)?, rows, options)
+    }
+
+    /// Build a forest from the rows projected by the given projection, for example the
+    /// [folding](crate::correlation::Correlation::fold) of collinear features.
+    pub fn with_projection<R>(
+        projection: Projection<T, K>,
+        rows: &[R],
+        options: &ForestOptions,
+    ) -> Result<Self, Error>
+    where
+        R: AsRef<[T]>,
+    {
+        let projected = rows
+            .iter()
+            .map(|row| projection.project(row.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let forest = Forest::from_slice(projected.as_slice(), options)?;
+        Ok(Self { projection, forest })
+    }
+}
+
+impl<T, const K: usize> ProjectedForest<T, K>
+where
+    T: Float,
+{
+    /// Score the sample, which must have as many features as the training rows.
+    pub fn score(&self, sample: &[T]) -> Result<f64, Error> {
+        Ok(self.forest.score(&self.projection.project(sample)?))
+    }
+
+    pub fn projection(&self) -> &Projection<T, K> {
+        &self.projection
+    }
+
+    /// The forest built from the projected samples.
+    pub fn forest(&self) -> &Forest<T, K> {
+        &self.forest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProjectedForest, Projection, ProjectionKind};
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn project_rows() {
+        let projection: Projection<f64, 8> = Projection::new(500, ProjectionKind::Sparse).unwrap();
+        assert_eq!(projection.input_dimensions(), 500);
+        assert_eq!(projection.project(&[0.0; 500]).unwrap(), [0.0; 8]);
+        assert!(matches!(
+            projection.project(&[1.0; 10]),
+            Err(Error::DimensionMismatch {
+                expected: 500,
+                actual: 10
+            })
+        ));
+        let folded: Projection<f64, 3> = Projection::new(5, ProjectionKind::Folded).unwrap();
+        assert_eq!(
+            folded.project(&[1.0, 2.0, 3.0, 4.0, 5.0]).unwrap(),
+            [5.0, 7.0, 3.0]
+        );
+        assert!(matches!(
+            Projection::<f64, 65>::new(10, ProjectionKind::Gaussian),
+            Err(Error::TooManyDimensions {
+                max: 64,
+                actual: 65
+            })
+        ));
+
+        let rows: Vec<Vec<f64>> = (0..300)
+            .map(|i| (0..200).map(|j| ((i + j) % 5) as f64).collect())
+            .collect();
+        let forest: ProjectedForest<f64, 4> =
+            ProjectedForest::from_rows(&rows, ProjectionKind::Gaussian, &ForestOptions::default())
+                .unwrap();
+        let anomaly = vec![50.0; 200];
+        assert!(forest.score(&anomaly).unwrap() > forest.score(&rows[0]).unwrap());
+
+        #[cfg(feature = "serde")]
+        {
+            let serialized = serde_json::to_string(&forest).unwrap();
+            let deserialized: ProjectedForest<f64, 4> = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.projection().input_dimensions(), 200);
+            assert!(
+                (deserialized.score(&anomaly).unwrap() - forest.score(&anomaly).unwrap()).abs()
+                    < 1e-9
+            );
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Where the trees of a forest come from.
+//!
+//! Forests changed over time by [`Forest::refresh`] mix trees built at different times from
+//! different data. To keep such models auditable, every tree records its [`TreeProvenance`]:
+//! the generation it has been built in, when it has been built, the seed of its random number
+//! generator and an optional label of its training data, set with
+//! [`Forest::set_source_label`].
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let may: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     seed: Some(7),
+//!     ..Default::default()
+//! };
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&may, &options).unwrap();
+//! forest.set_source_label("2024-05");
+//!
+//! let june: Vec<_> = (0..100).map(|i| [(i % 10) as f64 + 1.0, (i / 10) as f64]).collect();
+//! forest.refresh(&june, 0.5).unwrap();
+//! forest.set_source_label("2024-06");
+//!
+//! let provenance = forest.tree_provenance();
+//! assert_eq!(provenance[0].label.as_deref(), Some("2024-06"));
+//! assert_eq!(provenance[19].label.as_deref(), Some("2024-05"));
+//! assert_eq!(provenance[19].seed, Some(7));
+//! assert!(provenance[19].created_at.is_some());
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+use crate::Forest;
+
+/// The origin of a tree, see the [module documentation](self). Unknown values are `None`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TreeProvenance {
+    /// `0` for the initial training and `k` for trees built in the `k`-th refresh.
+    pub generation: u64,
+
+    /// When the tree has been built, in seconds since the Unix epoch.
+    pub created_at: Option<u64>,
+
+    /// The seed the generator of the tree has been derived from together with the index of
+    /// the tree, see [`seed`](crate::seed). `None` for trees built with the thread-local
+    /// generator.
+    pub seed: Option<u64>,
+
+    /// The label of the training data, see [`Forest::set_source_label`].
+    pub label: Option<String>,
+}
+
+impl TreeProvenance {
+    /// The provenance of a tree built now.
+    pub(crate) fn now(generation: u64, seed: Option<u64>) -> Self {
+        Self {
+            generation,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs()),
+            seed,
+            label: None,
+        }
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The provenance of every tree, in the order of the trees. The trees of forests converted
+    /// from other implementations have the default provenance with every value unknown.
+    pub fn tree_provenance(&self) -> Vec<TreeProvenance> {
+        if self.provenance.len() == self.trees.len() {
+            self.provenance.clone()
+        } else {
+            vec![TreeProvenance::default(); self.trees.len()]
+        }
+    }
+
+    /// Label the trees of the newest generation with the source of their training data, like
+    /// a partition or a file name, after training or [`Forest::refresh`].
+    pub fn set_source_label(&mut self, label: impl Into<String>) {
+        let mut provenance = self.tree_provenance();
+        let newest = provenance.iter().map(|tree| tree.generation).max();
+        let label = label.into();
+        for tree in provenance.iter_mut() {
+            if Some(tree.generation) == newest {
+                tree.label = Some(label.clone());
+            }
+        }
+        self.provenance = provenance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn trees_record_their_provenance() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 64,
+            seed: Some(19),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let provenance = forest.tree_provenance();
+        assert_eq!(provenance.len(), 10);
+        assert!(provenance.iter().all(|tree| tree.generation == 0
+            && tree.seed == Some(19)
+            && tree.created_at.is_some()
+            && tree.label.is_none()));
+
+        forest.set_source_label("monday");
+        forest.refresh(&values, 0.2).unwrap();
+        forest.refresh(&values, 0.2).unwrap();
+        forest.set_source_label("wednesday");
+        let provenance = forest.tree_provenance();
+        let labels: Vec<_> = provenance
+            .iter()
+            .map(|tree| tree.label.as_deref())
+            .collect();
+        assert_eq!(
+            &labels[..4],
+            &[None, None, Some("wednesday"), Some("wednesday")]
+        );
+        assert!(labels[4..].iter().all(|label| *label == Some("monday")));
+        assert_eq!(provenance[0].generation, 1);
+        assert_eq!(provenance[0].seed, Some(20));
+
+        forest.prune_trees(&values, 0.5).unwrap();
+        assert_eq!(forest.tree_provenance().len(), 5);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&forest).unwrap();
+            let restored: Forest<f64, 2> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.tree_provenance(), forest.tree_provenance());
+        }
+    }
+}
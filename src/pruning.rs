@@ -0,0 +1,227 @@
+//! Dropping trees which contribute little to the scores.
+//!
+//! Forests are often built with more trees than needed. Some trees hardly separate the
+//! samples, for example when their subsample happened to be dense everywhere, and only add
+//! cost. [`Forest::prune_trees`] measures on probe data how well the path lengths of each tree
+//! correlate with the mean path length of the whole forest, which determines the scores, keeps
+//! the best correlated trees and reports how closely the scores of the smaller forest match the
+//! original ones.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..400).map(|i| [(i % 20) as f64, (i / 20) as f64]).collect();
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let probe: Vec<_> = (0..200).map(|i| [(i % 40) as f64 - 10.0, (i / 5) as f64]).collect();
+//! let report = forest.prune_trees(&probe, 0.5).unwrap();
+//! assert_eq!(report.kept.len(), 10);
+//! assert_eq!(forest.options().n_trees, 10);
+//! assert!(report.correlation > 0.8);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::eval::pearson;
+use crate::leaf::LeafModel;
+use crate::{normalize_path_length, Error, Forest, SampleAccess};
+
+/// The fidelity of a pruned forest on the probe data, see [`Forest::prune_trees`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PruneReport {
+    /// The indices of the kept trees in the original forest, in ascending order.
+    pub kept: Vec<usize>,
+
+    /// Per tree of the original forest the correlation of its path lengths with the mean path
+    /// lengths of the forest, `0` for trees with the same path length for all probe samples.
+    pub tree_correlations: Vec<f64>,
+
+    /// The Pearson correlation of the scores before and after pruning.
+    pub correlation: f64,
+
+    /// The mean absolute change of the scores.
+    pub mean_abs_change: f64,
+
+    /// The maximum absolute change of the scores.
+    pub max_abs_change: f64,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Keep the `keep_fraction` of the trees best correlated with the forest on the probe data,
+    /// at least one tree, see the [module documentation](self). `options().n_trees` is updated to
+    /// the number of kept trees.
+    ///
+    /// Samples in suppressed regions are left out, as their scores do not depend on the trees.
+    /// Monotone constraints and clamping are applied like in [`Forest::score`], without
+    /// counting samples for [`RangePolicy::Warn`](crate::envelope::RangePolicy::Warn).
+    ///
+    /// Fails with [`Error::InvalidFraction`] unless `0 < keep_fraction <= 1` and with
+    /// [`Error::EmptyData`] if no probe sample is left.
+    pub fn prune_trees<S>(
+        &mut self,
+        probe_data: &[S],
+        keep_fraction: f64,
+    ) -> Result<PruneReport, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if !(keep_fraction > 0.0 && keep_fraction <= 1.0) {
+            return Err(Error::InvalidFraction);
+        }
+        let max_depth = self.recursion_cap();
+        let samples: Vec<[T; N]> = probe_data
+            .iter()
+            .filter(|sample| !self.is_suppressed(*sample))
+            .map(|sample| self.adjusted_without_counting(&sample.to_array()))
+            .collect();
+        if samples.is_empty() {
+            return Err(Error::EmptyData);
+        }
+
+        // path_lengths[t][s] of sample s in tree t
+        let path_lengths: Vec<Vec<f64>> = self
+            .trees
+            .iter()
+            .map(|tree| {
+                samples
+                    .iter()
+                    .map(|sample| tree.path_length_with_cap(sample, max_depth))
+                    .collect()
+            })
+            .collect();
+        let ensemble = mean_path_lengths(&path_lengths, 0..self.trees.len());
+        let tree_correlations: Vec<f64> = path_lengths
+            .iter()
+            .map(|lengths| pearson(lengths, &ensemble))
+            .collect();
+
+        let num_kept = ((self.trees.len() as f64 * keep_fraction).ceil() as usize)
+            .clamp(1, self.trees.len().max(1));
+        let mut ranking: Vec<usize> = (0..self.trees.len()).collect();
+        ranking.sort_by(|a, b| tree_correlations[*b].total_cmp(&tree_correlations[*a]));
+        let mut kept = ranking[..num_kept.min(ranking.len())].to_vec();
+        kept.sort_unstable();
+
+        let scores = |mean_path_lengths: &[f64]| -> Vec<f64> {
+            mean_path_lengths
+                .iter()
+                .map(|mean| normalize_path_length(*mean, self.avg_path_length_c))
+                .collect()
+        };
+        let before = scores(&ensemble);
+        let after = scores(&mean_path_lengths(&path_lengths, kept.iter().copied()));
+        let changes: Vec<f64> = before
+            .iter()
+            .zip(after.iter())
+            .map(|(before, after)| (after - before).abs())
+            .collect();
+
+        let mut keep = vec![false; self.trees.len()];
+        for index in kept.iter() {
+            keep[*index] = true;
+        }
+        if self.provenance.len() == self.trees.len() {
+            self.provenance = kept
+                .iter()
+                .map(|index| self.provenance[*index].clone())
+                .collect();
+        }
+        let trees = std::mem::take(&mut self.trees);
+        self.trees = trees
+            .into_vec()
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(tree, keep)| keep.then_some(tree))
+            .collect();
+        self.options.n_trees = self.trees.len();
+
+        Ok(PruneReport {
+            kept,
+            tree_correlations,
+            correlation: pearson(&before, &after),
+            mean_abs_change: changes.iter().sum::<f64>() / changes.len() as f64,
+            max_abs_change: changes.iter().copied().fold(0.0, f64::max),
+        })
+    }
+}
+
+/// The mean path length of every sample in the trees.
+fn mean_path_lengths(
+    path_lengths: &[Vec<f64>],
+    trees: impl Iterator<Item = usize> + Clone,
+) -> Vec<f64> {
+    let num_samples = path_lengths.first().map_or(0, Vec::len);
+    let num_trees = trees.clone().count().max(1) as f64;
+    (0..num_samples)
+        .map(|s| trees.clone().map(|t| path_lengths[t][s]).sum::<f64>() / num_trees)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn prune_trees_keeps_the_best_correlated_trees() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 40,
+            sample_size: 64,
+            seed: Some(15),
+            ..Default::default()
+        };
+        let probe: Vec<_> = (0..300)
+            .map(|i| [(i % 30) as f64 - 5.0, (i / 10) as f64 - 5.0])
+            .collect();
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let before: Vec<f64> = probe.iter().map(|sample| forest.score(sample)).collect();
+
+        assert!(matches!(
+            forest.prune_trees(&probe, 0.0),
+            Err(Error::InvalidFraction)
+        ));
+        assert!(matches!(
+            forest.prune_trees(&[] as &[[f64; 2]], 0.5),
+            Err(Error::EmptyData)
+        ));
+
+        let report = forest.prune_trees(&probe, 0.25).unwrap();
+        assert_eq!(report.kept.len(), 10);
+        assert_eq!(report.tree_correlations.len(), 40);
+        assert!(report.kept.windows(2).all(|pair| pair[0] < pair[1]));
+        let min_kept = report
+            .kept
+            .iter()
+            .map(|index| report.tree_correlations[*index])
+            .fold(f64::INFINITY, f64::min);
+        for (index, correlation) in report.tree_correlations.iter().enumerate() {
+            if !report.kept.contains(&index) {
+                assert!(*correlation <= min_kept);
+            }
+        }
+        assert_eq!(forest.options().n_trees, 10);
+
+        let after: Vec<f64> = probe.iter().map(|sample| forest.score(sample)).collect();
+        let max_abs_change = before
+            .iter()
+            .zip(after.iter())
+            .map(|(before, after)| (after - before).abs())
+            .fold(0.0, f64::max);
+        assert!((report.max_abs_change - max_abs_change).abs() < 1e-12);
+        assert!(report.mean_abs_change <= report.max_abs_change);
+        assert!(report.correlation > 0.9);
+
+        let report = forest.prune_trees(&probe, 1.0).unwrap();
+        assert_eq!(report.kept, (0..10).collect::<Vec<_>>());
+        assert_eq!(report.max_abs_change, 0.0);
+    }
+}
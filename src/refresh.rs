@@ -0,0 +1,214 @@
+//! Replacing the oldest trees with trees built from fresh data.
+//!
+//! Retraining a whole forest from scratch changes all scores at once. [`Forest::refresh`]
+//! instead rebuilds a fraction of the trees from recent data and keeps the others, so the
+//! model follows a drifting distribution in small steps between full retrains. Every refresh
+//! starts a new generation, the trees built in it belong to it, and the oldest trees are the
+//! next to be replaced. [`Forest::tree_ages`] reports how many refreshes ago each tree has been
+//! built, [`Forest::tree_provenance`] when and from which seed.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let old: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&old, &ForestOptions::default()).unwrap();
+//!
+//! let new: Vec<_> = (0..100).map(|i| [(i % 10) as f64 + 50.0, (i / 10) as f64]).collect();
+//! assert_eq!(forest.refresh(&new, 0.25).unwrap(), 5);
+//! assert_eq!(forest.refresh(&new, 0.25).unwrap(), 5);
+//! let ages = forest.tree_ages();
+//! assert_eq!(ages.iter().filter(|age| **age == 0).count(), 5);
+//! assert_eq!(ages.iter().filter(|age| **age == 2).count(), 10);
+//! ```
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand::seq::index;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::leaf::LeafModel;
+use crate::ood::Bounds;
+use crate::provenance::TreeProvenance;
+use crate::seed::TreeRng;
+use crate::split::{ExtendedSplit, SplitStrategy};
+use crate::{
+    constant_features, training_medians, Error, Forest, ForestFloat, SampleAccess, TreeBuilder,
+};
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The generation of every tree: `0` for the initial training and `k` for trees built in
+    /// the `k`-th refresh.
+    pub fn tree_generations(&self) -> Vec<u64> {
+        self.tree_provenance()
+            .iter()
+            .map(|tree| tree.generation)
+            .collect()
+    }
+
+    /// The number of refreshes since every tree has been built, `0` for the newest trees.
+    pub fn tree_ages(&self) -> Vec<u64> {
+        let generations = self.tree_generations();
+        let current = generations.iter().copied().max().unwrap_or(0);
+        generations
+            .into_iter()
+            .map(|generation| current - generation)
+            .collect()
+    }
+}
+
+impl<'de, T, const N: usize, L> Forest<T, N, L>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+    L: LeafModel<T, N>,
+{
+    /// Rebuild the oldest `fraction` of the trees, at least one, from subsamples of the fresh
+    /// data and keep the other trees, see the [module documentation](self). Trees of the same
+    /// age are replaced in the order of their index. Returns the number of rebuilt trees.
+    ///
+    /// The trees are built with the options and jittered features of the initial training.
+    /// With [`ForestOptions::seed`](crate::ForestOptions::seed) the trees of the `k`-th refresh
+    /// are built from the seed plus `k`, as recorded in their [`TreeProvenance`]. The training range grows to include the fresh
+    /// data and the training medians are those of the fresh data.
+    ///
+    /// Fails with [`Error::InvalidFraction`] unless `0 < fraction <= 1` and with
+    /// [`Error::InsufficientTrainingData`] if there are less than `sample_size` fresh samples.
+    pub fn refresh<S>(&mut self, data: &[S], fraction: f64) -> Result<usize, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(Error::InvalidFraction);
+        }
+        if data.len() < self.options.sample_size {
+            return Err(Error::InsufficientTrainingData);
+        }
+
+        let mut provenance = self.tree_provenance();
+        let generation = provenance
+            .iter()
+            .map(|tree| tree.generation)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let num_rebuilt = ((provenance.len() as f64 * fraction).ceil() as usize)
+            .max(1)
+            .min(provenance.len());
+        let mut oldest: Vec<usize> = (0..provenance.len()).collect();
+        oldest.sort_by_key(|index| (provenance[*index].generation, *index));
+
+        let max_tree_depth = self
+            .options
+            .max_tree_depth
+            .unwrap_or_else(|| self.recursion_cap());
+        let mut split = ExtendedSplit::new(self.options.hyperplanes);
+        if self.options.drop_constant_features {
+            SplitStrategy::<T, N>::exclude_features(&mut split, &self.constant_features);
+        }
+        let mut builder = TreeBuilder::new(
+            &self.options,
+            max_tree_depth,
+            &self.jittered_features,
+            split,
+        );
+        let seed = self.options.seed.map(|seed| seed.wrapping_add(generation));
+        let mut trees = Vec::with_capacity(num_rebuilt);
+        for &tree_index in &oldest[..num_rebuilt] {
+            let rng = &mut TreeRng::new(seed, tree_index);
+            let mut tree_sample: Vec<&S> = index::sample(rng, data.len(), self.options.sample_size)
+                .into_iter()
+                .map(|row| &data[row])
+                .collect();
+            let extension_level = self.options.extension_level.sample(rng);
+            trees.push((
+                tree_index,
+                builder.build(tree_index, tree_sample.as_mut_slice(), extension_level, rng),
+            ));
+        }
+        for (tree_index, tree) in trees {
+            self.trees[tree_index] = tree;
+            provenance[tree_index] = TreeProvenance::now(generation, seed);
+        }
+        self.provenance = provenance;
+
+        let previous: Vec<[T; N]> = self
+            .training_range
+            .iter()
+            .flat_map(|range| [*range.min(), *range.max()])
+            .collect();
+        self.training_range =
+            Bounds::of(data.iter().map(|sample| sample.to_array()).chain(previous));
+        if self.options.drop_constant_features {
+            self.constant_features = self
+                .training_range
+                .as_ref()
+                .map(constant_features)
+                .unwrap_or_default();
+        }
+        self.training_medians = training_medians(data.len(), |i| &data[i]);
+        Ok(num_rebuilt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn refresh_replaces_the_oldest_trees() {
+        let old: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let new: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64 + 100.0, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            sample_size: 64,
+            seed: Some(18),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&old, &options).unwrap();
+        assert_eq!(forest.tree_ages(), vec![0; 20]);
+        let before = forest.score(&[110.0, 10.0]);
+
+        assert!(matches!(
+            forest.refresh(&new, 1.5),
+            Err(Error::InvalidFraction)
+        ));
+        assert!(matches!(
+            forest.refresh(&new[..10], 0.5),
+            Err(Error::InsufficientTrainingData)
+        ));
+
+        assert_eq!(forest.refresh(&new, 0.3).unwrap(), 6);
+        let generations = forest.tree_generations();
+        assert_eq!(&generations[..6], &[1; 6]);
+        assert_eq!(&generations[6..], &[0; 14]);
+        for tree in &forest.trees[..6] {
+            assert!(tree.bounds.as_ref().unwrap().min()[0] >= 100.0);
+        }
+        assert!(forest.score(&[110.0, 10.0]) < before);
+        let (min, max) = forest.training_range().unwrap();
+        assert_eq!((min[0], max[0]), (0.0, 119.0));
+
+        // the trees of the initial training are replaced before the refreshed ones
+        assert_eq!(forest.refresh(&new, 0.75).unwrap(), 15);
+        let generations = forest.tree_generations();
+        assert_eq!(generations[0], 2);
+        assert_eq!(&generations[1..6], &[1; 5]);
+        assert_eq!(&generations[6..], &[2; 14]);
+        assert_eq!(&forest.tree_ages()[..7], &[0, 1, 1, 1, 1, 1, 0]);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&forest).unwrap();
+            let restored: Forest<f64, 2> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.tree_ages(), forest.tree_ages());
+        }
+    }
+}
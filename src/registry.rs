@@ -0,0 +1,167 @@
+//! Named forests for multi-tenant scoring.
+//!
+//! A [`ForestRegistry`] holds many forests by key, for example one per customer or per metric,
+//! and routes scoring requests to them. Forests are stored behind an [`Arc`], so replacing a
+//! forest is atomic: requests already scoring keep using the previous forest, all later requests
+//! use the new one.
+//!
+//! All forests of a registry have the float type `T` and the dimension `N` of the registry, as
+//! both are part of the type of a forest, and the default leaf model. Use one registry per
+//! dimension to serve forests of different dimensions, or a map of
+//! [`DynForest`](crate::dynamic::DynForest)s if the dimensions are only known at runtime, like
+//! for models uploaded by the tenants.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//! use extended_isolation_forest::registry::ForestRegistry;
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let registry = ForestRegistry::new();
+//! registry.insert("tenant-a".to_string(), forest);
+//!
+//! assert!(registry.score("tenant-a", &[4.0, 5.0]).is_some());
+//! assert!(registry.score("tenant-b", &[4.0, 5.0]).is_none());
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+
+use num_traits::Float;
+
+use crate::{Forest, SampleAccess};
+
+/// Forests stored by key.
+pub struct ForestRegistry<T, const N: usize, K = String> {
+    forests: RwLock<HashMap<K, Arc<Forest<T, N>>>>,
+}
+
+impl<T, const N: usize, K> Default for ForestRegistry<T, N, K> {
+    fn default() -> Self {
+        Self {
+            forests: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T, const N: usize, K> ForestRegistry<T, N, K>
+where
+    T: Float,
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a forest, atomically replacing the forest previously stored with the key.
+    ///
+    /// Returns the replaced forest.
+    pub fn insert(&self, key: K, forest: Forest<T, N>) -> Option<Arc<Forest<T, N>>> {
+        self.write().insert(key, Arc::new(forest))
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<Arc<Forest<T, N>>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.write().remove(key)
+    }
+
+    /// The forest currently stored with the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<Arc<Forest<T, N>>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.read().get(key).cloned()
+    }
+
+    /// Score the sample with the forest stored with the key. Returns `None` for unknown keys.
+    ///
+    /// The registry is not locked while scoring, so inserting forests does not wait for
+    /// running requests.
+    pub fn score<Q, S>(&self, key: &Q, sample: &S) -> Option<f64>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.get(key).map(|forest| forest.score(sample))
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Keys of all stored forests, in arbitrary order.
+    pub fn keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.read().keys().cloned().collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<K, Arc<Forest<T, N>>>> {
+        // the map is never left in an inconsistent state, so poisoning can be ignored
+        self.forests
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<K, Arc<Forest<T, N>>>> {
+        self.forests
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForestRegistry;
+    use crate::eif::{EifForest, EifNode};
+    use crate::Forest;
+
+    fn make_forest(p: f64) -> Forest<f64, 1> {
+        Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0],
+                p: vec![p],
+                left: Box::new(EifNode::ExNode { size: 7 }),
+                right: Box::new(EifNode::ExNode { size: 1 }),
+            }],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn registry_routes_and_swaps() {
+        let registry = ForestRegistry::new();
+        assert!(registry.insert("a", make_forest(5.0)).is_none());
+        registry.insert("b", make_forest(50.0));
+        assert_eq!(registry.len(), 2);
+
+        // 10 is isolated by the first forest only
+        let a = registry.score("a", &[10.0]).unwrap();
+        let b = registry.score("b", &[10.0]).unwrap();
+        assert!(a > b);
+        assert_eq!(registry.score("c", &[10.0]), None);
+
+        // forests held by running requests survive the swap
+        let previous = registry.get("a").unwrap();
+        assert!(registry.insert("a", make_forest(50.0)).is_some());
+        assert_eq!(previous.score(&[10.0]), a);
+        assert_eq!(registry.score("a", &[10.0]), Some(b));
+
+        registry.remove("b");
+        assert_eq!(registry.keys(), vec!["a"]);
+    }
+}
@@ -0,0 +1,213 @@
+//! Structured summaries of forests for model governance.
+//!
+//! [`Forest::report`] scores a set of evaluation samples and collects the options of the forest,
+//! statistics of the evaluation data and of the scores, suggested thresholds and, when labels are
+//! given, the ROC AUC into a [`Report`]. With the `serde` feature the report can be serialized
+//! and stored next to the model.
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{eval, to_f64, Error, Forest, ForestOptions, SampleAccess};
+
+/// Quantiles of the scores included in the report.
+const QUANTILES: [f64; 7] = [0.01, 0.05, 0.25, 0.5, 0.75, 0.95, 0.99];
+
+/// Expected fractions of anomalies for which thresholds are suggested.
+const CONTAMINATIONS: [f64; 4] = [0.001, 0.01, 0.05, 0.1];
+
+/// Summary of a forest and its scores on evaluation data, see [`Forest::report`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Report {
+    /// Options the forest has been built with.
+    pub options: ForestOptions,
+
+    /// Number of dimensions of the samples.
+    pub dimensions: usize,
+
+    /// See [`Forest::model_hash`].
+    pub model_hash: u64,
+
+    /// Number of evaluation samples.
+    pub num_samples: usize,
+
+    /// Statistics of every dimension of the evaluation samples.
+    pub features: Vec<Summary>,
+
+    /// Statistics of the scores of the evaluation samples.
+    pub scores: Summary,
+
+    /// Quantiles of the scores of the evaluation samples.
+    pub score_quantiles: Vec<Quantile>,
+
+    /// Thresholds flagging the given fraction of the evaluation samples as anomalies.
+    pub thresholds: Vec<ThresholdSuggestion>,
+
+    /// Area under the ROC curve, if labels have been given and contain both classes.
+    pub roc_auc: Option<f64>,
+}
+
+/// Statistics of a set of values. `std_dev` is the population standard deviation.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Summary {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Quantile {
+    pub quantile: f64,
+    pub score: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ThresholdSuggestion {
+    /// Expected fraction of anomalies.
+    pub contamination: f64,
+
+    /// Samples with a score above the threshold are anomalies.
+    pub threshold: f64,
+}
+
+impl Summary {
+    pub(crate) fn of(values: impl Iterator<Item = f64> + Clone) -> Self {
+        let count = values.clone().count() as f64;
+        let mean = values.clone().sum::<f64>() / count;
+        let variance = values
+            .clone()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Self {
+            min: values.clone().fold(f64::INFINITY, f64::min),
+            max: values.fold(f64::NEG_INFINITY, f64::max),
+            mean,
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+/// Quantile of sorted values, interpolating linearly between neighbouring values.
+pub(crate) fn quantile(sorted: &[f64], quantile: f64) -> f64 {
+    let position = quantile * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Summarize the forest and its scores on the evaluation data.
+    ///
+    /// `labels` mark the anomalies in `eval_data` and are used to compute the ROC AUC.
+    pub fn report<S>(&self, eval_data: &[S], labels: Option<&[bool]>) -> Result<Report, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if eval_data.is_empty() {
+            return Err(Error::EmptyData);
+        }
+        if let Some(labels) = labels {
+            if labels.len() != eval_data.len() {
+                return Err(Error::LabelCountMismatch {
+                    expected: eval_data.len(),
+                    actual: labels.len(),
+                });
+            }
+        }
+
+        let scores: Vec<f64> = eval_data.iter().map(|sample| self.score(sample)).collect();
+        let mut sorted_scores = scores.clone();
+        sorted_scores.sort_by(f64::total_cmp);
+
+        Ok(Report {
+            options: self.options.clone(),
+            dimensions: N,
+            model_hash: self.model_hash(),
+            num_samples: eval_data.len(),
+            features: (0..N)
+                .map(|i| {
+                    Summary::of(
+                        eval_data
+                            .iter()
+                            .map(move |sample| to_f64(sample.feature(i))),
+                    )
+                })
+                .collect(),
+            scores: Summary::of(scores.iter().copied()),
+            score_quantiles: QUANTILES
+                .iter()
+                .map(|q| Quantile {
+                    quantile: *q,
+                    score: quantile(&sorted_scores, *q),
+                })
+                .collect(),
+            thresholds: CONTAMINATIONS
+                .iter()
+                .map(|contamination| ThresholdSuggestion {
+                    contamination: *contamination,
+                    threshold: quantile(&sorted_scores, 1.0 - contamination),
+                })
+                .collect(),
+            roc_auc: labels.and_then(|labels| eval::roc_auc(&scores, labels)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn report_of_forest() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let forest: Forest<f64, 2> = Forest::from_slice(
+            values.as_slice(),
+            &ForestOptions {
+                n_trees: 100,
+                sample_size: 64,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // the center of the training data
+        let mut eval_data: Vec<_> = values
+            .iter()
+            .filter(|[x, y]| (5.0..15.0).contains(x) && (5.0..15.0).contains(y))
+            .copied()
+            .collect();
+        eval_data.extend([[100.0, 100.0], [-50.0, 10.0]]);
+        let mut labels = vec![false; 100];
+        labels.extend([true, true]);
+
+        let report = forest.report(&eval_data, Some(&labels)).unwrap();
+        assert_eq!(report.dimensions, 2);
+        assert_eq!(report.num_samples, 102);
+        assert_eq!(report.features[0].min, -50.0);
+        assert_eq!(report.features[1].max, 100.0);
+        assert!(report.scores.min <= report.score_quantiles[0].score);
+        assert!(report
+            .thresholds
+            .windows(2)
+            .all(|pair| pair[0].threshold >= pair[1].threshold));
+        assert!(report.roc_auc.unwrap() > 0.9);
+
+        assert_eq!(forest.report(&eval_data, None).unwrap().roc_auc, None);
+        assert!(matches!(
+            forest.report(&eval_data, Some(&labels[1..])),
+            Err(Error::LabelCountMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use rand::distributions::uniform::SampleUniform;
+use rand::Rng;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from rows read once in order, like the row groups of a Parquet file
+    /// or the results of a database query.
+    ///
+    /// Every tree keeps a uniform random subsample of `sample_size` rows while the rows are
+    /// read, so only `n_trees * sample_size` rows are kept in memory regardless of the number of
+    /// rows. The training range and the integer features of `ForestOptions::integer_jitter` are
+    /// determined from these subsamples.
+    pub fn from_stream<S>(
+        rows: impl IntoIterator<Item = S>,
+        options: &ForestOptions,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let (subsamples, num_rows) = reservoirs(
+            rows,
+            options.sample_size,
+            options.n_trees,
+            &mut rand::thread_rng(),
+        );
+        if num_rows < options.sample_size {
+            return Err(Error::InsufficientTrainingData);
+        }
+        let rows: Vec<[T; N]> = subsamples.into_iter().flatten().collect();
+        let mut next_tree = 0;
+        Self::train(
+            rows.len(),
+            |i| &rows[i],
+            |_| {
+                let start = next_tree * options.sample_size;
+                next_tree += 1;
+                (start..start + options.sample_size).collect()
+            },
+            options,
+        )
+    }
+}
+
+/// Draw `count` independent uniform subsamples of `size` rows in one pass over the rows, using
+/// algorithm L of Li (1994) so the rows are only touched when a subsample changes. Returns the
+/// subsamples and the number of rows.
+fn reservoirs<T, S, const N: usize>(
+    rows: impl IntoIterator<Item = S>,
+    size: usize,
+    count: usize,
+    rng: &mut impl Rng,
+) -> (Vec<Vec<[T; N]>>, usize)
+where
+    T: Copy,
+    S: SampleAccess<T, N>,
+{
+    let mut subsamples: Vec<Vec<[T; N]>> = (0..count).map(|_| Vec::with_capacity(size)).collect();
+    let mut weights = vec![1.0; count];
+    // the row at which each subsample changes next
+    let mut next = BinaryHeap::new();
+    let mut num_rows = 0;
+
+    for (index, row) in rows.into_iter().enumerate() {
+        num_rows = index + 1;
+        if index < size {
+            let row = row.to_array();
+            subsamples
+                .iter_mut()
+                .for_each(|subsample| subsample.push(row));
+            if num_rows == size {
+                for (tree, weight) in weights.iter_mut().enumerate() {
+                    *weight = (unit(rng).ln() / size as f64).exp();
+                    next.push(Reverse((index + skip(*weight, rng) + 1, tree)));
+                }
+            }
+            continue;
+        }
+        while let Some(Reverse((position, tree))) = next.peek().copied() {
+            if position != index {
+                break;
+            }
+            next.pop();
+            subsamples[tree][rng.gen_range(0..size)] = row.to_array();
+            weights[tree] *= (unit(rng).ln() / size as f64).exp();
+            next.push(Reverse((
+                index
+                    .saturating_add(skip(weights[tree], rng))
+                    .saturating_add(1),
+                tree,
+            )));
+        }
+    }
+    (subsamples, num_rows)
+}
+
+/// Uniformly distributed in `(0, 1]`.
+fn unit(rng: &mut impl Rng) -> f64 {
+    1.0 - rng.gen::<f64>()
+}
+
+/// Number of rows skipped before the next replacement.
+fn skip(weight: f64, rng: &mut impl Rng) -> usize {
+    // saturates for huge skips
+    (unit(rng).ln() / (1.0 - weight).ln()).floor() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reservoirs;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn subsamples_are_uniform() {
+        let rng = &mut rand::thread_rng();
+        let (subsamples, num_rows) = reservoirs((0..1000).map(|i| [i as f64]), 10, 2000, rng);
+        assert_eq!(num_rows, 1000);
+        assert!(subsamples.iter().all(|subsample| subsample.len() == 10));
+
+        // the mean of 20000 uniformly sampled rows has a standard deviation of about 2
+        let mean = subsamples.iter().flatten().map(|row| row[0]).sum::<f64>() / 20_000.0;
+        assert!((mean - 499.5).abs() < 15.0, "{}", mean);
+        let late = subsamples.iter().flatten().filter(|row| row[0] >= 900.0);
+        assert!((late.count() as f64 / 20_000.0 - 0.1).abs() < 0.02);
+
+        let (subsamples, num_rows) = reservoirs((0..5).map(|i| [i as f64]), 10, 3, rng);
+        assert_eq!(num_rows, 5);
+        assert!(subsamples.iter().all(|subsample| subsample.len() == 5));
+
+        let values = (0..10_000).map(|i| [(i % 100) as f64, (i / 100) as f64]);
+        let forest: Forest<f64, 2> =
+            Forest::from_stream(values, &ForestOptions::default()).unwrap();
+        assert!(forest.score(&[500.0, 500.0]) > forest.score(&[50.0, 50.0]));
+        assert!(matches!(
+            Forest::<f64, 2>::from_stream(vec![[1.0, 2.0]; 5], &ForestOptions::default()),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+}
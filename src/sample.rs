@@ -0,0 +1,299 @@
+use num_traits::Float;
+
+use crate::{hyperplane_offset, Error};
+
+/// Access to the features of a sample.
+///
+/// Training and scoring accept any type implementing this trait, so slices of structs or other
+/// row types can be used directly instead of first copying them into `[[T; N]]`.
+///
+/// ```rust
+/// use extended_isolation_forest::{Forest, ForestOptions, SampleAccess};
+///
+/// struct Measurement {
+///     temperature: f64,
+///     pressure: f64,
+/// }
+///
+/// impl SampleAccess<f64, 2> for Measurement {
+///     fn feature(&self, i: usize) -> f64 {
+///         match i {
+///             0 => self.temperature,
+///             _ => self.pressure,
+///         }
+///     }
+/// }
+///
+/// let measurements: Vec<_> = (0..100)
+///     .map(|i| Measurement {
+///         temperature: 20.0 + (i % 10) as f64,
+///         pressure: 1000.0 + (i / 10) as f64,
+///     })
+///     .collect();
+/// let forest: Forest<f64, 2> = Forest::from_slice(&measurements, &ForestOptions::default()).unwrap();
+/// let score = forest.score(&Measurement {
+///     temperature: 80.0,
+///     pressure: 900.0,
+/// });
+/// ```
+pub trait SampleAccess<T, const N: usize> {
+    /// The value of the feature with the index `i`. `i` is always smaller than `N`.
+    fn feature(&self, i: usize) -> T;
+
+    /// Copy all features into an array.
+    fn to_array(&self) -> [T; N] {
+        std::array::from_fn(|i| self.feature(i))
+    }
+
+    /// The dot product of `sample - p` and the normal vector `n`, which decides on which side of
+    /// a hyperplane the sample lies. Sample types which can compute it more efficiently than
+    /// by reading every feature, such as [`SparseSample`], override it.
+    fn direction(&self, n: &[T; N], p: &[T; N]) -> T
+    where
+        T: Float,
+    {
+        (0..N).fold(T::zero(), |sum, i| sum + (self.feature(i) - p[i]) * n[i])
+    }
+
+    /// [`SampleAccess::direction`] with the dot product `offset` of `n` and `p` precomputed
+    /// when the tree has been built. Defaults to `direction`, sample types for which the offset
+    /// saves work, such as [`SparseSample`], override it.
+    fn direction_with_offset(&self, n: &[T; N], p: &[T; N], offset: T) -> T
+    where
+        T: Float,
+    {
+        let _ = offset;
+        self.direction(n, p)
+    }
+}
+
+impl<T, const N: usize> SampleAccess<T, N> for [T; N]
+where
+    T: Copy,
+{
+    #[inline]
+    fn feature(&self, i: usize) -> T {
+        self[i]
+    }
+
+    fn to_array(&self) -> [T; N] {
+        *self
+    }
+}
+
+impl<T, const N: usize, S> SampleAccess<T, N> for &S
+where
+    S: SampleAccess<T, N> + ?Sized,
+{
+    #[inline]
+    fn feature(&self, i: usize) -> T {
+        (**self).feature(i)
+    }
+
+    fn to_array(&self) -> [T; N] {
+        (**self).to_array()
+    }
+
+    #[inline]
+    fn direction(&self, n: &[T; N], p: &[T; N]) -> T
+    where
+        T: Float,
+    {
+        (**self).direction(n, p)
+    }
+
+    #[inline]
+    fn direction_with_offset(&self, n: &[T; N], p: &[T; N], offset: T) -> T
+    where
+        T: Float,
+    {
+        (**self).direction_with_offset(n, p, offset)
+    }
+}
+
+/// A row of training data stored column-wise.
+pub(crate) struct ColumnRow<'a, T, const N: usize> {
+    pub columns: &'a [&'a [T]; N],
+    pub row: usize,
+}
+
+impl<T, const N: usize> SampleAccess<T, N> for ColumnRow<'_, T, N>
+where
+    T: Copy,
+{
+    #[inline]
+    fn feature(&self, i: usize) -> T {
+        self.columns[i][self.row]
+    }
+}
+
+/// A sample storing only its non-zero features as index/value pairs, all other features are
+/// zero.
+///
+/// Training and scoring read sparse samples without converting them to dense arrays, and the
+/// side of a hyperplane is computed from the stored features only.
+///
+/// ```rust
+/// use extended_isolation_forest::{Forest, ForestOptions, SparseSample};
+///
+/// let values: Vec<SparseSample<f64, 100>> = (0..1000)
+///     .map(|i| SparseSample::new([(i % 10, 1.0), (50, (i % 100) as f64)]).unwrap())
+///     .collect();
+/// let options = ForestOptions {
+///     n_trees: 100,
+///     sample_size: 200,
+///     ..Default::default()
+/// };
+/// let forest = Forest::from_slice(&values, &options).unwrap();
+///
+/// // non-zero in features which are always zero in the training data
+/// let sample = SparseSample::new((60..70).map(|i| (i, 1.0))).unwrap();
+/// assert!(forest.score(&sample) > forest.score(&values[0]));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseSample<T, const N: usize> {
+    /// Strictly increasing feature indices.
+    indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T, const N: usize> SparseSample<T, N>
+where
+    T: Float,
+{
+    /// Create a sample from index/value pairs in any order. The values of repeated indices are
+    /// added up.
+    pub fn new(features: impl IntoIterator<Item = (usize, T)>) -> Result<Self, Error> {
+        let mut features: Vec<_> = features.into_iter().collect();
+        if let Some((index, _)) = features.iter().find(|(index, _)| *index >= N) {
+            return Err(Error::FeatureIndexOutOfRange {
+                index: *index,
+                dimensions: N,
+            });
+        }
+        features.sort_by_key(|(index, _)| *index);
+
+        let mut indices: Vec<usize> = Vec::with_capacity(features.len());
+        let mut values: Vec<T> = Vec::with_capacity(features.len());
+        for (index, value) in features {
+            match (indices.last(), values.last_mut()) {
+                (Some(last), Some(sum)) if *last == index => *sum = *sum + value,
+                _ => {
+                    indices.push(index);
+                    values.push(value);
+                }
+            }
+        }
+        Ok(Self { indices, values })
+    }
+
+    /// The stored features as index/value pairs, ordered by index.
+    pub fn features(&self) -> impl Iterator<Item = (usize, T)> + '_ {
+        self.indices
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+}
+
+impl<T, const N: usize> SampleAccess<T, N> for SparseSample<T, N>
+where
+    T: Float,
+{
+    fn feature(&self, i: usize) -> T {
+        match self.indices.binary_search(&i) {
+            Ok(position) => self.values[position],
+            Err(_) => T::zero(),
+        }
+    }
+
+    fn to_array(&self) -> [T; N] {
+        let mut values = [T::zero(); N];
+        for (index, value) in self.features() {
+            values[index] = value;
+        }
+        values
+    }
+
+    fn direction(&self, n: &[T; N], p: &[T; N]) -> T {
+        self.direction_with_offset(n, p, hyperplane_offset(n, p))
+    }
+
+    /// Only reads the components of `n` of the stored features, so the cost grows with their
+    /// number instead of `N`.
+    fn direction_with_offset(&self, n: &[T; N], _p: &[T; N], offset: T) -> T {
+        self.features()
+            .fold(T::zero(), |sum, (index, value)| sum + value * n[index])
+            - offset
+    }
+}
+
+/// Statically sized vectors of [nalgebra](https://nalgebra.org), available with the `nalgebra`
+/// feature. Dynamically sized vectors are not supported as the dimension of a forest is fixed
+/// at compile time.
+#[cfg(feature = "nalgebra")]
+impl<T, const N: usize> SampleAccess<T, N> for nalgebra::SVector<T, N>
+where
+    T: nalgebra::Scalar + Copy,
+{
+    #[inline]
+    fn feature(&self, i: usize) -> T {
+        self[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Forest, ForestOptions, SampleAccess, SparseSample};
+
+    #[test]
+    fn sparse_samples() {
+        let sample: SparseSample<f64, 4> =
+            SparseSample::new([(2, 1.0), (0, 3.0), (2, 0.5)]).unwrap();
+        assert_eq!(
+            sample.features().collect::<Vec<_>>(),
+            vec![(0, 3.0), (2, 1.5)]
+        );
+        assert_eq!(sample.to_array(), [3.0, 0.0, 1.5, 0.0]);
+        assert_eq!(sample.feature(1), 0.0);
+        assert!(matches!(
+            SparseSample::<f64, 4>::new([(4, 1.0)]),
+            Err(Error::FeatureIndexOutOfRange {
+                index: 4,
+                dimensions: 4
+            })
+        ));
+
+        let values: Vec<_> = (0..200)
+            .map(|i| SparseSample::new([(i % 4, (i % 7) as f64), (i % 3, 1.0)]).unwrap())
+            .collect();
+        let forest: Forest<f64, 4> =
+            Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+        for sample in &values[..20] {
+            assert!((forest.score(sample) - forest.score(&sample.to_array())).abs() < 1e-12);
+        }
+        // only the components of the normal vector of the stored features are read, so the
+        // cost grows with their number and not with the dimension
+        let sample: SparseSample<f64, 1000> = SparseSample::new([(3, 2.0), (700, -1.0)]).unwrap();
+        let mut n = [f64::NAN; 1000];
+        (n[3], n[700]) = (0.5, 1.5);
+        let p = [f64::NAN; 1000];
+        assert_eq!(sample.direction_with_offset(&n, &p, 0.25), -0.75);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn score_nalgebra_vectors() {
+        use nalgebra::Vector2;
+
+        let values: Vec<_> = (0..400)
+            .map(|i| Vector2::new((i % 20) as f64, (i / 20) as f64))
+            .collect();
+        let forest: Forest<f64, 2> =
+            Forest::from_slice(values.as_slice(), &ForestOptions::default()).unwrap();
+
+        assert!(
+            forest.score(&Vector2::new(100.0, -50.0)) > forest.score(&Vector2::new(10.0, 10.0))
+        );
+    }
+}
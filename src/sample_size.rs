@@ -0,0 +1,238 @@
+//! Choosing `sample_size` from the training data.
+//!
+//! The sample size of 256 recommended for isolation forests assumes mostly distinct samples of
+//! a low intrinsic dimension. Telemetry often repeats the same readings, so most of a
+//! subsample of 256 rows are duplicates and the trees are built from a handful of distinct
+//! values. [`SampleSizeChoice::estimate`] measures the fraction of distinct rows and the
+//! intrinsic dimension of the data, and scales the sample size so every subsample contains
+//! enough distinct rows.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! // 100 distinct readings, each repeated 20 times
+//! let values: Vec<_> = (0..2000).map(|i| [(i % 10) as f64, (i % 100 / 10) as f64]).collect();
+//! let (forest, choice) =
+//!     Forest::<f64, 2>::from_slice_auto_sample_size(&values, &ForestOptions::default()).unwrap();
+//!
+//! assert_eq!(choice.distinct_fraction, 0.05);
+//! assert_eq!(forest.options().sample_size, choice.sample_size);
+//! assert_eq!(choice.sample_size, 2000);
+//! ```
+
+use std::collections::HashSet;
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand::seq::index;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::{to_f64, Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Distinct rows used for estimating the intrinsic dimension.
+const PROBE_ROWS: usize = 1000;
+
+/// Neighbors of every probe row considered by the estimate of the intrinsic dimension.
+const NEIGHBORS: usize = 10;
+
+/// Larger sample sizes are never chosen.
+const MAX_SAMPLE_SIZE: usize = 8192;
+
+/// The sample size chosen for some training data, and the properties of the data it has been
+/// chosen from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleSizeChoice {
+    /// The chosen sample size, at most the number of rows.
+    pub sample_size: usize,
+
+    /// The number of distinct rows divided by the number of rows.
+    pub distinct_fraction: f64,
+
+    /// Maximum likelihood estimate of the intrinsic dimension of the distinct rows, at most
+    /// the number of dimensions, zero with fewer than three distinct rows.
+    pub intrinsic_dimension: f64,
+}
+
+impl SampleSizeChoice {
+    /// Choose the sample size for the training data.
+    ///
+    /// A subsample should contain 256 distinct rows for data of up to four intrinsic
+    /// dimensions, and 64 more for every further dimension. The sample size is this number
+    /// divided by the distinct fraction, so subsamples of heavily duplicated data are larger,
+    /// limited to 8192 and the number of rows.
+    ///
+    /// The intrinsic dimension is estimated following Levina and Bickel (2004) from the 10
+    /// nearest neighbors of up to 1000 distinct rows, with every dimension scaled to the range
+    /// of these rows. Ties of the distances on quantized data do not distort it.
+    pub fn estimate<T, S, const N: usize>(training_data: &[S]) -> Self
+    where
+        T: Float,
+        S: SampleAccess<T, N>,
+    {
+        let mut seen = HashSet::new();
+        let mut distinct = Vec::new();
+        for sample in training_data {
+            let row: [f64; N] = sample.to_array().map(to_f64);
+            if seen.insert(row.map(f64::to_bits)) {
+                distinct.push(row);
+            }
+        }
+
+        let distinct_fraction = if training_data.is_empty() {
+            0.0
+        } else {
+            distinct.len() as f64 / training_data.len() as f64
+        };
+        let probe: Vec<[f64; N]> = index::sample(
+            &mut rand::thread_rng(),
+            distinct.len(),
+            PROBE_ROWS.min(distinct.len()),
+        )
+        .into_iter()
+        .map(|i| distinct[i])
+        .collect();
+        let intrinsic_dimension = intrinsic_dimension(&probe).min(N as f64);
+
+        let distinct_rows = 256.0 + 64.0 * (intrinsic_dimension.ceil() - 4.0).max(0.0);
+        let sample_size = if distinct_fraction > 0.0 {
+            (distinct_rows / distinct_fraction).ceil() as usize
+        } else {
+            0
+        };
+        Self {
+            sample_size: sample_size.min(MAX_SAMPLE_SIZE).min(training_data.len()),
+            distinct_fraction,
+            intrinsic_dimension,
+        }
+    }
+}
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the given training data like [`Forest::from_slice`], with
+    /// `options.sample_size` replaced by the sample size chosen by
+    /// [`SampleSizeChoice::estimate`]. The choice is returned together with the forest.
+    pub fn from_slice_auto_sample_size<S>(
+        training_data: &[S],
+        options: &ForestOptions,
+    ) -> Result<(Self, SampleSizeChoice), Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let choice = SampleSizeChoice::estimate(training_data);
+        let options = ForestOptions {
+            sample_size: choice.sample_size,
+            ..options.clone()
+        };
+        Ok((Self::from_slice(training_data, &options)?, choice))
+    }
+}
+
+/// The mean of the inverse local estimates of the dimension around every row, inverted as
+/// suggested by MacKay and Ghahramani.
+fn intrinsic_dimension<const N: usize>(rows: &[[f64; N]]) -> f64 {
+    let neighbors = NEIGHBORS.min(rows.len().saturating_sub(1));
+    if neighbors < 2 {
+        return 0.0;
+    }
+
+    let mut min = [f64::INFINITY; N];
+    let mut max = [f64::NEG_INFINITY; N];
+    for row in rows {
+        for i in 0..N {
+            min[i] = min[i].min(row[i]);
+            max[i] = max[i].max(row[i]);
+        }
+    }
+    let scale: [f64; N] = std::array::from_fn(|i| {
+        let range = max[i] - min[i];
+        if range > 0.0 && range.is_finite() {
+            1.0 / range
+        } else {
+            0.0
+        }
+    });
+
+    let mut inverse_sum = 0.0;
+    let mut counted = 0;
+    let mut distances = Vec::with_capacity(rows.len());
+    for row in rows {
+        distances.clear();
+        distances.extend(rows.iter().map(|other| {
+            (0..N)
+                .map(|i| ((row[i] - other[i]) * scale[i]).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        }));
+        // the row itself is the nearest at a distance of zero
+        distances.select_nth_unstable_by(neighbors, f64::total_cmp);
+        let nearest = &mut distances[..=neighbors];
+        nearest.sort_unstable_by(f64::total_cmp);
+        let farthest = nearest[neighbors];
+        if nearest[1] <= 0.0 || !farthest.is_finite() {
+            continue;
+        }
+        let log_ratios: f64 = nearest[1..neighbors]
+            .iter()
+            .map(|distance| (farthest / distance).ln())
+            .sum();
+        inverse_sum += log_ratios / (neighbors - 1) as f64;
+        counted += 1;
+    }
+    if counted == 0 || inverse_sum <= 0.0 {
+        0.0
+    } else {
+        counted as f64 / inverse_sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::{intrinsic_dimension, SampleSizeChoice};
+
+    #[test]
+    fn sample_size_grows_with_duplicates_and_dimension() {
+        let rng = &mut rand::thread_rng();
+        let line: Vec<[f64; 3]> = (0..1000)
+            .map(|_| {
+                let t = rng.gen::<f64>();
+                [t, 2.0 * t, -t]
+            })
+            .collect();
+        let plane: Vec<[f64; 3]> = (0..1000).map(|_| [rng.gen(), rng.gen(), 0.5]).collect();
+        assert!((intrinsic_dimension(&line) - 1.0).abs() < 0.3);
+        assert!((intrinsic_dimension(&plane) - 2.0).abs() < 0.5);
+        let grid: Vec<[f64; 2]> = (0..900)
+            .map(|i| [(i % 30) as f64, (i / 30) as f64])
+            .collect();
+        assert!((intrinsic_dimension(&grid) - 2.0).abs() < 0.5);
+
+        let choice = SampleSizeChoice::estimate(line.as_slice());
+        assert_eq!((choice.sample_size, choice.distinct_fraction), (256, 1.0));
+
+        // every row four times
+        let repeated: Vec<[f64; 3]> = line.iter().flat_map(|row| [*row; 4]).collect();
+        let choice = SampleSizeChoice::estimate(repeated.as_slice());
+        assert_eq!((choice.sample_size, choice.distinct_fraction), (1024, 0.25));
+
+        let cube: Vec<[f64; 8]> = (0..2000).map(|_| rng.gen()).collect();
+        let choice = SampleSizeChoice::estimate(cube.as_slice());
+        assert!(choice.intrinsic_dimension > 5.0, "{:?}", choice);
+        assert!(choice.sample_size > 256);
+
+        let constant = SampleSizeChoice::estimate(&[[1.0f64]; 10]);
+        assert_eq!(
+            (constant.sample_size, constant.intrinsic_dimension),
+            (10, 0.0)
+        );
+        assert_eq!(
+            SampleSizeChoice::estimate::<f64, [f64; 1], 1>(&[]).sample_size,
+            0
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! One forest per phase of a season, like the hour of the day.
+//!
+//! Traffic at 3 am differs from traffic at noon, so a single forest flags every quiet night as
+//! anomalous. A [`SeasonalForest`] assigns samples to the phase of their timestamp and scores
+//! them against the forest of that phase, built from the samples of the same phase. Timestamps
+//! are seconds since the Unix epoch.
+//!
+//! ```rust
+//! use extended_isolation_forest::seasonal::{Season, SeasonalForest};
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let mut forests = SeasonalForest::new(Season::hour_of_day(), ForestOptions::default(), 50);
+//! for day in 0..50 {
+//!     for hour in 0..24 {
+//!         let timestamp = (day * 24 + hour) * 3600;
+//!         let requests = if hour < 6 { 10.0 } else { 1000.0 } + (day % 10) as f64;
+//!         forests.add_sample(timestamp, [requests]).unwrap();
+//!     }
+//! }
+//!
+//! // normal at night, but not at noon
+//! let night = 3 * 3600;
+//! let noon = 12 * 3600;
+//! assert!(forests.score(noon, &[15.0]).unwrap() > forests.score(night, &[15.0]).unwrap());
+//! ```
+
+use std::time::Duration;
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::grouped::GroupedForest;
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+const HOUR: u64 = 3600;
+const DAY: u64 = 24 * HOUR;
+
+/// The Unix epoch was a Thursday, shifting by three days makes Monday the first day.
+const MONDAY: i64 = 3 * DAY as i64;
+
+/// A period divided into phases of equal length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Season {
+    /// Length of the period in seconds.
+    period: u64,
+    phases: usize,
+
+    /// Seconds added to timestamps before determining their phase.
+    offset: i64,
+}
+
+impl Season {
+    /// Divide the period into `phases` phases, both at least one second and one phase.
+    pub fn new(period: Duration, phases: usize) -> Self {
+        Self {
+            period: period.as_secs().max(1),
+            phases: phases.max(1),
+            offset: 0,
+        }
+    }
+
+    /// The 24 hours of a day in UTC, starting at midnight.
+    pub fn hour_of_day() -> Self {
+        Self::new(Duration::from_secs(DAY), 24)
+    }
+
+    /// The 7 days of a week in UTC, starting on Monday.
+    pub fn day_of_week() -> Self {
+        Self::new(Duration::from_secs(7 * DAY), 7).with_offset(MONDAY)
+    }
+
+    /// The 168 hours of a week in UTC, starting on Monday at midnight.
+    pub fn hour_of_week() -> Self {
+        Self::new(Duration::from_secs(7 * DAY), 168).with_offset(MONDAY)
+    }
+
+    /// Shift the phases by `seconds`, for example by the UTC offset of a time zone to make
+    /// hours of the day local.
+    pub fn with_offset(self, seconds: i64) -> Self {
+        Self {
+            offset: self.offset + seconds,
+            ..self
+        }
+    }
+
+    pub fn phases(&self) -> usize {
+        self.phases
+    }
+
+    /// The phase of the timestamp, in `0..phases`.
+    pub fn phase(&self, timestamp: u64) -> usize {
+        let time = (timestamp as i128 + self.offset as i128).rem_euclid(self.period as i128);
+        (time * self.phases as i128 / self.period as i128) as usize
+    }
+}
+
+/// Forests trained per phase of a season with shared options.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: Serialize",
+        deserialize = "T: Deserialize<'de> + Float"
+    ))
+)]
+pub struct SeasonalForest<T, const N: usize> {
+    season: Season,
+    forests: GroupedForest<usize, T, N>,
+}
+
+impl<'de, T, const N: usize> SeasonalForest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// The forest of a phase is trained from its first `training_size` samples.
+    pub fn new(season: Season, options: ForestOptions, training_size: usize) -> Self {
+        Self {
+            season,
+            forests: GroupedForest::new(options, training_size),
+        }
+    }
+
+    /// Train the forests of all phases of the training data, `timestamps` holds the timestamp
+    /// of every sample. Samples of phases with less than `training_size` samples are buffered.
+    pub fn from_slice<S>(
+        timestamps: &[u64],
+        training_data: &[S],
+        season: Season,
+        options: ForestOptions,
+        training_size: usize,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let phases: Vec<usize> = timestamps
+            .iter()
+            .map(|timestamp| season.phase(*timestamp))
+            .collect();
+        if phases.len() != training_data.len() {
+            return Err(Error::KeyCountMismatch {
+                expected: training_data.len(),
+                actual: phases.len(),
+            });
+        }
+        Ok(Self {
+            season,
+            forests: GroupedForest::from_groups(training_data, &phases, options, training_size)?,
+        })
+    }
+
+    /// Add a training sample to the phase of the timestamp, see [`GroupedForest::add_sample`].
+    /// Returns `true` when the forest of the phase has been trained by this call.
+    pub fn add_sample<S>(&mut self, timestamp: u64, sample: S) -> Result<bool, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        self.forests
+            .add_sample(self.season.phase(timestamp), sample)
+    }
+
+    /// Train the forests of all phases with buffered samples, even if there are less than
+    /// `training_size`. Fails on the first phase with too few samples for a forest, whose
+    /// samples stay buffered.
+    pub fn train_pending(&mut self) -> Result<(), Error> {
+        let mut pending: Vec<usize> = self.forests.pending_groups().copied().collect();
+        pending.sort_unstable();
+        for phase in pending {
+            self.forests.train_group(&phase)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> SeasonalForest<T, N>
+where
+    T: Float,
+{
+    /// Score the sample with the forest of the phase of the timestamp. Returns `None` if the
+    /// phase has no trained forest yet.
+    pub fn score<S>(&self, timestamp: u64, sample: &S) -> Option<f64>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.forests.score(&self.season.phase(timestamp), sample)
+    }
+
+    pub fn season(&self) -> &Season {
+        &self.season
+    }
+
+    /// The forest of the phase, see [`Season::phase`].
+    pub fn forest(&self, phase: usize) -> Option<&Forest<T, N>> {
+        self.forests.forest(&phase)
+    }
+
+    /// The forests keyed by phase.
+    pub fn grouped(&self) -> &GroupedForest<usize, T, N> {
+        &self.forests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Season, SeasonalForest, DAY, HOUR};
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn samples_are_routed_by_phase() {
+        // 2024-01-01 was a Monday
+        let monday = 1_704_067_200;
+        assert_eq!(Season::day_of_week().phase(monday), 0);
+        assert_eq!(Season::day_of_week().phase(monday - 1), 6);
+        assert_eq!(Season::hour_of_day().phase(monday + 13 * HOUR + 59), 13);
+        assert_eq!(Season::hour_of_week().phase(monday + DAY + 2 * HOUR), 26);
+        // UTC+2
+        assert_eq!(
+            Season::hour_of_day()
+                .with_offset(2 * HOUR as i64)
+                .phase(monday + 23 * HOUR),
+            1
+        );
+        assert_eq!(Season::new(Duration::ZERO, 0).phase(monday), 0);
+
+        // weekdays busy, weekends quiet
+        let timestamps: Vec<u64> = (0..140).map(|day| monday + day * DAY).collect();
+        let values: Vec<_> = (0..140)
+            .map(|day| match day % 7 {
+                5 | 6 => [100.0 + day as f64],
+                _ => [1000.0 + day as f64],
+            })
+            .collect();
+        let mut forests = SeasonalForest::from_slice(
+            &timestamps,
+            &values,
+            Season::day_of_week(),
+            ForestOptions::default(),
+            30,
+        )
+        .unwrap();
+        assert_eq!(forests.grouped().trained_groups().count(), 0);
+        forests.train_pending().unwrap();
+        assert_eq!(forests.grouped().trained_groups().count(), 7);
+
+        let saturday = monday + 5 * DAY;
+        assert!(
+            forests.score(saturday, &[1000.0]).unwrap()
+                > forests.score(saturday, &[130.0]).unwrap()
+        );
+        assert!(
+            forests.score(monday, &[130.0]).unwrap() > forests.score(monday, &[1030.0]).unwrap()
+        );
+        assert!(forests.forest(7).is_none());
+
+        assert!(matches!(
+            SeasonalForest::<f64, 1>::from_slice(
+                &timestamps[1..],
+                &values,
+                Season::day_of_week(),
+                ForestOptions::default(),
+                20
+            ),
+            Err(Error::KeyCountMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+//! Reproducible training from a master seed.
+//!
+//! Without [`ForestOptions::seed`] the trees draw their random numbers from the thread-local
+//! generator of `rand`. With a seed, every tree gets its own generator derived only from the
+//! master seed and the index of the tree, so the same seed builds the same forest no matter
+//! in which order or on how many threads the trees are built.
+//!
+//! The seed of tree `i` is the output of the SplitMix64 mixing function for the state
+//! `seed + (i + 1) * 0x9e37_79b9_7f4a_7c15`. The generator of the tree is a Xoshiro256++
+//! whose four state words are the next four outputs of SplitMix64 started at the seed of the
+//! tree. Both algorithms are fixed, so seeded forests stay reproducible across versions of this
+//! crate and of `rand`.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     seed: Some(42),
+//!     ..Default::default()
+//! };
+//! let a: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//! let b: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//! assert_eq!(a.score(&[3.0, 7.0]), b.score(&[3.0, 7.0]));
+//! ```
+//!
+//! [`ForestOptions::seed`]: crate::ForestOptions::seed
+
+use rand::rngs::ThreadRng;
+use rand::RngCore;
+
+/// The increment of the SplitMix64 state, the fractional part of the golden ratio.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The random number generator used while building a tree.
+#[derive(Clone, Debug)]
+pub struct TreeRng(Generator);
+
+#[derive(Clone, Debug)]
+enum Generator {
+    Thread(ThreadRng),
+    Seeded(Xoshiro256PlusPlus),
+}
+
+impl TreeRng {
+    /// The thread-local generator of `rand`.
+    pub fn thread() -> Self {
+        Self(Generator::Thread(rand::thread_rng()))
+    }
+
+    /// The generator of the tree with index `tree_index` of a forest trained from `seed`.
+    pub fn for_tree(seed: u64, tree_index: usize) -> Self {
+        let tree_seed = split_mix(
+            seed.wrapping_add(
+                (tree_index as u64)
+                    .wrapping_add(1)
+                    .wrapping_mul(GOLDEN_GAMMA),
+            ),
+        );
+        Self::from_seed(tree_seed)
+    }
+
+    /// A generator seeded directly, without deriving a seed per tree.
+    pub fn from_seed(seed: u64) -> Self {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_add(GOLDEN_GAMMA);
+            split_mix(state)
+        };
+        Self(Generator::Seeded(Xoshiro256PlusPlus {
+            s: [next(), next(), next(), next()],
+        }))
+    }
+
+    /// The generator for a tree, thread-local without seed.
+    pub(crate) fn new(seed: Option<u64>, tree_index: usize) -> Self {
+        match seed {
+            Some(seed) => Self::for_tree(seed, tree_index),
+            None => Self::thread(),
+        }
+    }
+}
+
+impl RngCore for TreeRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match &mut self.0 {
+            Generator::Thread(rng) => rng.next_u64(),
+            Generator::Seeded(rng) => rng.next(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The SplitMix64 mixing function.
+fn split_mix(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Clone, Debug)]
+struct Xoshiro256PlusPlus {
+    s: [u64; 4],
+}
+
+impl Xoshiro256PlusPlus {
+    fn next(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::RngCore;
+
+    use super::{split_mix, TreeRng};
+    use crate::{ExtensionLevel, Forest, ForestOptions};
+
+    #[test]
+    fn seeded_forests_are_reproducible() {
+        // reference values of SplitMix64 seeded with 1234567
+        let mut state = 1234567u64;
+        let outputs: Vec<u64> = (0..2)
+            .map(|_| {
+                state = state.wrapping_add(super::GOLDEN_GAMMA);
+                split_mix(state)
+            })
+            .collect();
+        assert_eq!(outputs, [6457827717110365317, 3203168211198807973]);
+
+        let mut a = TreeRng::for_tree(7, 3);
+        let mut b = TreeRng::for_tree(7, 3);
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(
+            TreeRng::for_tree(7, 3).next_u64(),
+            TreeRng::for_tree(7, 4).next_u64()
+        );
+        let mut bytes = [0u8; 11];
+        a.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..8], b.next_u64().to_le_bytes());
+
+        let values: Vec<_> = (0..200)
+            .map(|i| [(i % 10) as f64, (i / 10) as f64, (i % 7) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 30,
+            sample_size: 64,
+            extension_level: ExtensionLevel::Range { min: 0, max: 2 },
+            integer_jitter: true,
+            seed: Some(42),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        let again: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        let other: Forest<f64, 3> = Forest::from_slice(
+            &values,
+            &ForestOptions {
+                seed: Some(43),
+                ..options.clone()
+            },
+        )
+        .unwrap();
+        for sample in [[1.0, 2.0, 3.0], [4.5, 4.5, 4.5], [-30.0, 100.0, 0.0]] {
+            assert_eq!(forest.score(&sample), again.score(&sample));
+        }
+        assert_ne!(
+            forest.score(&[4.5, 4.5, 4.5]),
+            other.score(&[4.5, 4.5, 4.5])
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! How the score of a sample reacts to small changes of its features.
+//!
+//! [`Forest::sensitivity`] moves every dimension of a sample by each of the given deltas and
+//! reports how the score changes, for estimating how close a normal sample is to crossing an
+//! alert threshold. Moving a single dimension `i` by `δ` shifts the position of the sample
+//! relative to a hyperplane with normal vector `n` by `n[i] · δ`, so all perturbed samples are
+//! routed through a tree together and the position of the unperturbed sample is computed only
+//! once per node.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     seed: Some(3),
+//!     ..Default::default()
+//! };
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//!
+//! let sensitivity = forest.sensitivity(&[4.5, 4.5], &[-1.0, 20.0]);
+//! assert_eq!(sensitivity.changes.len(), 2);
+//! // moving a dimension far outside of the training data raises the score most
+//! let (_, delta, change) = sensitivity.max_increase().unwrap();
+//! assert_eq!(delta, 20.0);
+//! assert!(sensitivity.score + change > 0.5);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::envelope::RangePolicy;
+use crate::leaf::LeafModel;
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{c_factor, normalize_path_length, to_f64, Forest, Node, SampleAccess, Tree};
+
+/// Score changes of a sample for perturbations of its dimensions, see [`Forest::sensitivity`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sensitivity<const N: usize> {
+    /// The score of the unperturbed sample.
+    pub score: f64,
+
+    /// The deltas the dimensions have been moved by.
+    pub deltas: Vec<f64>,
+
+    /// Per delta the change of the score when moving each dimension by the delta:
+    /// `changes[k][i]` for dimension `i` moved by `deltas[k]`.
+    #[cfg_attr(feature = "serde", serde(with = "serde_array::vec"))]
+    pub changes: Vec<[f64; N]>,
+}
+
+impl<const N: usize> Sensitivity<N> {
+    /// The perturbation raising the score the most as `(dimension, delta, change)`, `None`
+    /// if no perturbation raises it.
+    pub fn max_increase(&self) -> Option<(usize, f64, f64)> {
+        self.deltas
+            .iter()
+            .zip(self.changes.iter())
+            .flat_map(|(delta, changes)| {
+                changes
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, change)| (i, *delta, *change))
+            })
+            .filter(|(_, _, change)| *change > 0.0)
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+    }
+}
+
+/// A perturbed sample: the unperturbed sample moved by `delta` in `dimension`.
+pub(crate) struct Perturbation<T> {
+    pub dimension: usize,
+    pub delta: T,
+
+    /// Sum of the path lengths in the trees routed so far.
+    pub path_length: f64,
+}
+
+impl<T> Perturbation<T> {
+    pub fn new(dimension: usize, delta: T) -> Self {
+        Self {
+            dimension,
+            delta,
+            path_length: 0.0,
+        }
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Move every dimension of the sample by each of the deltas and report the changes of the
+    /// score, see the [module documentation](self).
+    ///
+    /// Suppressed regions, monotone constraints and clamping to the training range are applied
+    /// to every perturbed sample like in [`Forest::score`], but samples are not counted by
+    /// [`RangePolicy::Warn`] and the path lengths are computed with the regular floating point
+    /// arithmetic even in strict mode.
+    pub fn sensitivity<S>(&self, sample: &S, deltas: &[T]) -> Sensitivity<N>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let original = sample.to_array();
+        let base = self.adjusted_without_counting(&original);
+        let mut suppressed = vec![self.is_suppressed(&original)];
+        // the unperturbed sample comes first
+        let mut perturbations = vec![Perturbation::new(0, T::zero())];
+        for delta in deltas.iter() {
+            for i in 0..N {
+                let mut perturbed = original;
+                perturbed[i] = perturbed[i] + *delta;
+                suppressed.push(self.is_suppressed(&perturbed));
+                // the adjustments work per dimension, so they only change dimension `i`
+                let adjusted = self.adjusted_without_counting(&perturbed);
+                perturbations.push(Perturbation::new(i, adjusted[i] - base[i]));
+            }
+        }
+
+        let scores = self.perturbed_scores(&base, perturbations, &suppressed);
+        Sensitivity {
+            score: scores[0],
+            deltas: deltas.iter().map(|delta| to_f64(*delta)).collect(),
+            changes: scores[1..]
+                .chunks(N.max(1))
+                .map(|chunk| std::array::from_fn(|i| chunk[i] - scores[0]))
+                .collect(),
+        }
+    }
+
+    /// The scores of the perturbed samples, `0` for the suppressed ones.
+    pub(crate) fn perturbed_scores(
+        &self,
+        base: &[T; N],
+        mut perturbations: Vec<Perturbation<T>>,
+        suppressed: &[bool],
+    ) -> Vec<f64> {
+        let max_depth = self.recursion_cap();
+        for tree in self.trees.iter() {
+            route(tree, base, &mut perturbations, max_depth);
+        }
+        perturbations
+            .iter()
+            .zip(suppressed.iter())
+            .map(|(perturbation, suppressed)| {
+                if *suppressed {
+                    0.0
+                } else {
+                    normalize_path_length(
+                        perturbation.path_length / self.trees.len().max(1) as f64,
+                        self.avg_path_length_c,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// The sample after the monotone constraints and clamping, without counting it for
+    /// `RangePolicy::Warn`.
+    pub(crate) fn adjusted_without_counting(&self, sample: &[T; N]) -> [T; N] {
+        let adjusted = self.apply_monotone_constraints(sample).unwrap_or(*sample);
+        if self.range_policy == RangePolicy::Clamp {
+            if let Some(clamped) = self.apply_range_policy(&adjusted) {
+                return clamped;
+            }
+        }
+        adjusted
+    }
+}
+
+/// Add the path lengths of all perturbations in the tree, like `Tree::path_length_with_cap`.
+fn route<T, const N: usize, L>(
+    tree: &Tree<T, N, L>,
+    base: &[T; N],
+    perturbations: &mut [Perturbation<T>],
+    max_depth: usize,
+) where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    let mut stack = vec![(&tree.root, 0, (0..perturbations.len()).collect::<Vec<_>>())];
+    while let Some((node, depth, group)) = stack.pop() {
+        let path_length = match node.skip_depth_offset(depth, max_depth) {
+            None => Some(max_depth as f64),
+            Some(depth) => match node {
+                Node::Ex(ex_node) => Some(depth as f64 + c_factor(ex_node.num_samples)),
+                Node::In(_) if depth >= max_depth => Some(depth as f64),
+                Node::In(in_node) => {
+                    let direction = base.direction(&in_node.n, &in_node.p);
+                    let (left, right): (Vec<usize>, Vec<usize>) = group.iter().partition(|index| {
+                        let perturbation = &perturbations[**index];
+                        direction + in_node.n[perturbation.dimension] * perturbation.delta
+                            <= T::zero()
+                    });
+                    for (child, group) in [(&in_node.left, left), (&in_node.right, right)] {
+                        if !group.is_empty() {
+                            stack.push((child.as_ref(), depth + 1, group));
+                        }
+                    }
+                    None
+                }
+            },
+        };
+        if let Some(path_length) = path_length {
+            for index in group {
+                perturbations[index].path_length += path_length;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::envelope::RangePolicy;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn sensitivity_matches_perturbed_scores() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, (i % 7) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 40,
+            sample_size: 64,
+            extension_level: 2.into(),
+            seed: Some(13),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        let sample = [12.3, 4.1, 2.6];
+        let deltas = [-3.7, -0.45, 0.45, 3.7, 40.1];
+
+        for range_policy in [RangePolicy::Ignore, RangePolicy::Clamp] {
+            forest.set_range_policy(range_policy);
+            let sensitivity = forest.sensitivity(&sample, &deltas);
+            assert_eq!(sensitivity.score, forest.score(&sample));
+            assert_eq!(sensitivity.deltas, deltas);
+            for (delta, changes) in deltas.iter().zip(sensitivity.changes.iter()) {
+                for (i, change) in changes.iter().enumerate() {
+                    let mut perturbed = sample;
+                    perturbed[i] += delta;
+                    let expected = forest.score(&perturbed) - forest.score(&sample);
+                    assert!((change - expected).abs() < 1e-12, "{} {}", i, delta);
+                }
+            }
+            let (_, delta, change) = sensitivity.max_increase().unwrap();
+            assert!(change > 0.0);
+            if range_policy == RangePolicy::Ignore {
+                assert_eq!(delta, 40.1);
+            }
+        }
+        assert!(forest.sensitivity(&sample, &[]).changes.is_empty());
+    }
+}
@@ -58,3 +58,184 @@ where
 {
     deserializer.deserialize_tuple(N, ArrayVisitor::<T, N>(PhantomData))
 }
+
+/// (De)serialize a `Vec<[T; N]>` as a sequence of arrays.
+pub mod vec {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    struct ArrayRef<'a, T, const N: usize>(&'a [T; N]);
+
+    impl<T: Serialize, const N: usize> Serialize for ArrayRef<'_, T, N> {
+        fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, ser)
+        }
+    }
+
+    struct Array<T, const N: usize>([T; N]);
+
+    impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for Array<T, N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            super::deserialize(deserializer).map(Array)
+        }
+    }
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+        data: &[[T; N]],
+        ser: S,
+    ) -> Result<S::Ok, S::Error> {
+        ser.collect_seq(data.iter().map(ArrayRef))
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<Vec<[T; N]>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let arrays: Vec<Array<T, N>> = Vec::deserialize(deserializer)?;
+        Ok(arrays.into_iter().map(|Array(array)| array).collect())
+    }
+}
+
+/// (De)serialize a `HashMap<K, Vec<[T; N]>>` as a map of sequences of arrays.
+pub mod map_of_vecs {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    struct ArraysRef<'a, T, const N: usize>(&'a [[T; N]]);
+
+    impl<T: Serialize, const N: usize> Serialize for ArraysRef<'_, T, N> {
+        fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+            super::vec::serialize(self.0, ser)
+        }
+    }
+
+    struct Arrays<T, const N: usize>(Vec<[T; N]>);
+
+    impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for Arrays<T, N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            super::vec::deserialize(deserializer).map(Arrays)
+        }
+    }
+
+    pub fn serialize<S, K, T, const N: usize>(
+        data: &HashMap<K, Vec<[T; N]>>,
+        ser: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Serialize,
+        T: Serialize,
+    {
+        ser.collect_map(data.iter().map(|(key, arrays)| (key, ArraysRef(arrays))))
+    }
+
+    pub fn deserialize<'de, D, K, T, const N: usize>(
+        deserializer: D,
+    ) -> Result<HashMap<K, Vec<[T; N]>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de> + Eq + Hash,
+        T: Deserialize<'de>,
+    {
+        let map: HashMap<K, Arrays<T, N>> = HashMap::deserialize(deserializer)?;
+        Ok(map
+            .into_iter()
+            .map(|(key, Arrays(arrays))| (key, arrays))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use crate::eif::{EifForest, EifNode};
+    use crate::leaf::GaussianLeaf;
+    use crate::{ExtensionLevel, Forest, ForestOptions};
+
+    /// The binary encodings, compact MessagePack writes structs as arrays of their fields.
+    #[derive(Clone, Copy, Debug)]
+    enum Encoding {
+        MessagePack,
+        MessagePackNamed,
+        Cbor,
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T, encoding: Encoding) -> T {
+        match encoding {
+            Encoding::MessagePack => {
+                rmp_serde::from_slice(&rmp_serde::to_vec(value).unwrap()).unwrap()
+            }
+            Encoding::MessagePackNamed => {
+                rmp_serde::from_slice(&rmp_serde::to_vec_named(value).unwrap()).unwrap()
+            }
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).unwrap();
+                ciborium::from_reader(bytes.as_slice()).unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_binary_formats() {
+        for encoding in [
+            Encoding::MessagePack,
+            Encoding::MessagePackNamed,
+            Encoding::Cbor,
+        ] {
+            for extension_level in [
+                ExtensionLevel::Fixed(1),
+                ExtensionLevel::Range { min: 0, max: 2 },
+                ExtensionLevel::List(vec![0, 2]),
+            ] {
+                let options = ForestOptions {
+                    extension_level,
+                    integer_jitter: true,
+                    leaf_samples: 2,
+                    ..Default::default()
+                };
+                assert_eq!(round_trip(&options, encoding), options, "{:?}", encoding);
+
+                let values: Vec<_> = (0..100)
+                    .map(|i| [(i % 10) as f64, (i / 10) as f64, i as f64 * 0.5])
+                    .collect();
+                let forest: Forest<f64, 3> =
+                    Forest::from_slice(values.as_slice(), &options).unwrap();
+                let restored = round_trip(&forest, encoding);
+                assert_eq!(restored.model_hash(), forest.model_hash());
+                assert_eq!(restored.options, forest.options);
+                for sample in [[4.0, 5.0, 20.0], [50.0, -3.0, 0.5]] {
+                    assert_eq!(restored.score(&sample), forest.score(&sample));
+                }
+            }
+
+            let eif = EifForest {
+                sample_size: 4,
+                trees: vec![EifNode::InNode {
+                    n: vec![1.0, -0.5],
+                    p: vec![0.5, 0.0],
+                    left: Box::new(EifNode::ExNode { size: 3 }),
+                    right: Box::new(EifNode::ExNode { size: 1 }),
+                }],
+            };
+            assert_eq!(round_trip(&eif, encoding), eif);
+
+            let values: Vec<_> = (0..100)
+                .map(|i| [(i % 10) as f32, (i / 10) as f32])
+                .collect();
+            let forest: Forest<f32, 2, GaussianLeaf<2>> =
+                Forest::from_slice_with_leaves(values.as_slice(), &ForestOptions::default())
+                    .unwrap();
+            let restored = round_trip(&forest, encoding);
+            assert_eq!(restored.score(&[4.5, 20.0]), forest.score(&[4.5, 20.0]));
+            assert_eq!(
+                restored.gaussian_tail_score(&[4.5, 20.0]),
+                forest.gaussian_tail_score(&[4.5, 20.0])
+            );
+        }
+    }
+}
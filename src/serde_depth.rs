@@ -0,0 +1,55 @@
+//! Limits the nesting of the nodes of serialized trees.
+//!
+//! serde serializes and deserializes nested nodes recursively, so deep trees would overflow the
+//! stack - in debug builds on the 2 MiB stacks of spawned threads already below 512 levels. The
+//! child nodes are wrapped by these functions, which fail once the trees are nested deeper than
+//! `MAX_DEPTH` on the current thread.
+
+use std::cell::Cell;
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The max. depth of serialized trees. Deeper trees can be stored in the binary format.
+pub const MAX_DEPTH: usize = 256;
+
+const TOO_DEEP: &str = "tree deeper than 256 levels, use the binary format for deep trees";
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A level of nesting, left when dropped.
+struct Level;
+
+impl Level {
+    fn enter() -> Option<Self> {
+        DEPTH.with(|depth| {
+            if depth.get() >= MAX_DEPTH {
+                None
+            } else {
+                depth.set(depth.get() + 1);
+                Some(Self)
+            }
+        })
+    }
+}
+
+impl Drop for Level {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+pub fn serialize<S: Serializer, V: Serialize>(child: &V, ser: S) -> Result<S::Ok, S::Error> {
+    let _level = Level::enter().ok_or_else(|| <S::Error as ser::Error>::custom(TOO_DEEP))?;
+    child.serialize(ser)
+}
+
+pub fn deserialize<'de, D, V>(deserializer: D) -> Result<V, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    let _level = Level::enter().ok_or_else(|| <D::Error as de::Error>::custom(TOO_DEEP))?;
+    V::deserialize(deserializer)
+}
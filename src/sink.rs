@@ -0,0 +1,263 @@
+//! Writing the scores of many samples without collecting them first.
+//!
+//! [`Forest::score_into`] passes the score of every sample to a [`ScoreSink`] as soon as it is
+//! computed, so streaming billions of scores to disk does not allocate a vector of all of them.
+//! Sinks are provided for vectors, for closures like `|score| builder.append_value(score)` of
+//! an Arrow builder, and for CSV output with [`CsvSink`]. Other containers, like ndarray
+//! arrays, are filled through [`SliceSink`] on their underlying slice or implement the trait.
+//!
+//! ```rust
+//! use extended_isolation_forest::sink::CsvSink;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let mut csv = CsvSink::with_header(Vec::new());
+//! assert_eq!(forest.score_into(&values, &mut csv).unwrap(), 100);
+//! let text = String::from_utf8(csv.into_inner()).unwrap();
+//! assert_eq!(text.lines().count(), 101);
+//!
+//! let mut scores: Vec<f64> = Vec::new();
+//! forest.score_into(&[[50.0, 50.0]], &mut scores).unwrap();
+//! assert!(scores[0] > 0.5);
+//! ```
+
+use std::io::Write;
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{Error, Forest, SampleAccess};
+
+/// A consumer of scores, see the [module documentation](self).
+pub trait ScoreSink {
+    /// Prepare for `additional` scores, if their number is known. Does nothing by default.
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Consume the score of the next sample.
+    fn push(&mut self, score: f64) -> Result<(), Error>;
+
+    /// Called after the last score, like for flushing buffered output. Does nothing by
+    /// default.
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ScoreSink for Vec<f64> {
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+
+    fn push(&mut self, score: f64) -> Result<(), Error> {
+        Vec::push(self, score);
+        Ok(())
+    }
+}
+
+/// Halves the memory of the scores, rounding them to the nearest `f32`.
+impl ScoreSink for Vec<f32> {
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+
+    fn push(&mut self, score: f64) -> Result<(), Error> {
+        Vec::push(self, score as f32);
+        Ok(())
+    }
+}
+
+impl<F> ScoreSink for F
+where
+    F: FnMut(f64),
+{
+    fn push(&mut self, score: f64) -> Result<(), Error> {
+        self(score);
+        Ok(())
+    }
+}
+
+/// Fills a preallocated buffer from the start.
+#[derive(Debug)]
+pub struct SliceSink<'a> {
+    buffer: &'a mut [f64],
+    len: usize,
+}
+
+impl<'a> SliceSink<'a> {
+    pub fn new(buffer: &'a mut [f64]) -> Self {
+        Self { buffer, len: 0 }
+    }
+
+    /// The number of scores written to the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl ScoreSink for SliceSink<'_> {
+    /// Fails with [`Error::InsufficientCapacity`] if there are more scores than the buffer
+    /// holds.
+    fn push(&mut self, score: f64) -> Result<(), Error> {
+        let capacity = self.buffer.len();
+        let slot = self
+            .buffer
+            .get_mut(self.len)
+            .ok_or(Error::InsufficientCapacity { capacity })?;
+        *slot = score;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+/// Writes one score per line, like the output of
+/// [`Forest::score_file`](crate::Forest::score_file).
+#[derive(Debug)]
+pub struct CsvSink<W: Write> {
+    writer: W,
+    header: bool,
+    line: String,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header: false,
+            line: String::new(),
+        }
+    }
+
+    /// Start the output with the header `score`, written with the first score or when
+    /// finishing without scores.
+    pub fn with_header(writer: W) -> Self {
+        Self {
+            header: true,
+            ..Self::new(writer)
+        }
+    }
+
+    fn write_header(&mut self) -> Result<(), Error> {
+        if std::mem::take(&mut self.header) {
+            self.writer.write_all(b"score\n").map_err(Error::Io)?;
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ScoreSink for CsvSink<W> {
+    fn push(&mut self, score: f64) -> Result<(), Error> {
+        use std::fmt::Write;
+
+        self.write_header()?;
+        self.line.clear();
+        let _ = writeln!(self.line, "{}", score);
+        self.writer
+            .write_all(self.line.as_bytes())
+            .map_err(Error::Io)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.write_header()?;
+        self.writer.flush().map_err(Error::Io)
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// Score the samples in order and pass every score to the sink, then finish the sink, see
+    /// the [module documentation](self). The samples may be a slice or an iterator reading
+    /// them lazily. Returns the number of scores.
+    ///
+    /// The samples are scored on the calling thread, use [`Forest::score_slice`] for
+    /// scoring on multiple threads. Fails with the first error of the sink.
+    pub fn score_into<I, K>(&self, samples: I, sink: &mut K) -> Result<usize, Error>
+    where
+        I: IntoIterator,
+        I::Item: SampleAccess<T, N>,
+        K: ScoreSink + ?Sized,
+    {
+        let samples = samples.into_iter();
+        sink.reserve(samples.size_hint().0);
+        let mut count = 0;
+        for sample in samples {
+            sink.push(self.score(&sample))?;
+            count += 1;
+        }
+        sink.finish()?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvSink, ScoreSink, SliceSink};
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn score_into_sinks() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 10,
+            sample_size: 64,
+            seed: Some(21),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let expected: Vec<f64> = values.iter().map(|sample| forest.score(sample)).collect();
+
+        let mut scores: Vec<f64> = Vec::new();
+        assert_eq!(forest.score_into(&values, &mut scores).unwrap(), 400);
+        assert_eq!(scores, expected);
+
+        let mut narrow: Vec<f32> = Vec::new();
+        forest
+            .score_into(values.iter().take(3), &mut narrow)
+            .unwrap();
+        assert_eq!(
+            narrow,
+            [expected[0] as f32, expected[1] as f32, expected[2] as f32]
+        );
+
+        let mut sum = 0.0;
+        forest
+            .score_into(&values, &mut |score: f64| sum += score)
+            .unwrap();
+        assert!((sum - expected.iter().sum::<f64>()).abs() < 1e-9);
+
+        let mut buffer = [0.0; 400];
+        let mut slice = SliceSink::new(&mut buffer);
+        forest.score_into(&values, &mut slice).unwrap();
+        assert_eq!(slice.len(), 400);
+        assert_eq!(buffer.to_vec(), expected);
+        let mut small = [0.0; 2];
+        assert!(matches!(
+            forest.score_into(&values, &mut SliceSink::new(&mut small)),
+            Err(Error::InsufficientCapacity { capacity: 2 })
+        ));
+
+        let mut csv = CsvSink::with_header(Vec::new());
+        forest.score_into(&values[..2], &mut csv).unwrap();
+        let text = String::from_utf8(csv.into_inner()).unwrap();
+        assert_eq!(text, format!("score\n{}\n{}\n", expected[0], expected[1]));
+        let mut empty = CsvSink::with_header(Vec::new());
+        empty.finish().unwrap();
+        assert_eq!(empty.into_inner(), b"score\n");
+    }
+}
@@ -0,0 +1,256 @@
+//! Export of axis-parallel forests to the tree arrays used by scikit-learn.
+//!
+//! Forests built with an `extension_level` of 0 only split along single dimensions, which makes
+//! them equivalent to the trees of scikit-learns `IsolationForest`. [`Forest::to_sklearn`] converts
+//! such a forest into the arrays of the `tree_` attribute of each estimator, so it can be
+//! inspected and visualized with the usual python tooling. With the `serde` feature enabled, the
+//! exported structure can be serialized - for example to JSON.
+//!
+//! Nodes are numbered in depth-first order, leaves use `-1` in `children_left`, `children_right`
+//! and `-2` in `feature` and `threshold`, just like scikit-learn does.
+//!
+//! scikit-learn sends samples to the left child when `x[feature] <= threshold`. For splits with a
+//! negative normal vector the children are swapped, which only affects samples lying exactly on the
+//! threshold.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use num_traits::Float;
+
+use crate::{to_f64, Error, Forest, Node};
+
+const TREE_LEAF: i64 = -1;
+const TREE_UNDEFINED: i64 = -2;
+
+/// The arrays describing a single tree, named like the attributes of `sklearn.tree._tree.Tree`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SklearnTree {
+    pub children_left: Vec<i64>,
+    pub children_right: Vec<i64>,
+    pub feature: Vec<i64>,
+    pub threshold: Vec<f64>,
+    pub n_node_samples: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SklearnForest {
+    /// Number of features of the samples (`n_features_in_`).
+    pub n_features: usize,
+
+    /// Number of samples used to build each tree (`max_samples_`).
+    pub max_samples: usize,
+
+    /// The trees of the forest (`estimators_`).
+    pub estimators: Vec<SklearnTree>,
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Export the forest to the tree arrays used by scikit-learn.
+    ///
+    /// Fails with [`Error::NotAxisParallel`] when the forest contains splits which do not follow a
+    /// single dimension.
+    pub fn to_sklearn(&self) -> Result<SklearnForest, Error> {
+        let estimators = self
+            .trees
+            .iter()
+            .map(|tree| {
+                let mut sklearn_tree = SklearnTree::default();
+                push_tree(&mut sklearn_tree, &tree.root)?;
+                Ok(sklearn_tree)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(SklearnForest {
+            n_features: N,
+            max_samples: self.options.sample_size,
+            estimators,
+        })
+    }
+}
+
+/// A pending step of `push_tree`.
+enum Step<'a, T, const N: usize> {
+    /// Append the node and its children, linking the first appended id into `parent`.
+    Node(&'a Node<T, N>, Option<(usize, Child)>),
+
+    /// Append the empty right leaf of a merged internal node.
+    MergedLeaf(usize),
+}
+
+#[derive(Clone, Copy)]
+enum Child {
+    Left,
+    Right,
+}
+
+/// Appends the nodes of the tree in depth-first order, using an explicit stack so deep trees
+/// can not overflow the call stack.
+///
+/// Internal nodes merged into the depth offset of a node are restored as splits with an
+/// infinite threshold, sending all samples accepted by scikit-learn to the left and none to the
+/// empty leaf on the right.
+///
+/// The sample counts of the internal nodes are summed up in a single pass afterwards, children
+/// always have larger ids than their parents.
+fn push_tree<T, const N: usize>(
+    sklearn_tree: &mut SklearnTree,
+    root: &Node<T, N>,
+) -> Result<(), Error>
+where
+    T: Float,
+{
+    let mut stack = vec![Step::Node(root, None)];
+    while let Some(step) = stack.pop() {
+        let node = match step {
+            Step::Node(node, parent) => {
+                let first_id = sklearn_tree.children_left.len();
+                if let Some((parent_id, child)) = parent {
+                    match child {
+                        Child::Left => sklearn_tree.children_left[parent_id] = first_id as i64,
+                        Child::Right => sklearn_tree.children_right[parent_id] = first_id as i64,
+                    }
+                }
+                node
+            }
+            Step::MergedLeaf(merged_id) => {
+                sklearn_tree.children_right[merged_id] = push_leaf(sklearn_tree, 0) as i64;
+                continue;
+            }
+        };
+
+        for _ in 0..node.depth_offset() {
+            let merged_id = push_leaf(sklearn_tree, 0);
+            sklearn_tree.children_left[merged_id] = merged_id as i64 + 1;
+            sklearn_tree.feature[merged_id] = 0;
+            sklearn_tree.threshold[merged_id] = f64::INFINITY;
+            stack.push(Step::MergedLeaf(merged_id));
+        }
+
+        match node {
+            Node::Ex(ex_node) => {
+                push_leaf(sklearn_tree, ex_node.num_samples);
+            }
+            Node::In(in_node) => {
+                let mut components = in_node.n.iter().enumerate().filter(|(_, v)| !v.is_zero());
+                let (feature, normal) = match (components.next(), components.next()) {
+                    (Some((feature, normal)), None) => (feature, *normal),
+                    _ => return Err(Error::NotAxisParallel),
+                };
+
+                let (left, right) = if normal > T::zero() {
+                    (in_node.left.as_ref(), in_node.right.as_ref())
+                } else {
+                    (in_node.right.as_ref(), in_node.left.as_ref())
+                };
+                let node_id = push_leaf(sklearn_tree, 0);
+                sklearn_tree.feature[node_id] = feature as i64;
+                sklearn_tree.threshold[node_id] = to_f64(in_node.p[feature]);
+                stack.push(Step::Node(right, Some((node_id, Child::Right))));
+                stack.push(Step::Node(left, Some((node_id, Child::Left))));
+            }
+        }
+    }
+
+    for node_id in (0..sklearn_tree.children_left.len()).rev() {
+        let (left, right) = (
+            sklearn_tree.children_left[node_id],
+            sklearn_tree.children_right[node_id],
+        );
+        if left != TREE_LEAF {
+            sklearn_tree.n_node_samples[node_id] = sklearn_tree.n_node_samples[left as usize]
+                + sklearn_tree.n_node_samples[right as usize];
+        }
+    }
+    Ok(())
+}
+
+fn push_leaf(sklearn_tree: &mut SklearnTree, num_samples: usize) -> usize {
+    let node_id = sklearn_tree.children_left.len();
+    sklearn_tree.children_left.push(TREE_LEAF);
+    sklearn_tree.children_right.push(TREE_LEAF);
+    sklearn_tree.feature.push(TREE_UNDEFINED);
+    sklearn_tree.threshold.push(TREE_UNDEFINED as f64);
+    sklearn_tree.n_node_samples.push(num_samples);
+    node_id
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::SklearnTree;
+    use crate::{c_factor, score_from_path_length, Error, Forest, ForestOptions};
+
+    fn make_forest(extension_level: usize) -> Forest<f64, 2> {
+        let rng = &mut rand::thread_rng();
+        let distribution = Uniform::new(-4., 4.);
+        let values: Vec<_> = (0..1000)
+            .map(|_| [rng.sample(distribution), rng.sample(distribution)])
+            .collect();
+
+        let options = ForestOptions {
+            n_trees: 20,
+            sample_size: 100,
+            max_tree_depth: None,
+            extension_level: extension_level.into(),
+            ..Default::default()
+        };
+        Forest::from_slice(values.as_slice(), &options).unwrap()
+    }
+
+    /// path length following the conventions of scikit-learn
+    fn sklearn_path_length(tree: &SklearnTree, values: &[f64; 2]) -> f64 {
+        let mut node_id = 0;
+        let mut depth = 0.0;
+        while tree.children_left[node_id] >= 0 {
+            let feature = tree.feature[node_id] as usize;
+            node_id = if values[feature] <= tree.threshold[node_id] {
+                tree.children_left[node_id]
+            } else {
+                tree.children_right[node_id]
+            } as usize;
+            depth += 1.0;
+        }
+        match tree.n_node_samples[node_id] {
+            0 | 1 => depth,
+            n => depth + c_factor(n),
+        }
+    }
+
+    #[test]
+    fn export_matches_scores() {
+        let forest = make_forest(0);
+        let exported = forest.to_sklearn().unwrap();
+        assert_eq!(exported.n_features, 2);
+        assert_eq!(exported.max_samples, 100);
+        assert_eq!(exported.estimators.len(), 20);
+
+        for estimator in exported.estimators.iter() {
+            assert_eq!(estimator.n_node_samples[0], 100);
+        }
+
+        for values in [[0.0, 0.0], [3.9, -3.5], [10.0, 1.0]] {
+            let path_length: f64 = exported
+                .estimators
+                .iter()
+                .map(|tree| sklearn_path_length(tree, &values))
+                .sum();
+            let eh = path_length / exported.estimators.len() as f64;
+            let score = score_from_path_length(eh, exported.max_samples);
+            assert!((score - forest.score(&values)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn export_requires_axis_parallel_splits() {
+        let forest = make_forest(1);
+        assert!(matches!(forest.to_sklearn(), Err(Error::NotAxisParallel)));
+    }
+}
@@ -0,0 +1,201 @@
+//! Pluggable choice of the hyperplanes splitting the nodes of the trees.
+//!
+//! A [`SplitStrategy`] replaces the way the normal vector and the intercept point of every split
+//! are drawn, for prototyping other distributions of the cuts without forking the tree
+//! construction. [`ExtendedSplit`] is the strategy of the paper and used by all other
+//! constructors of [`Forest`].
+//!
+//! ```rust
+//! use extended_isolation_forest::seed::TreeRng;
+//! use extended_isolation_forest::split::SplitStrategy;
+//! use extended_isolation_forest::{Forest, ForestOptions, SampleAccess};
+//! use rand::Rng;
+//!
+//! /// Axis-parallel cuts at random positions of the widest dimension.
+//! struct WidestDimension;
+//!
+//! impl<const N: usize> SplitStrategy<f64, N> for WidestDimension {
+//!     fn hyperplane<S: SampleAccess<f64, N>>(
+//!         &mut self,
+//!         samples: &[S],
+//!         _extension_level: usize,
+//!         rng: &mut TreeRng,
+//!     ) -> ([f64; N], [f64; N]) {
+//!         let (mut mins, mut maxs) = ([f64::INFINITY; N], [f64::NEG_INFINITY; N]);
+//!         for sample in samples {
+//!             for i in 0..N {
+//!                 mins[i] = mins[i].min(sample.feature(i));
+//!                 maxs[i] = maxs[i].max(sample.feature(i));
+//!             }
+//!         }
+//!         let widest = (0..N)
+//!             .max_by(|a, b| (maxs[*a] - mins[*a]).total_cmp(&(maxs[*b] - mins[*b])))
+//!             .unwrap();
+//!         let mut n = [0.0; N];
+//!         n[widest] = 1.0;
+//!         let mut p = [0.0; N];
+//!         p[widest] = mins[widest] + (maxs[widest] - mins[widest]) * rng.gen::<f64>();
+//!         (n, p)
+//!     }
+//! }
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest =
+//!     Forest::from_slice_with_strategy(&values, &ForestOptions::default(), WidestDimension).unwrap();
+//! assert!(forest.score(&[50.0, 50.0]) > forest.score(&[5.0, 5.0]));
+//! ```
+
+use rand::{distributions::uniform::SampleUniform, seq::index};
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::halton::Halton;
+use crate::seed::TreeRng;
+use crate::{
+    make_hyperplane, Error, Forest, ForestFloat, ForestOptions, Hyperplanes, SampleAccess,
+};
+
+/// Chooses the hyperplanes splitting the nodes of the trees.
+pub trait SplitStrategy<T, const N: usize> {
+    /// The normal vector `n` and the intercept point `p` of the hyperplane splitting the
+    /// samples of a node, of which there are always at least two. Samples `x` with
+    /// `(x - p) · n <= 0` go to the left child.
+    ///
+    /// `extension_level` is the number of components of the normal vector which may be non-zero
+    /// besides the first one, as configured in [`ForestOptions::extension_level`].
+    fn hyperplane<S>(
+        &mut self,
+        samples: &[S],
+        extension_level: usize,
+        rng: &mut TreeRng,
+    ) -> ([T; N], [T; N])
+    where
+        S: SampleAccess<T, N>;
+
+    /// Called before building the trees with the features to leave out of the hyperplanes, see
+    /// [`ForestOptions::drop_constant_features`]. Ignored by default.
+    fn exclude_features(&mut self, _features: &[usize]) {}
+}
+
+/// The hyperplanes of the paper: Gaussian normal vectors with `extension_level + 1` non-zero
+/// components and intercepts uniformly distributed within the range of the samples, or the
+/// Halton sequence if selected by [`Hyperplanes::Halton`].
+#[derive(Clone, Debug)]
+pub struct ExtendedSplit {
+    hyperplanes: Hyperplanes,
+    halton: Option<Halton>,
+
+    /// The features the random hyperplanes may use, all if `None`.
+    features: Option<Vec<usize>>,
+}
+
+impl ExtendedSplit {
+    pub fn new(hyperplanes: Hyperplanes) -> Self {
+        Self {
+            hyperplanes,
+            halton: None,
+            features: None,
+        }
+    }
+}
+
+impl<'de, T, const N: usize> SplitStrategy<T, N> for ExtendedSplit
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    fn hyperplane<S>(
+        &mut self,
+        samples: &[S],
+        extension_level: usize,
+        rng: &mut TreeRng,
+    ) -> ([T; N], [T; N])
+    where
+        S: SampleAccess<T, N>,
+    {
+        if self.hyperplanes == Hyperplanes::Halton && self.halton.is_none() {
+            self.halton = Some(Halton::new(3 * N));
+        }
+        make_hyperplane(
+            samples,
+            rng,
+            &mut self.halton,
+            extension_level,
+            self.features.as_deref(),
+        )
+    }
+
+    /// Keep at least one feature, excluding all would not split anything.
+    fn exclude_features(&mut self, features: &[usize]) {
+        let remaining: Vec<usize> = (0..N).filter(|i| !features.contains(i)).collect();
+        self.features = (!features.is_empty() && !remaining.is_empty()).then_some(remaining);
+    }
+}
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the given training data, splitting the nodes with the
+    /// hyperplanes chosen by `split`. [`ForestOptions::hyperplanes`] is ignored.
+    pub fn from_slice_with_strategy<S, P>(
+        training_data: &[S],
+        options: &ForestOptions,
+        split: P,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+        P: SplitStrategy<T, N>,
+    {
+        Self::train_with_strategy(
+            training_data.len(),
+            |i| &training_data[i],
+            |rng| index::sample(rng, training_data.len(), options.sample_size).into_vec(),
+            options,
+            split,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::seed::TreeRng;
+    use rand::Rng;
+
+    use super::SplitStrategy;
+    use crate::{Forest, ForestOptions, SampleAccess};
+
+    /// Random cuts along the first dimension only.
+    struct FirstDimension;
+
+    impl SplitStrategy<f64, 2> for FirstDimension {
+        fn hyperplane<S: SampleAccess<f64, 2>>(
+            &mut self,
+            samples: &[S],
+            _extension_level: usize,
+            rng: &mut TreeRng,
+        ) -> ([f64; 2], [f64; 2]) {
+            let values = samples.iter().map(|sample| sample.feature(0));
+            let min = values.clone().fold(f64::INFINITY, f64::min);
+            let max = values.fold(f64::NEG_INFINITY, f64::max);
+            ([1.0, 0.0], [min + (max - min) * rng.gen::<f64>(), 0.0])
+        }
+    }
+
+    #[test]
+    fn custom_split_strategy() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let forest = Forest::from_slice_with_strategy(
+            values.as_slice(),
+            &ForestOptions::default(),
+            FirstDimension,
+        )
+        .unwrap();
+
+        // the second dimension is never looked at
+        assert_eq!(forest.score(&[5.0, 0.0]), forest.score(&[5.0, 1000.0]));
+        assert!(forest.score(&[100.0, 10.0]) > forest.score(&[10.0, 10.0]));
+    }
+}
@@ -0,0 +1,157 @@
+//! Saving the state of streaming types across restarts. Available with the `json` feature.
+//!
+//! Streaming types adapt to the samples they have seen: a [`DecayedForest`] keeps subsamples
+//! of the stream, a [`GroupedForest`] buffers samples of groups not trained yet and a
+//! [`CountingSink`] counts alerts. A consumer restarted with fresh instances starts from
+//! scratch, so these types implement [`PersistentState`] to save their complete state as JSON
+//! on shutdown and restore it on startup.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use extended_isolation_forest::online::DecayedForest;
+//! use extended_isolation_forest::state::PersistentState;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let mut forest = DecayedForest::new(ForestOptions::default(), Duration::from_secs(600)).unwrap();
+//! for t in 0..100 {
+//!     forest.observe(t, [(t % 10) as f64]).unwrap();
+//! }
+//!
+//! let mut state = Vec::new();
+//! forest.save_state(&mut state).unwrap();
+//! let restored = DecayedForest::<f64, 1>::restore_state(state.as_slice()).unwrap();
+//! assert_eq!(restored.score(&[5.0]), forest.score(&[5.0]));
+//! ```
+//!
+//! [`DecayedForest`]: crate::online::DecayedForest
+//! [`GroupedForest`]: crate::grouped::GroupedForest
+//! [`CountingSink`]: crate::monitor::CountingSink
+
+use std::hash::Hash;
+use std::io::{Read, Write};
+
+use num_traits::Float;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::grouped::GroupedForest;
+use crate::monitor::{AnomalyMonitor, CountingSink};
+use crate::online::DecayedForest;
+use crate::seasonal::SeasonalForest;
+use crate::Error;
+
+/// State which can be saved and restored, see the [module documentation](self).
+pub trait PersistentState: Serialize + DeserializeOwned {
+    /// Write the state as JSON.
+    fn save_state<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer(writer, self).map_err(json_error)
+    }
+
+    /// Read a state written by [`PersistentState::save_state`].
+    fn restore_state<R: Read>(reader: R) -> Result<Self, Error> {
+        serde_json::from_reader(std::io::BufReader::new(reader)).map_err(json_error)
+    }
+}
+
+fn json_error(err: serde_json::Error) -> Error {
+    if err.is_io() {
+        Error::Io(err.into())
+    } else {
+        Error::Deserialization(err.to_string())
+    }
+}
+
+impl<T, const N: usize> PersistentState for DecayedForest<T, N> where
+    T: Float + Serialize + DeserializeOwned
+{
+}
+
+impl<K, T, const N: usize> PersistentState for GroupedForest<K, T, N>
+where
+    K: Eq + Hash + Serialize + DeserializeOwned,
+    T: Float + Serialize + DeserializeOwned,
+{
+}
+
+impl<T, const N: usize> PersistentState for SeasonalForest<T, N> where
+    T: Float + Serialize + DeserializeOwned
+{
+}
+
+impl PersistentState for CountingSink {}
+
+impl<T, const N: usize, M> PersistentState for AnomalyMonitor<T, N, M>
+where
+    T: Float + Serialize + DeserializeOwned,
+    M: Serialize + DeserializeOwned,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentState;
+    use crate::grouped::GroupedForest;
+    use crate::monitor::{AnomalyMonitor, CountingSink};
+    use crate::seasonal::{Season, SeasonalForest};
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn restored_state_continues() {
+        let mut grouped = GroupedForest::new(ForestOptions::default(), 50);
+        for i in 0..80 {
+            grouped
+                .add_sample(format!("host-{}", i % 2), [(i % 10) as f64])
+                .unwrap();
+        }
+        grouped.add_sample("host-2".to_string(), [1.0]).unwrap();
+        let mut state = Vec::new();
+        grouped.save_state(&mut state).unwrap();
+        let mut restored: GroupedForest<String, f64, 1> =
+            GroupedForest::restore_state(state.as_slice()).unwrap();
+        assert_eq!(restored.trained_groups().count(), 0);
+        assert_eq!(restored.pending_groups().count(), 3);
+        // the buffered samples are kept
+        for i in 0..10 {
+            restored
+                .add_sample("host-0".to_string(), [i as f64])
+                .unwrap();
+        }
+        assert!(restored.score("host-0", &[100.0]).is_some());
+
+        let mut seasonal = SeasonalForest::new(Season::hour_of_day(), ForestOptions::default(), 20);
+        for t in 0..24 * 30 {
+            seasonal.add_sample(t * 3600, [(t % 24) as f64]).unwrap();
+        }
+        state.clear();
+        seasonal.save_state(&mut state).unwrap();
+        let restored = SeasonalForest::<f64, 1>::restore_state(state.as_slice()).unwrap();
+        assert_eq!(restored.season(), &Season::hour_of_day());
+        assert_eq!(restored.score(3600, &[5.0]), seasonal.score(3600, &[5.0]));
+
+        let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64]).collect();
+        let forest: Forest<f64, 1> =
+            Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+        let monitor = AnomalyMonitor::with_sink(forest, 0.6, CountingSink::default());
+        monitor.observe(&[1.0]);
+        monitor.observe(&[100.0]);
+        state.clear();
+        monitor.save_state(&mut state).unwrap();
+        let restored: AnomalyMonitor<f64, 1, CountingSink> =
+            AnomalyMonitor::restore_state(state.as_slice()).unwrap();
+        assert_eq!(restored.sink().samples_scored(), 2);
+        assert_eq!(
+            restored.sink().alerts_raised(),
+            monitor.sink().alerts_raised()
+        );
+        assert_eq!(
+            restored.sink().buckets().collect::<Vec<_>>(),
+            monitor.sink().buckets().collect::<Vec<_>>()
+        );
+
+        assert!(matches!(
+            CountingSink::restore_state(&b"{"[..]),
+            Err(Error::Deserialization(_))
+        ));
+    }
+}
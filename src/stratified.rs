@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::{distributions::uniform::SampleUniform, seq::index};
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the given training data, preserving the proportions of the
+    /// strata in the subsample of every tree.
+    ///
+    /// `strata_keys` assigns every training sample to a stratum, for example the host or region
+    /// it originates from. Without stratification trees may by chance be built mostly from
+    /// samples of the dominant strata.
+    pub fn from_slice_stratified<S, K>(
+        training_data: &[S],
+        strata_keys: &[K],
+        options: &ForestOptions,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+        K: Eq + Hash,
+    {
+        if strata_keys.len() != training_data.len() {
+            return Err(Error::KeyCountMismatch {
+                expected: training_data.len(),
+                actual: strata_keys.len(),
+            });
+        }
+
+        let mut strata: HashMap<&K, Vec<usize>> = HashMap::new();
+        for (row, key) in strata_keys.iter().enumerate() {
+            strata.entry(key).or_default().push(row);
+        }
+        let mut strata: Vec<Vec<usize>> = strata.into_values().collect();
+        // independent of the iteration order of the map
+        strata.sort_by_key(|rows| rows[0]);
+
+        let allocation = allocate(&strata, options.sample_size, training_data.len());
+        Self::train(
+            training_data.len(),
+            |i| &training_data[i],
+            |rng| {
+                strata
+                    .iter()
+                    .zip(allocation.iter())
+                    .flat_map(|(rows, count)| {
+                        index::sample(rng, rows.len(), *count)
+                            .into_iter()
+                            .map(|i| rows[i])
+                    })
+                    .collect()
+            },
+            options,
+        )
+    }
+}
+
+/// Number of samples drawn from each stratum, proportional to the size of the strata. The
+/// remaining samples are assigned to the strata with the largest fractional allocations.
+fn allocate(strata: &[Vec<usize>], sample_size: usize, num_rows: usize) -> Vec<usize> {
+    if num_rows == 0 {
+        return vec![0; strata.len()];
+    }
+    let sample_size = sample_size.min(num_rows);
+    let exact: Vec<f64> = strata
+        .iter()
+        .map(|rows| (rows.len() * sample_size) as f64 / num_rows as f64)
+        .collect();
+    let mut allocation: Vec<usize> = exact.iter().map(|count| count.floor() as usize).collect();
+
+    let mut by_remainder: Vec<usize> = (0..strata.len()).collect();
+    by_remainder.sort_by(|a, b| {
+        (exact[*b] - exact[*b].floor()).total_cmp(&(exact[*a] - exact[*a].floor()))
+    });
+    let mut missing = sample_size - allocation.iter().sum::<usize>();
+    for i in by_remainder.into_iter().cycle() {
+        if missing == 0 {
+            break;
+        }
+        if allocation[i] < strata[i].len() {
+            allocation[i] += 1;
+            missing -= 1;
+        }
+    }
+    allocation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::allocate;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn allocation_is_proportional() {
+        let strata = vec![(0..90).collect(), (90..99).collect(), vec![99]];
+        assert_eq!(allocate(&strata, 20, 100), vec![18, 2, 0]);
+        assert_eq!(allocate(&strata, 100, 100), vec![90, 9, 1]);
+        assert_eq!(allocate(&strata, 5, 100), vec![5, 0, 0]);
+    }
+
+    #[test]
+    fn train_stratified() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let keys: Vec<_> = (0..400)
+            .map(|i| if i < 40 { "small" } else { "large" })
+            .collect();
+
+        let options = ForestOptions {
+            sample_size: 50,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> =
+            Forest::from_slice_stratified(values.as_slice(), &keys, &options).unwrap();
+        assert!(forest.score(&[100.0, 100.0]) > forest.score(&[10.0, 10.0]));
+
+        assert!(matches!(
+            Forest::<f64, 2>::from_slice_stratified(values.as_slice(), &keys[1..], &options),
+            Err(Error::KeyCountMismatch { .. })
+        ));
+    }
+}
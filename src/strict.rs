@@ -0,0 +1,224 @@
+//! Scoring with bit-identical results on all platforms, see [`Forest::set_strict_fp`].
+//!
+//! Rust never fuses multiplications and additions into FMA instructions on its own and the
+//! trees are traversed in a fixed order, so the only platform dependent parts of scoring are
+//! the logarithm in [`c_factor`](crate::c_factor) and the power of two normalizing the path
+//! lengths. Both are provided by the math library of the platform, which does not guarantee
+//! correct rounding. In strict mode they are replaced by computations using only basic IEEE 754
+//! operations, which are rounded identically everywhere.
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{Direction, Forest, Node, SampleAccess, Tree};
+
+/// Number of terms of the Taylor series of `2^f` for `f` in `[0, 1)`, more than enough for
+/// double precision.
+const EXP2_TERMS: usize = 24;
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The mean path length and the score of the sample on the trees, see
+    /// `Forest::score_trees`.
+    pub(crate) fn strict_score<'a, S, I>(
+        &self,
+        trees: I,
+        values: &S,
+        max_depth: usize,
+    ) -> (f64, f64)
+    where
+        S: SampleAccess<T, N> + ?Sized,
+        I: IntoIterator<Item = &'a Tree<T, N, L>>,
+        T: 'a,
+        L: 'a,
+    {
+        let c_factors = self.strict_c_factors.get_or_init(|| {
+            // leaves of converted forests may be larger than the sample size
+            let largest_leaf = self
+                .trees
+                .iter()
+                .flat_map(|tree| tree.root.iter())
+                .filter_map(|(node, _)| match node {
+                    Node::Ex(ex_node) => Some(ex_node.num_samples),
+                    Node::In(_) => None,
+                })
+                .max()
+                .unwrap_or(0);
+            exact_c_factors(self.options.sample_size.max(largest_leaf))
+        });
+        let (mut path_length, mut num_trees) = (0.0, 0);
+        for tree in trees {
+            path_length += path_length_with_cap(tree, values, max_depth, c_factors);
+            num_trees += 1;
+        }
+        let mean_path_length = path_length / num_trees as f64;
+        (
+            mean_path_length,
+            exp2(-mean_path_length / c_factor(self.options.sample_size, c_factors)),
+        )
+    }
+}
+
+fn path_length_with_cap<T, S, L, const N: usize>(
+    tree: &Tree<T, N, L>,
+    values: &S,
+    max_depth: usize,
+    c_factors: &[f64],
+) -> f64
+where
+    T: Float,
+    S: SampleAccess<T, N> + ?Sized,
+    L: LeafModel<T, N>,
+{
+    let mut node = &tree.root;
+    let mut depth = 0;
+    loop {
+        depth = match node.skip_depth_offset(depth, max_depth) {
+            Some(depth) => depth,
+            None => return max_depth as f64,
+        };
+        match node {
+            Node::Ex(ex_node) => return depth as f64 + c_factor(ex_node.num_samples, c_factors),
+            Node::In(_) if depth >= max_depth => return depth as f64,
+            Node::In(in_node) => {
+                node = match in_node.direction(values) {
+                    Direction::Left => in_node.left.as_ref(),
+                    Direction::Right => in_node.right.as_ref(),
+                };
+                depth += 1;
+            }
+        }
+    }
+}
+
+/// The exact `c_factor` for all sizes up to `max_size`, with the harmonic numbers summed up
+/// instead of approximated using the logarithm.
+fn exact_c_factors(max_size: usize) -> Vec<f64> {
+    let mut harmonic_number = 0.0;
+    let mut c_factors = Vec::with_capacity(max_size + 1);
+    for n in 0..=max_size {
+        if n <= 1 {
+            c_factors.push(0.0);
+            continue;
+        }
+        // the harmonic number of `n - 1`
+        harmonic_number += 1.0 / (n - 1) as f64;
+        c_factors.push(2.0 * harmonic_number - (2.0 * (n as f64 - 1.0) / n as f64));
+    }
+    c_factors
+}
+
+/// The exact `c_factor` of a single size, identical to the value of `exact_c_factors`.
+fn exact_c_factor(n: usize) -> f64 {
+    if n <= 1 {
+        return 0.0;
+    }
+    let harmonic_number = (1..n).fold(0.0, |sum, k| sum + 1.0 / k as f64);
+    2.0 * harmonic_number - (2.0 * (n as f64 - 1.0) / n as f64)
+}
+
+/// The table covers the leaves of the forest when it is computed, only leaves of trees modified
+/// later may be larger.
+fn c_factor(n: usize, c_factors: &[f64]) -> f64 {
+    match c_factors.get(n) {
+        Some(c) => *c,
+        None => exact_c_factor(n),
+    }
+}
+
+/// `2^x` using only additions, multiplications and divisions.
+fn exp2(x: f64) -> f64 {
+    if x.is_nan() {
+        return f64::NAN;
+    }
+    let k = x.floor();
+    if k < -1100.0 {
+        return 0.0;
+    }
+    if k > 1023.0 {
+        return f64::INFINITY;
+    }
+
+    let y = (x - k) * std::f64::consts::LN_2;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for i in 1..=EXP2_TERMS {
+        term = term * y / i as f64;
+        sum += term;
+    }
+
+    // split the scaling, powers below 2^-1022 are not representable as normal floats
+    let k = k as i32;
+    let (high, low) = if k < -1000 { (k + 100, -100) } else { (k, 0) };
+    sum * power_of_two(high) * power_of_two(low)
+}
+
+fn power_of_two(k: i32) -> f64 {
+    f64::from_bits(((k + 1023) as u64) << 52)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exact_c_factor, exact_c_factors, exp2};
+    use crate::eif::{EifForest, EifNode};
+    use crate::{Forest, ForestOptions, Hyperplanes};
+
+    #[test]
+    fn strict_scores_are_stable() {
+        for x in [0.0, -0.25, -0.5, -1.0, -1.7, -3.3, -20.5, 2.5] {
+            let expected = 2.0_f64.powf(x);
+            assert!((exp2(x) - expected).abs() <= expected * 1e-15, "{}", x);
+        }
+        assert_eq!(exp2(-2000.0), 0.0);
+
+        // the Halton hyperplanes and the subsampling do not use the math library either, so the
+        // trees are identical on all platforms as well
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 20,
+            sample_size: 64,
+            hyperplanes: Hyperplanes::Halton,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        forest.set_strict_fp(true);
+
+        // must be identical on all platforms
+        assert_eq!(forest.score(&[1.0, 0.0]).to_bits(), 0x3fe2_d004_2372_241e);
+        assert_eq!(
+            forest.score(&[45.0, -30.0]).to_bits(),
+            0x3fe4_1a5b_8cf3_098d
+        );
+    }
+
+    #[test]
+    fn c_factors_cover_large_leaves() {
+        let c_factors = exact_c_factors(5000);
+        for n in [0, 1, 2, 7, 300, 5000] {
+            assert_eq!(exact_c_factor(n).to_bits(), c_factors[n].to_bits());
+        }
+
+        // a converted leaf larger than the sample size
+        let mut forest: Forest<f64, 1> = Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0],
+                p: vec![0.0],
+                left: Box::new(EifNode::ExNode { size: 300 }),
+                right: Box::new(EifNode::ExNode { size: 1 }),
+            }],
+        })
+        .unwrap();
+        forest.set_strict_fp(true);
+        let score = forest.score(&[-1.0]);
+        assert_eq!(forest.strict_c_factors.get().unwrap().len(), 301);
+        forest.set_strict_fp(false);
+        assert!((forest.score(&[-1.0]) - score).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,196 @@
+//! Regions of the feature space which are always normal.
+//!
+//! Some states look anomalous to a forest but are known to be benign, like the load during a
+//! maintenance window or an idle standby machine. Instead of filtering them in every caller,
+//! suppressed [`Region`]s are stored with the forest: samples inside any of them score `0`, a
+//! mean path length of infinity, in [`Forest::score`] and all scores derived from it.
+//!
+//! ```rust
+//! use extended_isolation_forest::suppression::{HalfSpace, Region};
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! forest.set_suppressed_regions(vec![
+//!     // a known benign state
+//!     Region::Box {
+//!         min: [90.0, -10.0],
+//!         max: [110.0, 10.0],
+//!     },
+//!     // everything above the line x + y = 200
+//!     Region::Polytope(vec![HalfSpace {
+//!         normal: [-1.0, -1.0],
+//!         point: [100.0, 100.0],
+//!     }]),
+//! ]);
+//! assert_eq!(forest.score(&[100.0, 0.0]), 0.0);
+//! assert_eq!(forest.score(&[150.0, 150.0]), 0.0);
+//! assert!(forest.score(&[50.0, 50.0]) > 0.5);
+//! ```
+
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::LeafModel;
+#[cfg(feature = "serde")]
+use crate::serde_array;
+use crate::{Forest, SampleAccess};
+
+/// The samples `x` with `(x - point) · normal <= 0`, on the side of the hyperplane through
+/// `point` the normal vector points away from.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub struct HalfSpace<T, const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    pub normal: [T; N],
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+    pub point: [T; N],
+}
+
+/// A region in which samples are scored as normal, see the [module documentation](self).
+/// Samples with missing values are never inside a region.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub enum Region<T, const N: usize> {
+    /// The samples with `min[i] <= x[i] <= max[i]` in every dimension `i`.
+    Box {
+        #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+        min: [T; N],
+
+        #[cfg_attr(feature = "serde", serde(with = "serde_array"))]
+        max: [T; N],
+    },
+
+    /// The samples inside all half-spaces.
+    Polytope(Vec<HalfSpace<T, N>>),
+}
+
+impl<T, const N: usize> Region<T, N>
+where
+    T: Float,
+{
+    pub fn contains<S>(&self, sample: &S) -> bool
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        match self {
+            Self::Box { min, max } => (0..N).all(|i| {
+                let value = sample.feature(i);
+                min[i] <= value && value <= max[i]
+            }),
+            Self::Polytope(half_spaces) => half_spaces.iter().all(|half_space| {
+                sample.direction(&half_space.normal, &half_space.point) <= T::zero()
+            }),
+        }
+    }
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The regions scored as normal, empty unless set.
+    pub fn suppressed_regions(&self) -> &[Region<T, N>] {
+        &self.suppressed
+    }
+
+    /// Set the regions whose samples score `0`. The regions are stored with the forest and
+    /// apply to [`Forest::score`], [`Forest::explain`] and the scores derived from them. They
+    /// are checked against the samples as passed, before monotone constraints and the range
+    /// policy.
+    pub fn set_suppressed_regions(&mut self, regions: Vec<Region<T, N>>) {
+        self.suppressed = regions;
+    }
+
+    /// Whether the sample lies inside a suppressed region.
+    pub(crate) fn is_suppressed<S>(&self, sample: &S) -> bool
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.suppressed.iter().any(|region| region.contains(sample))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HalfSpace, Region};
+    use crate::normalization::ScoreMode;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn suppressed_regions_score_normal() {
+        let region = Region::Box {
+            min: [0.0, 0.0],
+            max: [1.0, 1.0],
+        };
+        assert!(region.contains(&[1.0, 0.5]));
+        assert!(!region.contains(&[1.5, 0.5]));
+        assert!(!region.contains(&[f64::NAN, 0.5]));
+        let triangle = Region::Polytope(vec![
+            HalfSpace {
+                normal: [-1.0, 0.0],
+                point: [0.0, 0.0],
+            },
+            HalfSpace {
+                normal: [0.0, -1.0],
+                point: [0.0, 0.0],
+            },
+            HalfSpace {
+                normal: [1.0, 1.0],
+                point: [1.0, 0.0],
+            },
+        ]);
+        assert!(triangle.contains(&[0.2, 0.2]));
+        assert!(!triangle.contains(&[0.8, 0.8]));
+        assert!(!triangle.contains(&[0.2, f64::NAN]));
+
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 30,
+            sample_size: 64,
+            seed: Some(10),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+        let sample = [100.0, 100.0];
+        let score = forest.score(&sample);
+        forest.set_suppressed_regions(vec![Region::Box {
+            min: [90.0, 90.0],
+            max: [110.0, 110.0],
+        }]);
+        assert_eq!(forest.suppressed_regions().len(), 1);
+        assert_eq!(forest.score(&sample), 0.0);
+        assert_eq!(
+            forest.score_as(&sample, ScoreMode::PathLength),
+            f64::INFINITY
+        );
+        assert_eq!(forest.explain(&sample).score, 0.0);
+        let outside = forest.score(&[100.0, 120.0]);
+        assert!(outside > 0.5);
+        forest.set_suppressed_regions(Vec::new());
+        assert_eq!(forest.score(&[100.0, 120.0]), outside);
+        assert_eq!(forest.score(&sample), score);
+
+        #[cfg(feature = "serde")]
+        {
+            forest.set_suppressed_regions(vec![triangle]);
+            let json = serde_json::to_string(&forest).unwrap();
+            let restored: Forest<f64, 2> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.suppressed_regions(), forest.suppressed_regions());
+        }
+    }
+}
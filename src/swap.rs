@@ -0,0 +1,146 @@
+//! Replacing the forest of a long-running scoring service without interrupting it.
+//!
+//! A [`SwappableForest`] holds the active forest behind an [`Arc`]. Scoring only holds a lock
+//! for cloning the `Arc`, so replacing the forest never waits for running `score` calls and
+//! running calls finish with the forest they started with. New forests are validated on a set of
+//! probe samples before they get activated, which also warms up their memory.
+
+use std::sync::{Arc, RwLock};
+
+use num_traits::Float;
+
+use crate::{Error, Forest, SampleAccess};
+
+pub struct SwappableForest<T, const N: usize> {
+    active: RwLock<Arc<Forest<T, N>>>,
+}
+
+impl<T, const N: usize> SwappableForest<T, N>
+where
+    T: Float,
+{
+    pub fn new(forest: Forest<T, N>) -> Self {
+        Self {
+            active: RwLock::new(Arc::new(forest)),
+        }
+    }
+
+    /// The active forest.
+    pub fn current(&self) -> Arc<Forest<T, N>> {
+        // the lock only guards replacing the `Arc`, so poisoning can be ignored
+        self.active
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Score the sample with the active forest.
+    pub fn score<S>(&self, sample: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        self.current().score(sample)
+    }
+
+    /// Activate the forest without validating it. Returns the previously active forest.
+    pub fn swap(&self, forest: Forest<T, N>) -> Arc<Forest<T, N>> {
+        let forest = Arc::new(forest);
+        let mut active = self
+            .active
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::mem::replace(&mut *active, forest)
+    }
+
+    /// Score the probe samples with the forest and activate it if all scores are valid.
+    /// Returns the previously active forest.
+    ///
+    /// On failure the active forest is kept.
+    pub fn reload<S>(&self, forest: Forest<T, N>, probe: &[S]) -> Result<Arc<Forest<T, N>>, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if let Some(index) = probe.iter().position(|sample| {
+            let score = forest.score(sample);
+            !(0.0..=1.0).contains(&score)
+        }) {
+            return Err(Error::InvalidProbeScore { index });
+        }
+        Ok(self.swap(forest))
+    }
+
+    /// Load a forest serialized as JSON from the file and [`reload`](Self::reload) it.
+    ///
+    /// Only available with the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn reload_from<P, S>(&self, path: P, probe: &[S]) -> Result<Arc<Forest<T, N>>, Error>
+    where
+        P: AsRef<std::path::Path>,
+        S: SampleAccess<T, N>,
+        T: serde::de::DeserializeOwned,
+    {
+        let file = std::fs::File::open(path).map_err(Error::Io)?;
+        let forest: Forest<T, N> = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|err| Error::Deserialization(err.to_string()))?;
+        self.reload(forest, probe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwappableForest;
+    use crate::eif::{EifForest, EifNode};
+    use crate::{Error, Forest};
+
+    fn make_forest(p: f64) -> Forest<f64, 1> {
+        Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0],
+                p: vec![p],
+                left: Box::new(EifNode::ExNode { size: 7 }),
+                right: Box::new(EifNode::ExNode { size: 1 }),
+            }],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn reload_validates_and_swaps() {
+        let swappable = SwappableForest::new(make_forest(5.0));
+        let before = swappable.score(&[10.0]);
+        let in_flight = swappable.current();
+
+        swappable
+            .reload(make_forest(50.0), &[[1.0], [10.0]])
+            .unwrap();
+        assert!(swappable.score(&[10.0]) < before);
+        assert_eq!(in_flight.score(&[10.0]), before);
+
+        // a forest with a broken depth normalization produces NaN scores
+        let mut broken = make_forest(50.0);
+        broken.avg_path_length_c = f64::NAN;
+        assert!(matches!(
+            swappable.reload(broken, &[[1.0]]),
+            Err(Error::InvalidProbeScore { index: 0 })
+        ));
+        assert!(swappable.score(&[10.0]) < before);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn reload_from_json_file() {
+        let path = std::env::temp_dir().join(format!("eif-swap-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&make_forest(50.0)).unwrap()).unwrap();
+
+        let swappable = SwappableForest::new(make_forest(5.0));
+        swappable.reload_from(&path, &[[1.0]]).unwrap();
+        assert_eq!(swappable.score(&[10.0]), make_forest(50.0).score(&[10.0]));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            swappable.reload_from(&path, &[[1.0]]),
+            Err(Error::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,220 @@
+//! Tagging explanations with the kind of anomaly.
+//!
+//! Alert routing often needs more than a score: a spike in one metric is handled differently
+//! than a drop or a combination of values never seen before. A [`Tagger`] holds rules, closures
+//! deciding from the explanation of a sample and the training range of the forest whether a tag
+//! applies. [`Forest::explain_tagged`] explains a sample and stores the tags of all matching
+//! rules in [`Explanation::tags`], so the classification stays next to the data it is based on.
+//!
+//! ```rust
+//! use extended_isolation_forest::tagging::Tagger;
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     seed: Some(4),
+//!     ..Default::default()
+//! };
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//!
+//! let mut tagger = Tagger::new();
+//! tagger.add_rule("spike", |context| {
+//!     context.dominant_dimension().is_some_and(|i| context.above_range(i))
+//! });
+//! tagger.add_rule("drop", |context| {
+//!     context.dominant_dimension().is_some_and(|i| context.below_range(i))
+//! });
+//!
+//! let explanation = forest.explain_tagged(&[4.5, 100.0], &tagger);
+//! assert_eq!(explanation.tags, ["spike"]);
+//! ```
+
+use num_traits::Float;
+
+use crate::explain::Explanation;
+use crate::{Forest, SampleAccess};
+
+/// A rule deciding whether its tag applies to a sample.
+type Rule<T, const N: usize> = Box<dyn Fn(&TagContext<'_, T, N>) -> bool + Send + Sync>;
+
+/// Rules tagging explanations, see the [module documentation](self).
+pub struct Tagger<T, const N: usize> {
+    rules: Vec<(String, Rule<T, N>)>,
+}
+
+/// What a rule of a [`Tagger`] can base its decision on.
+pub struct TagContext<'a, T, const N: usize> {
+    /// The sample as passed to [`Forest::explain_tagged`].
+    pub sample: [T; N],
+
+    /// The explanation of the sample, without tags.
+    pub explanation: &'a Explanation<N>,
+
+    /// The per-dimension minimum and maximum of the training data, see
+    /// [`Forest::training_range`].
+    pub training_range: Option<(&'a [T; N], &'a [T; N])>,
+}
+
+impl<T, const N: usize> Default for Tagger<T, N> {
+    fn default() -> Self {
+        Self { rules: Vec::new() }
+    }
+}
+
+impl<T, const N: usize> Tagger<T, N> {
+    /// A tagger without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule, the tag is added to every explanation the rule returns `true` for. Tags are
+    /// listed in the order their rules were added.
+    pub fn add_rule<F>(&mut self, tag: impl Into<String>, rule: F)
+    where
+        F: Fn(&TagContext<'_, T, N>) -> bool + Send + Sync + 'static,
+    {
+        self.rules.push((tag.into(), Box::new(rule)));
+    }
+
+    /// The tags of all rules matching the context.
+    pub fn tags(&self, context: &TagContext<'_, T, N>) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|(_, rule)| rule(context))
+            .map(|(tag, _)| tag.clone())
+            .collect()
+    }
+}
+
+impl<T, const N: usize> TagContext<'_, T, N>
+where
+    T: Float,
+{
+    /// The dimension contributing most to isolating the sample, `None` if the sample has not
+    /// been split by any tree.
+    pub fn dominant_dimension(&self) -> Option<usize> {
+        self.explanation
+            .contributions
+            .iter()
+            .enumerate()
+            .filter(|(_, contribution)| **contribution > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+
+    /// Whether the value of dimension `i` exceeds the training maximum, `false` without
+    /// training range.
+    pub fn above_range(&self, i: usize) -> bool {
+        self.training_range
+            .is_some_and(|(_, max)| self.sample[i] > max[i])
+    }
+
+    /// Whether the value of dimension `i` falls below the training minimum, `false` without
+    /// training range.
+    pub fn below_range(&self, i: usize) -> bool {
+        self.training_range
+            .is_some_and(|(min, _)| self.sample[i] < min[i])
+    }
+
+    /// Whether all values lie within the training range, so an anomalous sample combines known
+    /// values in a new way. `false` without training range.
+    pub fn within_range(&self) -> bool {
+        self.training_range.is_some()
+            && (0..N).all(|i| !self.above_range(i) && !self.below_range(i))
+    }
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// Explain the score of the sample like [`Forest::explain`] and tag the explanation with
+    /// the matching rules of the tagger.
+    pub fn explain_tagged<S>(&self, sample: &S, tagger: &Tagger<T, N>) -> Explanation<N>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let explanation = self.explain(sample);
+        self.tag(sample, explanation, tagger)
+    }
+
+    /// Explain and tag all samples, see [`Forest::explain_slice`].
+    pub fn explain_slice_tagged<S>(
+        &self,
+        samples: &[S],
+        tagger: &Tagger<T, N>,
+    ) -> Vec<Explanation<N>>
+    where
+        T: Sync,
+        S: SampleAccess<T, N> + Sync,
+    {
+        samples
+            .iter()
+            .zip(self.explain_slice(samples))
+            .map(|(sample, explanation)| self.tag(sample, explanation, tagger))
+            .collect()
+    }
+
+    fn tag<S>(
+        &self,
+        sample: &S,
+        mut explanation: Explanation<N>,
+        tagger: &Tagger<T, N>,
+    ) -> Explanation<N>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let tags = tagger.tags(&TagContext {
+            sample: sample.to_array(),
+            explanation: &explanation,
+            training_range: self.training_range(),
+        });
+        explanation.tags = tags;
+        explanation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tagger;
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn tag_explanations() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            seed: Some(12),
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+
+        let mut tagger = Tagger::new();
+        tagger.add_rule("spike", |context| {
+            context
+                .dominant_dimension()
+                .is_some_and(|i| context.above_range(i))
+        });
+        tagger.add_rule("drop", |context| {
+            context
+                .dominant_dimension()
+                .is_some_and(|i| context.below_range(i))
+        });
+        tagger.add_rule("new region", |context| {
+            context.within_range() && context.explanation.score > 0.52
+        });
+        tagger.add_rule("anomaly", |context| context.explanation.score > 0.52);
+
+        let samples = [[10.0, 100.0], [-80.0, 10.0], [9.5, 9.5]];
+        let explanations = forest.explain_slice_tagged(&samples, &tagger);
+        assert_eq!(explanations[0].tags, ["spike", "anomaly"]);
+        assert_eq!(explanations[1].tags, ["drop", "anomaly"]);
+        assert!(explanations[2].tags.is_empty());
+        assert_eq!(explanations[0], forest.explain_tagged(&samples[0], &tagger));
+        assert!(forest.explain(&samples[0]).tags.is_empty());
+        assert!(explanations[0].to_html().contains("spike, anomaly"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt::Write;
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::{to_f64, Forest, Node};
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// An indented outline of the first `max_trees` trees down to `max_depth`, for comparing
+    /// forests while debugging. The format is meant to be read and may change between versions.
+    ///
+    /// Internal nodes show their hyperplane as `n · x <= n · p` with the zero components left
+    /// out, and all nodes the number of training samples which reached them. Subtrees below
+    /// `max_depth` are summarized by their number of nodes.
+    ///
+    /// ```rust
+    /// use extended_isolation_forest::{Forest, ForestOptions};
+    ///
+    /// let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+    /// let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+    ///
+    /// let text = forest.to_pretty_text(1, 2);
+    /// assert!(text.starts_with("forest of 20 trees"));
+    /// assert!(text.contains("tree 0"));
+    /// assert!(!text.contains("tree 1"));
+    /// ```
+    pub fn to_pretty_text(&self, max_trees: usize, max_depth: usize) -> String {
+        let mut text = String::new();
+        // writing to a string never fails
+        let _ = writeln!(
+            text,
+            "forest of {} trees, {} dimensions, sample size {}, recursion cap {}",
+            self.trees.len(),
+            N,
+            self.options.sample_size,
+            self.recursion_cap(),
+        );
+
+        for (index, tree) in self.trees.iter().take(max_trees).enumerate() {
+            let _ = writeln!(
+                text,
+                "tree {}: {} nodes, depth {}",
+                index,
+                tree.root.iter().count(),
+                tree.root.depth()
+            );
+            let mut stack = vec![(&tree.root, 1, tree.root.depth_offset())];
+            while let Some((node, level, depth)) = stack.pop() {
+                let indent = "  ".repeat(level);
+                if depth > max_depth {
+                    let _ = writeln!(text, "{}... {} nodes", indent, node.iter().count());
+                    continue;
+                }
+                let merged = match node.depth_offset() {
+                    0 => String::new(),
+                    offset => format!(", {} merged levels", offset),
+                };
+                match node {
+                    Node::Ex(ex_node) => {
+                        let _ = writeln!(
+                            text,
+                            "{}leaf: {} samples{}",
+                            indent, ex_node.num_samples, merged
+                        );
+                    }
+                    Node::In(in_node) => {
+                        let _ = writeln!(
+                            text,
+                            "{}split {}: {} samples{}",
+                            indent,
+                            hyperplane(&in_node.n, &in_node.p),
+                            node.num_samples(),
+                            merged
+                        );
+                        for child in [in_node.right.as_ref(), in_node.left.as_ref()] {
+                            stack.push((child, level + 1, depth + 1 + child.depth_offset()));
+                        }
+                    }
+                }
+            }
+        }
+        if self.trees.len() > max_trees {
+            let _ = writeln!(text, "... {} more trees", self.trees.len() - max_trees);
+        }
+        text
+    }
+}
+
+/// The hyperplane as `n · x <= n · p`, the inequality of the samples going left.
+fn hyperplane<T: Float, const N: usize>(n: &[T; N], p: &[T; N]) -> String {
+    let mut text = String::new();
+    let mut offset = 0.0;
+    for (i, (n_i, p_i)) in n.iter().zip(p.iter()).enumerate() {
+        let (n_i, p_i) = (to_f64(*n_i), to_f64(*p_i));
+        if n_i == 0.0 {
+            continue;
+        }
+        offset += n_i * p_i;
+        let sign = match (text.is_empty(), n_i < 0.0) {
+            (true, false) => "",
+            (true, true) => "-",
+            (false, false) => " + ",
+            (false, true) => " - ",
+        };
+        let _ = write!(text, "{}{:.4}·x{}", sign, n_i.abs(), i);
+    }
+    if text.is_empty() {
+        text.push('0');
+    }
+    let _ = write!(text, " <= {:.4}", offset);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eif::{EifForest, EifNode};
+    use crate::Forest;
+
+    #[test]
+    fn outline_of_trees() {
+        let leaf = |size| Box::new(EifNode::ExNode { size });
+        let forest: Forest<f64, 2> = Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![
+                EifNode::InNode {
+                    n: vec![1.0, -0.5],
+                    p: vec![2.0, 1.0],
+                    left: Box::new(EifNode::InNode {
+                        n: vec![0.0, 2.0],
+                        p: vec![0.0, 3.0],
+                        left: leaf(5),
+                        right: leaf(2),
+                    }),
+                    right: leaf(1),
+                },
+                EifNode::ExNode { size: 8 },
+            ],
+        })
+        .unwrap();
+
+        assert_eq!(
+            forest.to_pretty_text(1, 1),
+            "forest of 2 trees, 2 dimensions, sample size 8, recursion cap 2\n\
+             tree 0: 5 nodes, depth 2\n  \
+             split 1.0000·x0 - 0.5000·x1 <= 1.5000: 8 samples\n    \
+             split 2.0000·x1 <= 6.0000: 7 samples\n      \
+             ... 1 nodes\n      \
+             ... 1 nodes\n    \
+             leaf: 1 samples\n\
+             ... 1 more trees\n"
+        );
+        assert!(forest
+            .to_pretty_text(2, 10)
+            .ends_with("tree 1: 1 nodes, depth 0\n  leaf: 8 samples\n"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Measuring how fast a forest scores, for capacity planning.
+//!
+//! [`Forest::bench_scoring`] scores synthetic samples drawn uniformly from the range of the
+//! training data on a number of threads, and reports the latency of single samples and the
+//! throughput of all threads together.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let report = forest.bench_scoring(1000, 2);
+//! assert_eq!(report.samples, 1000);
+//! assert!(report.p50 <= report.p99);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use num_traits::Float;
+use rand::Rng;
+
+use crate::leaf::LeafModel;
+use crate::{Forest, Node};
+
+/// Result of [`Forest::bench_scoring`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThroughputReport {
+    /// Number of scored samples.
+    pub samples: usize,
+
+    /// Number of threads which scored the samples.
+    pub threads: usize,
+
+    /// Wall clock time until all threads finished.
+    pub elapsed: Duration,
+
+    /// Samples scored per second by all threads together.
+    pub samples_per_second: f64,
+
+    /// Median latency of scoring a single sample.
+    pub p50: Duration,
+
+    /// 99th percentile of the latency of scoring a single sample.
+    pub p99: Duration,
+}
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float + Sync,
+    L: LeafModel<T, N> + Sync,
+{
+    /// Score `n_samples` synthetic samples on `n_threads` threads, at least one, and measure the
+    /// latency and the throughput.
+    ///
+    /// The samples are drawn uniformly from the training range of the forest, or from the range
+    /// of its split points for converted forests. They are generated before the clock starts.
+    /// Latencies include the overhead of reading the clock, which matters for tiny forests.
+    pub fn bench_scoring(&self, n_samples: usize, n_threads: usize) -> ThroughputReport {
+        let threads = n_threads.max(1);
+        let samples = self.synthetic_samples(n_samples, &mut rand::thread_rng());
+        let chunk_size = samples.len().div_ceil(threads).max(1);
+
+        let start = Instant::now();
+        let mut latencies: Vec<Duration> = std::thread::scope(|scope| {
+            let handles: Vec<_> = samples
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|sample| {
+                                let start = Instant::now();
+                                std::hint::black_box(self.score(sample));
+                                start.elapsed()
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("scoring samples does not panic"))
+                .collect()
+        });
+        let elapsed = start.elapsed();
+
+        latencies.sort_unstable();
+        let percentile = |p: f64| {
+            let index = (p * latencies.len() as f64).ceil() as usize;
+            latencies
+                .get(index.saturating_sub(1))
+                .copied()
+                .unwrap_or_default()
+        };
+        ThroughputReport {
+            samples: n_samples,
+            threads,
+            elapsed,
+            samples_per_second: n_samples as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE),
+            p50: percentile(0.5),
+            p99: percentile(0.99),
+        }
+    }
+
+    fn synthetic_samples(&self, count: usize, rng: &mut impl Rng) -> Vec<[T; N]> {
+        let (min, max) = match self.training_range() {
+            Some((min, max)) => (*min, *max),
+            None => self.split_point_range(),
+        };
+        (0..count)
+            .map(|_| {
+                std::array::from_fn(|i| {
+                    let unit = T::from(rng.gen::<f64>()).unwrap_or_else(T::zero);
+                    min[i] + (max[i] - min[i]) * unit
+                })
+            })
+            .collect()
+    }
+
+    /// The per-dimension range of the split points of all trees, zero where there are none.
+    fn split_point_range(&self) -> ([T; N], [T; N]) {
+        let mut min = [T::infinity(); N];
+        let mut max = [T::neg_infinity(); N];
+        for tree in self.trees.iter() {
+            for (node, _) in tree.root.iter() {
+                if let Node::In(in_node) = node {
+                    for i in 0..N {
+                        min[i] = min[i].min(in_node.p[i]);
+                        max[i] = max[i].max(in_node.p[i]);
+                    }
+                }
+            }
+        }
+        for i in 0..N {
+            if min[i] > max[i] {
+                min[i] = T::zero();
+                max[i] = T::zero();
+            }
+        }
+        (min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eif::{EifForest, EifNode};
+    use crate::Forest;
+
+    #[test]
+    fn samples_within_range_of_split_points() {
+        let forest: Forest<f64, 2> = Forest::from_eif(&EifForest {
+            sample_size: 8,
+            trees: vec![EifNode::InNode {
+                n: vec![1.0, 1.0],
+                p: vec![2.0, -1.0],
+                left: Box::new(EifNode::InNode {
+                    n: vec![1.0, 0.0],
+                    p: vec![4.0, -1.0],
+                    left: Box::new(EifNode::ExNode { size: 5 }),
+                    right: Box::new(EifNode::ExNode { size: 2 }),
+                }),
+                right: Box::new(EifNode::ExNode { size: 1 }),
+            }],
+        })
+        .unwrap();
+        assert_eq!(forest.split_point_range(), ([2.0, -1.0], [4.0, -1.0]));
+        let samples = forest.synthetic_samples(100, &mut rand::thread_rng());
+        assert!(samples
+            .iter()
+            .all(|sample| (2.0..=4.0).contains(&sample[0]) && sample[1] == -1.0));
+
+        let report = forest.bench_scoring(101, 4);
+        assert_eq!((report.samples, report.threads), (101, 4));
+        assert!(report.p50 <= report.p99 && report.p99 <= report.elapsed);
+        assert!(report.samples_per_second > 0.0);
+        assert_eq!(forest.bench_scoring(0, 0).threads, 1);
+    }
+}
@@ -0,0 +1,214 @@
+//! Scoring with a small forest first and the full forest only where needed.
+//!
+//! A [`TieredScorer`] decides with a coarse forest of a few trees whether a sample is an anomaly
+//! and only scores it with the full forest if the coarse score lies within a margin around the
+//! threshold. The margin is calibrated on samples scored by both forests, so the decisions of
+//! the coarse forest agree with the full forest on all of them. Most samples of a stream are
+//! clearly normal and skip the full forest.
+//!
+//! ```rust
+//! use extended_isolation_forest::tiered::TieredScorer;
+//! use extended_isolation_forest::ForestOptions;
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let options = ForestOptions {
+//!     n_trees: 100,
+//!     ..Default::default()
+//! };
+//! let scorer = TieredScorer::from_slice(&values, &options, 10, 0.6).unwrap();
+//!
+//! // the margin has been calibrated on the training data
+//! for sample in &values {
+//!     let tiered = scorer.score(sample);
+//!     assert_eq!(tiered.is_anomaly, scorer.fine().score(sample) > 0.6);
+//! }
+//! ```
+
+use num_traits::Float;
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Result of scoring a sample with a [`TieredScorer`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TieredScore {
+    /// The score of the full forest if the sample has been refined, otherwise the score of the
+    /// coarse forest.
+    pub score: f64,
+
+    /// The score exceeded the threshold of the scorer.
+    pub is_anomaly: bool,
+
+    /// The sample has been scored with the full forest.
+    pub refined: bool,
+}
+
+/// A coarse forest deciding clear cases and a full forest scoring the samples close to the
+/// threshold.
+pub struct TieredScorer<T, const N: usize> {
+    coarse: Forest<T, N>,
+    fine: Forest<T, N>,
+    threshold: f64,
+    margin: f64,
+}
+
+impl<'de, T, const N: usize> TieredScorer<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build the full forest using `options` and a coarse forest with `coarse_trees` trees from
+    /// the same training data, and calibrate the margin on the training data.
+    ///
+    /// The coarse forest has at least one tree.
+    pub fn from_slice<S>(
+        training_data: &[S],
+        options: &ForestOptions,
+        coarse_trees: usize,
+        threshold: f64,
+    ) -> Result<Self, Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        let fine = Forest::from_slice(training_data, options)?;
+        let coarse = Forest::from_slice(
+            training_data,
+            &ForestOptions {
+                n_trees: coarse_trees.max(1),
+                ..options.clone()
+            },
+        )?;
+        let mut scorer = Self::new(coarse, fine, threshold, 0.0);
+        scorer.calibrate(training_data, 0.0);
+        Ok(scorer)
+    }
+}
+
+impl<T, const N: usize> TieredScorer<T, N>
+where
+    T: Float,
+{
+    /// Combine two forests trained on the same data. Samples with a coarse score within
+    /// `margin` of the threshold are refined, see [`TieredScorer::calibrate`] for choosing it.
+    pub fn new(coarse: Forest<T, N>, fine: Forest<T, N>, threshold: f64, margin: f64) -> Self {
+        Self {
+            coarse,
+            fine,
+            threshold,
+            margin,
+        }
+    }
+
+    /// Score the sample with the coarse forest, and with the full forest if the coarse score is
+    /// within the margin of the threshold.
+    pub fn score<S>(&self, sample: &S) -> TieredScore
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let coarse = self.coarse.score(sample);
+        let (score, refined) = if (coarse - self.threshold).abs() > self.margin {
+            (coarse, false)
+        } else {
+            (self.fine.score(sample), true)
+        };
+        TieredScore {
+            score,
+            is_anomaly: score > self.threshold,
+            refined,
+        }
+    }
+
+    /// Choose the smallest margin for which the coarse forest decides at most a fraction of
+    /// `tolerance` of the samples differently than the full forest, returning the margin.
+    ///
+    /// The samples should come from the same distribution as the samples scored later, a
+    /// `tolerance` of zero makes both forests agree on all of them.
+    pub fn calibrate<S>(&mut self, samples: &[S], tolerance: f64) -> f64
+    where
+        S: SampleAccess<T, N>,
+    {
+        // every sample decided differently needs a margin reaching its coarse score
+        let mut distances: Vec<f64> = samples
+            .iter()
+            .filter_map(|sample| {
+                let coarse = self.coarse.score(sample);
+                let fine = self.fine.score(sample);
+                ((coarse > self.threshold) != (fine > self.threshold))
+                    .then_some((coarse - self.threshold).abs())
+            })
+            .collect();
+        distances.sort_by(|a, b| b.total_cmp(a));
+
+        let allowed = (tolerance.clamp(0.0, 1.0) * samples.len() as f64).floor() as usize;
+        self.margin = distances.get(allowed).copied().unwrap_or(0.0);
+        self.margin
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn margin(&self) -> f64 {
+        self.margin
+    }
+
+    pub fn coarse(&self) -> &Forest<T, N> {
+        &self.coarse
+    }
+
+    /// The full forest.
+    pub fn fine(&self) -> &Forest<T, N> {
+        &self.fine
+    }
+
+    pub fn into_inner(self) -> (Forest<T, N>, Forest<T, N>) {
+        (self.coarse, self.fine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TieredScorer;
+    use crate::ForestOptions;
+
+    #[test]
+    fn coarse_forest_agrees_on_calibration_samples() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            sample_size: 64,
+            ..Default::default()
+        };
+        let threshold = 0.6;
+        let mut scorer =
+            TieredScorer::from_slice(values.as_slice(), &options, 10, threshold).unwrap();
+        assert_eq!(scorer.coarse().trees.len(), 10);
+
+        let mut refined = 0;
+        for sample in values.iter() {
+            let tiered = scorer.score(sample);
+            assert_eq!(tiered.is_anomaly, scorer.fine().score(sample) > threshold);
+            let coarse = scorer.coarse().score(sample);
+            assert_eq!(
+                tiered.refined,
+                (coarse - threshold).abs() <= scorer.margin()
+            );
+            refined += tiered.refined as usize;
+        }
+        assert!(refined < values.len());
+
+        // tolerating disagreements never widens the margin
+        let margin = scorer.margin();
+        assert!(scorer.calibrate(values.as_slice(), 0.05) <= margin);
+
+        // scores are within `[0, 1]`, a margin of one refines everything
+        let (coarse, fine) = scorer.into_inner();
+        let refine_all = TieredScorer::new(coarse, fine, threshold, 1.0);
+        let anomaly = refine_all.score(&[1000.0, 1000.0]);
+        assert!(anomaly.refined);
+        assert_eq!(anomaly.score, refine_all.fine().score(&[1000.0, 1000.0]));
+    }
+}
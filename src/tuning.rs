@@ -0,0 +1,249 @@
+//! Comparing variations of the options on the same data.
+//!
+//! [`ablation`] trains a forest with the base options and one forest per variation, all from
+//! the same seed, so the differences are due to the options and not to the randomness of the
+//! training. Every variation is compared to the base forest by the correlation of the scores of
+//! the data, the change of the ROC AUC if labels are given, the training time and the size of
+//! the trees.
+//!
+//! ```rust
+//! use extended_isolation_forest::tuning::ablation;
+//! use extended_isolation_forest::{ExtensionLevel, ForestOptions};
+//!
+//! let mut values: Vec<_> = (0..400).map(|i| [(i % 20) as f64, (i / 20) as f64]).collect();
+//! values.push([100.0, 100.0]);
+//! let labels: Vec<bool> = (0..values.len()).map(|i| i == 400).collect();
+//!
+//! let base = ForestOptions {
+//!     seed: Some(5),
+//!     ..Default::default()
+//! };
+//! let variations = [
+//!     (
+//!         "more trees",
+//!         ForestOptions {
+//!             n_trees: 100,
+//!             ..base.clone()
+//!         },
+//!     ),
+//!     (
+//!         "axis-parallel",
+//!         ForestOptions {
+//!             extension_level: ExtensionLevel::Fixed(0),
+//!             ..base.clone()
+//!         },
+//!     ),
+//! ];
+//! let report = ablation(&values, Some(&labels), &base, &variations).unwrap();
+//! assert_eq!(report.variations[0].name, "more trees");
+//! assert!(report.variations[0].model_bytes > report.base.model_bytes);
+//! assert_eq!(report.seed, 5);
+//! let auc_delta = report.variations[1].auc_delta.unwrap();
+//! println!("axis-parallel changes the AUC by {:+.3}", auc_delta);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::eval::{pearson, roc_auc};
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// The forest of one set of options, see [`ablation`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VariationResult {
+    pub name: String,
+
+    /// The Pearson correlation of the scores of the data with the scores of the base forest,
+    /// `1` for the base forest itself.
+    pub score_correlation: f64,
+
+    /// The ROC AUC of the scores, `None` without labels or if one of the classes is missing.
+    pub auc: Option<f64>,
+
+    /// The change of the ROC AUC compared to the base forest.
+    pub auc_delta: Option<f64>,
+
+    /// Wall clock time of building the forest.
+    pub training_time: Duration,
+
+    /// Approximate number of bytes occupied by the nodes of all trees.
+    pub model_bytes: usize,
+}
+
+/// The result of [`ablation`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AblationReport {
+    /// The seed all forests have been trained from.
+    pub seed: u64,
+
+    /// The forest of the base options, named `base`.
+    pub base: VariationResult,
+
+    /// The forests of the variations, in the order of the variations.
+    pub variations: Vec<VariationResult>,
+}
+
+/// Train a forest on the data with the base options and with every named variation of them, and
+/// compare the variations to the base forest, see the [module documentation](self).
+///
+/// All forests are trained from the seed of the base options, a random seed if it has none,
+/// overriding the seeds of the variations. Labels mark anomalies with `true`. Fails with
+/// [`Error::LabelCountMismatch`] if the numbers of labels and samples differ, and with the error
+/// of the first forest which can not be trained.
+pub fn ablation<'de, T, S, const N: usize>(
+    data: &[S],
+    labels: Option<&[bool]>,
+    base_options: &ForestOptions,
+    variations: &[(&str, ForestOptions)],
+) -> Result<AblationReport, Error>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+    S: SampleAccess<T, N>,
+{
+    if let Some(labels) = labels {
+        if labels.len() != data.len() {
+            return Err(Error::LabelCountMismatch {
+                expected: data.len(),
+                actual: labels.len(),
+            });
+        }
+    }
+    let seed = base_options.seed.unwrap_or_else(rand::random);
+
+    let train = |options: &ForestOptions| -> Result<(Vec<f64>, Duration, usize), Error> {
+        let options = ForestOptions {
+            seed: Some(seed),
+            ..options.clone()
+        };
+        let started = Instant::now();
+        let forest: Forest<T, N> = Forest::from_slice(data, &options)?;
+        let training_time = started.elapsed();
+        let scores = data.iter().map(|sample| forest.score(sample)).collect();
+        Ok((scores, training_time, forest.tree_bytes()))
+    };
+
+    let (base_scores, training_time, model_bytes) = train(base_options)?;
+    let base_auc = labels.and_then(|labels| roc_auc(&base_scores, labels));
+    let base = VariationResult {
+        name: "base".to_string(),
+        score_correlation: 1.0,
+        auc: base_auc,
+        auc_delta: base_auc.map(|_| 0.0),
+        training_time,
+        model_bytes,
+    };
+
+    let variations = variations
+        .iter()
+        .map(|(name, options)| {
+            let (scores, training_time, model_bytes) = train(options)?;
+            let auc = labels.and_then(|labels| roc_auc(&scores, labels));
+            Ok(VariationResult {
+                name: name.to_string(),
+                score_correlation: pearson(&base_scores, &scores),
+                auc,
+                auc_delta: auc.zip(base_auc).map(|(auc, base_auc)| auc - base_auc),
+                training_time,
+                model_bytes,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(AblationReport {
+        seed,
+        base,
+        variations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ablation;
+    use crate::{Error, ForestOptions};
+
+    #[test]
+    fn ablation_compares_variations_with_the_base() {
+        let mut values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        values.extend([[60.0, 60.0], [-40.0, 10.0], [10.0, 50.0]]);
+        let labels: Vec<bool> = (0..values.len()).map(|i| i >= 400).collect();
+        let base = ForestOptions {
+            n_trees: 40,
+            sample_size: 64,
+            seed: Some(20),
+            ..Default::default()
+        };
+        let variations = [
+            (
+                "same",
+                ForestOptions {
+                    seed: Some(1),
+                    ..base.clone()
+                },
+            ),
+            (
+                "fewer trees",
+                ForestOptions {
+                    n_trees: 10,
+                    ..base.clone()
+                },
+            ),
+            (
+                "tiny subsamples",
+                ForestOptions {
+                    sample_size: 4,
+                    ..base.clone()
+                },
+            ),
+        ];
+
+        let report = ablation(&values, Some(&labels), &base, &variations).unwrap();
+        assert_eq!(report.seed, 20);
+        assert_eq!(report.base.name, "base");
+        assert_eq!(report.base.auc_delta, Some(0.0));
+        let names: Vec<_> = report.variations.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["same", "fewer trees", "tiny subsamples"]);
+
+        // the seed of the base options is used for all variations
+        let same = &report.variations[0];
+        assert_eq!(same.score_correlation, 1.0);
+        assert_eq!(same.auc, report.base.auc);
+        assert_eq!(same.model_bytes, report.base.model_bytes);
+
+        let fewer = &report.variations[1];
+        assert!(fewer.model_bytes < report.base.model_bytes);
+        assert!(fewer.score_correlation > 0.8);
+        let tiny = &report.variations[2];
+        assert!(tiny.score_correlation < fewer.score_correlation);
+        assert_eq!(
+            tiny.auc_delta,
+            Some(tiny.auc.unwrap() - report.base.auc.unwrap())
+        );
+
+        let report = ablation(&values, None, &base, &variations).unwrap();
+        assert!(report.variations.iter().all(|v| v.auc_delta.is_none()));
+        assert!(matches!(
+            ablation(&values, Some(&labels[1..]), &base, &variations),
+            Err(Error::LabelCountMismatch { .. })
+        ));
+        let invalid = [(
+            "too large",
+            ForestOptions {
+                sample_size: 1000,
+                ..base.clone()
+            },
+        )];
+        assert!(matches!(
+            ablation(&values, None, &base, &invalid),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+//! Sanity checks of a forest on held-out training data.
+//!
+//! Bad options rarely make training fail, they make the scores meaningless: a forest whose
+//! trees can not separate the samples scores everything close to `0.5`.
+//! [`Forest::from_slice_validated`] holds out a fraction of the training data, scores it after
+//! training and reports [`ValidationWarning`]s for degenerate score distributions, for
+//! features without any variation and for nearly collinear features, which usually point to an
+//! extension level too low for the structure of the data or to features which should have been
+//! removed or [folded](crate::correlation::Correlation::fold).
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..500).map(|i| [(i % 10) as f64, 3.0]).collect();
+//! let (forest, validation) =
+//!     Forest::<f64, 2>::from_slice_validated(&values, &ForestOptions::default(), 0.2).unwrap();
+//! assert_eq!(validation.holdout, 100);
+//! assert!(!validation.warnings.is_empty());
+//! ```
+
+use rand::distributions::uniform::SampleUniform;
+use rand::seq::SliceRandom;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::correlation::{CollinearPair, Correlation};
+use crate::report::Summary;
+use crate::seed::TreeRng;
+use crate::{Error, Forest, ForestFloat, ForestOptions, SampleAccess};
+
+/// Held-out scores within this distance of `0.5` on average, with a standard deviation below
+/// it, are not separated by the trees.
+const NEAR_HALF: f64 = 0.025;
+
+/// Held-out scores with a smaller standard deviation hardly differ.
+const MIN_STD_DEV: f64 = 0.01;
+
+/// Training features with a larger absolute correlation are reported as collinear.
+const COLLINEAR: f64 = 0.99;
+
+/// Result of validating a forest on held-out samples.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Validation {
+    /// Number of held-out samples.
+    pub holdout: usize,
+
+    /// Statistics of the scores of the held-out samples.
+    pub scores: Summary,
+
+    /// Problems found, empty if the forest looks sane.
+    pub warnings: Vec<ValidationWarning>,
+}
+
+/// A problem found by [`Forest::from_slice_validated`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ValidationWarning {
+    /// The held-out samples score close to `0.5` on average with little spread, the trees
+    /// isolate them about as fast as random samples.
+    ScoresNearHalf { mean: f64, std_dev: f64 },
+
+    /// All held-out samples score about the same, so no threshold can separate them.
+    NoScoreSpread { std_dev: f64 },
+
+    /// These features are (nearly) constant in the training data, see
+    /// [`Forest::constant_features`].
+    ConstantFeatures(Vec<usize>),
+
+    /// These pairs of features are nearly collinear in the training data, with an absolute
+    /// correlation of at least `0.99`.
+    CollinearFeatures(Vec<CollinearPair>),
+}
+
+impl<'de, T, const N: usize> Forest<T, N>
+where
+    T: ForestFloat<'de> + SampleUniform + Default,
+    StandardNormal: Distribution<T>,
+{
+    /// Build a new forest from the training data like [`Forest::from_slice`], holding out a
+    /// random fraction `holdout` in `(0, 1)` of the samples, at least one. The held-out samples
+    /// are scored by the trained forest and checked for degenerate scores.
+    ///
+    /// With [`ForestOptions::seed`] the held-out samples are chosen reproducibly. Fails with
+    /// [`Error::InsufficientTrainingData`] if the remaining samples are fewer than the sample
+    /// size or `holdout` is not in `(0, 1)`.
+    pub fn from_slice_validated<S>(
+        training_data: &[S],
+        options: &ForestOptions,
+        holdout: f64,
+    ) -> Result<(Self, Validation), Error>
+    where
+        S: SampleAccess<T, N>,
+    {
+        if !(holdout > 0.0 && holdout < 1.0) {
+            return Err(Error::InsufficientTrainingData);
+        }
+        let num_holdout = ((training_data.len() as f64 * holdout).round() as usize).max(1);
+        if num_holdout >= training_data.len() {
+            return Err(Error::InsufficientTrainingData);
+        }
+
+        let mut order: Vec<usize> = (0..training_data.len()).collect();
+        let rng = &mut match options.seed {
+            Some(seed) => TreeRng::from_seed(seed),
+            None => TreeRng::thread(),
+        };
+        order.shuffle(rng);
+        let (held_out, training) = order.split_at(num_holdout);
+        let training: Vec<&S> = training.iter().map(|i| &training_data[*i]).collect();
+        let forest = Forest::from_slice(training.as_slice(), options)?;
+        let collinear = Correlation::of(training.as_slice()).collinear(COLLINEAR);
+
+        let scores = Summary::of(held_out.iter().map(|i| forest.score(&training_data[*i])));
+        let mut warnings = Vec::new();
+        if (scores.mean - 0.5).abs() < NEAR_HALF && scores.std_dev < NEAR_HALF {
+            warnings.push(ValidationWarning::ScoresNearHalf {
+                mean: scores.mean,
+                std_dev: scores.std_dev,
+            });
+        }
+        if scores.std_dev < MIN_STD_DEV {
+            warnings.push(ValidationWarning::NoScoreSpread {
+                std_dev: scores.std_dev,
+            });
+        }
+        if !forest.constant_features().is_empty() {
+            warnings.push(ValidationWarning::ConstantFeatures(
+                forest.constant_features().to_vec(),
+            ));
+        }
+        if !collinear.is_empty() {
+            warnings.push(ValidationWarning::CollinearFeatures(collinear));
+        }
+
+        Ok((
+            forest,
+            Validation {
+                holdout: num_holdout,
+                scores,
+                warnings,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use rand_distr::StandardNormal;
+
+    use super::ValidationWarning;
+    use crate::seed::TreeRng;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn degenerate_forests_are_reported() {
+        let options = ForestOptions {
+            n_trees: 50,
+            sample_size: 64,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let rng = &mut TreeRng::from_seed(1);
+        let values: Vec<[f64; 2]> = (0..400)
+            .map(|_| [rng.sample(StandardNormal), rng.sample(StandardNormal)])
+            .collect();
+        let (forest, validation) =
+            Forest::<f64, 2>::from_slice_validated(&values, &options, 0.25).unwrap();
+        assert_eq!(validation.holdout, 100);
+        assert!(validation.warnings.is_empty(), "{:?}", validation.warnings);
+        assert!(validation.scores.max > validation.scores.min);
+        let (_, again) = Forest::<f64, 2>::from_slice_validated(&values, &options, 0.25).unwrap();
+        assert_eq!(again.scores, validation.scores);
+        assert_eq!(forest.options().sample_size, 64);
+
+        // identical samples are never isolated
+        let constant = vec![[1.0, 2.0]; 200];
+        let (_, validation) =
+            Forest::<f64, 2>::from_slice_validated(&constant, &options, 0.1).unwrap();
+        assert_eq!(validation.holdout, 20);
+        assert!(validation
+            .warnings
+            .contains(&ValidationWarning::ConstantFeatures(vec![0, 1])));
+        assert!(validation
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, ValidationWarning::NoScoreSpread { .. })));
+
+        let doubled: Vec<[f64; 2]> = values.iter().map(|[x, _]| [*x, 2.0 * x]).collect();
+        let (_, validation) =
+            Forest::<f64, 2>::from_slice_validated(&doubled, &options, 0.25).unwrap();
+        assert!(validation.warnings.iter().any(|warning| matches!(
+            warning,
+            ValidationWarning::CollinearFeatures(pairs) if pairs.len() == 1
+        )));
+
+        for holdout in [0.0, 1.0, f64::NAN] {
+            assert!(matches!(
+                Forest::<f64, 2>::from_slice_validated(&values, &options, holdout),
+                Err(Error::InsufficientTrainingData)
+            ));
+        }
+        assert!(matches!(
+            Forest::<f64, 2>::from_slice_validated(&values, &options, 0.9),
+            Err(Error::InsufficientTrainingData)
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Scoring by the votes of the trees.
+//!
+//! [`Forest::score`] averages the path lengths over all trees, so a few trees with unusually
+//! long paths can pull the score of an anomaly down. A [`VoteScorer`] instead lets every tree
+//! vote whether it isolates the sample as early as the `quantile` of the most isolated training
+//! samples, and scores the sample by the fraction of trees voting for it being anomalous.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..400).map(|i| [(i % 20) as f64, (i / 20) as f64]).collect();
+//! let options = ForestOptions {
+//!     n_trees: 100,
+//!     ..Default::default()
+//! };
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &options).unwrap();
+//!
+//! let scorer = forest.vote_scorer(0.1);
+//! assert!(scorer.score(&[100.0, 100.0]) > scorer.score(&[10.0, 10.0]));
+//! ```
+
+use num_traits::Float;
+
+use crate::{c_factor, Forest, Node, SampleAccess, Tree};
+
+/// Scores samples by the fraction of trees isolating them unusually early.
+pub struct VoteScorer<'a, T, const N: usize> {
+    forest: &'a Forest<T, N>,
+    quantile: f64,
+
+    /// Per tree the path length below which the tree votes for an anomaly.
+    thresholds: Vec<f64>,
+}
+
+impl<T, const N: usize> Forest<T, N>
+where
+    T: Float,
+{
+    /// A scorer letting the trees vote. A tree votes for a sample when its path length is at
+    /// most the `quantile` of the path lengths of the training samples in the tree, so `0.05`
+    /// counts the trees isolating the sample as early as the 5% most isolated training samples.
+    ///
+    /// The thresholds of the trees are computed once when creating the scorer.
+    pub fn vote_scorer(&self, quantile: f64) -> VoteScorer<'_, T, N> {
+        let max_depth = self.recursion_cap();
+        VoteScorer {
+            forest: self,
+            quantile,
+            thresholds: self
+                .trees
+                .iter()
+                .map(|tree| training_path_length_quantile(tree, quantile, max_depth))
+                .collect(),
+        }
+    }
+}
+
+impl<T, const N: usize> VoteScorer<'_, T, N>
+where
+    T: Float,
+{
+    /// The fraction of trees voting for the sample being anomalous, in `[0, 1]`.
+    pub fn score<S>(&self, sample: &S) -> f64
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        let max_depth = self.forest.recursion_cap();
+        let votes = self
+            .forest
+            .trees
+            .iter()
+            .zip(self.thresholds.iter())
+            .filter(|(tree, threshold)| tree.path_length_with_cap(sample, max_depth) <= **threshold)
+            .count();
+        votes as f64 / self.thresholds.len().max(1) as f64
+    }
+
+    pub fn quantile(&self) -> f64 {
+        self.quantile
+    }
+
+    /// The path length threshold of every tree, in the order of the trees.
+    pub fn thresholds(&self) -> &[f64] {
+        &self.thresholds
+    }
+
+    pub fn forest(&self) -> &Forest<T, N> {
+        self.forest
+    }
+}
+
+/// The `quantile` of the path lengths of the training samples the tree has been built from,
+/// which are known from the depths and sizes of its external nodes.
+fn training_path_length_quantile<T, const N: usize>(
+    tree: &Tree<T, N>,
+    quantile: f64,
+    max_depth: usize,
+) -> f64 {
+    let mut path_lengths: Vec<(f64, usize)> = tree
+        .root
+        .iter()
+        .filter_map(|(node, depth)| match node {
+            Node::Ex(ex_node) if depth <= max_depth && ex_node.num_samples > 0 => Some((
+                depth as f64 + c_factor(ex_node.num_samples),
+                ex_node.num_samples,
+            )),
+            _ => None,
+        })
+        .collect();
+    // samples below the recursion cap all get its depth as path length, counted at the first
+    // node on their paths entered above the cap and ending below it
+    let capped: usize = tree
+        .root
+        .iter()
+        .filter(|(node, depth)| {
+            *depth - node.depth_offset() <= max_depth
+                && match node {
+                    Node::Ex(_) => *depth > max_depth,
+                    Node::In(_) => *depth >= max_depth,
+                }
+        })
+        .map(|(node, _)| {
+            node.iter()
+                .map(|(node, _)| match node {
+                    Node::Ex(ex_node) => ex_node.num_samples,
+                    Node::In(_) => 0,
+                })
+                .sum::<usize>()
+        })
+        .sum();
+    if capped > 0 {
+        path_lengths.push((max_depth as f64, capped));
+    }
+    path_lengths.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    let total: usize = path_lengths.iter().map(|(_, count)| count).sum();
+    let required = quantile.clamp(0.0, 1.0) * total as f64;
+    let mut cumulative = 0;
+    for (path_length, count) in &path_lengths {
+        cumulative += count;
+        if cumulative as f64 >= required {
+            return *path_length;
+        }
+    }
+    path_lengths
+        .last()
+        .map(|(path_length, _)| *path_length)
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Forest, ForestOptions};
+
+    #[test]
+    fn trees_vote_for_anomalies() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 100,
+            ..Default::default()
+        };
+        let forest: Forest<f64, 2> = Forest::from_slice(values.as_slice(), &options).unwrap();
+
+        let scorer = forest.vote_scorer(0.1);
+        assert_eq!(scorer.thresholds().len(), 100);
+        assert!(scorer.score(&[100.0, -100.0]) > scorer.score(&[10.0, 10.0]) + 0.2);
+
+        let median = forest.vote_scorer(0.5);
+        assert!(median
+            .thresholds()
+            .iter()
+            .zip(scorer.thresholds())
+            .all(|(a, b)| a >= b));
+    }
+}
@@ -0,0 +1,117 @@
+//! Scores across a sweep of one feature.
+//!
+//! [`Forest::what_if`] scores a sample with one dimension set to each of a list of values, for
+//! interactive sliders over a single feature. Like [`Forest::sensitivity`] all values are routed
+//! through a tree together: the position of the sample relative to a hyperplane is computed
+//! once per node and only the term of the swept dimension differs between the values, so nodes
+//! and trees not splitting on the dimension are traversed only once for the whole sweep.
+//!
+//! ```rust
+//! use extended_isolation_forest::{Forest, ForestOptions};
+//!
+//! let values: Vec<_> = (0..100).map(|i| [(i % 10) as f64, (i / 10) as f64]).collect();
+//! let forest: Forest<f64, 2> = Forest::from_slice(&values, &ForestOptions::default()).unwrap();
+//!
+//! let sweep: Vec<f64> = (-10..=20).map(|x| x as f64).collect();
+//! let scores = forest.what_if(&[4.5, 4.5], 0, &sweep).unwrap();
+//! assert_eq!(scores.len(), sweep.len());
+//! assert!((scores[14] - forest.score(&[4.0, 4.5])).abs() < 1e-12);
+//! ```
+
+use num_traits::Float;
+
+use crate::leaf::LeafModel;
+use crate::sensitivity::Perturbation;
+use crate::{Error, Forest, SampleAccess};
+
+impl<T, const N: usize, L> Forest<T, N, L>
+where
+    T: Float,
+    L: LeafModel<T, N>,
+{
+    /// The scores of the sample with `dimension` set to each of the values, in the order of the
+    /// values, see the [module documentation](self).
+    ///
+    /// Suppressed regions, monotone constraints and clamping to the training range are applied
+    /// like in [`Forest::sensitivity`]. Fails with [`Error::FeatureIndexOutOfRange`] if the
+    /// dimension is not less than `N`.
+    pub fn what_if<S>(
+        &self,
+        base_sample: &S,
+        dimension: usize,
+        values: &[T],
+    ) -> Result<Vec<f64>, Error>
+    where
+        S: SampleAccess<T, N> + ?Sized,
+    {
+        if dimension >= N {
+            return Err(Error::FeatureIndexOutOfRange {
+                index: dimension,
+                dimensions: N,
+            });
+        }
+
+        let mut base = base_sample.to_array();
+        // the swept value is added to the base as the delta of the perturbation, so a missing
+        // value of the base sample does not spread to the sweep
+        base[dimension] = T::zero();
+        let mut adjusted_base = self.adjusted_without_counting(&base);
+        adjusted_base[dimension] = T::zero();
+
+        let mut suppressed = Vec::with_capacity(values.len());
+        let mut perturbations = Vec::with_capacity(values.len());
+        for value in values.iter() {
+            let mut sample = base;
+            sample[dimension] = *value;
+            suppressed.push(self.is_suppressed(&sample));
+            // the adjustments work per dimension, so they only change the swept dimension
+            let adjusted = self.adjusted_without_counting(&sample);
+            perturbations.push(Perturbation::new(dimension, adjusted[dimension]));
+        }
+        Ok(self.perturbed_scores(&adjusted_base, perturbations, &suppressed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::envelope::RangePolicy;
+    use crate::{Error, Forest, ForestOptions};
+
+    #[test]
+    fn what_if_matches_scores_of_the_sweep() {
+        let values: Vec<_> = (0..400)
+            .map(|i| [(i % 20) as f64, (i / 20) as f64, (i % 7) as f64])
+            .collect();
+        let options = ForestOptions {
+            n_trees: 40,
+            sample_size: 64,
+            extension_level: 1.into(),
+            seed: Some(14),
+            ..Default::default()
+        };
+        let mut forest: Forest<f64, 3> = Forest::from_slice(&values, &options).unwrap();
+        let sample = [12.3, f64::NAN, 2.6];
+        let sweep: Vec<f64> = (-20..60).map(|x| x as f64 * 0.7).collect();
+
+        for range_policy in [RangePolicy::Ignore, RangePolicy::Clamp] {
+            forest.set_range_policy(range_policy);
+            for dimension in 0..3 {
+                let scores = forest.what_if(&sample, dimension, &sweep).unwrap();
+                for (value, score) in sweep.iter().zip(scores.iter()) {
+                    let mut swept = sample;
+                    swept[dimension] = *value;
+                    let expected = forest.score(&swept);
+                    assert!((score - expected).abs() < 1e-12, "{} {}", dimension, value);
+                }
+            }
+        }
+        assert!(forest.what_if(&sample, 0, &[]).unwrap().is_empty());
+        assert!(matches!(
+            forest.what_if(&sample, 3, &sweep),
+            Err(Error::FeatureIndexOutOfRange {
+                index: 3,
+                dimensions: 3
+            })
+        ));
+    }
+}